@@ -69,75 +69,11 @@ var _focused_app_id := 0
 func _init() -> void:
 	_load_persist_data()
 
-	# Get the window ID of OpenGamepadUI
-	if _xwayland_ogui:
-		var ogui_windows := _xwayland_ogui.get_windows_for_pid(PID)
-		if not ogui_windows.is_empty():
-			_ogui_window_id = ogui_windows[0]
-
-	# Listen for signals from the primary Gamescope XWayland
-	if _xwayland_primary:
-		# Debug print when the focused window changes
-		var on_focus_changed := func(from: int, to: int):
-			if from == to:
-				return
-			logger.info("Window focus changed from " + str(from) + " to: " + str(to))
-			self.check_running.call_deferred()
-		_xwayland_primary.focused_window_updated.connect(on_focus_changed)
-
-		# When focused app changes, update the current app and gamepad profile
-		var on_focused_app_changed := func(_from: int, to: int) -> void:
-			if _focused_app_id == to:
-				return
-			logger.debug("Focused app changed from " + str(_focused_app_id) + " to " + str(to))
-			_focused_app_id = to
-
-			# If OGUI was focused, set the global gamepad profile
-			if to in [gamescope.OVERLAY_GAME_ID, 0, 1]:
-				set_gamepad_profile("")
-				return
-
-			# Find the running app for the given app id
-			var last_app := self._current_app
-			var detected_app: RunningApp
-			for app in _running:
-				if app.app_id == to:
-					detected_app = app
-
-			# If the running app was not launched by OpenGamepadUI, then detect it.
-			if not detected_app:
-				detected_app = _detect_running_app(to)
-			self._current_app = detected_app
-
-			logger.debug("Last app: " + str(last_app) + " current_app: " + str(self._current_app))
-			app_switched.emit(last_app, self._current_app)
-
-			# If the app has a gamepad profile, set it
-			if self._current_app:
-				set_app_gamepad_profile(self._current_app)
-		_xwayland_primary.focused_app_updated.connect(on_focused_app_changed)
-
-		# Listen for when focusable apps change
-		var on_focusable_apps_changed := func(from: PackedInt64Array, to: PackedInt64Array):
-			if from == to:
-				return
-			logger.debug("Focusable apps changed from", from, "to", to)
-			self.check_running.call_deferred()
-			# If focusable apps has changed and the currently focused app no longer exists,
-			# remove the manual focus
-			var baselayer_app := _xwayland_primary.baselayer_app
-			to.append(_xwayland_primary.focused_app)
-			if baselayer_app > 0 and not baselayer_app in to:
-				_xwayland_primary.remove_baselayer_app()
-		_xwayland_primary.focusable_apps_updated.connect(on_focusable_apps_changed)
-
-	# Listen for signals from the secondary Gamescope XWayland
-	if _xwayland_game:
-		# Listen for window created/destroyed events
-		var on_window_created := func(window_id: int):
-			logger.debug("Window created:", window_id)
-			self.check_running.call_deferred()
-		_xwayland_game.window_created.connect(on_window_created)
+	# Listen for signals from the Gamescope XWayland displays and follow them
+	# when gamescope assigns a different display to one of the types
+	_update_ogui_window_id()
+	_connect_xwayland_signals()
+	gamescope.xwayland_type_updated.connect(_on_xwayland_type_updated)
 
 	# Whenever the in-game state is entered, set the gamepad profile
 	var on_game_state_entered := func(_from: State):
@@ -169,6 +105,124 @@ func _init() -> void:
 	set_gamepad_profile("")
 
 
+# Re-fetch the XWayland display of the given type after gamescope assigned a
+# different display to it, moving the signal connections to the new display
+func _on_xwayland_type_updated(kind: int, display_name: String) -> void:
+	logger.debug("XWayland type", kind, "is now display", display_name)
+	_disconnect_xwayland_signals()
+	match kind:
+		GamescopeInstance.XWAYLAND_TYPE_PRIMARY:
+			_xwayland_primary = gamescope.get_xwayland(kind)
+		GamescopeInstance.XWAYLAND_TYPE_OGUI:
+			_xwayland_ogui = gamescope.get_xwayland(kind)
+			_update_ogui_window_id()
+		GamescopeInstance.XWAYLAND_TYPE_GAME:
+			_xwayland_game = gamescope.get_xwayland(kind)
+	_connect_xwayland_signals()
+	self.check_running.call_deferred()
+
+
+# Get the window ID of OpenGamepadUI
+func _update_ogui_window_id() -> void:
+	_ogui_window_id = 0
+	if not _xwayland_ogui:
+		return
+	var ogui_windows := _xwayland_ogui.get_windows_for_pid(PID)
+	if not ogui_windows.is_empty():
+		_ogui_window_id = ogui_windows[0]
+
+
+# Connect to the signals of the current XWayland displays
+func _connect_xwayland_signals() -> void:
+	# Listen for signals from the primary Gamescope XWayland
+	if _xwayland_primary:
+		if not _xwayland_primary.focused_window_updated.is_connected(_on_focus_changed):
+			_xwayland_primary.focused_window_updated.connect(_on_focus_changed)
+		if not _xwayland_primary.focused_app_updated.is_connected(_on_focused_app_changed):
+			_xwayland_primary.focused_app_updated.connect(_on_focused_app_changed)
+		if not _xwayland_primary.focusable_apps_updated.is_connected(_on_focusable_apps_changed):
+			_xwayland_primary.focusable_apps_updated.connect(_on_focusable_apps_changed)
+
+	# Listen for signals from the secondary Gamescope XWayland
+	if _xwayland_game:
+		if not _xwayland_game.window_created.is_connected(_on_window_created):
+			_xwayland_game.window_created.connect(_on_window_created)
+
+
+# Disconnect from the signals of the current XWayland displays
+func _disconnect_xwayland_signals() -> void:
+	if _xwayland_primary:
+		if _xwayland_primary.focused_window_updated.is_connected(_on_focus_changed):
+			_xwayland_primary.focused_window_updated.disconnect(_on_focus_changed)
+		if _xwayland_primary.focused_app_updated.is_connected(_on_focused_app_changed):
+			_xwayland_primary.focused_app_updated.disconnect(_on_focused_app_changed)
+		if _xwayland_primary.focusable_apps_updated.is_connected(_on_focusable_apps_changed):
+			_xwayland_primary.focusable_apps_updated.disconnect(_on_focusable_apps_changed)
+	if _xwayland_game:
+		if _xwayland_game.window_created.is_connected(_on_window_created):
+			_xwayland_game.window_created.disconnect(_on_window_created)
+
+
+# Debug print when the focused window changes
+func _on_focus_changed(from: int, to: int) -> void:
+	if from == to:
+		return
+	logger.info("Window focus changed from " + str(from) + " to: " + str(to))
+	self.check_running.call_deferred()
+
+
+# When focused app changes, update the current app and gamepad profile
+func _on_focused_app_changed(_from: int, to: int) -> void:
+	if _focused_app_id == to:
+		return
+	logger.debug("Focused app changed from " + str(_focused_app_id) + " to " + str(to))
+	_focused_app_id = to
+
+	# If OGUI was focused, set the global gamepad profile
+	if to in [gamescope.OVERLAY_GAME_ID, 0, 1]:
+		set_gamepad_profile("")
+		return
+
+	# Find the running app for the given app id
+	var last_app := self._current_app
+	var detected_app: RunningApp
+	for app in _running:
+		if app.app_id == to:
+			detected_app = app
+
+	# If the running app was not launched by OpenGamepadUI, then detect it.
+	if not detected_app:
+		detected_app = _detect_running_app(to)
+	self._current_app = detected_app
+
+	logger.debug("Last app: " + str(last_app) + " current_app: " + str(self._current_app))
+	app_switched.emit(last_app, self._current_app)
+
+	# If the app has a gamepad profile, set it
+	if self._current_app:
+		set_app_gamepad_profile(self._current_app)
+
+
+# Listen for when focusable apps change
+func _on_focusable_apps_changed(from: PackedInt64Array, to: PackedInt64Array) -> void:
+	if from == to:
+		return
+	logger.debug("Focusable apps changed from", from, "to", to)
+	self.check_running.call_deferred()
+	# If focusable apps has changed and the currently focused app no longer exists,
+	# remove the manual focus
+	var baselayer_app := _xwayland_primary.baselayer_app
+	to.append(_xwayland_primary.focused_app)
+	if baselayer_app > 0 and not baselayer_app in to:
+		_xwayland_primary.remove_baselayer_app()
+
+
+# Listen for window created/destroyed events
+func _on_window_created(window_id: int) -> void:
+	logger.debug("Window created:", window_id)
+	self.check_running.call_deferred()
+
+
 # Loads persistent data like recent games launched, etc.
 func _load_persist_data():
 	# Create the data directory if it doesn't exist
@@ -286,6 +340,10 @@ func _launch(app: LibraryLaunchItem) -> RunningApp:
 	# Launch the application process
 	var running_app := RunningApp.spawn(app, env, exec, command)
 	logger.info("Launched with PID: {0}".format([running_app.pid]))
+
+	# Group windows created by the process or its children with the app
+	if running_app.pid > 0:
+		gamescope.get_app_tracker().track_launch(running_app.app_id, running_app.pid)
 	
 	return running_app
 
@@ -4,13 +4,21 @@ class_name BluetoothManager
 
 ## BluetoothManager interfaces with the bluetooth system
 ##
-## This node is used to drive the bluetooth instance forward by calling its
-## 'process()' method every frame to dispatch signals.
+## This node is used to drive the bluetooth instance forward by registering it
+## with the [ResourceRegistry] to dispatch signals.
 
 @export var instance: BluezInstance = load("res://core/systems/bluetooth/bluetooth_manager.tres")
+var registry := load("res://core/systems/resource/resource_registry.tres") as ResourceRegistry
 
 
-func _process(_delta: float) -> void:
+func _ready() -> void:
 	if not instance:
 		return
-	instance.process()
+	# Dispatch signals on frames where the instance has events waiting
+	registry.register_on_event(instance)
+
+
+func _exit_tree() -> void:
+	if not instance:
+		return
+	registry.unregister(instance)
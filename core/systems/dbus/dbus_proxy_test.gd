@@ -0,0 +1,107 @@
+extends GutTest
+
+var proxy: DBusProxy
+
+
+func before_each() -> void:
+	# Ensure the [ResourceProcessor] node is added to the scene tree
+	var resource_processor := ResourceProcessor.new()
+	resource_processor.registry = load("res://core/systems/resource/resource_registry.tres") as ResourceRegistry
+	add_child_autoqfree(resource_processor)
+
+	proxy = DBusProxy.create("org.freedesktop.DBus", "/org/freedesktop/DBus", "org.freedesktop.DBus")
+
+
+func test_call_method() -> void:
+	if not proxy.is_running():
+		gut.p("DBus is not running. Skipping tests.")
+		return
+
+	var call_id := proxy.call_method("NameHasOwner", ["org.freedesktop.DBus"], "s")
+	assert_ne(call_id, -1, "should start the method call")
+	var result = await proxy.call_completed
+	gut.p("Result: " + str(result))
+	assert_eq(result[0], call_id, "should complete the same call")
+	assert_true(result[1], "should return that the bus name has an owner")
+
+
+func test_call_method_failure() -> void:
+	if not proxy.is_running():
+		gut.p("DBus is not running. Skipping tests.")
+		return
+
+	var call_id := proxy.call_method("IDontExist", [], "")
+	var result = await proxy.call_failed
+	gut.p("Error: " + str(result[1]))
+	assert_eq(result[0], call_id, "should fail the same call")
+	assert_false(result[1].is_empty(), "should return an error message")
+
+
+func test_call_method_invalid_arguments() -> void:
+	# Arguments that do not match the signature should be rejected before the call
+	assert_eq(proxy.call_method("NameHasOwner", ["org.freedesktop.DBus"], "u"), -1, "should reject a mismatched type")
+	assert_eq(proxy.call_method("NameHasOwner", [], "s"), -1, "should reject a missing argument")
+	assert_eq(proxy.call_method("NameHasOwner", ["org.freedesktop.DBus"], "a{"), -1, "should reject an invalid signature")
+	assert_eq(proxy.call_method("GetConnectionUnixUser", [1], "s"), -1, "should reject an integer for a string")
+
+
+func test_call_method_blocking() -> void:
+	if not proxy.is_running():
+		gut.p("DBus is not running. Skipping tests.")
+		return
+
+	var names = proxy.call_method_blocking("ListNames", [], "")
+	gut.p("Names: " + str(names))
+	assert_true(names is Array, "should return a list of names")
+	assert_has(names, "org.freedesktop.DBus", "should include the bus itself")
+
+
+func test_call_method_container_arguments() -> void:
+	if not proxy.is_running():
+		gut.p("DBus is not running. Skipping tests.")
+		return
+
+	# UpdateActivationEnvironment takes a dictionary of strings. The message bus
+	# may refuse the call, but the arguments should still be converted.
+	var call_id := proxy.call_method("UpdateActivationEnvironment", [{}], "a{ss}")
+	assert_ne(call_id, -1, "should convert an empty dictionary")
+	assert_eq(proxy.call_method("UpdateActivationEnvironment", [{"FOO": 1}], "a{ss}"), -1, "should reject a dictionary with mismatched values")
+	assert_eq(proxy.call_method("UpdateActivationEnvironment", [["FOO"]], "a{ss}"), -1, "should reject an array for a dictionary")
+
+
+func test_get_property() -> void:
+	if not proxy.is_running():
+		gut.p("DBus is not running. Skipping tests.")
+		return
+
+	var features = proxy.get_property("Features")
+	gut.p("Features: " + str(features))
+	assert_true(features is Array, "should return the list of features")
+
+	var call_id := proxy.get_property_async("Features")
+	var result = await proxy.call_completed
+	assert_eq(result[0], call_id, "should complete the same call")
+	assert_eq(result[1], features, "should return the same value")
+
+
+func test_subscribe() -> void:
+	if not proxy.is_running():
+		gut.p("DBus is not running. Skipping tests.")
+		return
+
+	assert_eq(proxy.subscribe("NameOwnerChanged"), OK, "should subscribe to signal")
+	assert_has(proxy.get_subscriptions(), "NameOwnerChanged", "should be subscribed")
+	proxy.unsubscribe("NameOwnerChanged")
+	assert_eq(proxy.get_subscriptions().size(), 0, "should be unsubscribed")
+
+
+func test_session_bus() -> void:
+	var session := DBusProxy.create_session("org.freedesktop.DBus", "/org/freedesktop/DBus", "org.freedesktop.DBus")
+	assert_eq(session.get_bus(), DBusProxy.BUS_SESSION, "should use the session bus")
+	assert_eq(proxy.get_bus(), DBusProxy.BUS_SYSTEM, "should use the system bus by default")
+	if not session.is_running():
+		gut.p("DBus session bus is not running. Skipping tests.")
+		return
+
+	var names = session.call_method_blocking("ListNames", [], "")
+	assert_has(names, "org.freedesktop.DBus", "should include the bus itself")
@@ -3,9 +3,17 @@ class_name DiskManager
 
 
 @export var instance: UDisks2Instance = load("res://core/systems/disks/disk_manager.tres") as UDisks2Instance
+var registry := load("res://core/systems/resource/resource_registry.tres") as ResourceRegistry
 
 
-func _process(_delta: float) -> void:
+func _ready() -> void:
 	if not instance:
 		return
-	instance.process()
+	# Dispatch signals on frames where the instance has events waiting
+	registry.register_on_event(instance)
+
+
+func _exit_tree() -> void:
+	if not instance:
+		return
+	registry.unregister(instance)
@@ -5,9 +5,10 @@ class_name Gamescope
 ## Manages gamescope.
 ##
 ## The [Gamescope] class is responsible for loading a [GamescopeInstance] and
-## calling its 'process()' method each frame.
+## registering it with the [ResourceRegistry] to dispatch its signals.
 
 @export var instance: GamescopeInstance = load("res://core/systems/gamescope/gamescope.tres") as GamescopeInstance
+var registry := load("res://core/systems/resource/resource_registry.tres") as ResourceRegistry
 
 # Keep a reference to xwayland instances so they are not cleaned up automatically
 var _xwaylands: Array[GamescopeXWayland]
@@ -15,12 +16,24 @@ var logger := Log.get_logger("Gamescope")
 
 
 func _ready() -> void:
+	if not instance:
+		return
+	# Dispatch signals on frames where the instance has events waiting
+	registry.register_on_event(instance)
+
 	_xwaylands = instance.get_xwaylands()
 	if _xwaylands.is_empty():
 		logger.warn("Gamescope not detected. Unexpected behavior expected.")
+	instance.xwayland_added.connect(_on_xwaylands_changed)
+	instance.xwayland_removed.connect(_on_xwaylands_changed)
+
+
+func _on_xwaylands_changed(name: String) -> void:
+	logger.debug("XWayland displays changed: " + name)
+	_xwaylands = instance.get_xwaylands()
 
 
-func _process(_delta: float) -> void:
+func _exit_tree() -> void:
 	if not instance:
 		return
-	instance.process()
+	registry.unregister(instance)
@@ -12,6 +12,7 @@ const DEFAULT_GLOBAL_PROFILE := "user://data/gamepad/profiles/global_default.jso
 const PROFILES_DIR := "user://data/gamepad/profiles"
 
 @export var instance: InputPlumberInstance = load("res://core/systems/input/input_plumber.tres")
+var registry := load("res://core/systems/resource/resource_registry.tres") as ResourceRegistry
 
 # Keep a reference to dbus devices so they are not cleaned up automatically
 var _dbus_devices := {}
@@ -32,6 +33,11 @@ func _init() -> void:
 
 
 func _ready() -> void:
+	if not instance:
+		return
+	# Dispatch signals on frames where the instance has events waiting
+	registry.register_on_event(instance)
+
 	# Add listeners for any new devices
 	var on_device_added := func(device: CompositeDevice):
 		var dbus_devices := device.dbus_devices
@@ -55,10 +61,10 @@ func _ready() -> void:
 		on_device_added.call(device)
 
 
-func _process(_delta: float) -> void:
+func _exit_tree() -> void:
 	if not instance:
 		return
-	instance.process()
+	registry.unregister(instance)
 
 
  ## Load the given profile on the composite device, optionally specifying a profile
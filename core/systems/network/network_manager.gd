@@ -5,7 +5,7 @@ class_name NetworkManager
 ## Manages NetworkManager.
 ##
 ## The [NetworkManager] class is responsible for loading a [NetworkManagerInstance] and
-## calling its 'process()' method each frame.
+## registering it with the [ResourceRegistry] to dispatch its signals.
 
 const bar_0 := preload("res://assets/ui/icons/wifi-none.svg")
 const bar_1 := preload("res://assets/ui/icons/wifi-low.svg")
@@ -15,6 +15,7 @@ const no_network := preload("res://assets/ui/icons/tabler--network-off.svg")
 const ethernet := preload("res://assets/ui/icons/mdi--ethernet.svg")
 
 @export var instance: NetworkManagerInstance = load("res://core/systems/network/network_manager.tres") as NetworkManagerInstance
+var registry := load("res://core/systems/resource/resource_registry.tres") as ResourceRegistry
 
 
 ## Returns the texture reflecting the given wifi strength
@@ -28,7 +29,14 @@ static func get_strength_texture(strength: int) -> Texture2D:
 	return bar_0
 
 
-func _process(_delta: float) -> void:
+func _ready() -> void:
 	if not instance:
 		return
-	instance.process()
+	# Dispatch signals on frames where the instance has events waiting
+	registry.register_on_event(instance)
+
+
+func _exit_tree() -> void:
+	if not instance:
+		return
+	registry.unregister(instance)
@@ -38,7 +38,12 @@ func test_connect() -> void:
 		pass_test("No valid wireless networks found, skipping")
 		return
 	
-	var connection := access_point.connect(device, PSK)
+	var result := access_point.connect(device, PSK)
+	await result.completed
+	if not result.is_ok():
+		pass_test("Unable to connect: " + result.get_error())
+		return
+	var connection := result.get_result() as NetworkActiveConnection
 	
 	for i in range(50):
 		var state := connection.state
@@ -8,6 +8,7 @@ class_name PowerStation
 ## DBus to control CPU and GPU performance.
 
 @export var instance: PowerStationInstance = load("res://core/systems/performance/power_station.tres") as PowerStationInstance
+var registry := load("res://core/systems/resource/resource_registry.tres") as ResourceRegistry
 
 # Keep a reference to instances so they are not cleaned up automatically
 var _cpu: Cpu
@@ -16,11 +17,16 @@ var logger := Log.get_logger("PowerStation")
 
 
 func _ready() -> void:
+	if not instance:
+		return
+	# Dispatch signals on frames where the instance has events waiting
+	registry.register_on_event(instance)
+
 	_cpu = instance.get_cpu()
 	_gpu = instance.get_gpu()
 
 
-func _process(_delta: float) -> void:
+func _exit_tree() -> void:
 	if not instance:
 		return
-	instance.process()
+	registry.unregister(instance)
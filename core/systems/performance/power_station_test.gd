@@ -68,6 +68,11 @@ func test_gpu() -> void:
 	powerstation.instance = load("res://core/systems/performance/power_station.tres") as PowerStationInstance
 	add_child_autoqfree(powerstation)
 
+	# Results of async setters are delivered by the [ResourceProcessor]
+	var resource_processor := ResourceProcessor.new()
+	resource_processor.registry = load("res://core/systems/resource/resource_registry.tres") as ResourceRegistry
+	add_child_autoqfree(resource_processor)
+
 	if not powerstation.instance.is_running():
 		pass_test("PowerStation is not running, skipping")
 		return
@@ -84,24 +89,35 @@ func test_gpu() -> void:
 		
 		# TDP Control
 		var tdp := card.tdp
-		card.tdp = 10.0
+		await _set_card_property(card, "tdp", 10.0)
 		assert_eq(card.tdp, 10.0, "TDP value should have changed")
-		card.tdp = tdp
+		await _set_card_property(card, "tdp", tdp)
 		
 		# TDP Boost
 		var boost := card.boost
-		card.boost = 2.0
+		await _set_card_property(card, "boost", 2.0)
 		assert_eq(card.boost, 2.0, "Boost should have changed")
-		card.boost = boost
+		await _set_card_property(card, "boost", boost)
 		
 		# Power profile
 		var profile := card.power_profile
-		card.power_profile = "max-performance"
+		await _set_card_property(card, "power_profile", "max-performance")
 		assert_eq(card.power_profile, "max-performance")
-		card.power_profile = profile
+		await _set_card_property(card, "power_profile", profile)
 
 		# GPU temperature control
 		var temp := card.thermal_throttle_limit_c
-		card.thermal_throttle_limit_c = 97.0
+		await _set_card_property(card, "thermal_throttle_limit_c", 97.0)
 		assert_eq(card.thermal_throttle_limit_c, 97.0)
-		card.thermal_throttle_limit_c = temp
+		await _set_card_property(card, "thermal_throttle_limit_c", temp)
+
+
+## Sets the given property on the card and waits until the new value is reported
+func _set_card_property(card: GpuCard, property: String, value: Variant) -> void:
+	var result := card.call("set_" + property + "_async", value) as AsyncResult
+	await result.completed
+	assert_true(result.is_ok(), "should set " + property + ": " + result.get_error())
+	for i in range(10):
+		if card.get(property) == value:
+			return
+		await wait_frames(1, "wait for change")
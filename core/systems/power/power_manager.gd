@@ -5,9 +5,10 @@ class_name PowerManager
 ## Manages power settings.
 ##
 ## The [PowerManager] class is responsible for loading a [UPowerInstance] and
-## calling its 'process()' method each frame.
+## registering it with the [ResourceRegistry] to dispatch its signals.
 
 @export var instance: UPowerInstance = load("res://core/systems/power/power_manager.tres") as UPowerInstance
+var registry := load("res://core/systems/resource/resource_registry.tres") as ResourceRegistry
 
 # Keep a reference to device instances so they are not cleaned up automatically
 var _devices: Array[UPowerDevice]
@@ -15,13 +16,18 @@ var logger := Log.get_logger("PowerManager")
 
 
 func _ready() -> void:
+	if not instance:
+		return
+	# Dispatch signals on frames where the instance has events waiting
+	registry.register_on_event(instance)
+
 	var display_device := instance.get_display_device()
 	_devices.push_back(display_device)
 	if _devices.is_empty():
 		logger.warn("UPower not detected.")
 
 
-func _process(_delta: float) -> void:
+func _exit_tree() -> void:
 	if not instance:
 		return
-	instance.process()
+	registry.unregister(instance)
@@ -37,7 +37,25 @@ var overlay_window_id := 0
 var logger = Log.get_logger("Main", Log.LEVEL.INFO)
 
 func _init() -> void:
-	# Discover the window id of OpenGamepadUI
+	_discover_overlay_window()
+	gamescope.xwayland_type_updated.connect(_on_xwayland_type_updated)
+
+
+# Re-fetch the XWayland displays after gamescope assigned a different display to
+# one of the types and set ourselves up as the overlay again
+func _on_xwayland_type_updated(kind: int, display_name: String) -> void:
+	logger.debug("XWayland type", kind, "is now display", display_name)
+	if _xwayland_game and _xwayland_game.window_created.is_connected(_on_game_window_created):
+		_xwayland_game.window_created.disconnect(_on_game_window_created)
+	_xwayland_primary = gamescope.get_xwayland(gamescope.XWAYLAND_TYPE_PRIMARY)
+	_xwayland_ogui = gamescope.get_xwayland(gamescope.XWAYLAND_TYPE_OGUI)
+	_xwayland_game = gamescope.get_xwayland(gamescope.XWAYLAND_TYPE_GAME)
+	_discover_overlay_window()
+
+
+# Discover the window id of OpenGamepadUI and configure it as the overlay
+func _discover_overlay_window() -> void:
+	overlay_window_id = 0
 	if _xwayland_ogui:
 		var ogui_windows := _xwayland_ogui.get_windows_for_pid(PID)
 		if not ogui_windows.is_empty():
@@ -52,7 +70,7 @@ func _init() -> void:
 
 # Lets us run as an overlay in gamescope
 func _setup(window_id: int) -> void:
-	if window_id <= 0:
+	if window_id <= 0 or not _xwayland_primary:
 		logger.error("Unable to configure gamescope atoms")
 		return
 	# Pretend to be Steam
@@ -64,26 +82,30 @@ func _setup(window_id: int) -> void:
 		logger.error("Unable to set STEAM_INPUT_FOCUS atom!")
 
 	# Override reserved app ids for any newly created windows
-	# Listen for window created/destroyed events
-	var on_window_created := func(window_id: int):
-		logger.debug("Window created:", window_id)
-		var try := 0
-		while try < 10:
-			if _xwayland_game.has_app_id(window_id):
-				break
-			try += 1
-			await get_tree().create_timer(0.2).timeout # wait a beat
-		var app_id := _xwayland_game.get_app_id(window_id)
-		if app_id == GamescopeInstance.OVERLAY_GAME_ID:
-			# Find the current running app and use that app id
-			var running := launch_manager.get_running()
-			running.reverse()
-			for app in running:
-				_xwayland_game.set_app_id(window_id, app.app_id)
-				return
-			logger.warn("Unable to find a running app to tie Steam to")
-			_xwayland_game.set_app_id(window_id, 7769)
-	_xwayland_game.window_created.connect(on_window_created)
+	if _xwayland_game and not _xwayland_game.window_created.is_connected(_on_game_window_created):
+		_xwayland_game.window_created.connect(_on_game_window_created)
+
+
+# Listen for window created/destroyed events
+func _on_game_window_created(window_id: int) -> void:
+	logger.debug("Window created:", window_id)
+	var xwayland_game := _xwayland_game
+	var try := 0
+	while try < 10:
+		if xwayland_game.has_app_id(window_id):
+			break
+		try += 1
+		await get_tree().create_timer(0.2).timeout # wait a beat
+	var app_id := xwayland_game.get_app_id(window_id)
+	if app_id == GamescopeInstance.OVERLAY_GAME_ID:
+		# Find the current running app and use that app id
+		var running := launch_manager.get_running()
+		running.reverse()
+		for app in running:
+			xwayland_game.set_app_id(window_id, app.app_id)
+			return
+		logger.warn("Unable to find a running app to tie Steam to")
+		xwayland_game.set_app_id(window_id, 7769)
 
 
 # Called when the node enters the scene tree for the first time.
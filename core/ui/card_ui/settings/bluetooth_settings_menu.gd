@@ -258,9 +258,19 @@ func _on_item_activated() -> void:
 	# Disconnect if already connected
 	if device.connected:
 		selected.set_text(4, "Disconnecting")
-		device.disconnect_from()
+		_show_result(selected, device.disconnect_from())
 		return
 	
 	# Try connecting to the device
 	selected.set_text(4, "Connecting")
-	device.connect_to()
+	_show_result(selected, device.connect_to())
+
+
+## Shows the error of the given connection result on the tree item if it fails
+func _show_result(item: TreeItem, result: AsyncResult) -> void:
+	await result.completed
+	if result.is_ok():
+		return
+	logger.warn("Bluetooth operation failed: " + result.get_error())
+	if is_instance_valid(item):
+		item.set_text(4, result.get_error())
@@ -65,14 +65,8 @@ var logger := Log.get_logger("Main", Log.LEVEL.INFO)
 
 ## Sets up overlay mode.
 func _init():
-	# Discover the OpenGamepadUI window ID
-	if xwayland_ogui:
-		var ogui_windows := xwayland_ogui.get_windows_for_pid(PID)
-		if not ogui_windows.is_empty():
-			overlay_window_id = ogui_windows[0]
-	if overlay_window_id <= 0:
-		logger.error("Unable to detect Window ID. Overlay is not going to work!")
-	logger.info("Found primary window id: {0}".format([overlay_window_id]))
+	_discover_overlay_window()
+	gamescope.xwayland_type_updated.connect(_on_xwayland_type_updated)
 
 	# Ensure LaunchManager doesn't override our custom overlay management l
 	launch_manager.should_manage_overlay = false
@@ -86,6 +80,27 @@ func _init():
 	add_child(plugin_manager)
 
 
+## Re-fetches the XWayland displays after gamescope assigned a different display
+## to one of the types.
+func _on_xwayland_type_updated(kind: int, display_name: String) -> void:
+	logger.debug("XWayland type", kind, "is now display", display_name)
+	xwayland_primary = gamescope.get_xwayland(gamescope.XWAYLAND_TYPE_PRIMARY)
+	xwayland_ogui = gamescope.get_xwayland(gamescope.XWAYLAND_TYPE_OGUI)
+	_discover_overlay_window()
+
+
+## Discovers the OpenGamepadUI window ID.
+func _discover_overlay_window() -> void:
+	overlay_window_id = 0
+	if xwayland_ogui:
+		var ogui_windows := xwayland_ogui.get_windows_for_pid(PID)
+		if not ogui_windows.is_empty():
+			overlay_window_id = ogui_windows[0]
+	if overlay_window_id <= 0:
+		logger.error("Unable to detect Window ID. Overlay is not going to work!")
+	logger.info("Found primary window id: {0}".format([overlay_window_id]))
+
+
 ## Starts the --overlay-mode session.
 func _ready() -> void:
 	# Workaround old versions that don't pass launch args via update pack
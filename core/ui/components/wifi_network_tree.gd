@@ -69,11 +69,17 @@ func network_connect(password: String, ssid: String) -> void:
 	var access_point := valid_access_points[0] as NetworkAccessPoint
 	
 	# Try to connect to the access point
-	access_point.connect(device, password)
+	var result := access_point.connect(device, password)
 	
 	# Update the tree item's status
 	var item := get_selected()
 	item.set_text(4, "Connecting")
+	await result.completed
+	if not result.is_ok():
+		logger.warn("Failed to connect to", ssid + ":", result.get_error())
+		item.set_text(4, result.get_error())
+		connecting = false
+		return
 
 	# Wait for the connection to be established
 	for i in range(60):
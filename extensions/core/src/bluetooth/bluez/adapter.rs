@@ -1,5 +1,3 @@
-use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
-
 use futures_util::StreamExt;
 use godot::obj::WithBaseField;
 use godot::prelude::*;
@@ -8,11 +6,16 @@ use godot::classes::{Resource, ResourceLoader};
 use zvariant::ObjectPath;
 
 use crate::dbus::bluez::adapter1::{Adapter1Proxy, Adapter1ProxyBlocking};
-use crate::dbus::RunError;
-use crate::{get_dbus_system, get_dbus_system_blocking, RUNTIME};
+use crate::dbus::property_cache::PropertyCache;
+use crate::dbus::{impl_last_error, GodotVariant, LastError, RunError};
+use crate::{
+    get_dbus_system,
+    resource::dispatcher::{channel, Receiver, Sender, TryRecvError},
+    RUNTIME,
+};
 
 use super::device::BluetoothDevice;
-use super::BLUEZ_BUS;
+use super::{BLUEZ_BUS, WAKEUP};
 
 /// Signals that can be emitted
 #[derive(Debug)]
@@ -29,7 +32,8 @@ enum Signal {
 pub struct BluetoothAdapter {
     base: Base<Resource>,
     rx: Receiver<Signal>,
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<Adapter1ProxyBlocking<'static>>,
+    last_error: LastError,
 
     #[allow(dead_code)]
     #[var(get = get_dbus_path)]
@@ -90,6 +94,8 @@ pub struct BluetoothAdapter {
     version: u8,
 }
 
+impl_last_error!(BluetoothAdapter);
+
 #[godot_api]
 impl BluetoothAdapter {
     #[signal]
@@ -107,12 +113,17 @@ impl BluetoothAdapter {
     #[signal]
     fn power_state_changed(value: GString);
 
+    /// Emitted when a property of the adapter changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Create a new [BluetoothAdapter] with the given DBus path
     pub fn from_path(path: GString) -> Gd<Self> {
         // Create a channel to communicate with the signals task
         log::debug!("BluetoothAdapter created with path: {path}");
-        let (tx, rx) = channel();
-        let dbus_path = path.clone().into();
+        let (tx, rx) = channel(&WAKEUP);
+        let dbus_path: String = path.clone().into();
+        let cache = PropertyCache::new(dbus_path.as_str(), &WAKEUP);
 
         // Spawn a task using the shared tokio runtime to listen for signals
         RUNTIME.spawn(async move {
@@ -122,14 +133,12 @@ impl BluetoothAdapter {
         });
 
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
-
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
                 rx,
-                conn,
+                cache,
+                last_error: Default::default(),
                 dbus_path: path,
                 address: Default::default(),
                 address_type: Default::default(),
@@ -153,17 +162,16 @@ impl BluetoothAdapter {
         })
     }
 
-    /// Return a proxy instance to the adapter
+    /// Return a proxy instance to the adapter whose property getters read from
+    /// the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<Adapter1ProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            let path: String = self.dbus_path.clone().into();
-            Adapter1ProxyBlocking::builder(conn)
-                .path(path)
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
+    }
+
+    /// Return a proxy instance to call methods on the adapter
+    fn get_call_proxy(&self) -> Option<Adapter1ProxyBlocking> {
+        let result = self.cache.call_proxy();
+        self.last_error.check(self, result.map(Some))
     }
 
     /// Get or create a [BluetoothAdapter] with the given DBus path. If an instance
@@ -202,7 +210,7 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.address().unwrap_or_default().into()
+        self.last_error.check(self, proxy.address()).into()
     }
 
     #[func]
@@ -210,7 +218,7 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.address_type().unwrap_or_default().into()
+        self.last_error.check(self, proxy.address_type()).into()
     }
 
     #[func]
@@ -218,17 +226,16 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.alias().unwrap_or_default().into()
+        self.last_error.check(self, proxy.alias()).into()
     }
 
     #[func]
     pub fn set_alias(&self, value: GString) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
-        proxy
-            .set_alias(value.to_string().as_str())
-            .unwrap_or_default()
+        self.last_error
+            .check(self, proxy.set_alias(value.to_string().as_str()))
     }
 
     #[func]
@@ -236,7 +243,7 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.class().unwrap_or_default()
+        self.last_error.check(self, proxy.class())
     }
 
     #[func]
@@ -244,15 +251,15 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.discoverable().unwrap_or_default()
+        self.last_error.check(self, proxy.discoverable())
     }
 
     #[func]
     pub fn set_discoverable(&self, value: bool) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
-        proxy.set_discoverable(value).unwrap_or_default()
+        self.last_error.check(self, proxy.set_discoverable(value))
     }
 
     #[func]
@@ -260,15 +267,16 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.discoverable_timeout().unwrap_or_default()
+        self.last_error.check(self, proxy.discoverable_timeout())
     }
 
     #[func]
     pub fn set_discoverable_timeout(&self, value: u32) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
-        proxy.set_discoverable_timeout(value).unwrap_or_default()
+        self.last_error
+            .check(self, proxy.set_discoverable_timeout(value))
     }
 
     #[func]
@@ -276,7 +284,7 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.discovering().unwrap_or_default()
+        self.last_error.check(self, proxy.discovering())
     }
 
     #[func]
@@ -284,9 +292,9 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        let values: Vec<GString> = proxy
-            .experimental_features()
-            .unwrap_or_default()
+        let values: Vec<GString> = self
+            .last_error
+            .check(self, proxy.experimental_features())
             .into_iter()
             .map(|v| v.to_godot())
             .collect();
@@ -298,7 +306,7 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.manufacturer().unwrap_or_default()
+        self.last_error.check(self, proxy.manufacturer())
     }
 
     #[func]
@@ -306,7 +314,7 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.modalias().unwrap_or_default().into()
+        self.last_error.check(self, proxy.modalias()).into()
     }
 
     #[func]
@@ -314,7 +322,7 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.name().unwrap_or_default().into()
+        self.last_error.check(self, proxy.name()).into()
     }
 
     #[func]
@@ -322,15 +330,15 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.pairable().unwrap_or_default()
+        self.last_error.check(self, proxy.pairable())
     }
 
     #[func]
     pub fn set_pairable(&self, value: bool) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
-        proxy.set_pairable(value).unwrap_or_default()
+        self.last_error.check(self, proxy.set_pairable(value))
     }
 
     #[func]
@@ -338,15 +346,16 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.pairable_timeout().unwrap_or_default()
+        self.last_error.check(self, proxy.pairable_timeout())
     }
 
     #[func]
     pub fn set_pairable_timeout(&self, value: u32) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
-        proxy.set_pairable_timeout(value).unwrap_or_default()
+        self.last_error
+            .check(self, proxy.set_pairable_timeout(value))
     }
 
     #[func]
@@ -354,7 +363,7 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.power_state().unwrap_or_default().into()
+        self.last_error.check(self, proxy.power_state()).into()
     }
 
     #[func]
@@ -362,15 +371,15 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.powered().unwrap_or_default()
+        self.last_error.check(self, proxy.powered())
     }
 
     #[func]
     pub fn set_powered(&self, value: bool) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
-        proxy.set_powered(value).unwrap_or_default()
+        self.last_error.check(self, proxy.set_powered(value))
     }
 
     #[func]
@@ -378,9 +387,9 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        let values: Vec<GString> = proxy
-            .roles()
-            .unwrap_or_default()
+        let values: Vec<GString> = self
+            .last_error
+            .check(self, proxy.roles())
             .into_iter()
             .map(|v| v.to_godot())
             .collect();
@@ -392,9 +401,9 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        let values: Vec<GString> = proxy
-            .uuids()
-            .unwrap_or_default()
+        let values: Vec<GString> = self
+            .last_error
+            .check(self, proxy.uuids())
             .into_iter()
             .map(|v| v.to_godot())
             .collect();
@@ -406,17 +415,17 @@ impl BluetoothAdapter {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.version().unwrap_or_default()
+        self.last_error.check(self, proxy.version())
     }
 
     #[func]
     pub fn get_discovery_filters(&self) -> PackedStringArray {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
-        let filters: Vec<GString> = proxy
-            .get_discovery_filters()
-            .unwrap_or_default()
+        let filters: Vec<GString> = self
+            .last_error
+            .check(self, proxy.get_discovery_filters())
             .into_iter()
             .map(|v| v.to_godot())
             .collect();
@@ -425,32 +434,39 @@ impl BluetoothAdapter {
 
     #[func]
     pub fn remove_device(&self, device: Gd<BluetoothDevice>) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return;
         };
         let path = device.bind().get_dbus_path().to_string();
         let path = ObjectPath::try_from(path).unwrap_or_default();
-        proxy.remove_device(&path).unwrap_or_default()
+        self.last_error.check(self, proxy.remove_device(&path))
     }
 
     #[func]
     pub fn start_discovery(&self) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return;
         };
-        proxy.start_discovery().unwrap_or_default()
+        self.last_error.check(self, proxy.start_discovery())
     }
 
     #[func]
     pub fn stop_discovery(&self) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return;
         };
-        proxy.stop_discovery().unwrap_or_default()
+        self.last_error.check(self, proxy.stop_discovery())
     }
 
     /// Dispatches signals
     pub fn process(&mut self) {
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+
         // Drain all messages from the channel to process them
         loop {
             let signal = match self.rx.try_recv() {
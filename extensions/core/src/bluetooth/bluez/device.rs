@@ -1,4 +1,4 @@
-use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::future::Future;
 
 use futures_util::StreamExt;
 use godot::prelude::*;
@@ -6,10 +6,16 @@ use godot::prelude::*;
 use godot::classes::{Resource, ResourceLoader};
 
 use crate::dbus::bluez::device1::{Device1Proxy, Device1ProxyBlocking};
-use crate::dbus::RunError;
-use crate::{get_dbus_system, get_dbus_system_blocking, RUNTIME};
+use crate::dbus::property_cache::PropertyCache;
+use crate::dbus::{impl_last_error, GodotVariant, LastError, RunError};
+use crate::resource::async_result::AsyncResult;
+use crate::{
+    get_dbus_system,
+    resource::dispatcher::{channel, Receiver, Sender, TryRecvError},
+    RUNTIME,
+};
 
-use super::BLUEZ_BUS;
+use super::{BLUEZ_BUS, WAKEUP};
 
 /// Signals that can be emitted
 #[derive(Debug)]
@@ -24,7 +30,8 @@ enum Signal {
 pub struct BluetoothDevice {
     base: Base<Resource>,
     rx: Receiver<Signal>,
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<Device1ProxyBlocking<'static>>,
+    last_error: LastError,
 
     #[allow(dead_code)]
     #[var(get = get_dbus_path)]
@@ -91,6 +98,8 @@ pub struct BluetoothDevice {
     wake_allowed: bool,
 }
 
+impl_last_error!(BluetoothDevice);
+
 #[godot_api]
 impl BluetoothDevice {
     #[signal]
@@ -102,12 +111,17 @@ impl BluetoothDevice {
     #[signal]
     fn paired_changed(value: bool);
 
+    /// Emitted when a property of the device changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Create a new [BluetoothDevice] with the given DBus path
     pub fn from_path(path: GString) -> Gd<Self> {
         // Create a channel to communicate with the signals task
         log::debug!("BluetoothDevice created with path: {path}");
-        let (tx, rx) = channel();
-        let dbus_path = path.clone().into();
+        let (tx, rx) = channel(&WAKEUP);
+        let dbus_path: String = path.clone().into();
+        let cache = PropertyCache::new(dbus_path.as_str(), &WAKEUP);
 
         // Spawn a task using the shared tokio runtime to listen for signals
         RUNTIME.spawn(async move {
@@ -117,14 +131,12 @@ impl BluetoothDevice {
         });
 
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
-
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
                 rx,
-                conn,
+                cache,
+                last_error: Default::default(),
                 dbus_path: path,
                 adapter: Default::default(),
                 address: Default::default(),
@@ -150,17 +162,27 @@ impl BluetoothDevice {
         })
     }
 
-    /// Return a proxy instance to the device
+    /// Return a proxy instance to the device whose property getters read from
+    /// the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<Device1ProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            let path: String = self.dbus_path.clone().into();
-            Device1ProxyBlocking::builder(conn)
-                .path(path)
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
+    }
+
+    /// Return a proxy instance to call methods on the device
+    fn get_call_proxy(&self) -> Option<Device1ProxyBlocking> {
+        let result = self.cache.call_proxy();
+        self.last_error.check(self, result.map(Some))
+    }
+
+    /// Call the given method on the device in the async runtime
+    fn call<F, Fut, T>(&self, method: F) -> Gd<AsyncResult>
+    where
+        F: FnOnce(Device1Proxy<'static>) -> Fut + Send + 'static,
+        Fut: Future<Output = zbus::Result<T>> + Send,
+        T: ToGodot + Send + 'static,
+    {
+        let proxy = self.cache.async_call_proxy();
+        AsyncResult::spawn(async move { method(proxy.await?).await })
     }
 
     /// Get or create a [BluetoothDevice] with the given DBus path. If an instance
@@ -194,54 +216,43 @@ impl BluetoothDevice {
         self.dbus_path.clone()
     }
 
+    /// Cancel a pairing operation that is in progress
     #[func]
-    pub fn cancel_pairing(&self) {
-        let Some(proxy) = self.get_proxy() else {
-            return Default::default();
-        };
-        proxy.cancel_pairing().unwrap_or_default()
+    pub fn cancel_pairing(&self) -> Gd<AsyncResult> {
+        self.call(|proxy| async move { proxy.cancel_pairing().await })
     }
 
+    /// Connect all profiles of the device that are marked as auto-connectable
     #[func]
-    pub fn connect_to(&self) {
-        let Some(proxy) = self.get_proxy() else {
-            return Default::default();
-        };
-        proxy.connect().unwrap_or_default()
+    pub fn connect_to(&self) -> Gd<AsyncResult> {
+        self.call(|proxy| async move { proxy.connect().await })
     }
 
+    /// Connect the profile with the given UUID
     #[func]
-    pub fn connect_to_profile(&self, uuid: GString) {
-        let Some(proxy) = self.get_proxy() else {
-            return Default::default();
-        };
+    pub fn connect_to_profile(&self, uuid: GString) -> Gd<AsyncResult> {
         let uuid = uuid.to_string();
-        proxy.connect_profile(uuid.as_str()).unwrap_or_default()
+        self.call(|proxy| async move { proxy.connect_profile(uuid.as_str()).await })
     }
 
+    /// Disconnect all connected profiles of the device
     #[func]
-    pub fn disconnect_from(&self) {
-        let Some(proxy) = self.get_proxy() else {
-            return Default::default();
-        };
-        proxy.disconnect().unwrap_or_default()
+    pub fn disconnect_from(&self) -> Gd<AsyncResult> {
+        self.call(|proxy| async move { proxy.disconnect().await })
     }
 
+    /// Disconnect the profile with the given UUID
     #[func]
-    pub fn disconnect_from_profile(&self, uuid: GString) {
-        let Some(proxy) = self.get_proxy() else {
-            return Default::default();
-        };
+    pub fn disconnect_from_profile(&self, uuid: GString) -> Gd<AsyncResult> {
         let uuid = uuid.to_string();
-        proxy.disconnect_profile(uuid.as_str()).unwrap_or_default()
+        self.call(|proxy| async move { proxy.disconnect_profile(uuid.as_str()).await })
     }
 
+    /// Pair with the device. The returned [AsyncResult] completes once pairing
+    /// has finished, which may require confirmation from the user.
     #[func]
-    pub fn pair(&self) {
-        let Some(proxy) = self.get_proxy() else {
-            return Default::default();
-        };
-        proxy.pair().unwrap_or_default()
+    pub fn pair(&self) -> Gd<AsyncResult> {
+        self.call(|proxy| async move { proxy.pair().await })
     }
 
     #[func]
@@ -249,15 +260,15 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.wake_allowed().unwrap_or_default()
+        self.last_error.check(self, proxy.wake_allowed())
     }
 
     #[func]
     pub fn set_wake_allowed(&self, allowed: bool) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
-        proxy.set_wake_allowed(allowed).unwrap_or_default()
+        self.last_error.check(self, proxy.set_wake_allowed(allowed))
     }
 
     #[func]
@@ -265,9 +276,9 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        let values: Vec<GString> = proxy
-            .uuids()
-            .unwrap_or_default()
+        let values: Vec<GString> = self
+            .last_error
+            .check(self, proxy.uuids())
             .into_iter()
             .map(|v| v.to_godot())
             .collect();
@@ -279,7 +290,7 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.tx_power().unwrap_or_default()
+        self.last_error.check(self, proxy.tx_power())
     }
 
     #[func]
@@ -287,15 +298,15 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.trusted().unwrap_or_default()
+        self.last_error.check(self, proxy.trusted())
     }
 
     #[func]
     pub fn set_trusted(&self, value: bool) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
-        proxy.set_trusted(value).unwrap_or_default()
+        self.last_error.check(self, proxy.set_trusted(value))
     }
 
     #[func]
@@ -303,7 +314,7 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.services_resolved().unwrap_or_default()
+        self.last_error.check(self, proxy.services_resolved())
     }
 
     #[func]
@@ -311,7 +322,7 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.rssi().unwrap_or_default()
+        self.last_error.check(self, proxy.rssi())
     }
 
     #[func]
@@ -319,7 +330,7 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.paired().unwrap_or_default()
+        self.last_error.check(self, proxy.paired())
     }
 
     #[func]
@@ -327,7 +338,7 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.name().unwrap_or_default().into()
+        self.last_error.check(self, proxy.name()).into()
     }
 
     #[func]
@@ -335,7 +346,7 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.modalias().unwrap_or_default().into()
+        self.last_error.check(self, proxy.modalias()).into()
     }
 
     #[func]
@@ -343,7 +354,7 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.legacy_pairing().unwrap_or_default()
+        self.last_error.check(self, proxy.legacy_pairing())
     }
 
     #[func]
@@ -351,7 +362,7 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.icon().unwrap_or_default().into()
+        self.last_error.check(self, proxy.icon()).into()
     }
 
     #[func]
@@ -359,7 +370,7 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.connected().unwrap_or_default()
+        self.last_error.check(self, proxy.connected())
     }
 
     #[func]
@@ -367,7 +378,7 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.class().unwrap_or_default()
+        self.last_error.check(self, proxy.class())
     }
 
     #[func]
@@ -375,7 +386,7 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.bonded().unwrap_or_default()
+        self.last_error.check(self, proxy.bonded())
     }
 
     #[func]
@@ -383,15 +394,15 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.blocked().unwrap_or_default()
+        self.last_error.check(self, proxy.blocked())
     }
 
     #[func]
     pub fn set_blocked(&self, value: bool) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
-        proxy.set_blocked(value).unwrap_or_default()
+        self.last_error.check(self, proxy.set_blocked(value))
     }
 
     #[func]
@@ -399,7 +410,7 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.appearance().unwrap_or_default()
+        self.last_error.check(self, proxy.appearance())
     }
 
     #[func]
@@ -407,17 +418,16 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.alias().unwrap_or_default().into()
+        self.last_error.check(self, proxy.alias()).into()
     }
 
     #[func]
     pub fn set_alias(&self, value: GString) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
-        proxy
-            .set_alias(value.to_string().as_str())
-            .unwrap_or_default()
+        self.last_error
+            .check(self, proxy.set_alias(value.to_string().as_str()))
     }
 
     #[func]
@@ -425,7 +435,7 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.address_type().unwrap_or_default().into()
+        self.last_error.check(self, proxy.address_type()).into()
     }
 
     #[func]
@@ -433,7 +443,7 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.address().unwrap_or_default().into()
+        self.last_error.check(self, proxy.address()).into()
     }
 
     #[func]
@@ -441,11 +451,21 @@ impl BluetoothDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.adapter().unwrap_or_default().to_string().into()
+        self.last_error
+            .check(self, proxy.adapter())
+            .to_string()
+            .into()
     }
 
     /// Dispatches signals
     pub fn process(&mut self) {
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+
         // Drain all messages from the channel to process them
         loop {
             let signal = match self.rx.try_recv() {
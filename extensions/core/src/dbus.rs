@@ -1,10 +1,19 @@
+use std::{cell::RefCell, fmt::Display};
+
+use godot::obj::{Inherits, WithBaseField};
 use godot::prelude::*;
-use zvariant::NoneValue;
+use zbus::DBusError as _;
+use zvariant::{NoneValue, ObjectPath, Signature, StructureBuilder};
 
 pub mod bluez;
+pub mod dbus_error;
+pub mod dbus_proxy;
 pub mod inputplumber;
 pub mod networkmanager;
+pub mod object_watcher;
 pub mod powerstation;
+pub mod property_cache;
+pub mod supervisor;
 pub mod udisks2;
 pub mod upower;
 
@@ -13,8 +22,37 @@ pub mod upower;
 pub enum RunError {
     Zbus(zbus::Error),
     ZbusFdo(zbus::fdo::Error),
+    /// No connection to the bus could be established
+    NotConnected,
+    /// The arguments of an operation were rejected before calling the service
+    InvalidArgument(String),
 }
 
+impl RunError {
+    /// Returns the kind of the error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            RunError::Zbus(e) => ErrorKind::from_zbus(e),
+            RunError::ZbusFdo(e) => ErrorKind::from_error_name(e.name().as_str()),
+            RunError::NotConnected => ErrorKind::ServiceNotRunning,
+            RunError::InvalidArgument(_) => ErrorKind::InvalidArgument,
+        }
+    }
+}
+
+impl Display for RunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunError::Zbus(e) => write!(f, "{e}"),
+            RunError::ZbusFdo(e) => write!(f, "{e}"),
+            RunError::NotConnected => write!(f, "Not connected to DBus"),
+            RunError::InvalidArgument(reason) => write!(f, "Invalid argument: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for RunError {}
+
 impl From<zbus::Error> for RunError {
     fn from(value: zbus::Error) -> Self {
         RunError::Zbus(value)
@@ -27,6 +65,170 @@ impl From<zbus::fdo::Error> for RunError {
     }
 }
 
+/// Kinds of errors that can be reported by DBus wrapper objects. The values are
+/// exposed to Godot as error codes (see [dbus_error::DBusError]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Any error that does not fit one of the other kinds
+    Failed = 1,
+    /// The service is not running or the object does not exist
+    ServiceNotRunning = 2,
+    /// The caller is not allowed to perform the operation
+    PermissionDenied = 3,
+    /// The arguments or the called method were rejected by the service
+    InvalidArgument = 4,
+    /// The service did not reply in time
+    Timeout = 5,
+}
+
+impl ErrorKind {
+    /// Returns the error code that is exposed to Godot
+    pub fn code(&self) -> i32 {
+        *self as i32
+    }
+
+    /// Returns the kind of the given zbus error
+    pub fn from_zbus(error: &zbus::Error) -> Self {
+        match error {
+            zbus::Error::MethodError(name, _, _) => Self::from_error_name(name.as_str()),
+            zbus::Error::FDO(e) => Self::from_error_name(e.name().as_str()),
+            zbus::Error::InputOutput(e) => match e.kind() {
+                std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused => {
+                    Self::ServiceNotRunning
+                }
+                std::io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+                std::io::ErrorKind::TimedOut => Self::Timeout,
+                _ => Self::Failed,
+            },
+            zbus::Error::Variant(_) | zbus::Error::InvalidField => Self::InvalidArgument,
+            _ => Self::Failed,
+        }
+    }
+
+    /// Returns the kind of the given DBus error name (e.g.
+    /// "org.freedesktop.DBus.Error.AccessDenied"). Services use their own
+    /// prefix for their errors (e.g. "org.bluez.Error.NotAuthorized"), so only
+    /// the last part of the name is compared.
+    pub fn from_error_name(name: &str) -> Self {
+        let name = name.rsplit('.').next().unwrap_or(name);
+        match name {
+            "ServiceUnknown" | "NameHasNoOwner" | "NoServer" | "Disconnected" => {
+                Self::ServiceNotRunning
+            }
+            "AccessDenied"
+            | "AuthFailed"
+            | "InteractiveAuthorizationRequired"
+            | "NotAuthorized"
+            | "NotPermitted"
+            | "PermissionDenied" => Self::PermissionDenied,
+            "InvalidArgs" | "InvalidArguments" | "InvalidSignature" | "UnknownMethod"
+            | "UnknownProperty" | "UnknownInterface" | "PropertyReadOnly" => Self::InvalidArgument,
+            "Timeout" | "TimedOut" | "NoReply" => Self::Timeout,
+            _ => Self::Failed,
+        }
+    }
+}
+
+/// Most recent error of a DBus wrapper object. Errors can be recorded from
+/// methods that only borrow the object immutably (e.g. property getters).
+#[derive(Debug, Default)]
+pub struct LastError(RefCell<Option<(ErrorKind, String)>>);
+
+impl LastError {
+    /// Returns the error code of the most recent call, or 0 if it succeeded
+    pub fn code(&self) -> i32 {
+        self.0
+            .borrow()
+            .as_ref()
+            .map(|(kind, _)| kind.code())
+            .unwrap_or_default()
+    }
+
+    /// Returns the error message of the most recent call, or an empty string if
+    /// it succeeded
+    pub fn message(&self) -> String {
+        self.0
+            .borrow()
+            .as_ref()
+            .map(|(_, message)| message.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the value of the given DBus call. If the call failed, the error
+    /// is reported on the given owner of this [LastError] and a default value
+    /// is returned.
+    pub fn check<T, E, O>(&self, owner: &O, result: Result<T, E>) -> T
+    where
+        T: Default,
+        E: Into<RunError>,
+        O: WithBaseField + Inherits<Object>,
+    {
+        match result {
+            Ok(value) => {
+                self.0.replace(None);
+                value
+            }
+            Err(e) => {
+                self.report(owner.to_gd().upcast(), e.into());
+                T::default()
+            }
+        }
+    }
+
+    /// Record the given error and emit it with the `error_occurred` signal of the
+    /// given object. The signal is emitted deferred, since the object is usually
+    /// bound while the error is reported.
+    pub fn report(&self, object: Gd<Object>, error: RunError) {
+        self.report_kind(object, error.kind(), error.to_string());
+    }
+
+    /// Record the error with the given kind and message and emit it with the
+    /// `error_occurred` signal of the given object. This is used for failures
+    /// of operations that finished in the background.
+    pub fn report_kind(&self, mut object: Gd<Object>, kind: ErrorKind, message: String) {
+        log::debug!("DBus call failed: {message}");
+        object.call_deferred(
+            "emit_signal",
+            &[
+                "error_occurred".to_variant(),
+                kind.code().to_variant(),
+                message.to_variant(),
+            ],
+        );
+        self.0.replace(Some((kind, message)));
+    }
+}
+
+/// Declares the `error_occurred` signal and the methods that expose the
+/// [LastError] of a DBus wrapper class. The class must store it in a field
+/// named `last_error`.
+macro_rules! impl_last_error {
+    ($class:ty) => {
+        #[::godot::prelude::godot_api(secondary)]
+        impl $class {
+            /// Emitted when a call to the service fails. The code is one of the
+            /// [DBusError] constants.
+            #[signal]
+            fn error_occurred(code: i32, message: ::godot::builtin::GString);
+
+            /// Returns the error code of the most recent call, or [constant DBusError.OK]
+            /// if it succeeded
+            #[func]
+            pub fn get_last_error_code(&self) -> i32 {
+                self.last_error.code()
+            }
+
+            /// Returns the error message of the most recent call, or an empty string if
+            /// it succeeded
+            #[func]
+            pub fn get_last_error(&self) -> ::godot::builtin::GString {
+                self.last_error.message().into()
+            }
+        }
+    };
+}
+pub(crate) use impl_last_error;
+
 /// Interface for converting DBus types -> Godot types
 pub trait GodotVariant {
     fn as_godot_variant(&self) -> Option<Variant>;
@@ -88,9 +290,110 @@ impl<'a> GodotVariant for zvariant::Value<'a> {
     }
 }
 
+/// Possible errors converting Godot types into DBus types
+#[derive(Debug)]
+pub enum VariantConversionError {
+    /// The given DBus signature is not valid
+    InvalidSignature(String),
+    /// The Godot value is not compatible with the DBus type
+    TypeMismatch {
+        signature: String,
+        found: VariantType,
+    },
+    /// The Godot integer does not fit in the DBus integer type
+    OutOfRange { signature: String, value: i64 },
+    /// The Godot value has the right type but is not valid for the DBus type
+    /// (e.g. a malformed object path)
+    InvalidValue { signature: String, reason: String },
+    /// The number of values does not match the number of types in the signature
+    LengthMismatch {
+        signature: String,
+        expected: usize,
+        found: usize,
+    },
+    /// The DBus type cannot be created from a Godot value
+    Unsupported(String),
+    /// The error occurred inside of an array, dictionary, or struct
+    Nested {
+        location: String,
+        error: Box<VariantConversionError>,
+    },
+}
+
+impl VariantConversionError {
+    /// Wrap the error with the location in a container where it occurred
+    fn at(self, location: String) -> Self {
+        VariantConversionError::Nested {
+            location,
+            error: Box::new(self),
+        }
+    }
+}
+
+impl Display for VariantConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VariantConversionError::InvalidSignature(signature) => {
+                write!(f, "invalid DBus signature '{signature}'")
+            }
+            VariantConversionError::TypeMismatch { signature, found } => {
+                write!(f, "cannot convert {found:?} to DBus type '{signature}'")
+            }
+            VariantConversionError::OutOfRange { signature, value } => {
+                write!(
+                    f,
+                    "value {value} is out of range for DBus type '{signature}'"
+                )
+            }
+            VariantConversionError::InvalidValue { signature, reason } => {
+                write!(f, "invalid value for DBus type '{signature}': {reason}")
+            }
+            VariantConversionError::LengthMismatch {
+                signature,
+                expected,
+                found,
+            } => {
+                write!(
+                    f,
+                    "signature '{signature}' expects {expected} value(s), but {found} were given"
+                )
+            }
+            VariantConversionError::Unsupported(signature) => {
+                write!(f, "conversion to DBus type '{signature}' is not supported")
+            }
+            VariantConversionError::Nested { .. } => {
+                // Build the full location of the error (e.g. `["ipv4"]["method"]`)
+                let mut location = String::new();
+                let mut error = self;
+                while let VariantConversionError::Nested {
+                    location: loc,
+                    error: inner,
+                } = error
+                {
+                    location.push_str(loc);
+                    error = inner;
+                }
+                write!(f, "{error} at {location}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for VariantConversionError {}
+
 /// Interface for converting Godot types -> DBus types
 pub trait DBusVariant {
+    /// Convert the Godot type into a DBus type, inferring the DBus type from
+    /// the Godot type.
     fn as_zvariant(&self) -> Option<zvariant::Value>;
+
+    /// Convert the Godot type into the DBus type described by the given
+    /// signature. The signature must be a single complete type (e.g. "u",
+    /// "a{sv}" or "(iay)").
+    fn to_zvariant(
+        &self,
+        signature: &str,
+    ) -> Result<zvariant::Value<'static>, VariantConversionError>;
 }
 
 impl DBusVariant for Variant {
@@ -143,14 +446,17 @@ impl DBusVariant for Variant {
             VariantType::OBJECT => None,
             VariantType::CALLABLE => None,
             VariantType::SIGNAL => None,
-            VariantType::DICTIONARY => None,
-            VariantType::ARRAY => None,
-            VariantType::PACKED_BYTE_ARRAY => None,
-            VariantType::PACKED_INT32_ARRAY => None,
-            VariantType::PACKED_INT64_ARRAY => None,
-            VariantType::PACKED_FLOAT32_ARRAY => None,
-            VariantType::PACKED_FLOAT64_ARRAY => None,
-            VariantType::PACKED_STRING_ARRAY => None,
+            VariantType::DICTIONARY
+            | VariantType::ARRAY
+            | VariantType::PACKED_BYTE_ARRAY
+            | VariantType::PACKED_INT32_ARRAY
+            | VariantType::PACKED_INT64_ARRAY
+            | VariantType::PACKED_FLOAT32_ARRAY
+            | VariantType::PACKED_FLOAT64_ARRAY
+            | VariantType::PACKED_STRING_ARRAY => {
+                let signature = infer_signature(self).ok()?;
+                self.to_zvariant(signature).ok()
+            }
             VariantType::PACKED_VECTOR2_ARRAY => None,
             VariantType::PACKED_VECTOR3_ARRAY => None,
             VariantType::PACKED_COLOR_ARRAY => None,
@@ -161,4 +467,329 @@ impl DBusVariant for Variant {
             _ => None,
         }
     }
+
+    /// Convert the Godot variant type into the DBus type described by the
+    /// given signature.
+    fn to_zvariant(
+        &self,
+        signature: &str,
+    ) -> Result<zvariant::Value<'static>, VariantConversionError> {
+        let (sig, rest) = split_signature(signature)?;
+        if !rest.is_empty() {
+            return Err(VariantConversionError::InvalidSignature(
+                signature.to_string(),
+            ));
+        }
+        let mismatch = || VariantConversionError::TypeMismatch {
+            signature: sig.to_string(),
+            found: self.get_type(),
+        };
+
+        let value = match sig.as_bytes()[0] {
+            b'y' => zvariant::Value::U8(to_int(self, sig)?),
+            b'b' => {
+                if self.get_type() != VariantType::BOOL {
+                    return Err(mismatch());
+                }
+                zvariant::Value::Bool(self.to())
+            }
+            b'n' => zvariant::Value::I16(to_int(self, sig)?),
+            b'q' => zvariant::Value::U16(to_int(self, sig)?),
+            b'i' => zvariant::Value::I32(to_int(self, sig)?),
+            b'u' => zvariant::Value::U32(to_int(self, sig)?),
+            b'x' => zvariant::Value::I64(to_int(self, sig)?),
+            b't' => zvariant::Value::U64(to_int(self, sig)?),
+            b'd' => match self.get_type() {
+                VariantType::FLOAT => zvariant::Value::F64(self.to()),
+                VariantType::INT => zvariant::Value::F64(self.to::<i64>() as f64),
+                _ => return Err(mismatch()),
+            },
+            b's' => zvariant::Value::from(to_string(self).ok_or_else(mismatch)?),
+            b'o' => {
+                let path = to_string(self).ok_or_else(mismatch)?;
+                let path = ObjectPath::try_from(path).map_err(|e| {
+                    VariantConversionError::InvalidValue {
+                        signature: sig.to_string(),
+                        reason: e.to_string(),
+                    }
+                })?;
+                zvariant::Value::ObjectPath(path)
+            }
+            b'g' => {
+                let value = to_string(self).ok_or_else(mismatch)?;
+                let value = Signature::try_from(value).map_err(|e| {
+                    VariantConversionError::InvalidValue {
+                        signature: sig.to_string(),
+                        reason: e.to_string(),
+                    }
+                })?;
+                zvariant::Value::Signature(value)
+            }
+            b'v' => {
+                let inner = self.to_zvariant(infer_signature(self)?)?;
+                zvariant::Value::Value(Box::new(inner))
+            }
+            b'a' if sig.as_bytes()[1] == b'{' => {
+                if self.get_type() != VariantType::DICTIONARY {
+                    return Err(mismatch());
+                }
+                let (key_sig, value_sig) = split_signature(&sig[2..sig.len() - 1])?;
+                let mut dict =
+                    zvariant::Dict::new(new_signature(key_sig)?, new_signature(value_sig)?);
+                let dictionary: Dictionary = self.to();
+                for (key, value) in dictionary.iter_shared() {
+                    let location = format!("[{key}]");
+                    let key = key
+                        .to_zvariant(key_sig)
+                        .map_err(|e| e.at(location.clone()))?;
+                    let value = value
+                        .to_zvariant(value_sig)
+                        .map_err(|e| e.at(location.clone()))?;
+                    dict.append(key, value)
+                        .map_err(|e| VariantConversionError::InvalidValue {
+                            signature: sig.to_string(),
+                            reason: e.to_string(),
+                        })?;
+                }
+                zvariant::Value::Dict(dict)
+            }
+            b'a' => {
+                let element_sig = &sig[1..];
+                let elements = to_elements(self).ok_or_else(mismatch)?;
+                let mut array = zvariant::Array::new(new_signature(element_sig)?);
+                for (i, element) in elements.iter().enumerate() {
+                    let element = element
+                        .to_zvariant(element_sig)
+                        .map_err(|e| e.at(format!("[{i}]")))?;
+                    array
+                        .append(element)
+                        .map_err(|e| VariantConversionError::InvalidValue {
+                            signature: sig.to_string(),
+                            reason: e.to_string(),
+                        })?;
+                }
+                zvariant::Value::Array(array)
+            }
+            b'(' => {
+                let elements = to_elements(self).ok_or_else(mismatch)?;
+                let field_sigs = split_signatures(&sig[1..sig.len() - 1])?;
+                if field_sigs.len() != elements.len() {
+                    return Err(VariantConversionError::LengthMismatch {
+                        signature: sig.to_string(),
+                        expected: field_sigs.len(),
+                        found: elements.len(),
+                    });
+                }
+                let mut builder = StructureBuilder::new();
+                for (i, (element, field_sig)) in elements.iter().zip(field_sigs).enumerate() {
+                    let field = element
+                        .to_zvariant(field_sig)
+                        .map_err(|e| e.at(format!("[{i}]")))?;
+                    builder = builder.append_field(field);
+                }
+                zvariant::Value::Structure(builder.build())
+            }
+            _ => return Err(VariantConversionError::Unsupported(sig.to_string())),
+        };
+
+        Ok(value)
+    }
+}
+
+/// Convert the given list of Godot values into DBus values using the given
+/// signature, which must contain one complete type for each value (e.g. "sua{sv}"
+/// for three values). If the signature is empty, the DBus types will be
+/// inferred from the Godot types.
+pub fn to_zvariant_args(
+    args: &VariantArray,
+    signature: &str,
+) -> Result<Vec<zvariant::Value<'static>>, VariantConversionError> {
+    let args: Vec<Variant> = args.iter_shared().collect();
+    if signature.is_empty() {
+        let mut values = Vec::with_capacity(args.len());
+        for (i, arg) in args.iter().enumerate() {
+            let value = infer_signature(arg)
+                .and_then(|sig| arg.to_zvariant(sig))
+                .map_err(|e| e.at(format!("argument {i}")))?;
+            values.push(value);
+        }
+        return Ok(values);
+    }
+
+    let signatures = split_signatures(signature)?;
+    if signatures.len() != args.len() {
+        return Err(VariantConversionError::LengthMismatch {
+            signature: signature.to_string(),
+            expected: signatures.len(),
+            found: args.len(),
+        });
+    }
+    let mut values = Vec::with_capacity(args.len());
+    for (i, (arg, sig)) in args.iter().zip(signatures).enumerate() {
+        let value = arg
+            .to_zvariant(sig)
+            .map_err(|e| e.at(format!("argument {i}")))?;
+        values.push(value);
+    }
+
+    Ok(values)
+}
+
+/// Returns the DBus signature that best matches the given Godot value. This is
+/// used for values that are sent as DBus variants ("v").
+pub fn infer_signature(value: &Variant) -> Result<&'static str, VariantConversionError> {
+    let signature = match value.get_type() {
+        VariantType::BOOL => "b",
+        VariantType::INT => "x",
+        VariantType::FLOAT => "d",
+        VariantType::STRING | VariantType::STRING_NAME | VariantType::NODE_PATH => "s",
+        VariantType::DICTIONARY => "a{sv}",
+        VariantType::ARRAY => "av",
+        VariantType::PACKED_BYTE_ARRAY => "ay",
+        VariantType::PACKED_INT32_ARRAY => "ai",
+        VariantType::PACKED_INT64_ARRAY => "ax",
+        VariantType::PACKED_FLOAT32_ARRAY | VariantType::PACKED_FLOAT64_ARRAY => "ad",
+        VariantType::PACKED_STRING_ARRAY => "as",
+        found => {
+            return Err(VariantConversionError::TypeMismatch {
+                signature: "v".into(),
+                found,
+            })
+        }
+    };
+
+    Ok(signature)
+}
+
+/// Convert the given Godot integer into the given DBus integer type
+fn to_int<T: TryFrom<i64>>(value: &Variant, signature: &str) -> Result<T, VariantConversionError> {
+    if value.get_type() != VariantType::INT {
+        return Err(VariantConversionError::TypeMismatch {
+            signature: signature.to_string(),
+            found: value.get_type(),
+        });
+    }
+    let value: i64 = value.to();
+    T::try_from(value).map_err(|_| VariantConversionError::OutOfRange {
+        signature: signature.to_string(),
+        value,
+    })
+}
+
+/// Convert the given Godot string-like value into a string
+fn to_string(value: &Variant) -> Option<String> {
+    match value.get_type() {
+        VariantType::STRING => Some(value.to::<GString>().to_string()),
+        VariantType::STRING_NAME | VariantType::NODE_PATH => Some(value.stringify().to_string()),
+        _ => None,
+    }
+}
+
+/// Returns the elements of the given Godot array or packed array
+fn to_elements(value: &Variant) -> Option<Vec<Variant>> {
+    let elements = match value.get_type() {
+        VariantType::ARRAY => {
+            // Typed arrays cannot be converted directly into an untyped array,
+            // so copy the elements into a new array.
+            let array = VariantArray::new();
+            array.to_variant().call("assign", &[value.clone()]);
+            array.iter_shared().collect()
+        }
+        VariantType::PACKED_BYTE_ARRAY => {
+            let array: PackedByteArray = value.to();
+            array.as_slice().iter().map(|v| v.to_variant()).collect()
+        }
+        VariantType::PACKED_INT32_ARRAY => {
+            let array: PackedInt32Array = value.to();
+            array.as_slice().iter().map(|v| v.to_variant()).collect()
+        }
+        VariantType::PACKED_INT64_ARRAY => {
+            let array: PackedInt64Array = value.to();
+            array.as_slice().iter().map(|v| v.to_variant()).collect()
+        }
+        VariantType::PACKED_FLOAT32_ARRAY => {
+            let array: PackedFloat32Array = value.to();
+            array.as_slice().iter().map(|v| v.to_variant()).collect()
+        }
+        VariantType::PACKED_FLOAT64_ARRAY => {
+            let array: PackedFloat64Array = value.to();
+            array.as_slice().iter().map(|v| v.to_variant()).collect()
+        }
+        VariantType::PACKED_STRING_ARRAY => {
+            let array: PackedStringArray = value.to();
+            array.as_slice().iter().map(|v| v.to_variant()).collect()
+        }
+        _ => return None,
+    };
+
+    Some(elements)
+}
+
+/// Create a new owned DBus signature from the given string
+fn new_signature(signature: &str) -> Result<Signature<'static>, VariantConversionError> {
+    Signature::try_from(signature.to_string())
+        .map_err(|_| VariantConversionError::InvalidSignature(signature.to_string()))
+}
+
+/// Split the first complete type from the given signature. Returns the
+/// complete type and the rest of the signature.
+fn split_signature(signature: &str) -> Result<(&str, &str), VariantConversionError> {
+    let Some(end) = complete_type_end(signature.as_bytes(), 0) else {
+        return Err(VariantConversionError::InvalidSignature(
+            signature.to_string(),
+        ));
+    };
+    Ok(signature.split_at(end))
+}
+
+/// Split the given signature into its complete types (e.g. "sa{sv}(ii)" into
+/// "s", "a{sv}" and "(ii)").
+pub fn split_signatures(signature: &str) -> Result<Vec<&str>, VariantConversionError> {
+    let mut signatures = vec![];
+    let mut rest = signature;
+    while !rest.is_empty() {
+        let (sig, remaining) = split_signature(rest)?;
+        signatures.push(sig);
+        rest = remaining;
+    }
+
+    Ok(signatures)
+}
+
+/// Returns the index just past the complete type starting at the given index
+/// in the signature, or `None` if the signature is invalid. Unix file
+/// descriptors ("h") cannot be created from Godot values, so they are
+/// rejected as invalid.
+fn complete_type_end(signature: &[u8], start: usize) -> Option<usize> {
+    match signature.get(start)? {
+        b'y' | b'b' | b'n' | b'q' | b'i' | b'u' | b'x' | b't' | b'd' | b's' | b'o' | b'g'
+        | b'v' => Some(start + 1),
+        b'a' => complete_type_end(signature, start + 1),
+        b'(' => {
+            let mut pos = start + 1;
+            // Structs must have at least one field
+            if *signature.get(pos)? == b')' {
+                return None;
+            }
+            while *signature.get(pos)? != b')' {
+                pos = complete_type_end(signature, pos)?;
+            }
+            Some(pos + 1)
+        }
+        b'{' => {
+            // Dictionary entries are only valid as array elements and must
+            // have a basic key type.
+            if start == 0 || signature[start - 1] != b'a' {
+                return None;
+            }
+            let key = *signature.get(start + 1)?;
+            if matches!(key, b'a' | b'(' | b'{' | b'v') {
+                return None;
+            }
+            let value_start = complete_type_end(signature, start + 1)?;
+            let end = complete_type_end(signature, value_start)?;
+            (*signature.get(end)? == b'}').then_some(end + 1)
+        }
+        _ => None,
+    }
 }
@@ -0,0 +1,42 @@
+use godot::prelude::*;
+
+use super::ErrorKind;
+
+/// Error codes reported by DBus wrapper objects.
+///
+/// When a call to a system service fails, the wrapper object (e.g. [GpuCard] or [BluetoothDevice]) records the error so it can be read with `get_last_error_code()` and `get_last_error()`, and emits its `error_occurred` signal. The [AsyncResult] of an asynchronous operation reports the same error codes with [method AsyncResult.get_error_code].
+///
+/// Example
+///
+/// [codeblock]
+/// var tdp := card.tdp
+/// if card.get_last_error_code() == DBusError.SERVICE_NOT_RUNNING:
+///     print("PowerStation is not running")
+/// [/codeblock]
+#[derive(GodotClass)]
+#[class(no_init, base=RefCounted)]
+pub struct DBusError {
+    base: Base<RefCounted>,
+}
+
+#[godot_api]
+impl DBusError {
+    /// The call succeeded
+    #[constant]
+    const OK: i32 = 0;
+    /// The call failed for a reason that does not fit one of the other codes
+    #[constant]
+    const FAILED: i32 = ErrorKind::Failed as i32;
+    /// The service is not running or the object does not exist
+    #[constant]
+    const SERVICE_NOT_RUNNING: i32 = ErrorKind::ServiceNotRunning as i32;
+    /// The caller is not allowed to perform the operation
+    #[constant]
+    const PERMISSION_DENIED: i32 = ErrorKind::PermissionDenied as i32;
+    /// The arguments or the called method were rejected by the service
+    #[constant]
+    const INVALID_ARGUMENT: i32 = ErrorKind::InvalidArgument as i32;
+    /// The service did not reply in time
+    #[constant]
+    const TIMEOUT: i32 = ErrorKind::Timeout as i32;
+}
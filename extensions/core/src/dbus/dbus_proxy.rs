@@ -0,0 +1,730 @@
+use std::collections::HashMap;
+
+use futures_util::StreamExt;
+use godot::{obj::WithBaseField, prelude::*};
+use tokio::task::JoinHandle;
+use zbus::{
+    fdo::PropertiesProxy,
+    names::{BusName, InterfaceName},
+    proxy::CacheProperties,
+    zvariant::{OwnedValue, Structure, StructureBuilder, Value},
+    Message,
+};
+
+use crate::{
+    get_dbus, get_dbus_blocking,
+    resource::dispatcher::{channel, Receiver, Sender, Wakeup},
+    resource::resource_registry::ResourceRegistry,
+    DBusBus, RUNTIME,
+};
+
+use super::{infer_signature, to_zvariant_args, DBusVariant, GodotVariant, VariantConversionError};
+
+/// Signals that can be emitted by this class
+#[derive(Debug)]
+enum Signal {
+    CallCompleted {
+        call_id: i64,
+        result: Result<Vec<OwnedValue>, String>,
+    },
+    SignalReceived {
+        name: String,
+        args: Vec<OwnedValue>,
+    },
+    PropertiesChanged {
+        changed: HashMap<String, OwnedValue>,
+        invalidated: Vec<String>,
+    },
+}
+
+/// Generic proxy for any object on the DBus system or session bus.
+///
+/// The [DBusProxy] can be used to call methods, get and set properties, and listen for signals on any bus name, object path, and interface. This allows talking to system services that do not have a dedicated class, such as `fwupd` or `timedated`, and to services running in the user session with [method create_session], such as MPRIS media players or `org.freedesktop.Notifications`.
+///
+/// Methods called with [method call_method], [method get_property_async], and [method set_property] are executed asyncronously and return a call id. When the call has completed, either [signal call_completed] or [signal call_failed] will fire with the same call id. Signals subscribed to with [method subscribe] will fire [signal signal_received] every time the signal is emitted.
+///
+/// Example
+///
+/// [codeblock]
+/// var timedate := DBusProxy.create("org.freedesktop.timedate1", "/org/freedesktop/timedate1", "org.freedesktop.timedate1")
+/// print(timedate.get_property("Timezone"))
+/// timedate.call_completed.connect(func(call_id: int, result: Variant): print("Done: ", call_id))
+/// timedate.call_method("SetNTP", [true, false], "bb")
+/// [/codeblock]
+///
+/// When using the asyncronous methods, the [ResourceProcessor] node [b]must[/b] be added to the scene tree or no signals will fire. While any calls are pending or signals are subscribed, the proxy is kept alive by the [ResourceRegistry]. Call [method unsubscribe] and [method unwatch_properties] to release it.
+#[derive(GodotClass)]
+#[class(base=RefCounted)]
+pub struct DBusProxy {
+    base: Base<RefCounted>,
+    /// Message bus the target object lives on
+    bus: DBusBus,
+    /// Raised when an async task sends a signal to this object
+    wakeup: Wakeup,
+    /// Transmitter given to async tasks to send signals back to this object
+    tx: Sender<Signal>,
+    /// Receiver to listen for signals emitted from the async runtime
+    rx: Receiver<Signal>,
+    /// Id to assign to the next asyncronous call
+    next_call_id: i64,
+    /// Number of asyncronous calls that have not completed yet
+    pending_calls: usize,
+    /// Running tasks listening for subscribed signals
+    subscriptions: HashMap<String, JoinHandle<()>>,
+    /// Running task listening for property changes
+    properties_task: Option<JoinHandle<()>>,
+    /// Whether or not this object is registered with the [ResourceRegistry]
+    registered: bool,
+
+    /// Well-known or unique bus name of the service (e.g. "org.freedesktop.timedate1")
+    #[var]
+    bus_name: GString,
+    /// Path to the object (e.g. "/org/freedesktop/timedate1")
+    #[var]
+    object_path: GString,
+    /// Name of the interface to use (e.g. "org.freedesktop.timedate1")
+    #[var]
+    interface_name: GString,
+}
+
+#[godot_api]
+impl DBusProxy {
+    #[constant]
+    const BUS_SYSTEM: i32 = 0;
+    #[constant]
+    const BUS_SESSION: i32 = 1;
+
+    /// Emitted when an asyncronous call has completed. If the method returns a single value, [param result] will be that value. If it returns multiple values, [param result] will be an [Array] of values. Otherwise [param result] will be null.
+    #[signal]
+    fn call_completed(call_id: i64, result: Variant);
+
+    /// Emitted when an asyncronous call has failed
+    #[signal]
+    fn call_failed(call_id: i64, error: GString);
+
+    /// Emitted when a signal subscribed to with [method subscribe] is received
+    #[signal]
+    fn signal_received(signal_name: GString, args: Array<Variant>);
+
+    /// Emitted when properties on the interface change after calling [method watch_properties]
+    #[signal]
+    fn properties_changed(changed: Dictionary, invalidated: PackedStringArray);
+
+    /// Create a new [DBusProxy] for the given bus name, object path, and interface on the system bus
+    #[func]
+    fn create(bus_name: GString, object_path: GString, interface_name: GString) -> Gd<Self> {
+        Self::new_for_bus(DBusBus::System, bus_name, object_path, interface_name)
+    }
+
+    /// Create a new [DBusProxy] for the given bus name, object path, and interface on the session bus of the current user
+    #[func]
+    fn create_session(
+        bus_name: GString,
+        object_path: GString,
+        interface_name: GString,
+    ) -> Gd<Self> {
+        Self::new_for_bus(DBusBus::Session, bus_name, object_path, interface_name)
+    }
+
+    /// Create a new [DBusProxy] for the given bus name, object path, and interface on the given bus. Must be one of [constant BUS_SYSTEM] or [constant BUS_SESSION].
+    #[func]
+    fn create_for_bus(
+        bus: i32,
+        bus_name: GString,
+        object_path: GString,
+        interface_name: GString,
+    ) -> Gd<Self> {
+        let bus = match bus {
+            Self::BUS_SESSION => DBusBus::Session,
+            Self::BUS_SYSTEM => DBusBus::System,
+            _ => {
+                log::error!("Invalid bus type '{bus}'. Using the system bus.");
+                DBusBus::System
+            }
+        };
+        Self::new_for_bus(bus, bus_name, object_path, interface_name)
+    }
+
+    /// Returns the bus that the target object lives on. Will be one of [constant BUS_SYSTEM] or [constant BUS_SESSION].
+    #[func]
+    fn get_bus(&self) -> i32 {
+        match self.bus {
+            DBusBus::System => Self::BUS_SYSTEM,
+            DBusBus::Session => Self::BUS_SESSION,
+        }
+    }
+
+    /// Returns true if the service that owns the bus name is currently running
+    #[func]
+    fn is_running(&self) -> bool {
+        let Ok(conn) = get_dbus_blocking(self.bus) else {
+            return false;
+        };
+        let Ok(bus) = BusName::try_from(self.bus_name.to_string()) else {
+            return false;
+        };
+        let dbus = zbus::blocking::fdo::DBusProxy::new(&conn).ok();
+        let Some(dbus) = dbus else {
+            return false;
+        };
+        dbus.name_has_owner(bus).unwrap_or_default()
+    }
+
+    /// Call the given method with the given arguments asyncronously. The arguments are converted using the given DBus signature (e.g. "sa{sv}"), or inferred from the argument types if the signature is empty. Returns a call id that will be passed to [signal call_completed] or [signal call_failed], or -1 if the arguments could not be converted.
+    #[func]
+    fn call_method(&mut self, method: GString, args: Array<Variant>, signature: GString) -> i64 {
+        let args = match to_zvariant_args(&args, signature.to_string().as_str()) {
+            Ok(args) => args,
+            Err(e) => {
+                log::error!("Failed to convert arguments for method call '{method}': {e}");
+                return -1;
+            }
+        };
+        let method = method.to_string();
+        let (bus, bus_name, path, iface) = self.target();
+
+        self.spawn_call(async move {
+            let proxy = build_proxy(bus, bus_name, path, iface).await?;
+            let message = match build_body(args) {
+                Some(body) => proxy.call_method(method.as_str(), &body).await?,
+                None => proxy.call_method(method.as_str(), &()).await?,
+            };
+            parse_body(&message)
+        })
+    }
+
+    /// Call the given method with the given arguments, blocking the current thread until the method returns. The arguments are converted using the given DBus signature, or inferred from the argument types if the signature is empty. Returns the result of the method call, or null if the call failed.
+    #[func]
+    fn call_method_blocking(
+        &self,
+        method: GString,
+        args: Array<Variant>,
+        signature: GString,
+    ) -> Variant {
+        let args = match to_zvariant_args(&args, signature.to_string().as_str()) {
+            Ok(args) => args,
+            Err(e) => {
+                log::error!("Failed to convert arguments for method call '{method}': {e}");
+                return Variant::nil();
+            }
+        };
+        let Some(proxy) = self.get_proxy() else {
+            return Variant::nil();
+        };
+        let method = method.to_string();
+        let result = match build_body(args) {
+            Some(body) => proxy.call_method(method.as_str(), &body),
+            None => proxy.call_method(method.as_str(), &()),
+        };
+        let values = match result.and_then(|message| parse_body(&message)) {
+            Ok(values) => values,
+            Err(e) => {
+                log::error!("Failed to call method '{method}': {e:?}");
+                return Variant::nil();
+            }
+        };
+
+        to_result_variant(values)
+    }
+
+    /// Returns the value of the given property, blocking the current thread until the value is read. Returns null if the property could not be read.
+    #[func]
+    fn get_property(&self, name: GString) -> Variant {
+        let Some(proxy) = self.get_proxy() else {
+            return Variant::nil();
+        };
+        let value: OwnedValue = match proxy.get_property(name.to_string().as_str()) {
+            Ok(value) => value,
+            Err(e) => {
+                log::error!("Failed to get property '{name}': {e:?}");
+                return Variant::nil();
+            }
+        };
+
+        value.as_godot_variant().unwrap_or_default()
+    }
+
+    /// Read the value of the given property asyncronously. Returns a call id that will be passed to [signal call_completed] with the value of the property, or [signal call_failed].
+    #[func]
+    fn get_property_async(&mut self, name: GString) -> i64 {
+        let name = name.to_string();
+        let (bus, bus_name, path, iface) = self.target();
+
+        self.spawn_call(async move {
+            let proxy = build_proxy(bus, bus_name, path, iface).await?;
+            let value: OwnedValue = proxy.get_property(name.as_str()).await?;
+            Ok(vec![value])
+        })
+    }
+
+    /// Set the given property to the given value asyncronously. The value is converted using the given DBus signature (e.g. "u"), or inferred from the value type if the signature is empty. Returns a call id that will be passed to [signal call_completed] or [signal call_failed], or -1 if the value could not be converted.
+    #[func]
+    fn set_property(&mut self, name: GString, value: Variant, signature: GString) -> i64 {
+        let value = match convert_property_value(&value, signature.to_string().as_str()) {
+            Ok(value) => value,
+            Err(e) => {
+                log::error!("Failed to convert value for property '{name}': {e}");
+                return -1;
+            }
+        };
+        let name = name.to_string();
+        let (bus, bus_name, path, iface) = self.target();
+
+        self.spawn_call(async move {
+            let proxy = build_proxy(bus, bus_name, path, iface).await?;
+            proxy
+                .set_property(name.as_str(), value)
+                .await
+                .map_err(zbus::Error::from)?;
+            Ok(vec![])
+        })
+    }
+
+    /// Set the given property to the given value, blocking the current thread until the value is set. The value is converted using the given DBus signature, or inferred from the value type if the signature is empty. Returns 0 on success or -1 on failure.
+    #[func]
+    fn set_property_blocking(&self, name: GString, value: Variant, signature: GString) -> i32 {
+        let value = match convert_property_value(&value, signature.to_string().as_str()) {
+            Ok(value) => value,
+            Err(e) => {
+                log::error!("Failed to convert value for property '{name}': {e}");
+                return -1;
+            }
+        };
+        let Some(proxy) = self.get_proxy() else {
+            return -1;
+        };
+        if let Err(e) = proxy.set_property(name.to_string().as_str(), value) {
+            log::error!("Failed to set property '{name}': {e:?}");
+            return -1;
+        }
+
+        0
+    }
+
+    /// Subscribe to the given signal on the interface. Every time the signal is emitted, [signal signal_received] will fire with the signal arguments. Returns 0 on success or -1 on failure.
+    #[func]
+    fn subscribe(&mut self, signal_name: GString) -> i32 {
+        let name = signal_name.to_string();
+        if self.subscriptions.contains_key(&name) {
+            return 0;
+        }
+        let (bus, bus_name, path, iface) = self.target();
+        let tx = self.tx.clone();
+
+        let signal_name = name.clone();
+        let task = RUNTIME.spawn(async move {
+            let proxy = match build_proxy(bus, bus_name, path, iface).await {
+                Ok(proxy) => proxy,
+                Err(e) => {
+                    log::error!("Failed to create proxy to subscribe to '{signal_name}': {e:?}");
+                    return;
+                }
+            };
+            let mut stream = match proxy.receive_signal(signal_name.as_str()).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::error!("Failed to subscribe to signal '{signal_name}': {e:?}");
+                    return;
+                }
+            };
+            while let Some(message) = stream.next().await {
+                let args = match parse_body(&message) {
+                    Ok(args) => args,
+                    Err(e) => {
+                        log::warn!("Failed to parse arguments for signal '{signal_name}': {e:?}");
+                        continue;
+                    }
+                };
+                let signal = Signal::SignalReceived {
+                    name: signal_name.clone(),
+                    args,
+                };
+                if tx.send(signal).is_err() {
+                    break;
+                }
+            }
+        });
+        self.subscriptions.insert(name, task);
+        self.update_registration();
+
+        0
+    }
+
+    /// Stop listening for the given signal
+    #[func]
+    fn unsubscribe(&mut self, signal_name: GString) {
+        let Some(task) = self.subscriptions.remove(&signal_name.to_string()) else {
+            return;
+        };
+        task.abort();
+        self.update_registration();
+    }
+
+    /// Returns the names of all currently subscribed signals
+    #[func]
+    fn get_subscriptions(&self) -> PackedStringArray {
+        let names: Vec<GString> = self
+            .subscriptions
+            .keys()
+            .map(|name| GString::from(name.as_str()))
+            .collect();
+        names.into()
+    }
+
+    /// Listen for changes to properties on the interface. Every time a property changes, [signal properties_changed] will fire with the changed properties.
+    #[func]
+    fn watch_properties(&mut self) {
+        if self.properties_task.is_some() {
+            return;
+        }
+        let (bus, bus_name, path, iface) = self.target();
+        let tx = self.tx.clone();
+
+        let task = RUNTIME.spawn(async move {
+            if let Err(e) = watch_properties(tx, bus, bus_name, path, iface).await {
+                log::error!("Failed to watch properties: {e:?}");
+            }
+        });
+        self.properties_task = Some(task);
+        self.update_registration();
+    }
+
+    /// Stop listening for property changes
+    #[func]
+    fn unwatch_properties(&mut self) {
+        let Some(task) = self.properties_task.take() else {
+            return;
+        };
+        task.abort();
+        self.update_registration();
+    }
+
+    /// Process signals and emit them as Godot signals. This is called by the [ResourceProcessor] on frames where signals are waiting, while calls are pending or signals are subscribed.
+    #[func]
+    pub fn process(&mut self, _delta: f64) {
+        // Skip frames where no async task sent any signals
+        if !self.wakeup.take() {
+            return;
+        }
+
+        // Drain all messages from the channel to process them
+        let mut signals = vec![];
+        while let Ok(signal) = self.rx.try_recv() {
+            signals.push(signal);
+        }
+        for signal in signals {
+            self.process_signal(signal);
+        }
+    }
+
+    /// Process and dispatch the given signal
+    fn process_signal(&mut self, signal: Signal) {
+        match signal {
+            Signal::CallCompleted { call_id, result } => {
+                self.pending_calls = self.pending_calls.saturating_sub(1);
+                self.update_registration();
+                match result {
+                    Ok(values) => {
+                        let result = to_result_variant(values);
+                        self.base_mut()
+                            .emit_signal("call_completed", &[call_id.to_variant(), result]);
+                    }
+                    Err(error) => {
+                        self.base_mut().emit_signal(
+                            "call_failed",
+                            &[call_id.to_variant(), error.to_godot().to_variant()],
+                        );
+                    }
+                }
+            }
+            Signal::SignalReceived { name, args } => {
+                let mut arr = array![];
+                for arg in args {
+                    arr.push(&arg.as_godot_variant().unwrap_or_default());
+                }
+                self.base_mut().emit_signal(
+                    "signal_received",
+                    &[name.to_godot().to_variant(), arr.to_variant()],
+                );
+            }
+            Signal::PropertiesChanged {
+                changed,
+                invalidated,
+            } => {
+                let mut dict = Dictionary::new();
+                for (key, value) in changed {
+                    dict.set(key, value.as_godot_variant().unwrap_or_default());
+                }
+                let invalidated: Vec<GString> =
+                    invalidated.into_iter().map(GString::from).collect();
+                let invalidated: PackedStringArray = invalidated.into();
+                self.base_mut().emit_signal(
+                    "properties_changed",
+                    &[dict.to_variant(), invalidated.to_variant()],
+                );
+            }
+        }
+    }
+
+    /// Create a new proxy to the given object on the given bus
+    fn new_for_bus(
+        bus: DBusBus,
+        bus_name: GString,
+        object_path: GString,
+        interface_name: GString,
+    ) -> Gd<Self> {
+        Gd::from_init_fn(|base| {
+            let mut proxy = Self::init(base);
+            proxy.bus = bus;
+            proxy.bus_name = bus_name;
+            proxy.object_path = object_path;
+            proxy.interface_name = interface_name;
+            proxy
+        })
+    }
+
+    /// Returns the bus, bus name, object path and interface name to target
+    fn target(&self) -> (DBusBus, String, String, String) {
+        (
+            self.bus,
+            self.bus_name.to_string(),
+            self.object_path.to_string(),
+            self.interface_name.to_string(),
+        )
+    }
+
+    /// Spawn the given call in the async runtime and return its call id
+    fn spawn_call<F>(&mut self, call: F) -> i64
+    where
+        F: std::future::Future<Output = zbus::Result<Vec<OwnedValue>>> + Send + 'static,
+    {
+        let call_id = self.next_call_id;
+        self.next_call_id += 1;
+        self.pending_calls += 1;
+        self.update_registration();
+
+        let tx = self.tx.clone();
+        RUNTIME.spawn(async move {
+            let result = call.await.map_err(|e| e.to_string());
+            let signal = Signal::CallCompleted { call_id, result };
+            if let Err(e) = tx.send(signal) {
+                log::error!("Failed to send signal: {e:?}");
+            }
+        });
+
+        call_id
+    }
+
+    /// Register this object with the [ResourceRegistry] while it is waiting on
+    /// calls or signals, and unregister it once it is idle.
+    fn update_registration(&mut self) {
+        let busy = self.pending_calls > 0
+            || !self.subscriptions.is_empty()
+            || self.properties_task.is_some();
+        if busy == self.registered {
+            return;
+        }
+        let Some(mut registry) = ResourceRegistry::get_registry() else {
+            log::warn!("Unable to load ResourceRegistry. Signals will not fire unless this class's 'process' method is called every frame.");
+            return;
+        };
+        self.registered = busy;
+        let method = if busy {
+            "register_on_event"
+        } else {
+            "unregister"
+        };
+        let this: Gd<RefCounted> = self.to_gd().upcast();
+        self.wakeup.set_owner(this.instance_id().to_i64());
+        registry.call_deferred(method, &[this.to_variant()]);
+    }
+
+    /// Return a blocking proxy instance to the target object
+    fn get_proxy(&self) -> Option<zbus::blocking::Proxy<'static>> {
+        let (bus, bus_name, path, iface) = self.target();
+        let conn = match get_dbus_blocking(bus) {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::error!("Failed to connect to the {bus:?} bus: {e:?}");
+                return None;
+            }
+        };
+        let proxy = zbus::blocking::proxy::Builder::<zbus::blocking::Proxy>::new(&conn)
+            .destination(bus_name)
+            .and_then(|builder| builder.path(path))
+            .and_then(|builder| builder.interface(iface))
+            .map(|builder| builder.cache_properties(CacheProperties::No))
+            .and_then(|builder| builder.build());
+        match proxy {
+            Ok(proxy) => Some(proxy),
+            Err(e) => {
+                log::error!("Failed to create proxy: {e:?}");
+                None
+            }
+        }
+    }
+}
+
+#[godot_api]
+impl IRefCounted for DBusProxy {
+    /// Called upon object initialization in the engine
+    fn init(base: Base<Self::Base>) -> Self {
+        let wakeup = Wakeup::default();
+        let (tx, rx) = channel(&wakeup);
+        Self {
+            base,
+            bus: DBusBus::System,
+            wakeup,
+            tx,
+            rx,
+            next_call_id: 0,
+            pending_calls: 0,
+            subscriptions: HashMap::new(),
+            properties_task: None,
+            registered: false,
+            bus_name: Default::default(),
+            object_path: Default::default(),
+            interface_name: Default::default(),
+        }
+    }
+}
+
+impl Drop for DBusProxy {
+    fn drop(&mut self) {
+        for (_, task) in self.subscriptions.drain() {
+            task.abort();
+        }
+        if let Some(task) = self.properties_task.take() {
+            task.abort();
+        }
+    }
+}
+
+/// Build an uncached async proxy to the given object
+async fn build_proxy(
+    bus: DBusBus,
+    bus_name: String,
+    path: String,
+    iface: String,
+) -> zbus::Result<zbus::Proxy<'static>> {
+    let conn = get_dbus(bus).await?;
+    zbus::proxy::Builder::<zbus::Proxy>::new(&conn)
+        .destination(bus_name)?
+        .path(path)?
+        .interface(iface)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+}
+
+/// Listen for PropertiesChanged signals for the given interface and send them
+/// over the given channel.
+async fn watch_properties(
+    tx: Sender<Signal>,
+    bus: DBusBus,
+    bus_name: String,
+    path: String,
+    iface: String,
+) -> zbus::Result<()> {
+    let conn = get_dbus(bus).await?;
+    let proxy = PropertiesProxy::builder(&conn)
+        .destination(bus_name)?
+        .path(path)?
+        .build()
+        .await?;
+    let iface = InterfaceName::try_from(iface)?;
+    let mut stream = proxy.receive_properties_changed().await?;
+    while let Some(event) = stream.next().await {
+        let args = event.args()?;
+        if args.interface_name != iface {
+            continue;
+        }
+        let mut changed = HashMap::new();
+        for (name, value) in args.changed_properties.iter() {
+            changed.insert(name.to_string(), value.try_to_owned()?);
+        }
+        let invalidated = args
+            .invalidated_properties
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let signal = Signal::PropertiesChanged {
+            changed,
+            invalidated,
+        };
+        if tx.send(signal).is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Convert the given Godot value into a DBus value using the given signature,
+/// inferring the type if the signature is empty.
+fn convert_value(
+    value: &Variant,
+    signature: &str,
+) -> Result<Value<'static>, VariantConversionError> {
+    if signature.is_empty() {
+        return value.to_zvariant(infer_signature(value)?);
+    }
+    value.to_zvariant(signature)
+}
+
+/// Convert the given Godot value into a DBus property value. Properties are
+/// always set as a variant, so a value converted with the "v" signature is
+/// unwrapped once to avoid sending a variant inside a variant.
+fn convert_property_value(
+    value: &Variant,
+    signature: &str,
+) -> Result<Value<'static>, VariantConversionError> {
+    match convert_value(value, signature)? {
+        Value::Value(inner) => Ok(*inner),
+        value => Ok(value),
+    }
+}
+
+/// Build a message body from the given arguments. Returns `None` if there are
+/// no arguments.
+fn build_body(args: Vec<Value<'static>>) -> Option<Structure<'static>> {
+    if args.is_empty() {
+        return None;
+    }
+    let mut builder = StructureBuilder::new();
+    for arg in args {
+        builder = builder.append_field(arg);
+    }
+    Some(builder.build())
+}
+
+/// Parse the body of the given message into a list of owned DBus values
+fn parse_body(message: &Message) -> zbus::Result<Vec<OwnedValue>> {
+    let body = message.body();
+    let is_empty = body.signature().map(|sig| sig.is_empty()).unwrap_or(true);
+    if is_empty {
+        return Ok(vec![]);
+    }
+    let structure: Structure = body.deserialize()?;
+    let mut values = Vec::with_capacity(structure.fields().len());
+    for field in structure.fields() {
+        values.push(field.try_to_owned()?);
+    }
+
+    Ok(values)
+}
+
+/// Convert the given method results into a single Godot value
+fn to_result_variant(values: Vec<OwnedValue>) -> Variant {
+    match values.len() {
+        0 => Variant::nil(),
+        1 => values[0].as_godot_variant().unwrap_or_default(),
+        _ => {
+            let mut arr = array![];
+            for value in values {
+                arr.push(&value.as_godot_variant().unwrap_or_default());
+            }
+            arr.to_variant()
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    hash::Hash,
+};
+
+use futures_util::stream::{self, BoxStream, StreamExt};
+use zbus::{
+    fdo::{ManagedObjects, ObjectManagerProxy},
+    names::BusName,
+    Connection,
+};
+
+use crate::{
+    get_dbus_blocking,
+    resource::dispatcher::{channel, Receiver, Sender, TryRecvError, Wakeup},
+    DBusBus, RUNTIME,
+};
+
+use super::supervisor::{watch_service, ServiceEvent};
+
+/// Type of DBus object that is identified by the interfaces it implements.
+/// This is usually implemented by an enum with one variant per interface that
+/// a manager cares about.
+pub trait ObjectKind: Debug + Copy + Eq + Hash + Send + 'static {
+    /// Returns the kind of object that implements the given interface, or
+    /// `None` if objects implementing it are not tracked
+    fn from_interface(iface: &str) -> Option<Self>;
+
+    /// Returns the kinds of object from the given list of implemented
+    /// interfaces
+    fn from_interfaces<S: AsRef<str>>(ifaces: impl IntoIterator<Item = S>) -> Vec<Self> {
+        let mut kinds = vec![];
+        for kind in ifaces
+            .into_iter()
+            .filter_map(|iface| Self::from_interface(iface.as_ref()))
+        {
+            if !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
+        kinds
+    }
+}
+
+/// Changes reported by an [ObjectWatcher]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObjectEvent<K> {
+    /// The service started running
+    Started,
+    /// The service stopped running. A [ObjectEvent::Removed] event is sent for
+    /// every tracked object before this.
+    Stopped,
+    /// An object of the given kind was added
+    Added { path: String, kind: K },
+    /// An object of the given kind was removed
+    Removed { path: String, kind: K },
+}
+
+/// Watches the objects of a service that implements the
+/// "org.freedesktop.DBus.ObjectManager" interface. Objects are classified
+/// by the interfaces they implement using [ObjectKind] and kept in a registry,
+/// so each object is only reported once as added and once as removed, even
+/// if the service or the bus restarts. Events are collected in the background
+/// and should be taken with [ObjectWatcher::take_events] every engine frame.
+pub struct ObjectWatcher<K: ObjectKind> {
+    rx: Receiver<ObjectEvent<K>>,
+    objects: HashMap<String, HashSet<K>>,
+}
+
+impl<K: ObjectKind> Default for ObjectWatcher<K> {
+    /// Returns a watcher that does not watch anything (e.g. in the editor)
+    fn default() -> Self {
+        let (_, rx) = channel(&Wakeup::default());
+        Self {
+            rx,
+            objects: HashMap::new(),
+        }
+    }
+}
+
+impl<K: ObjectKind> ObjectWatcher<K> {
+    /// Start watching the objects managed at the given path by the given
+    /// service. The objects that currently exist are added to the registry
+    /// without sending events for them. Events raise the given [Wakeup].
+    pub fn new(bus: DBusBus, service: &str, manager_path: &str, wakeup: &Wakeup) -> Self {
+        let (tx, rx) = channel(wakeup);
+        let service_name = service.to_string();
+        let path = manager_path.to_string();
+        RUNTIME.spawn(async move {
+            run(tx, bus, service_name, path).await;
+        });
+
+        let mut watcher = Self {
+            rx,
+            objects: HashMap::new(),
+        };
+
+        // Perform initial object discovery
+        let objects = get_managed_objects_blocking(bus, service, manager_path);
+        for (path, kinds) in objects {
+            for kind in kinds {
+                watcher.track(path.as_str(), kind);
+            }
+        }
+
+        watcher
+    }
+
+    /// Returns true if an object of the given kind exists at the given path
+    pub fn contains(&self, path: &str, kind: K) -> bool {
+        self.objects
+            .get(path)
+            .is_some_and(|kinds| kinds.contains(&kind))
+    }
+
+    /// Returns the sorted paths of all objects of the given kind
+    pub fn paths(&self, kind: K) -> Vec<String> {
+        let mut paths: Vec<String> = self
+            .objects
+            .iter()
+            .filter(|(_, kinds)| kinds.contains(&kind))
+            .map(|(path, _)| path.clone())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    /// Returns the path and kind of every tracked object
+    pub fn objects(&self) -> impl Iterator<Item = (&str, K)> {
+        self.objects
+            .iter()
+            .flat_map(|(path, kinds)| kinds.iter().map(|kind| (path.as_str(), *kind)))
+    }
+
+    /// Take all events that were received since the last call and update the
+    /// registry. Objects that are already known are not reported again.
+    pub fn take_events(&mut self) -> Vec<ObjectEvent<K>> {
+        let mut events = vec![];
+        loop {
+            let event = match self.rx.try_recv() {
+                Ok(value) => value,
+                Err(e) => match e {
+                    TryRecvError::Empty => break,
+                    TryRecvError::Disconnected => {
+                        log::error!("Backend thread is not running!");
+                        break;
+                    }
+                },
+            };
+
+            match event {
+                ObjectEvent::Added { path, kind } => {
+                    if self.track(path.as_str(), kind) {
+                        events.push(ObjectEvent::Added { path, kind });
+                    }
+                }
+                ObjectEvent::Removed { path, kind } => {
+                    if self.untrack(path.as_str(), kind) {
+                        events.push(ObjectEvent::Removed { path, kind });
+                    }
+                }
+                ObjectEvent::Stopped => {
+                    let mut objects: Vec<(String, HashSet<K>)> = self.objects.drain().collect();
+                    objects.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    for (path, kinds) in objects {
+                        for kind in kinds {
+                            let path = path.clone();
+                            events.push(ObjectEvent::Removed { path, kind });
+                        }
+                    }
+                    events.push(ObjectEvent::Stopped);
+                }
+                ObjectEvent::Started => events.push(ObjectEvent::Started),
+            }
+        }
+
+        events
+    }
+
+    /// Add the given object to the registry. Returns false if it was already
+    /// tracked.
+    fn track(&mut self, path: &str, kind: K) -> bool {
+        self.objects
+            .entry(path.to_string())
+            .or_default()
+            .insert(kind)
+    }
+
+    /// Remove the given object from the registry. Returns false if it was not
+    /// tracked.
+    fn untrack(&mut self, path: &str, kind: K) -> bool {
+        let Some(kinds) = self.objects.get_mut(path) else {
+            return false;
+        };
+        let removed = kinds.remove(&kind);
+        if kinds.is_empty() {
+            self.objects.remove(path);
+        }
+        removed
+    }
+}
+
+/// Classify the given managed objects by the interfaces they implement,
+/// skipping objects that do not implement any tracked interface
+fn classify<K: ObjectKind>(objects: ManagedObjects) -> Vec<(String, Vec<K>)> {
+    objects
+        .into_iter()
+        .map(|(path, ifaces)| {
+            (
+                path.to_string(),
+                K::from_interfaces(ifaces.keys().map(|iface| iface.as_str())),
+            )
+        })
+        .filter(|(_, kinds)| !kinds.is_empty())
+        .collect()
+}
+
+/// Returns the objects currently managed by the given service, or nothing if
+/// the service is not running
+fn get_managed_objects_blocking<K: ObjectKind>(
+    bus: DBusBus,
+    service: &str,
+    path: &str,
+) -> Vec<(String, Vec<K>)> {
+    let Ok(conn) = get_dbus_blocking(bus) else {
+        return vec![];
+    };
+    let Ok(destination) = BusName::try_from(service) else {
+        return vec![];
+    };
+    let object_manager = zbus::blocking::fdo::ObjectManagerProxy::builder(&conn)
+        .destination(destination)
+        .and_then(|builder| builder.path(path))
+        .and_then(|builder| builder.build());
+    let objects = match object_manager.map(|proxy| proxy.get_managed_objects()) {
+        Ok(Ok(objects)) => objects,
+        Ok(Err(e)) => {
+            log::debug!("Failed to get objects managed by {service}: {e:?}");
+            return vec![];
+        }
+        Err(e) => {
+            log::debug!("Failed to create object manager for {service}: {e:?}");
+            return vec![];
+        }
+    };
+
+    classify(objects)
+}
+
+/// Returns the objects currently managed by the given service, or nothing if
+/// the service is not running
+async fn get_managed_objects<K: ObjectKind>(
+    object_manager: &ObjectManagerProxy<'static>,
+) -> Vec<(String, Vec<K>)> {
+    match object_manager.get_managed_objects().await {
+        Ok(objects) => classify(objects),
+        Err(e) => {
+            log::debug!("Failed to get managed objects: {e:?}");
+            vec![]
+        }
+    }
+}
+
+/// Create an object manager proxy and a stream of objects being added and
+/// removed on the given connection
+async fn watch_objects<K: ObjectKind>(
+    conn: &Connection,
+    service: &str,
+    path: &str,
+) -> zbus::Result<(
+    ObjectManagerProxy<'static>,
+    BoxStream<'static, Vec<ObjectEvent<K>>>,
+)> {
+    let object_manager = ObjectManagerProxy::builder(conn)
+        .destination(service.to_string())?
+        .path(path.to_string())?
+        .build()
+        .await?;
+
+    let ifaces_added = object_manager
+        .receive_interfaces_added()
+        .await?
+        .map(|signal| {
+            let Ok(args) = signal.args() else {
+                log::warn!("Failed to get InterfacesAdded signal args");
+                return vec![];
+            };
+            let path = args.object_path.to_string();
+            K::from_interfaces(args.interfaces_and_properties.keys())
+                .into_iter()
+                .map(|kind| ObjectEvent::Added {
+                    path: path.clone(),
+                    kind,
+                })
+                .collect()
+        });
+    let ifaces_removed = object_manager
+        .receive_interfaces_removed()
+        .await?
+        .map(|signal| {
+            let Ok(args) = signal.args() else {
+                log::warn!("Failed to get InterfacesRemoved signal args");
+                return vec![];
+            };
+            let path = args.object_path.to_string();
+            K::from_interfaces(args.interfaces.iter())
+                .into_iter()
+                .map(|kind| ObjectEvent::Removed {
+                    path: path.clone(),
+                    kind,
+                })
+                .collect()
+        });
+
+    let signals = stream::select(ifaces_added, ifaces_removed).boxed();
+    Ok((object_manager, signals))
+}
+
+/// Send an event for every given object. Returns false if nobody is listening
+/// anymore.
+fn send_added<K: ObjectKind>(tx: &Sender<ObjectEvent<K>>, objects: Vec<(String, Vec<K>)>) -> bool {
+    for (path, kinds) in objects {
+        for kind in kinds {
+            let path = path.clone();
+            if tx.send(ObjectEvent::Added { path, kind }).is_err() {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Watch the service and its objects, sending events over the given channel
+/// until the receiving [ObjectWatcher] is dropped
+async fn run<K: ObjectKind>(
+    tx: Sender<ObjectEvent<K>>,
+    bus: DBusBus,
+    service: String,
+    path: String,
+) {
+    let mut events = watch_service(bus, service.as_str());
+    let mut object_manager = None;
+    let mut signals: BoxStream<'static, Vec<ObjectEvent<K>>> = stream::pending().boxed();
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let Some(event) = event else {
+                    break;
+                };
+                match event {
+                    ServiceEvent::Connected(conn) => {
+                        // Listen for objects again using the new connection. Objects
+                        // are fetched after subscribing so none are missed.
+                        match watch_objects(&conn, service.as_str(), path.as_str()).await {
+                            Ok((proxy, stream)) => {
+                                signals = stream;
+                                let objects = get_managed_objects(&proxy).await;
+                                object_manager = Some(proxy);
+                                if !send_added(&tx, objects) {
+                                    break;
+                                }
+                            }
+                            Err(e) => {
+                                log::warn!("Failed to watch objects of {service}: {e:?}");
+                                signals = stream::pending().boxed();
+                                object_manager = None;
+                            }
+                        }
+                    }
+                    ServiceEvent::Started => {
+                        if tx.send(ObjectEvent::Started).is_err() {
+                            break;
+                        }
+                        let Some(proxy) = object_manager.as_ref() else {
+                            continue;
+                        };
+                        let objects = get_managed_objects(proxy).await;
+                        if !send_added(&tx, objects) {
+                            break;
+                        }
+                    }
+                    ServiceEvent::Stopped => {
+                        if tx.send(ObjectEvent::Stopped).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+            batch = signals.next() => {
+                let Some(batch) = batch else {
+                    // The connection was lost; wait for a new one
+                    signals = stream::pending().boxed();
+                    continue;
+                };
+                if batch.into_iter().any(|event| tx.send(event).is_err()) {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use std::{
+    future::Future,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use futures_util::StreamExt;
+use tokio::task::JoinHandle;
+use zbus::{
+    fdo::{DBusProxy, PropertiesProxy},
+    names::InterfaceName,
+    proxy::{CacheProperties, ProxyDefault},
+    zvariant::OwnedValue,
+    Connection,
+};
+
+use crate::{
+    dbus::{
+        supervisor::{connect, is_lost},
+        RunError,
+    },
+    get_dbus, get_dbus_blocking, reset_dbus,
+    resource::dispatcher::{channel, Receiver, Sender, Wakeup},
+    DBusBus, RUNTIME,
+};
+
+/// Local cache of the properties of a single DBus interface on an object.
+///
+/// Once created, the cache is filled in the tokio runtime with the current
+/// property values and kept up-to-date from `PropertiesChanged` signals, so
+/// reading a property does not require a DBus round-trip from the main thread
+/// once the cache is filled.
+/// The proxy type `P` determines the bus name and interface to cache (e.g.
+/// `TDPProxyBlocking`).
+///
+/// Property changes are queued and coalesced, so a property that changes
+/// several times between two calls to [PropertyCache::take_changes] is only
+/// reported once with its latest value.
+///
+/// If the connection to the bus is lost, the cache is emptied until the bus
+/// comes back and the cache is filled again using a new connection.
+pub struct PropertyCache<P> {
+    /// Proxy with a filled property cache. Set once the initial values are read.
+    proxy: Arc<Mutex<Option<zbus::Proxy<'static>>>>,
+    /// Bus and path of the cached object
+    bus: DBusBus,
+    path: String,
+    /// Receiver for property changes from the async runtime
+    rx: Receiver<(String, OwnedValue)>,
+    /// Running task filling the cache and listening for property changes
+    task: JoinHandle<()>,
+    proxy_type: PhantomData<P>,
+}
+
+impl<P> PropertyCache<P>
+where
+    P: ProxyDefault + From<zbus::Proxy<'static>>,
+{
+    /// Start caching the properties of the object at the given path on the
+    /// system bus. Property changes raise the given [Wakeup].
+    pub fn new(path: &str, wakeup: &Wakeup) -> Self {
+        let bus = DBusBus::System;
+        let destination = P::DESTINATION.unwrap_or_default().to_string();
+        let interface = P::INTERFACE.unwrap_or_default().to_string();
+        let path = path.to_string();
+        let proxy = Arc::new(Mutex::new(None));
+        let (tx, rx) = channel(wakeup);
+
+        let cached_proxy = proxy.clone();
+        let cached_path = path.clone();
+        let task = RUNTIME.spawn(async move {
+            loop {
+                let conn = connect(bus).await;
+                let result = run(&tx, &cached_proxy, &conn, &destination, &path, &interface).await;
+                cached_proxy.lock().unwrap().take();
+                if !is_lost(&conn).await {
+                    if let Err(e) = result {
+                        log::debug!("Failed to cache properties of {interface} on {path}: {e:?}");
+                    }
+                    return;
+                }
+
+                // Fill the cache again once the bus is back
+                log::debug!("Lost connection while caching properties of {interface} on {path}");
+                reset_dbus(bus, &conn);
+            }
+        });
+
+        Self {
+            proxy,
+            bus,
+            path: cached_path,
+            rx,
+            task,
+            proxy_type: PhantomData,
+        }
+    }
+
+    /// Returns a blocking proxy whose property getters read from the cache, or
+    /// `None` if the cache has not been filled yet.
+    pub fn proxy(&self) -> Option<P> {
+        let proxy = self.proxy.lock().unwrap().clone()?;
+        Some(P::from(proxy))
+    }
+
+    /// Returns a blocking proxy to read properties with. This is the cached
+    /// proxy once the cache is filled. Until then, properties are read from the
+    /// bus with a blocking call, so values read right after creating the cache
+    /// are the current ones instead of defaults. Returns `None` if the bus is
+    /// unreachable.
+    pub fn read_proxy(&self) -> Option<P> {
+        self.call_proxy().ok()
+    }
+
+    /// Returns a blocking proxy to call methods and set properties with. This is
+    /// the cached proxy once the cache is filled. Until then, the proxy is built
+    /// on the shared blocking connection to the bus without a property cache, so
+    /// creating it does not read every property. The shared connection is looked
+    /// up on every call, so a connection that was lost with the bus is never used.
+    pub fn call_proxy(&self) -> Result<P, RunError> {
+        if let Some(proxy) = self.proxy() {
+            return Ok(proxy);
+        }
+        let conn = get_dbus_blocking(self.bus).map_err(|_| RunError::NotConnected)?;
+        let proxy = zbus::blocking::proxy::Builder::<zbus::blocking::Proxy>::new(&conn)
+            .destination(P::DESTINATION.unwrap_or_default())?
+            .path(self.path.clone())?
+            .interface(P::INTERFACE.unwrap_or_default())?
+            .cache_properties(CacheProperties::No)
+            .build()?;
+        Ok(P::from(proxy.into_inner()))
+    }
+
+    /// Returns a future resolving to an async proxy (e.g. `TDPProxy`) to call
+    /// methods and set properties with from the async runtime. Once the cache is
+    /// filled, the proxy shares its connection and cached values. Until then, the
+    /// proxy is built without a property cache.
+    pub fn async_call_proxy<A>(&self) -> impl Future<Output = zbus::Result<A>> + Send + 'static
+    where
+        A: From<zbus::Proxy<'static>>,
+    {
+        let cached = self.proxy.lock().unwrap().clone();
+        let bus = self.bus;
+        let destination = P::DESTINATION.unwrap_or_default();
+        let interface = P::INTERFACE.unwrap_or_default();
+        let path = self.path.clone();
+        async move {
+            if let Some(proxy) = cached {
+                return Ok(A::from(proxy));
+            }
+            let conn = get_dbus(bus).await?;
+            let proxy = zbus::proxy::Builder::<zbus::Proxy>::new(&conn)
+                .destination(destination)?
+                .path(path)?
+                .interface(interface)?
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await?;
+            Ok(A::from(proxy))
+        }
+    }
+
+    /// Returns true if the cache has been filled with the current property values
+    pub fn is_ready(&self) -> bool {
+        self.proxy.lock().unwrap().is_some()
+    }
+
+    /// Returns the name and latest value of every property that changed since
+    /// the last call, in the order they first changed.
+    pub fn take_changes(&self) -> Vec<(String, OwnedValue)> {
+        let mut changes: Vec<(String, OwnedValue)> = vec![];
+        while let Ok((name, value)) = self.rx.try_recv() {
+            match changes.iter_mut().find(|(changed, _)| *changed == name) {
+                Some(change) => change.1 = value,
+                None => changes.push((name, value)),
+            }
+        }
+        changes
+    }
+}
+
+impl<P> Drop for PropertyCache<P> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Fill the cache and send property changes over the given channel until the
+/// connection is lost or nobody is listening anymore
+async fn run(
+    tx: &Sender<(String, OwnedValue)>,
+    cache: &Mutex<Option<zbus::Proxy<'static>>>,
+    conn: &Connection,
+    destination: &str,
+    path: &str,
+    interface: &str,
+) -> zbus::Result<()> {
+    let interface_name = InterfaceName::try_from(interface)?;
+
+    // Listen for changes before reading the initial values so none are missed
+    let properties = PropertiesProxy::builder(conn)
+        .destination(destination)?
+        .path(path)?
+        .build()
+        .await?;
+    let mut changes = properties.receive_properties_changed().await?;
+
+    let proxy = build_cached_proxy(conn, destination, path, interface).await?;
+    cache.lock().unwrap().replace(proxy);
+
+    while let Some(signal) = changes.next().await {
+        let args = signal.args()?;
+        if args.interface_name != interface_name {
+            continue;
+        }
+        for (name, value) in args.changed_properties.iter() {
+            let change = (name.to_string(), value.try_to_owned()?);
+            if tx.send(change).is_err() {
+                return Ok(());
+            }
+        }
+        for name in args.invalidated_properties.iter() {
+            let value = match properties.get(interface_name.clone(), name).await {
+                Ok(value) => value,
+                Err(e) => {
+                    log::debug!("Failed to read invalidated property {name}: {e:?}");
+                    continue;
+                }
+            };
+            if tx.send((name.to_string(), value)).is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Build a proxy with a filled property cache. If the service is not running,
+/// this will wait for it to start.
+async fn build_cached_proxy(
+    conn: &Connection,
+    destination: &str,
+    path: &str,
+    interface: &str,
+) -> zbus::Result<zbus::Proxy<'static>> {
+    let dbus = DBusProxy::new(conn).await?;
+    let mut owner_changed = dbus
+        .receive_name_owner_changed_with_args(&[(0, destination)])
+        .await?;
+
+    loop {
+        let result = zbus::proxy::Builder::<zbus::Proxy>::new(conn)
+            .destination(destination.to_string())?
+            .path(path.to_string())?
+            .interface(interface.to_string())?
+            .cache_properties(CacheProperties::Yes)
+            .build()
+            .await;
+        let error = match result {
+            Ok(proxy) => return Ok(proxy),
+            Err(e) => e,
+        };
+        log::trace!("Waiting for {destination} to cache properties: {error:?}");
+
+        // Try again once the service has started
+        loop {
+            let Some(signal) = owner_changed.next().await else {
+                return Err(error);
+            };
+            if signal.args()?.new_owner().is_some() {
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use zbus::{fdo::DBusProxy, names::BusName, Connection};
+
+use crate::{get_dbus, reset_dbus, DBusBus, RUNTIME};
+
+/// How long to wait between attempts to reconnect to a bus that went away
+pub const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Changes in the availability of a DBus service
+#[derive(Debug, Clone)]
+pub enum ServiceEvent {
+    /// A connection to the bus was established. This is sent when the watcher
+    /// starts and again every time the bus comes back after it went away, so any
+    /// signal subscriptions should be made again using the given connection.
+    Connected(Connection),
+    /// The service started running
+    Started,
+    /// The service stopped running or the bus it was running on went away
+    Stopped,
+}
+
+/// Watch the given well-known name on the given bus and report when the
+/// service starts or stops. If the bus itself goes away (e.g. dbus-daemon is
+/// restarted), the shared connections to it are dropped, [ServiceEvent::Stopped]
+/// is sent if the service was running, and the watcher keeps trying to reconnect
+/// every [RECONNECT_INTERVAL]. The state the service is in when the watcher
+/// starts is not reported. The watcher stops once the receiver is dropped.
+pub fn watch_service(bus: DBusBus, name: &str) -> UnboundedReceiver<ServiceEvent> {
+    let (tx, rx) = unbounded_channel();
+    let name = name.to_string();
+
+    RUNTIME.spawn(async move {
+        let mut running = None;
+        loop {
+            let conn = connect(bus).await;
+            if tx.send(ServiceEvent::Connected(conn.clone())).is_err() {
+                return;
+            }
+            match watch_owner(&tx, &conn, name.as_str(), &mut running).await {
+                Ok(false) => return,
+                Ok(true) => log::debug!("Lost connection to {bus:?} bus while watching {name}"),
+                Err(e) => log::debug!("Failed to watch {name} on {bus:?} bus: {e:?}"),
+            }
+
+            // Don't spin if the connection is fine but the name can't be watched
+            if !is_lost(&conn).await {
+                tokio::time::sleep(RECONNECT_INTERVAL).await;
+                continue;
+            }
+            reset_dbus(bus, &conn);
+            if !update(&tx, &mut running, false) {
+                return;
+            }
+        }
+    });
+
+    rx
+}
+
+/// Return a shared connection to the given bus, waiting until the bus is
+/// available
+pub async fn connect(bus: DBusBus) -> Connection {
+    loop {
+        match get_dbus(bus).await {
+            Ok(conn) => return conn,
+            Err(e) => log::trace!("Waiting for {bus:?} bus: {e:?}"),
+        }
+        tokio::time::sleep(RECONNECT_INTERVAL).await;
+    }
+}
+
+/// Returns true if the given connection to the bus no longer works
+pub async fn is_lost(conn: &Connection) -> bool {
+    let result = conn
+        .call_method(
+            Some("org.freedesktop.DBus"),
+            "/org/freedesktop/DBus",
+            Some("org.freedesktop.DBus.Peer"),
+            "Ping",
+            &(),
+        )
+        .await;
+    result.is_err()
+}
+
+/// Send service events for the owner of the given name until the connection is
+/// lost. Returns true if the connection was lost, or false if nobody is
+/// listening for events anymore.
+async fn watch_owner(
+    tx: &UnboundedSender<ServiceEvent>,
+    conn: &Connection,
+    name: &str,
+    running: &mut Option<bool>,
+) -> zbus::Result<bool> {
+    let dbus = DBusProxy::new(conn).await?;
+
+    // Listen for changes before checking the owner so none are missed
+    let mut owner_changed = dbus
+        .receive_name_owner_changed_with_args(&[(0, name)])
+        .await?;
+    let bus_name = BusName::try_from(name)?;
+    let has_owner = dbus.name_has_owner(bus_name).await?;
+    if !update(tx, running, has_owner) {
+        return Ok(false);
+    }
+
+    while let Some(signal) = owner_changed.next().await {
+        let has_owner = signal.args()?.new_owner().is_some();
+        if !update(tx, running, has_owner) {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Record whether the service is running and send an event if that changed
+/// since the last known state. Returns false if nobody is listening anymore.
+fn update(
+    tx: &UnboundedSender<ServiceEvent>,
+    running: &mut Option<bool>,
+    is_running: bool,
+) -> bool {
+    let was_running = running.replace(is_running);
+    if was_running.is_none() || was_running == Some(is_running) {
+        return !tx.is_closed();
+    }
+    let event = if is_running {
+        ServiceEvent::Started
+    } else {
+        ServiceEvent::Stopped
+    };
+    tx.send(event).is_ok()
+}
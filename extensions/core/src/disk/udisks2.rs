@@ -3,22 +3,22 @@ pub mod drive_device;
 pub mod filesystem_device;
 pub mod partition_device;
 
-use std::{
-    collections::HashMap,
-    sync::mpsc::{channel, Receiver, Sender, TryRecvError},
-    time::Duration,
-};
+use std::collections::HashMap;
 
 use block_device::BlockDevice;
 use drive_device::DriveDevice;
 use filesystem_device::FilesystemDevice;
-use futures_util::stream::StreamExt;
 use godot::{classes::Engine, obj::WithBaseField, prelude::*};
+use once_cell::sync::Lazy;
 use partition_device::PartitionDevice;
-use zbus::fdo::{ManagedObjects, ObjectManagerProxy};
 use zbus::names::BusName;
 
-use crate::{dbus::RunError, get_dbus_system, get_dbus_system_blocking, RUNTIME};
+use crate::{
+    dbus::object_watcher::{ObjectEvent, ObjectKind, ObjectWatcher},
+    get_dbus_system_blocking,
+    resource::dispatcher::Wakeup,
+    DBusBus,
+};
 
 pub const UDISKS2_BUS: &str = "org.freedesktop.UDisks2";
 const UDISKS2_PATH: &str = "/org/freedesktop/UDisks2";
@@ -38,7 +38,7 @@ const PROTECTED_MOUNTS: [&str; 10] = [
 ];
 
 /// Supported UDisks2 DBus objects
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum ObjectType {
     Block,
     Drive,
@@ -46,44 +46,27 @@ enum ObjectType {
     Filesystem,
 }
 
-impl ObjectType {
-    /// Returns the object type(s) from the list of implemented interfaces
-    fn from_ifaces(ifaces: Vec<String>) -> Vec<Self> {
-        let mut types = vec![];
-
-        for iface in ifaces {
-            if iface.as_str() == "org.freedesktop.UDisks2.Drive" {
-                types.push(Self::Drive);
-            }
-            if iface.as_str() == "org.freedesktop.UDisks2.Block" {
-                types.push(Self::Block);
-            }
-            if iface.as_str() == "org.freedesktop.UDisks2.Partition" {
-                types.push(Self::Partition);
-            }
-            if iface.as_str() == "org.freedesktop.UDisks2.Filesystem" {
-                types.push(Self::Filesystem);
-            }
+impl ObjectKind for ObjectType {
+    fn from_interface(iface: &str) -> Option<Self> {
+        match iface {
+            "org.freedesktop.UDisks2.Drive" => Some(Self::Drive),
+            "org.freedesktop.UDisks2.Block" => Some(Self::Block),
+            "org.freedesktop.UDisks2.Partition" => Some(Self::Partition),
+            "org.freedesktop.UDisks2.Filesystem" => Some(Self::Filesystem),
+            _ => None,
         }
-
-        types
     }
 }
 
-/// Signals that can be emitted
-#[derive(Debug)]
-enum Signal {
-    Started,
-    Stopped,
-    ObjectAdded { path: String, ifaces: Vec<String> },
-    ObjectRemoved { path: String, ifaces: Vec<String> },
-}
+/// Raised when the UDisks2 manager or any of its objects has events waiting to
+/// be dispatched by [UDisks2Instance::process]
+static WAKEUP: Lazy<Wakeup> = Lazy::new(Wakeup::default);
 
 #[derive(GodotClass)]
 #[class(base=Resource)]
 pub struct UDisks2Instance {
     base: Base<Resource>,
-    rx: Receiver<Signal>,
+    objects: ObjectWatcher<ObjectType>,
     conn: Option<zbus::blocking::Connection>,
     drive_devices: HashMap<String, Gd<DriveDevice>>,
     block_devices: HashMap<String, Gd<BlockDevice>>,
@@ -144,27 +127,6 @@ impl UDisks2Instance {
         dbus.name_has_owner(bus.clone()).unwrap_or_default()
     }
 
-    /// Returns a HashMap of all the objects managed by this dbus interface
-    fn get_managed_objects(&self) -> Result<ManagedObjects, zbus::fdo::Error> {
-        let Some(conn) = self.conn.as_ref() else {
-            return Err(zbus::fdo::Error::Disconnected(
-                "No DBus connection found".into(),
-            ));
-        };
-
-        let bus = BusName::from_static_str(UDISKS2_BUS).unwrap();
-        let object_manager = zbus::blocking::fdo::ObjectManagerProxy::builder(conn)
-            .destination(bus)
-            .ok()
-            .and_then(|builder| builder.path(UDISKS2_PATH).ok())
-            .and_then(|builder| builder.build().ok());
-        let Some(object_manager) = object_manager else {
-            return Ok(ManagedObjects::new());
-        };
-
-        object_manager.get_managed_objects()
-    }
-
     /// Returns a HashMap of all the objects managed by this dbus interface that don't have
     /// [FilesystemDevice] objects with mounts in [PROTECTED_MOUNTS]
     #[func]
@@ -208,26 +170,41 @@ impl UDisks2Instance {
         unprotected_devices
     }
 
-    /// Process UDisks2 signals and emit them as Godot signals. This method
-    /// should be called every frame in the "_process" loop of a node.
+    /// Process UDisks2 signals and emit them as Godot signals. This method is
+    /// called by the [ResourceProcessor] on frames where any of the objects of
+    /// this manager sent events, once the manager is registered with
+    /// [method ResourceRegistry.register_on_event].
     #[func]
-    fn process(&mut self) {
-        let mut state_updated = false;
-        // Drain all messages from the channel to process them
-        loop {
-            let signal = match self.rx.try_recv() {
-                Ok(value) => value,
-                Err(e) => match e {
-                    TryRecvError::Empty => break,
-                    TryRecvError::Disconnected => {
-                        log::error!("Backend thread is not running!");
-                        return;
-                    }
-                },
-            };
-            state_updated = true;
-            self.process_signal(signal);
+    fn process(&mut self, _delta: f64) {
+        // Report this manager to the registry whenever its objects send events
+        WAKEUP.set_owner(self.base().instance_id().to_i64());
+
+        // Skip frames where none of the objects of this manager sent any events
+        if !WAKEUP.take() {
+            return;
         }
+
+        // Dispatch any objects that were added or removed
+        let events = self.objects.take_events();
+        let state_updated = !events.is_empty();
+        for event in events {
+            self.process_event(event);
+        }
+
+        // Process signals from tracked devices
+        for (_, device) in self.drive_devices.iter_mut() {
+            device.bind_mut().process();
+        }
+        for (_, device) in self.block_devices.iter_mut() {
+            device.bind_mut().process();
+        }
+        for (_, device) in self.partition_devices.iter_mut() {
+            device.bind_mut().process();
+        }
+        for (_, device) in self.filesystem_devices.iter_mut() {
+            device.bind_mut().process();
+        }
+
         if !state_updated {
             return;
         }
@@ -238,74 +215,65 @@ impl UDisks2Instance {
         );
     }
 
-    /// Process and dispatch the given signal
-    fn process_signal(&mut self, signal: Signal) {
-        match signal {
-            Signal::Started => {
+    /// Process and dispatch the given object event
+    fn process_event(&mut self, event: ObjectEvent<ObjectType>) {
+        match event {
+            ObjectEvent::Started => {
+                // The service or the bus may have restarted, so reconnect
+                self.conn = get_dbus_system_blocking().ok();
                 self.base_mut().emit_signal("started", &[]);
             }
-            Signal::Stopped => {
+            ObjectEvent::Stopped => {
                 self.base_mut().emit_signal("stopped", &[]);
             }
-            Signal::ObjectAdded { path, ifaces } => {
-                let obj_types = ObjectType::from_ifaces(ifaces);
-                for obj_type in obj_types {
-                    match obj_type {
-                        ObjectType::Block => {
-                            let block = BlockDevice::new(path.as_str());
-                            self.block_devices.insert(path.clone(), block.clone());
-                            self.base_mut()
-                                .emit_signal("block_device_added", &[block.to_variant()]);
-                        }
-                        ObjectType::Drive => {
-                            let drive = DriveDevice::new(path.as_str());
-                            self.drive_devices.insert(path.clone(), drive.clone());
-                            self.base_mut()
-                                .emit_signal("drive_device_added", &[drive.to_variant()]);
-                        }
-                        ObjectType::Partition => {
-                            let partition = PartitionDevice::new(path.as_str());
-                            self.partition_devices
-                                .insert(path.clone(), partition.clone());
-                            self.base_mut()
-                                .emit_signal("partition_added", &[partition.to_variant()]);
-                        }
-                        ObjectType::Filesystem => {
-                            let fs = FilesystemDevice::new(path.as_str());
-                            self.filesystem_devices.insert(path.clone(), fs.clone());
-                            self.base_mut()
-                                .emit_signal("filesystem_added", &[fs.to_variant()]);
-                        }
-                    }
+            ObjectEvent::Added { path, kind } => match kind {
+                ObjectType::Block => {
+                    let block = BlockDevice::new(path.as_str());
+                    self.block_devices.insert(path, block.clone());
+                    self.base_mut()
+                        .emit_signal("block_device_added", &[block.to_variant()]);
                 }
-            }
-            Signal::ObjectRemoved { path, ifaces } => {
-                let obj_types = ObjectType::from_ifaces(ifaces);
-                for obj_type in obj_types {
-                    match obj_type {
-                        ObjectType::Block => {
-                            self.block_devices.remove(&path);
-                            self.base_mut()
-                                .emit_signal("block_device_removed", &[path.to_variant()]);
-                        }
-                        ObjectType::Drive => {
-                            self.drive_devices.remove(&path);
-                            self.base_mut()
-                                .emit_signal("drive_device_removed", &[path.to_variant()]);
-                        }
-                        ObjectType::Partition => {
-                            self.partition_devices.remove(&path);
-                            self.base_mut()
-                                .emit_signal("partition_removed", &[path.to_variant()]);
-                        }
-                        ObjectType::Filesystem => {
-                            self.filesystem_devices.remove(&path);
-                            self.base_mut()
-                                .emit_signal("filesystem_removed", &[path.to_variant()]);
-                        }
-                    }
+                ObjectType::Drive => {
+                    let drive = DriveDevice::new(path.as_str());
+                    self.drive_devices.insert(path, drive.clone());
+                    self.base_mut()
+                        .emit_signal("drive_device_added", &[drive.to_variant()]);
                 }
-            }
+                ObjectType::Partition => {
+                    let partition = PartitionDevice::new(path.as_str());
+                    self.partition_devices.insert(path, partition.clone());
+                    self.base_mut()
+                        .emit_signal("partition_added", &[partition.to_variant()]);
+                }
+                ObjectType::Filesystem => {
+                    let fs = FilesystemDevice::new(path.as_str());
+                    self.filesystem_devices.insert(path, fs.clone());
+                    self.base_mut()
+                        .emit_signal("filesystem_added", &[fs.to_variant()]);
+                }
+            },
+            ObjectEvent::Removed { path, kind } => match kind {
+                ObjectType::Block => {
+                    self.block_devices.remove(&path);
+                    self.base_mut()
+                        .emit_signal("block_device_removed", &[path.to_variant()]);
+                }
+                ObjectType::Drive => {
+                    self.drive_devices.remove(&path);
+                    self.base_mut()
+                        .emit_signal("drive_device_removed", &[path.to_variant()]);
+                }
+                ObjectType::Partition => {
+                    self.partition_devices.remove(&path);
+                    self.base_mut()
+                        .emit_signal("partition_removed", &[path.to_variant()]);
+                }
+                ObjectType::Filesystem => {
+                    self.filesystem_devices.remove(&path);
+                    self.base_mut()
+                        .emit_signal("filesystem_removed", &[path.to_variant()]);
+                }
+            },
         }
     }
 }
@@ -316,8 +284,6 @@ impl IResource for UDisks2Instance {
     fn init(base: Base<Self::Base>) -> Self {
         log::debug!("Initializing UDisks2 instance");
 
-        // Create a channel to communicate with the service
-        let (tx, rx) = channel();
         let conn = get_dbus_system_blocking().ok();
 
         // Don't run in the editor
@@ -325,7 +291,7 @@ impl IResource for UDisks2Instance {
         if engine.is_editor_hint() {
             return Self {
                 base,
-                rx,
+                objects: Default::default(),
                 conn,
                 block_devices: Default::default(),
                 drive_devices: Default::default(),
@@ -334,161 +300,40 @@ impl IResource for UDisks2Instance {
             };
         }
 
-        // Spawn a task using the shared tokio runtime to listen for signals
-        RUNTIME.spawn(async move {
-            if let Err(e) = run(tx).await {
-                log::error!("Failed to run UDisks2 task: ${e:?}");
-            }
-        });
-
-        // Create a new UDisks2 instance
-        let mut instance = Self {
-            base,
-            rx,
-            conn,
-            block_devices: HashMap::new(),
-            drive_devices: HashMap::new(),
-            partition_devices: HashMap::new(),
-            filesystem_devices: HashMap::new(),
-        };
+        // Watch for drives, block devices, partitions and filesystems
+        let objects = ObjectWatcher::new(DBusBus::System, UDISKS2_BUS, UDISKS2_PATH, &WAKEUP);
 
-        // Perform initial object discovery
+        // Track the objects that were discovered
         let mut block_devices = HashMap::new();
         let mut drive_devices = HashMap::new();
         let mut partition_devices = HashMap::new();
         let mut filesystem_devices = HashMap::new();
-        let objects = instance.get_managed_objects().unwrap_or_default();
-        for (path, ifaces) in objects.into_iter() {
-            let path = path.to_string();
-            let ifaces: Vec<String> = ifaces.into_keys().map(|v| v.to_string()).collect();
-            let obj_types = ObjectType::from_ifaces(ifaces);
-
-            for obj_type in obj_types {
-                match obj_type {
-                    ObjectType::Block => {
-                        let block = BlockDevice::new(path.as_str());
-                        block_devices.insert(path.clone(), block);
-                    }
-                    ObjectType::Drive => {
-                        let drive = DriveDevice::new(path.as_str());
-                        drive_devices.insert(path.clone(), drive);
-                    }
-                    ObjectType::Partition => {
-                        let partition = PartitionDevice::new(path.as_str());
-                        partition_devices.insert(path.clone(), partition);
-                    }
-                    ObjectType::Filesystem => {
-                        let fs = FilesystemDevice::new(path.as_str());
-                        filesystem_devices.insert(path.clone(), fs);
-                    }
+        for (path, kind) in objects.objects() {
+            match kind {
+                ObjectType::Block => {
+                    block_devices.insert(path.to_string(), BlockDevice::new(path));
                 }
-            }
-        }
-        instance.block_devices = block_devices;
-        instance.drive_devices = drive_devices;
-        instance.partition_devices = partition_devices;
-        instance.filesystem_devices = filesystem_devices;
-
-        instance
-    }
-}
-
-/// Runs UDisks2 tasks in Tokio to listen for DBus signals and send them
-/// over the given channel so they can be processed during each engine frame.
-async fn run(tx: Sender<Signal>) -> Result<(), RunError> {
-    log::debug!("Spawning UDisks2 tasks");
-    // Establish a connection to the system bus
-    let conn = get_dbus_system().await?;
-
-    // Spawn a task to listen for UDisks2 start/stop
-    let dbus_conn = conn.clone();
-    let signals_tx = tx.clone();
-    RUNTIME.spawn(async move {
-        let bus = BusName::from_static_str(UDISKS2_BUS).unwrap();
-        let mut is_running = {
-            let dbus = zbus::fdo::DBusProxy::new(&dbus_conn).await.ok();
-            let Some(dbus) = dbus else {
-                return;
-            };
-            dbus.name_has_owner(bus.clone()).await.unwrap_or_default()
-        };
-
-        loop {
-            let dbus = zbus::fdo::DBusProxy::new(&dbus_conn).await.ok();
-            let Some(dbus) = dbus else {
-                break;
-            };
-            let running = dbus.name_has_owner(bus.clone()).await.unwrap_or_default();
-            if running != is_running {
-                let signal = if running {
-                    Signal::Started
-                } else {
-                    Signal::Stopped
-                };
-                if signals_tx.send(signal).is_err() {
-                    break;
+                ObjectType::Drive => {
+                    drive_devices.insert(path.to_string(), DriveDevice::new(path));
                 }
-            }
-            is_running = running;
-            tokio::time::sleep(Duration::from_secs(5)).await;
-        }
-    });
-
-    // Get a proxy instance to ObjectManager
-    let bus = BusName::from_static_str(UDISKS2_BUS).unwrap();
-    let object_manager: ObjectManagerProxy = ObjectManagerProxy::builder(&conn)
-        .destination(bus)?
-        .path(UDISKS2_PATH)?
-        .build()
-        .await?;
-
-    // Spawn a task to listen for objects added
-    let mut ifaces_added = object_manager.receive_interfaces_added().await?;
-    let signals_tx = tx.clone();
-    RUNTIME.spawn(async move {
-        while let Some(signal) = ifaces_added.next().await {
-            let args = match signal.args() {
-                Ok(args) => args,
-                Err(e) => {
-                    log::warn!("Failed to get signal args: ${e:?}");
-                    continue;
+                ObjectType::Partition => {
+                    partition_devices.insert(path.to_string(), PartitionDevice::new(path));
                 }
-            };
-
-            let path = args.object_path.to_string();
-            let ifaces = args
-                .interfaces_and_properties
-                .keys()
-                .map(|v| v.to_string())
-                .collect();
-            let signal = Signal::ObjectAdded { path, ifaces };
-            if signals_tx.send(signal).is_err() {
-                break;
-            }
-        }
-    });
-
-    // Spawn a task to listen for objects removed
-    let mut ifaces_removed = object_manager.receive_interfaces_removed().await?;
-    let signals_tx = tx.clone();
-    RUNTIME.spawn(async move {
-        while let Some(signal) = ifaces_removed.next().await {
-            let args = match signal.args() {
-                Ok(args) => args,
-                Err(e) => {
-                    log::warn!("Failed to get signal args: ${e:?}");
-                    continue;
+                ObjectType::Filesystem => {
+                    filesystem_devices.insert(path.to_string(), FilesystemDevice::new(path));
                 }
-            };
-
-            let path = args.object_path.to_string();
-            let ifaces = args.interfaces.iter().map(|v| v.to_string()).collect();
-            let signal = Signal::ObjectRemoved { path, ifaces };
-            if signals_tx.send(signal).is_err() {
-                break;
             }
         }
-    });
 
-    Ok(())
+        // Create a new UDisks2 instance
+        Self {
+            base,
+            objects,
+            conn,
+            block_devices,
+            drive_devices,
+            partition_devices,
+            filesystem_devices,
+        }
+    }
 }
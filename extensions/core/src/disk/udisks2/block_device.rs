@@ -1,22 +1,27 @@
 use byte_unit::{Byte, UnitType};
+use zbus::proxy::CacheProperties;
+
 use godot::prelude::*;
 
 use godot::classes::{Resource, ResourceLoader};
 
+use crate::dbus::property_cache::PropertyCache;
 use crate::dbus::udisks2::{
     block::BlockProxyBlocking, partition_table::PartitionTableProxyBlocking,
 };
+use crate::dbus::{impl_last_error, GodotVariant, LastError, RunError};
 use crate::get_dbus_system_blocking;
 
 use super::drive_device::DriveDevice;
 use super::partition_device::PartitionDevice;
-use super::UDISKS2_BUS;
+use super::{UDISKS2_BUS, WAKEUP};
 
 #[derive(GodotClass)]
 #[class(no_init, base=Resource)]
 pub struct BlockDevice {
     base: Base<Resource>,
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<BlockProxyBlocking<'static>>,
+    last_error: LastError,
 
     #[allow(dead_code)]
     #[var(get = get_dbus_path)]
@@ -27,52 +32,54 @@ pub struct BlockDevice {
     readable_size: GString,
 }
 
+impl_last_error!(BlockDevice);
+
 #[godot_api]
 impl BlockDevice {
     #[signal]
     fn updated();
 
+    /// Emitted when a property of the block device changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Create a new [BlockDevice] with the given DBus path
     pub fn from_path(path: GString) -> Gd<Self> {
         // Create a channel to communicate with the signals task
         log::debug!("BlockDevice created with path: {path}");
 
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
+            let cache = PropertyCache::new(path.to_string().as_str(), &WAKEUP);
 
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
-                conn,
+                cache,
+                last_error: Default::default(),
                 dbus_path: path,
                 readable_size: Default::default(),
             }
         })
     }
 
-    /// Return a proxy instance to the device
+    /// Return a proxy instance to the device whose property getters read from
+    /// the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<BlockProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            let path: String = self.dbus_path.clone().into();
-            BlockProxyBlocking::builder(conn)
-                .path(path)
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
     }
 
     /// Return a proxy instance to the partition table dbus interface
     fn get_partition_table_proxy(&self) -> Option<PartitionTableProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
+        if let Ok(conn) = get_dbus_system_blocking() {
             let path: String = self.dbus_path.clone().into();
-            PartitionTableProxyBlocking::builder(conn)
+            PartitionTableProxyBlocking::builder(&conn)
                 .path(path)
                 .ok()
+                .map(|builder| builder.cache_properties(CacheProperties::No))
                 .and_then(|builder| builder.build().ok())
         } else {
+            self.last_error
+                .report(self.to_gd().upcast(), RunError::NotConnected);
             None
         }
     }
@@ -110,7 +117,7 @@ impl BlockDevice {
             return array![];
         };
 
-        let partitions = proxy.partitions().unwrap_or_default();
+        let partitions = self.last_error.check(self, proxy.partitions());
         for partition in partitions {
             let path = partition.as_str();
             let partition_device = PartitionDevice::new(path);
@@ -145,6 +152,16 @@ impl BlockDevice {
         let size = Byte::from_u64(size_bytes).get_appropriate_unit(UnitType::Decimal);
         format!("{size:.2}").to_godot()
     }
+
+    /// Dispatches signals
+    pub fn process(&mut self) {
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+    }
 }
 
 impl Drop for BlockDevice {
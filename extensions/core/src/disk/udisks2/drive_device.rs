@@ -2,22 +2,26 @@ use godot::prelude::*;
 
 use godot::classes::{Resource, ResourceLoader};
 
+use crate::dbus::property_cache::PropertyCache;
 use crate::dbus::udisks2::drive::DriveProxyBlocking;
-use crate::get_dbus_system_blocking;
+use crate::dbus::{impl_last_error, GodotVariant, LastError};
 
-use super::UDISKS2_BUS;
+use super::{UDISKS2_BUS, WAKEUP};
 
 #[derive(GodotClass)]
 #[class(no_init, base=Resource)]
 pub struct DriveDevice {
     base: Base<Resource>,
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<DriveProxyBlocking<'static>>,
+    last_error: LastError,
 
     #[allow(dead_code)]
     #[var(get = get_dbus_path)]
     dbus_path: GString,
 }
 
+impl_last_error!(DriveDevice);
+
 #[godot_api]
 impl DriveDevice {
     #[constant]
@@ -36,35 +40,32 @@ impl DriveDevice {
     #[signal]
     fn updated();
 
+    /// Emitted when a property of the drive changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Create a new [DriveDevice] with the given DBus path
     pub fn from_path(path: GString) -> Gd<Self> {
         // Create a channel to communicate with the signals task
         log::debug!("DriveDevice created with path: {path}");
 
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
+            let cache = PropertyCache::new(path.to_string().as_str(), &WAKEUP);
 
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
-                conn,
+                cache,
+                last_error: Default::default(),
                 dbus_path: path,
             }
         })
     }
 
-    /// Return a proxy instance to the device
+    /// Return a proxy instance to the device whose property getters read from
+    /// the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<DriveProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            let path: String = self.dbus_path.clone().into();
-            DriveProxyBlocking::builder(conn)
-                .path(path)
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
     }
 
     /// Get or create a [DriveDevice] with the given DBus path. If an instance
@@ -98,14 +99,17 @@ impl DriveDevice {
         let Some(proxy) = self.get_proxy() else {
             return DriveDevice::INTERFACE_TYPE_UNKNOWN;
         };
-        let Ok(connection) = proxy.connection_bus() else {
+        let Some(connection) = self
+            .last_error
+            .check(self, proxy.connection_bus().map(Some))
+        else {
             return DriveDevice::INTERFACE_TYPE_UNKNOWN;
         };
         match connection.as_str() {
             "usb" => DriveDevice::INTERFACE_TYPE_USB,
             "sdio" => DriveDevice::INTERFACE_TYPE_SD,
             "" => {
-                let Ok(sort_key) = proxy.sort_key() else {
+                let Some(sort_key) = self.last_error.check(self, proxy.sort_key().map(Some)) else {
                     return DriveDevice::INTERFACE_TYPE_UNKNOWN;
                 };
                 if sort_key.contains("hotplug") || sort_key.contains("removable") {
@@ -113,7 +117,8 @@ impl DriveDevice {
                 } else if sort_key.contains("nvme") {
                     return DriveDevice::INTERFACE_TYPE_NVME;
                 } else if sort_key.contains("sd_") {
-                    let Ok(rotation_rate) = proxy.rotation_rate() else {
+                    let result = proxy.rotation_rate().map(Some);
+                    let Some(rotation_rate) = self.last_error.check(self, result) else {
                         return DriveDevice::INTERFACE_TYPE_UNKNOWN;
                     };
                     if rotation_rate > 0 {
@@ -132,6 +137,16 @@ impl DriveDevice {
     pub fn get_dbus_path(&self) -> GString {
         self.dbus_path.clone()
     }
+
+    /// Dispatches signals
+    pub fn process(&mut self) {
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+    }
 }
 
 impl Drop for DriveDevice {
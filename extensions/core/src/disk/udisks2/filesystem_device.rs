@@ -2,56 +2,57 @@ use godot::prelude::*;
 
 use godot::classes::{Resource, ResourceLoader};
 
+use crate::dbus::property_cache::PropertyCache;
 use crate::dbus::udisks2::filesystem::FilesystemProxyBlocking;
-use crate::get_dbus_system_blocking;
+use crate::dbus::{impl_last_error, GodotVariant, LastError};
 
-use super::UDISKS2_BUS;
+use super::{UDISKS2_BUS, WAKEUP};
 
 #[derive(GodotClass)]
 #[class(no_init, base=Resource)]
 pub struct FilesystemDevice {
     base: Base<Resource>,
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<FilesystemProxyBlocking<'static>>,
+    last_error: LastError,
 
     #[allow(dead_code)]
     #[var(get = get_dbus_path)]
     dbus_path: GString,
 }
 
+impl_last_error!(FilesystemDevice);
+
 #[godot_api]
 impl FilesystemDevice {
     #[signal]
     fn updated();
 
+    /// Emitted when a property of the filesystem changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Create a new [FilesystemDevice] with the given DBus path
     pub fn from_path(path: GString) -> Gd<Self> {
         // Create a channel to communicate with the signals task
         log::debug!("FilesystemDevice created with path: {path}");
 
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
+            let cache = PropertyCache::new(path.to_string().as_str(), &WAKEUP);
 
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
-                conn,
+                cache,
+                last_error: Default::default(),
                 dbus_path: path,
             }
         })
     }
 
-    /// Return a proxy instance to the device
+    /// Return a proxy instance to the device whose property getters read from
+    /// the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<FilesystemProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            let path: String = self.dbus_path.clone().into();
-            FilesystemProxyBlocking::builder(conn)
-                .path(path)
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
     }
 
     /// Get or create a [FilesystemDevice] with the given DBus path. If an instance
@@ -86,7 +87,7 @@ impl FilesystemDevice {
             return Default::default();
         };
         let mut mount_points = PackedStringArray::new();
-        let mount_points_bytes = proxy.mount_points().unwrap_or_default();
+        let mount_points_bytes = self.last_error.check(self, proxy.mount_points());
         for mount_point_bytes in mount_points_bytes {
             let mount_point = String::from_utf8_lossy(mount_point_bytes.as_slice());
             mount_points.push(mount_point.to_string().as_str());
@@ -100,6 +101,16 @@ impl FilesystemDevice {
     pub fn get_dbus_path(&self) -> GString {
         self.dbus_path.clone()
     }
+
+    /// Dispatches signals
+    pub fn process(&mut self) {
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+    }
 }
 
 impl Drop for FilesystemDevice {
@@ -1,22 +1,27 @@
 use byte_unit::{Byte, UnitType};
+use zbus::proxy::CacheProperties;
+
 use godot::prelude::*;
 
 use godot::classes::{Resource, ResourceLoader};
 
+use crate::dbus::property_cache::PropertyCache;
 use crate::dbus::udisks2::{
     block::BlockProxyBlocking, filesystem::FilesystemProxyBlocking,
     partition::PartitionProxyBlocking,
 };
+use crate::dbus::{impl_last_error, GodotVariant, LastError, RunError};
 use crate::get_dbus_system_blocking;
 
 use super::filesystem_device::FilesystemDevice;
-use super::UDISKS2_BUS;
+use super::{UDISKS2_BUS, WAKEUP};
 
 #[derive(GodotClass)]
 #[class(no_init, base=Resource)]
 pub struct PartitionDevice {
     base: Base<Resource>,
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<PartitionProxyBlocking<'static>>,
+    last_error: LastError,
 
     #[allow(dead_code)]
     #[var(get = get_filesystem_type)]
@@ -35,24 +40,30 @@ pub struct PartitionDevice {
     readable_size: GString,
 }
 
+impl_last_error!(PartitionDevice);
+
 #[godot_api]
 impl PartitionDevice {
     #[signal]
     fn updated();
 
+    /// Emitted when a property of the partition changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Create a new [PartitionDevice] with the given DBus path
     pub fn from_path(path: GString) -> Gd<Self> {
         // Create a channel to communicate with the signals task
         log::debug!("PartitionDevice created with path: {path}");
 
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
+            let cache = PropertyCache::new(path.to_string().as_str(), &WAKEUP);
 
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
-                conn,
+                cache,
+                last_error: Default::default(),
                 dbus_path: path,
                 filesystem_type: Default::default(),
                 partition_name: Default::default(),
@@ -62,39 +73,38 @@ impl PartitionDevice {
     }
     /// Return a proxy instance to the block device dbus interface
     fn get_block_proxy(&self) -> Option<BlockProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
+        if let Ok(conn) = get_dbus_system_blocking() {
             let path: String = self.dbus_path.clone().into();
-            BlockProxyBlocking::builder(conn)
+            BlockProxyBlocking::builder(&conn)
                 .path(path)
                 .ok()
+                .map(|builder| builder.cache_properties(CacheProperties::No))
                 .and_then(|builder| builder.build().ok())
         } else {
+            self.last_error
+                .report(self.to_gd().upcast(), RunError::NotConnected);
             None
         }
     }
 
-    /// Return a proxy instance to the partition dbus interface
+    /// Return a proxy instance to the partition dbus interface whose property
+    /// getters read from the cache, or from the bus until the cache has been filled
     fn get_partition_proxy(&self) -> Option<PartitionProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            let path: String = self.dbus_path.clone().into();
-            PartitionProxyBlocking::builder(conn)
-                .path(path)
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
     }
 
     /// Return a proxy instance to the filesystem dbus interface
     fn get_filesystem_proxy(&self) -> Option<FilesystemProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
+        if let Ok(conn) = get_dbus_system_blocking() {
             let path: String = self.dbus_path.clone().into();
-            FilesystemProxyBlocking::builder(conn)
+            FilesystemProxyBlocking::builder(&conn)
                 .path(path)
                 .ok()
+                .map(|builder| builder.cache_properties(CacheProperties::No))
                 .and_then(|builder| builder.build().ok())
         } else {
+            self.last_error
+                .report(self.to_gd().upcast(), RunError::NotConnected);
             None
         }
     }
@@ -146,7 +156,7 @@ impl PartitionDevice {
         let Some(proxy) = self.get_block_proxy() else {
             return Default::default();
         };
-        proxy.id_type().unwrap_or_default().to_godot()
+        self.last_error.check(self, proxy.id_type()).to_godot()
     }
 
     /// Return the name of the [PartitionDevice]
@@ -155,7 +165,7 @@ impl PartitionDevice {
         let Some(proxy) = self.get_partition_proxy() else {
             return Default::default();
         };
-        proxy.name().unwrap_or_default().to_godot()
+        self.last_error.check(self, proxy.name()).to_godot()
     }
 
     /// Return the size type of the [PartitionDevice] as a human readable String
@@ -169,6 +179,16 @@ impl PartitionDevice {
         let size = Byte::from_u64(size_bytes).get_appropriate_unit(UnitType::Decimal);
         format!("{size:.2}").to_godot()
     }
+
+    /// Dispatches signals
+    pub fn process(&mut self) {
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+    }
 }
 
 impl Drop for PartitionDevice {
@@ -1,17 +1,66 @@
+pub mod app_settings;
+pub mod app_tracker;
+pub mod clipboard;
+pub mod color;
+pub mod ping;
+pub mod screenshots;
+pub mod thumbnail;
 pub mod x11_client;
+pub mod x11_connection;
+pub mod x11_input;
 
+use app_settings::GamescopeAppSettings;
+use app_tracker::GamescopeAppTracker;
+use clipboard::GamescopeClipboard;
+use color::GamescopeColor;
+use screenshots::GamescopeScreenshots;
 use std::collections::HashMap;
 use std::env;
-use x11_client::GamescopeXWayland;
+use std::time::Duration;
+use x11_client::{GamescopeXWayland, WindowEvent};
 
 use godot::prelude::*;
+use once_cell::sync::Lazy;
 
 use godot::classes::{Engine, Resource};
 
+use crate::resource::dispatcher::{channel, Receiver, Sender, TryRecvError, Wakeup};
+use crate::RUNTIME;
+
+/// How often to look for XWayland displays that Gamescope created or removed
+const DISCOVERY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Signals that can be emitted
+#[derive(Debug)]
+enum Signal {
+    DisplaysDiscovered { displays: Vec<String> },
+}
+
+/// Resolution of the game XWayland and display refresh rate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct DisplaySettings {
+    /// Resolution of the game XWayland, or (0, 0) to leave it unchanged
+    resolution: (u32, u32),
+    /// Display refresh rate, or 0 for the native refresh rate
+    refresh_rate: u32,
+}
+
+/// Raised when the Gamescope manager or any of its objects has events waiting to
+/// be dispatched by [GamescopeInstance::process]
+static WAKEUP: Lazy<Wakeup> = Lazy::new(Wakeup::default);
+
 #[derive(GodotClass)]
 #[class(base=Resource)]
 pub struct GamescopeInstance {
     base: Base<Resource>,
+    rx: Receiver<Signal>,
+    app_tracker: Gd<GamescopeAppTracker>,
+    app_settings: Gd<GamescopeAppSettings>,
+    clipboard: Gd<GamescopeClipboard>,
+    color: Gd<GamescopeColor>,
+    screenshots: Gd<GamescopeScreenshots>,
+    display_overrides: HashMap<u32, DisplaySettings>,
+    display_restore: Option<DisplaySettings>,
     xwaylands: HashMap<String, Gd<GamescopeXWayland>>,
     xwayland_primary: String,
     xwayland_ogui: String,
@@ -37,6 +86,19 @@ impl GamescopeInstance {
     #[constant]
     const OVERLAY_GAME_ID: u32 = 769;
 
+    /// Emitted when a new XWayland display (e.g. ":1") is discovered
+    #[signal]
+    fn xwayland_added(name: GString);
+
+    /// Emitted when an XWayland display goes away or its connection is lost
+    #[signal]
+    fn xwayland_removed(name: GString);
+
+    /// Emitted when a different XWayland display (or none, if the name is
+    /// empty) is assigned to the given XWAYLAND_TYPE_*
+    #[signal]
+    fn xwayland_type_updated(kind: u32, name: GString);
+
     /// Return the Gamescope XWayland of the given type.
     #[func]
     pub fn get_xwayland(&self, kind: u32) -> Option<Gd<GamescopeXWayland>> {
@@ -57,6 +119,112 @@ impl GamescopeInstance {
         }
     }
 
+    /// Return the [GamescopeAppTracker] that tracks apps running on the game
+    /// XWayland display
+    #[func]
+    pub fn get_app_tracker(&self) -> Gd<GamescopeAppTracker> {
+        self.app_tracker.clone()
+    }
+
+    /// Return the [GamescopeAppSettings] that stores and applies Gamescope
+    /// settings for each app
+    #[func]
+    pub fn get_app_settings(&self) -> Gd<GamescopeAppSettings> {
+        self.app_settings.clone()
+    }
+
+    /// Return the [GamescopeClipboard] that bridges the clipboard between the
+    /// OpenGamepadUI and game XWayland displays
+    #[func]
+    pub fn get_clipboard(&self) -> Gd<GamescopeClipboard> {
+        self.clipboard.clone()
+    }
+
+    /// Return the [GamescopeScreenshots] that collects screenshots taken by
+    /// Gamescope
+    #[func]
+    pub fn get_screenshots(&self) -> Gd<GamescopeScreenshots> {
+        self.screenshots.clone()
+    }
+
+    /// Return the [GamescopeColor] that controls colour management on the
+    /// primary XWayland display
+    #[func]
+    pub fn get_color(&self) -> Gd<GamescopeColor> {
+        self.color.clone()
+    }
+
+    /// Returns the resolution of the game XWayland display
+    #[func]
+    pub fn get_game_resolution(&self) -> Vector2i {
+        let Some(xwayland) = self.xwaylands.get(&self.xwayland_game) else {
+            return Vector2i::ZERO;
+        };
+        xwayland.bind().get_resolution()
+    }
+
+    /// Asks Gamescope to change the resolution of the game XWayland display
+    #[func]
+    pub fn set_game_resolution(&self, width: u32, height: u32) -> i32 {
+        let Some(primary) = self.xwaylands.get(&self.xwayland_primary) else {
+            log::error!("No primary XWayland display to change the resolution with");
+            return -1;
+        };
+        let Some(server) = self.xwayland_server_index(&self.xwayland_game) else {
+            log::error!("No game XWayland display to change the resolution of");
+            return -1;
+        };
+        primary
+            .bind()
+            .set_xwayland_resolution(server, width, height, false)
+    }
+
+    /// Use the given game resolution and refresh rate while the given app is
+    /// focused. A zero resolution or refresh rate leaves that setting as it
+    /// was before the app was focused.
+    #[func]
+    pub fn set_app_display_override(
+        &mut self,
+        app_id: u32,
+        resolution: Vector2i,
+        refresh_rate: u32,
+    ) {
+        let settings = DisplaySettings {
+            resolution: (resolution.x.max(0) as u32, resolution.y.max(0) as u32),
+            refresh_rate,
+        };
+        self.display_overrides.insert(app_id, settings);
+        if self.get_focused_app() == app_id {
+            self.apply_display_override(app_id);
+        }
+    }
+
+    /// Stop overriding the game resolution and refresh rate for the given app
+    #[func]
+    pub fn remove_app_display_override(&mut self, app_id: u32) {
+        if self.display_overrides.remove(&app_id).is_none() {
+            return;
+        }
+        if self.get_focused_app() == app_id {
+            self.apply_display_override(app_id);
+        }
+    }
+
+    /// Returns the game resolution and refresh rate override for the given app
+    /// as a dictionary with "resolution" and "refresh_rate" keys, or an empty
+    /// dictionary if the app has no override.
+    #[func]
+    pub fn get_app_display_override(&self, app_id: u32) -> Dictionary {
+        let mut dict = Dictionary::new();
+        let Some(settings) = self.display_overrides.get(&app_id) else {
+            return dict;
+        };
+        let (width, height) = settings.resolution;
+        dict.set("resolution", Vector2i::new(width as i32, height as i32));
+        dict.set("refresh_rate", settings.refresh_rate);
+        dict
+    }
+
     /// Return all known XWayland instances
     #[func]
     pub fn get_xwaylands(&self) -> Array<Gd<GamescopeXWayland>> {
@@ -75,13 +243,397 @@ impl GamescopeInstance {
         self.xwaylands.get(&name).cloned()
     }
 
-    /// Process Gamescope signals and emit them as Godot signals. This method
-    /// should be called every frame in the "_process" loop of a node.
+    /// Process Gamescope signals and emit them as Godot signals. This method is
+    /// called by the [ResourceProcessor] on frames where any of the objects of
+    /// this manager sent events, once the manager is registered with
+    /// [method ResourceRegistry.register_on_event].
     #[func]
-    pub fn process(&mut self) {
+    pub fn process(&mut self, _delta: f64) {
+        // Report this manager to the registry whenever its objects send events
+        WAKEUP.set_owner(self.base().instance_id().to_i64());
+
+        // Skip frames where none of the objects of this manager sent any events
+        if !WAKEUP.take() {
+            return;
+        }
+
+        // Drain all messages from the channel to process them
+        loop {
+            let signal = match self.rx.try_recv() {
+                Ok(value) => value,
+                Err(e) => match e {
+                    TryRecvError::Empty => break,
+                    TryRecvError::Disconnected => {
+                        log::error!("Backend thread is not running!");
+                        break;
+                    }
+                },
+            };
+            self.process_signal(signal);
+        }
+
         for (_, xwayland) in self.xwaylands.iter_mut() {
             xwayland.bind_mut().process();
         }
+        self.process_window_events();
+        self.color.bind_mut().process();
+        self.clipboard.bind_mut().process();
+        self.screenshots.bind_mut().process();
+
+        // Drop any displays that lost their connection. They will be added
+        // again if Gamescope brings them back.
+        let lost: Vec<String> = self
+            .xwaylands
+            .iter()
+            .filter(|(_, xwayland)| !xwayland.bind().is_connected())
+            .map(|(name, _)| name.clone())
+            .collect();
+        if lost.is_empty() {
+            return;
+        }
+        for name in lost {
+            log::debug!("Lost connection to XWayland display: {name}");
+            self.remove_xwayland(name);
+        }
+        self.update_xwayland_types();
+    }
+
+    /// Process and dispatch the given signal
+    fn process_signal(&mut self, signal: Signal) {
+        match signal {
+            Signal::DisplaysDiscovered { displays } => {
+                let removed: Vec<String> = self
+                    .xwaylands
+                    .keys()
+                    .filter(|name| !displays.contains(name))
+                    .cloned()
+                    .collect();
+                let added: Vec<String> = displays
+                    .into_iter()
+                    .filter(|name| !self.xwaylands.contains_key(name))
+                    .collect();
+                if removed.is_empty() && added.is_empty() {
+                    return;
+                }
+
+                for name in removed {
+                    log::debug!("XWayland display was removed: {name}");
+                    self.remove_xwayland(name);
+                }
+                let display_added = !added.is_empty();
+                for name in added {
+                    log::debug!("Discovered XWayland display: {name}");
+                    self.add_xwayland(name);
+                }
+                let primary = self.xwayland_primary.clone();
+                self.update_xwayland_types();
+
+                // Gamescope starts out with default colour settings, so apply
+                // them again if the primary display was (re-)added.
+                if display_added && primary == self.xwayland_primary {
+                    self.color.bind_mut().apply();
+                }
+            }
+        }
+    }
+
+    /// Hand window and focus changes from the game and primary XWayland
+    /// displays to the [GamescopeAppTracker], apply the settings of newly
+    /// focused apps, and apply colour settings again when the baselayer of the
+    /// primary display changes.
+    fn process_window_events(&mut self) {
+        let mut baselayer_changed = false;
+        let mut focused_app = None;
+        let mut tracker = self.app_tracker.bind_mut();
+        for (name, xwayland) in self.xwaylands.iter_mut() {
+            let events = xwayland.bind_mut().take_window_events();
+            for event in events {
+                match event {
+                    WindowEvent::Created(window_id) if *name == self.xwayland_game => {
+                        let mut xwayland = xwayland.bind_mut();
+                        // Watch the window so app id changes are seen
+                        if !xwayland.get_watched_windows().contains(window_id as i64) {
+                            xwayland.watch_window(window_id);
+                        }
+                        let app_id = xwayland.get_app_id(window_id);
+                        let pids = Self::get_window_pids(&xwayland, window_id);
+                        tracker.window_created(window_id, app_id, pids.as_slice());
+                    }
+                    WindowEvent::Destroyed(window_id) if *name == self.xwayland_game => {
+                        xwayland.bind_mut().unwatch_window(window_id);
+                        tracker.window_destroyed(window_id);
+                    }
+                    WindowEvent::AppIdChanged(window_id) if *name == self.xwayland_game => {
+                        let xwayland = xwayland.bind();
+                        let app_id = xwayland.get_app_id(window_id);
+                        let pids = Self::get_window_pids(&xwayland, window_id);
+                        tracker.app_id_changed(window_id, app_id, pids.as_slice());
+                    }
+                    WindowEvent::FocusedApp(app_id) if *name == self.xwayland_primary => {
+                        tracker.focus_changed(app_id);
+                        focused_app = Some(app_id);
+                    }
+                    WindowEvent::BaselayerChanged if *name == self.xwayland_primary => {
+                        baselayer_changed = true;
+                    }
+                    _ => (),
+                }
+            }
+        }
+        tracker.process();
+        drop(tracker);
+
+        if baselayer_changed {
+            self.color.bind_mut().apply();
+            // Check the focused app in case the focused app atom didn't change
+            if focused_app.is_none() {
+                focused_app = Some(self.get_focused_app());
+            }
+        }
+        if let Some(app_id) = focused_app {
+            self.apply_display_override(app_id);
+            self.app_settings.bind_mut().focus_changed(app_id);
+            self.screenshots.bind_mut().set_focused_app(app_id);
+        }
+    }
+
+    /// Returns the app that is focused on the primary XWayland display
+    fn get_focused_app(&self) -> u32 {
+        let Some(primary) = self.xwaylands.get(&self.xwayland_primary) else {
+            return 0;
+        };
+        primary.clone().bind_mut().get_focused_app()
+    }
+
+    /// Apply the display override of the given newly focused app. The settings
+    /// from before the first override are restored once an app without an
+    /// override is focused.
+    fn apply_display_override(&mut self, app_id: u32) {
+        let Some(settings) = self.display_overrides.get(&app_id).copied() else {
+            if let Some(restore) = self.display_restore.take() {
+                log::debug!("Restoring display settings: {restore:?}");
+                self.apply_display_settings(restore);
+            }
+            return;
+        };
+
+        let restore = match self.display_restore {
+            Some(restore) => restore,
+            None => {
+                let Some(primary) = self.xwaylands.get(&self.xwayland_primary) else {
+                    return;
+                };
+                let refresh_rate = primary.clone().bind_mut().get_refresh_rate();
+                let resolution = self.get_game_resolution();
+                let restore = DisplaySettings {
+                    resolution: (resolution.x as u32, resolution.y as u32),
+                    refresh_rate,
+                };
+                self.display_restore = Some(restore);
+                restore
+            }
+        };
+
+        // Settings the app doesn't override keep their previous value
+        let mut target = settings;
+        if target.resolution == (0, 0) {
+            target.resolution = restore.resolution;
+        }
+        if target.refresh_rate == 0 {
+            target.refresh_rate = restore.refresh_rate;
+        }
+        log::debug!("Applying display override for app {app_id}: {target:?}");
+        self.apply_display_settings(target);
+    }
+
+    /// Switch the game resolution and refresh rate to the given settings
+    fn apply_display_settings(&mut self, settings: DisplaySettings) {
+        let Some(primary) = self.xwaylands.get(&self.xwayland_primary) else {
+            return;
+        };
+        let mut primary = primary.clone();
+        if primary.bind_mut().get_refresh_rate() != settings.refresh_rate {
+            primary.bind_mut().set_refresh_rate(settings.refresh_rate);
+        }
+
+        let (width, height) = settings.resolution;
+        if width == 0 || height == 0 {
+            return;
+        }
+        let resolution = self.get_game_resolution();
+        if resolution == Vector2i::new(width as i32, height as i32) {
+            return;
+        }
+        self.set_game_resolution(width, height);
+    }
+
+    /// Returns the process ids of the given window on the given display
+    fn get_window_pids(xwayland: &GamescopeXWayland, window_id: u32) -> Vec<u32> {
+        let pids = xwayland.get_pids_for_window(window_id);
+        pids.as_slice().iter().map(|pid| *pid as u32).collect()
+    }
+
+    /// Create a [GamescopeXWayland] for the given display and start tracking it
+    fn add_xwayland(&mut self, name: String) {
+        let xwayland = GamescopeXWayland::new(name.as_str());
+        if !xwayland.bind().is_connected() {
+            // Try again the next time the display is discovered
+            Self::forget_xwayland(xwayland);
+            return;
+        }
+        self.xwaylands.insert(name.clone(), xwayland);
+        self.base_mut()
+            .emit_signal("xwayland_added", &[name.to_godot().to_variant()]);
+    }
+
+    /// Stop tracking the [GamescopeXWayland] for the given display
+    fn remove_xwayland(&mut self, name: String) {
+        let Some(xwayland) = self.xwaylands.remove(&name) else {
+            return;
+        };
+        Self::forget_xwayland(xwayland);
+        self.base_mut()
+            .emit_signal("xwayland_removed", &[name.to_godot().to_variant()]);
+    }
+
+    /// Remove the given [GamescopeXWayland] from the resource cache, so a new
+    /// display with the same name gets a fresh connection instead of this one
+    fn forget_xwayland(mut xwayland: Gd<GamescopeXWayland>) {
+        xwayland.set_path("");
+    }
+
+    /// Assign the known XWayland displays to each XWAYLAND_TYPE_* and emit
+    /// [signal xwayland_type_updated] for any assignments that changed.
+    fn update_xwayland_types(&mut self) {
+        let (primary, ogui, game) = Self::categorize(&self.xwaylands);
+        let mut changes = Vec::new();
+        if self.xwayland_primary != primary {
+            self.xwayland_primary = primary.clone();
+            self.color.bind_mut().set_display(primary.as_str());
+            let xwayland = self.xwaylands.get(&primary).cloned();
+            self.app_settings.bind_mut().set_xwayland(xwayland);
+            let app_id = self.get_focused_app();
+            self.app_settings.bind_mut().focus_changed(app_id);
+            self.screenshots.bind_mut().set_focused_app(app_id);
+            changes.push((GamescopeInstance::XWAYLAND_TYPE_PRIMARY, primary));
+        }
+        if self.xwayland_ogui != ogui {
+            let previous = std::mem::replace(&mut self.xwayland_ogui, ogui.clone());
+            self.update_clipboard_link(previous.as_str(), ogui.as_str());
+            changes.push((GamescopeInstance::XWAYLAND_TYPE_OGUI, ogui));
+        }
+        if self.xwayland_game != game {
+            let previous = std::mem::replace(&mut self.xwayland_game, game.clone());
+            self.update_clipboard_link(previous.as_str(), game.as_str());
+            self.update_game_ping(previous.as_str(), game.as_str());
+            changes.push((GamescopeInstance::XWAYLAND_TYPE_GAME, game));
+        }
+
+        for (kind, name) in changes {
+            log::debug!("XWayland type {kind} is now display '{name}'");
+            self.base_mut().emit_signal(
+                "xwayland_type_updated",
+                &[kind.to_variant(), name.to_godot().to_variant()],
+            );
+        }
+    }
+
+    /// Bridge the clipboard of the given display instead of the previous one
+    /// after the OpenGamepadUI or game display changed
+    fn update_clipboard_link(&mut self, previous: &str, name: &str) {
+        let mut clipboard = self.clipboard.bind_mut();
+        let still_used = previous == self.xwayland_ogui || previous == self.xwayland_game;
+        if !previous.is_empty() && !still_used {
+            clipboard.unlink_display(previous.into());
+        }
+        if !name.is_empty() {
+            clipboard.link_display(name.into());
+        }
+    }
+
+    /// Ping the focused window of the new game display instead of the
+    /// previous one to detect games that stopped responding
+    fn update_game_ping(&mut self, previous: &str, name: &str) {
+        if let Some(xwayland) = self.xwaylands.get_mut(previous) {
+            xwayland.bind_mut().set_ping_enabled(false);
+        }
+        if let Some(xwayland) = self.xwaylands.get_mut(name) {
+            xwayland.bind_mut().set_ping_enabled(true);
+        }
+    }
+
+    /// Returns the names of the primary, OpenGamepadUI and game displays out
+    /// of the given XWayland displays
+    fn categorize(xwaylands: &HashMap<String, Gd<GamescopeXWayland>>) -> (String, String, String) {
+        // Get the X11 display that the process knows about
+        let ogui_display = env::var("DISPLAY").unwrap_or(":0".into());
+
+        let mut xwayland_primary = String::default();
+        let mut xwayland_ogui = String::default();
+        let mut xwayland_game = String::default();
+
+        // Sort the displays so the assignment doesn't depend on hash order
+        let mut names: Vec<&String> = xwaylands.keys().collect();
+        names.sort();
+        for name in names {
+            if *name == ogui_display {
+                xwayland_ogui = name.clone();
+            }
+            if xwaylands[name].bind().get_is_primary() {
+                xwayland_primary = name.clone();
+            } else {
+                xwayland_game = name.clone();
+            }
+        }
+
+        (xwayland_primary, xwayland_ogui, xwayland_game)
+    }
+
+    /// Returns the index of the given display among the XWayland servers of
+    /// Gamescope. Gamescope creates its servers in the order of their display
+    /// numbers, so the index is the position of the display number among all
+    /// known displays.
+    fn xwayland_server_index(&self, name: &str) -> Option<u32> {
+        let number = |name: &str| name.trim_start_matches(':').parse::<u32>().ok();
+        let target = number(name)?;
+        let mut numbers: Vec<u32> = self
+            .xwaylands
+            .keys()
+            .filter_map(|name| number(name))
+            .collect();
+        numbers.sort_unstable();
+        let index = numbers.iter().position(|number| *number == target)?;
+        Some(index as u32)
+    }
+
+    /// Look for Gamescope XWayland displays every [DISCOVERY_INTERVAL] and
+    /// send the result. The result is sent even if nothing changed, so displays
+    /// that failed to connect or lost their connection are picked up again.
+    /// Displays are not removed if discovery fails, since displays that go
+    /// away are also noticed through their lost connection.
+    async fn discover(tx: Sender<Signal>) {
+        loop {
+            tokio::time::sleep(DISCOVERY_INTERVAL).await;
+            let result = tokio::task::spawn_blocking(|| {
+                gamescope_x11_client::discover_gamescope_displays().map_err(|e| e.to_string())
+            })
+            .await;
+            let displays = match result {
+                Ok(Ok(displays)) => displays,
+                Ok(Err(e)) => {
+                    log::trace!("Failed to get Gamescope displays: {e}");
+                    continue;
+                }
+                Err(e) => {
+                    log::error!("Failed to run Gamescope display discovery: {e:?}");
+                    continue;
+                }
+            };
+            if tx.send(Signal::DisplaysDiscovered { displays }).is_err() {
+                log::debug!("Gamescope instance was dropped, stopping display discovery");
+                return;
+            }
+        }
     }
 }
 
@@ -90,12 +642,21 @@ impl IResource for GamescopeInstance {
     /// Called upon object initialization in the engine
     fn init(base: Base<Self::Base>) -> Self {
         log::debug!("Initializing Gamescope instance");
+        let (tx, rx) = channel(&WAKEUP);
 
         // Don't run in the editor
         let engine = Engine::singleton();
         if engine.is_editor_hint() {
             return Self {
                 base,
+                rx,
+                app_tracker: GamescopeAppTracker::new(),
+                app_settings: GamescopeAppSettings::new(),
+                clipboard: GamescopeClipboard::new(),
+                color: GamescopeColor::new(),
+                screenshots: GamescopeScreenshots::new(),
+                display_overrides: Default::default(),
+                display_restore: Default::default(),
                 xwaylands: Default::default(),
                 xwayland_primary: Default::default(),
                 xwayland_ogui: Default::default(),
@@ -103,52 +664,88 @@ impl IResource for GamescopeInstance {
             };
         }
 
-        // Discover any gamescope instances
-        let result = gamescope_x11_client::discover_gamescope_displays();
-        let x11_displays = match result {
+        // Discover any gamescope instances that already exist, so they are
+        // available as soon as the instance is loaded
+        let x11_displays = match gamescope_x11_client::discover_gamescope_displays() {
             Ok(displays) => displays,
             Err(e) => {
                 log::warn!("Failed to get Gamescope displays: {e:?}");
-                return Self {
-                    base,
-                    xwaylands: HashMap::new(),
-                    xwayland_primary: Default::default(),
-                    xwayland_ogui: Default::default(),
-                    xwayland_game: Default::default(),
-                };
+                Vec::new()
             }
         };
 
-        // Get the X11 display that the process knows about
-        let ogui_display = env::var("DISPLAY").unwrap_or(":0".into());
-
-        // Keep track of discovered XWaylands
-        let mut xwaylands = HashMap::new();
-        let mut xwayland_primary = Default::default();
-        let mut xwayland_ogui = Default::default();
-        let mut xwayland_game = Default::default();
-
         // Create an XWayland instance for each discovered XWayland display
+        let mut xwaylands = HashMap::new();
         for display in x11_displays {
             log::debug!("Discovered XWayland display: {display}");
             let xwayland = GamescopeXWayland::new(display.as_str());
+            xwaylands.insert(display, xwayland);
+        }
 
-            // Categorize the discovered displays
-            if display == ogui_display {
-                xwayland_ogui = display.clone();
-            }
-            if xwayland.bind().get_is_primary() {
-                xwayland_primary = display.clone();
-            } else {
-                xwayland_game = display.clone();
+        // Categorize the discovered displays
+        let (xwayland_primary, xwayland_ogui, xwayland_game) = Self::categorize(&xwaylands);
+
+        // Track any apps that are already running on the game display
+        let mut app_tracker = GamescopeAppTracker::new();
+        if let Some(xwayland) = xwaylands.get_mut(&xwayland_game) {
+            let mut xwayland = xwayland.bind_mut();
+            let root_window_id = xwayland.get_root_window_id();
+            for window_id in xwayland.get_all_windows(root_window_id).as_slice() {
+                let window_id = *window_id as u32;
+                let app_id = xwayland.get_app_id(window_id);
+                if app_id == 0 {
+                    continue;
+                }
+                xwayland.watch_window(window_id);
+                let pids = Self::get_window_pids(&xwayland, window_id);
+                app_tracker
+                    .bind_mut()
+                    .window_created(window_id, app_id, pids.as_slice());
             }
+        }
 
-            xwaylands.insert(display, xwayland);
+        // Apply the persisted colour settings to the primary display
+        let mut color = GamescopeColor::new();
+        color.bind_mut().set_display(xwayland_primary.as_str());
+
+        // Apply the settings of the focused app to the primary display
+        let mut app_settings = GamescopeAppSettings::new();
+        let mut screenshots = GamescopeScreenshots::new();
+        if let Some(xwayland) = xwaylands.get(&xwayland_primary) {
+            let app_id = xwayland.clone().bind_mut().get_focused_app();
+            let mut app_settings = app_settings.bind_mut();
+            app_settings.set_xwayland(Some(xwayland.clone()));
+            app_settings.focus_changed(app_id);
+            screenshots.bind_mut().set_focused_app(app_id);
+        }
+
+        // Detect games that stop responding on the game display
+        if let Some(xwayland) = xwaylands.get_mut(&xwayland_game) {
+            xwayland.bind_mut().set_ping_enabled(true);
         }
 
+        // Bridge the clipboard between the OpenGamepadUI and game displays
+        let mut clipboard = GamescopeClipboard::new();
+        for name in [&xwayland_ogui, &xwayland_game] {
+            if !name.is_empty() {
+                clipboard.bind_mut().link_display(name.as_str().into());
+            }
+        }
+
+        // Keep looking for displays that Gamescope creates or removes later
+        RUNTIME.spawn(Self::discover(tx));
+
         // Create a new Gamescope instance
         Self {
             base,
+            rx,
+            app_tracker,
+            app_settings,
+            clipboard,
+            color,
+            screenshots,
+            display_overrides: Default::default(),
+            display_restore: Default::default(),
             xwaylands,
             xwayland_ogui,
             xwayland_game,
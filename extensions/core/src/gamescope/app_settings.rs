@@ -0,0 +1,470 @@
+use std::collections::BTreeMap;
+
+use godot::{obj::WithBaseField, prelude::*};
+
+use godot::classes::{ConfigFile, Resource};
+
+use super::x11_client::GamescopeXWayland;
+
+/// Path to the file where per-app settings are persisted
+const SETTINGS_PATH: &str = "user://gamescope_apps.cfg";
+/// Section in the settings file for the settings of apps without their own
+const DEFAULTS_SECTION: &str = "defaults";
+/// Prefix of the sections in the settings file for each app
+const APP_SECTION_PREFIX: &str = "app_";
+
+/// Names of all settings that can be stored per app
+pub const SETTING_KEYS: [&str; 7] = [
+    "fps_limit",
+    "allow_tearing",
+    "blur_mode",
+    "blur_radius",
+    "upscale_filter",
+    "upscale_scaler",
+    "sharpness",
+];
+
+/// Gamescope settings for an app. Settings that are `None` are taken from the
+/// defaults instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AppSettings {
+    pub fps_limit: Option<u32>,
+    pub allow_tearing: Option<bool>,
+    pub blur_mode: Option<u32>,
+    pub blur_radius: Option<u32>,
+    pub upscale_filter: Option<u32>,
+    pub upscale_scaler: Option<u32>,
+    pub sharpness: Option<u32>,
+}
+
+impl AppSettings {
+    /// Returns the value of the setting with the given name, with booleans as
+    /// 0 or 1. Returns `None` if the setting is not set or unknown.
+    pub fn get(&self, key: &str) -> Option<u32> {
+        match key {
+            "fps_limit" => self.fps_limit,
+            "allow_tearing" => self.allow_tearing.map(u32::from),
+            "blur_mode" => self.blur_mode,
+            "blur_radius" => self.blur_radius,
+            "upscale_filter" => self.upscale_filter,
+            "upscale_scaler" => self.upscale_scaler,
+            "sharpness" => self.sharpness,
+            _ => None,
+        }
+    }
+
+    /// Set or unset the setting with the given name. Returns false if the
+    /// setting is unknown.
+    pub fn set(&mut self, key: &str, value: Option<u32>) -> bool {
+        match key {
+            "fps_limit" => self.fps_limit = value,
+            "allow_tearing" => self.allow_tearing = value.map(|value| value != 0),
+            "blur_mode" => self.blur_mode = value,
+            "blur_radius" => self.blur_radius = value,
+            "upscale_filter" => self.upscale_filter = value,
+            "upscale_scaler" => self.upscale_scaler = value,
+            "sharpness" => self.sharpness = value,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Returns these settings with any unset values taken from `fallback`
+    pub fn or(self, fallback: AppSettings) -> AppSettings {
+        AppSettings {
+            fps_limit: self.fps_limit.or(fallback.fps_limit),
+            allow_tearing: self.allow_tearing.or(fallback.allow_tearing),
+            blur_mode: self.blur_mode.or(fallback.blur_mode),
+            blur_radius: self.blur_radius.or(fallback.blur_radius),
+            upscale_filter: self.upscale_filter.or(fallback.upscale_filter),
+            upscale_scaler: self.upscale_scaler.or(fallback.upscale_scaler),
+            sharpness: self.sharpness.or(fallback.sharpness),
+        }
+    }
+
+    /// Returns true if no setting is set
+    pub fn is_empty(&self) -> bool {
+        *self == AppSettings::default()
+    }
+
+    /// Returns the values to write to switch to the `target` settings, where
+    /// these settings are the values that the currently overridden settings
+    /// had before they were overridden. Settings that `target` sets are
+    /// written, and their previous value is kept using `read` the first time
+    /// they are overridden. Overridden settings that `target` doesn't set are
+    /// restored and forgotten. All other settings are left alone.
+    pub fn switch_to(
+        &mut self,
+        target: AppSettings,
+        mut read: impl FnMut(&str) -> u32,
+    ) -> AppSettings {
+        let mut changes = AppSettings::default();
+        for key in SETTING_KEYS {
+            if let Some(value) = target.get(key) {
+                if self.get(key).is_none() {
+                    self.set(key, Some(read(key)));
+                }
+                changes.set(key, Some(value));
+            } else if let Some(value) = self.get(key) {
+                self.set(key, None);
+                changes.set(key, Some(value));
+            }
+        }
+        changes
+    }
+}
+
+/// Settings for each app by app id, with defaults for apps that don't have
+/// their own settings
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AppSettingsStore {
+    pub defaults: AppSettings,
+    pub apps: BTreeMap<u32, AppSettings>,
+}
+
+impl AppSettingsStore {
+    /// Returns the settings that were set for the given app. Settings the app
+    /// doesn't have are taken from the defaults, and settings without a
+    /// default are not set.
+    pub fn resolve(&self, app_id: u32) -> AppSettings {
+        let app = self.apps.get(&app_id).copied().unwrap_or_default();
+        app.or(self.defaults)
+    }
+
+    /// Set or unset a setting of the given app. Apps without any settings left
+    /// are removed. Returns false if the setting is unknown.
+    pub fn set(&mut self, app_id: u32, key: &str, value: Option<u32>) -> bool {
+        let mut settings = self.apps.get(&app_id).copied().unwrap_or_default();
+        if !settings.set(key, value) {
+            return false;
+        }
+        if settings.is_empty() {
+            self.apps.remove(&app_id);
+        } else {
+            self.apps.insert(app_id, settings);
+        }
+        true
+    }
+}
+
+/// Stores Gamescope settings like the FPS limit, tearing and blur for each app
+/// and applies them to the primary XWayland whenever a different app gets
+/// focused. Apps without their own settings use the defaults. Only settings
+/// that are set for the app or have a default are written, and settings that
+/// a previous app overrode are restored once an app without them is focused.
+#[derive(GodotClass)]
+#[class(no_init, base=Resource)]
+pub struct GamescopeAppSettings {
+    base: Base<Resource>,
+    store: AppSettingsStore,
+    xwayland: Option<Gd<GamescopeXWayland>>,
+    /// Values that the currently overridden settings had before they were
+    /// overridden
+    baseline: AppSettings,
+
+    /// The app whose settings are currently applied, or 0 if none are
+    #[var(get)]
+    applied_app: u32,
+}
+
+#[godot_api]
+impl GamescopeAppSettings {
+    /// Emitted after the settings of the given app were applied
+    #[signal]
+    fn settings_applied(app_id: u32);
+
+    /// Emitted when a setting of the given app changes. The app id is 0 if a
+    /// default setting changed.
+    #[signal]
+    fn settings_changed(app_id: u32);
+
+    /// Create a new [GamescopeAppSettings] with the persisted settings
+    pub fn new() -> Gd<Self> {
+        let mut settings = Gd::from_init_fn(|base| Self {
+            base,
+            store: Default::default(),
+            xwayland: None,
+            baseline: Default::default(),
+            applied_app: 0,
+        });
+        settings.bind_mut().load();
+        settings
+    }
+
+    /// Returns the names of all settings that can be stored per app
+    #[func]
+    pub fn get_setting_keys(&self) -> PackedStringArray {
+        let keys: Vec<GString> = SETTING_KEYS.iter().map(|key| GString::from(*key)).collect();
+        PackedStringArray::from(keys)
+    }
+
+    /// Returns the ids of all apps that have their own settings
+    #[func]
+    pub fn get_apps(&self) -> PackedInt64Array {
+        let apps: Vec<i64> = self.store.apps.keys().map(|id| *id as i64).collect();
+        apps.into()
+    }
+
+    /// Returns the setting with the given name for the given app, or null if
+    /// the app doesn't have its own value for it
+    #[func]
+    pub fn get_app_setting(&self, app_id: u32, key: GString) -> Variant {
+        let Some(settings) = self.store.apps.get(&app_id) else {
+            return Variant::nil();
+        };
+        let key = key.to_string();
+        Self::value_to_variant(key.as_str(), settings.get(key.as_str()))
+    }
+
+    /// Set the setting with the given name for the given app. Setting it to
+    /// null makes the app use the default value again.
+    #[func]
+    pub fn set_app_setting(&mut self, app_id: u32, key: GString, value: Variant) -> bool {
+        let key = key.to_string();
+        let Some(value) = Self::value_from_variant(key.as_str(), &value) else {
+            return false;
+        };
+        if !self.store.set(app_id, key.as_str(), value) {
+            log::error!("Unknown app setting: {key}");
+            return false;
+        }
+        self.changed(app_id);
+        true
+    }
+
+    /// Remove all settings of the given app, so it uses the defaults
+    #[func]
+    pub fn clear_app_settings(&mut self, app_id: u32) {
+        if self.store.apps.remove(&app_id).is_none() {
+            return;
+        }
+        self.changed(app_id);
+    }
+
+    /// Returns the default value of the setting with the given name, or null
+    /// if it has none
+    #[func]
+    pub fn get_default_setting(&self, key: GString) -> Variant {
+        let key = key.to_string();
+        Self::value_to_variant(key.as_str(), self.store.defaults.get(key.as_str()))
+    }
+
+    /// Set the default value of the setting with the given name for apps
+    /// without their own value. Setting it to null keeps the value the setting
+    /// had before any app settings were applied.
+    #[func]
+    pub fn set_default_setting(&mut self, key: GString, value: Variant) -> bool {
+        let key = key.to_string();
+        let Some(value) = Self::value_from_variant(key.as_str(), &value) else {
+            return false;
+        };
+        if !self.store.defaults.set(key.as_str(), value) {
+            log::error!("Unknown app setting: {key}");
+            return false;
+        }
+        self.changed(0);
+        true
+    }
+
+    /// Returns all settings that would be in effect for the given app as a
+    /// dictionary, with defaults and the values of settings that are not set
+    /// for the app filled in
+    #[func]
+    pub fn get_effective_settings(&self, app_id: u32) -> Dictionary {
+        let mut settings = self.store.resolve(app_id).or(self.baseline);
+        if let Some(xwayland) = self.xwayland.as_ref() {
+            let mut xwayland = xwayland.clone();
+            settings = settings.or(Self::read_settings(&mut xwayland));
+        }
+        let mut dict = Dictionary::new();
+        for key in SETTING_KEYS {
+            let value = Self::value_to_variant(key, settings.get(key));
+            if !value.is_nil() {
+                dict.set(key, value);
+            }
+        }
+        dict
+    }
+
+    /// Set the primary XWayland that settings are applied to. Settings that
+    /// were overridden on the previous display are not restored there.
+    pub fn set_xwayland(&mut self, xwayland: Option<Gd<GamescopeXWayland>>) {
+        self.applied_app = 0;
+        self.baseline = AppSettings::default();
+        self.xwayland = xwayland;
+    }
+
+    /// Apply the settings of the given app, if they are not applied already.
+    /// Called by [GamescopeInstance] when the focused app changes.
+    pub fn focus_changed(&mut self, app_id: u32) {
+        if self.applied_app == app_id {
+            return;
+        }
+        self.apply(app_id);
+    }
+
+    /// Apply the settings of the given app to the primary XWayland and
+    /// restore any settings that the previous app overrode
+    fn apply(&mut self, app_id: u32) {
+        let Some(xwayland) = self.xwayland.as_ref() else {
+            return;
+        };
+        let target = self.store.resolve(app_id);
+        let mut xwayland = xwayland.clone();
+        let settings = self
+            .baseline
+            .switch_to(target, |key| Self::read_setting(&mut xwayland, key));
+        log::debug!("Applying Gamescope settings for app {app_id}: {settings:?}");
+
+        let mut xwayland = xwayland.bind_mut();
+        if let Some(value) = settings.fps_limit {
+            xwayland.set_fps_limit(value);
+        }
+        if let Some(value) = settings.allow_tearing {
+            xwayland.set_allow_tearing(value);
+        }
+        if let Some(value) = settings.blur_mode {
+            xwayland.set_blur_mode(value);
+        }
+        if let Some(value) = settings.blur_radius {
+            xwayland.set_blur_radius(value);
+        }
+        if let Some(value) = settings.upscale_filter {
+            xwayland.set_upscale_filter(value);
+        }
+        if let Some(value) = settings.upscale_scaler {
+            xwayland.set_upscale_scaler(value);
+        }
+        if let Some(value) = settings.sharpness {
+            xwayland.set_sharpness(value);
+        }
+        drop(xwayland);
+
+        self.applied_app = app_id;
+        self.base_mut()
+            .emit_signal("settings_applied", &[app_id.to_variant()]);
+    }
+
+    /// Persist the settings and apply them again if they affect the app whose
+    /// settings are currently applied
+    fn changed(&mut self, app_id: u32) {
+        self.save();
+        let applied_app = self.applied_app;
+        if self.xwayland.is_some() && (app_id == 0 || app_id == applied_app) {
+            self.apply(applied_app);
+        }
+        self.base_mut()
+            .emit_signal("settings_changed", &[app_id.to_variant()]);
+    }
+
+    /// Returns the current values of all settings on the given XWayland
+    fn read_settings(xwayland: &mut Gd<GamescopeXWayland>) -> AppSettings {
+        let mut settings = AppSettings::default();
+        for key in SETTING_KEYS {
+            settings.set(key, Some(Self::read_setting(xwayland, key)));
+        }
+        settings
+    }
+
+    /// Returns the current value of the setting with the given name on the
+    /// given XWayland, with booleans as 0 or 1
+    fn read_setting(xwayland: &mut Gd<GamescopeXWayland>, key: &str) -> u32 {
+        let mut xwayland = xwayland.bind_mut();
+        match key {
+            "fps_limit" => xwayland.get_fps_limit(),
+            "allow_tearing" => u32::from(xwayland.get_allow_tearing()),
+            "blur_mode" => xwayland.get_blur_mode(),
+            "blur_radius" => xwayland.get_blur_radius(),
+            "upscale_filter" => xwayland.get_upscale_filter(),
+            "upscale_scaler" => xwayland.get_upscale_scaler(),
+            "sharpness" => xwayland.get_sharpness(),
+            _ => 0,
+        }
+    }
+
+    /// Returns the given setting value as a [Variant]
+    fn value_to_variant(key: &str, value: Option<u32>) -> Variant {
+        match (key, value) {
+            (_, None) => Variant::nil(),
+            ("allow_tearing", Some(value)) => (value != 0).to_variant(),
+            (_, Some(value)) => value.to_variant(),
+        }
+    }
+
+    /// Returns the setting value from the given [Variant], where null unsets
+    /// the setting. Returns `None` if the value has the wrong type.
+    fn value_from_variant(key: &str, value: &Variant) -> Option<Option<u32>> {
+        if value.is_nil() {
+            return Some(None);
+        }
+        if let Ok(value) = value.try_to::<bool>() {
+            return Some(Some(value as u32));
+        }
+        match value.try_to::<u32>() {
+            Ok(value) => Some(Some(value)),
+            Err(e) => {
+                log::error!("Invalid value for app setting {key}: {e:?}");
+                None
+            }
+        }
+    }
+
+    /// Load persisted settings from [SETTINGS_PATH]
+    fn load(&mut self) {
+        let mut config = ConfigFile::new_gd();
+        if config.load(SETTINGS_PATH) != godot::global::Error::OK {
+            return;
+        }
+        for section in config.get_sections().as_slice() {
+            let section = section.to_string();
+            let mut settings = AppSettings::default();
+            for key in SETTING_KEYS {
+                if !config.has_section_key(section.as_str(), key) {
+                    continue;
+                }
+                let value = config.get_value(section.as_str(), key);
+                if let Ok(value) = value.try_to::<u32>() {
+                    settings.set(key, Some(value));
+                }
+            }
+
+            if section == DEFAULTS_SECTION {
+                self.store.defaults = settings;
+                continue;
+            }
+            let app_id = section
+                .strip_prefix(APP_SECTION_PREFIX)
+                .and_then(|id| id.parse::<u32>().ok());
+            let Some(app_id) = app_id else {
+                log::warn!("Unknown section in {SETTINGS_PATH}: {section}");
+                continue;
+            };
+            if !settings.is_empty() {
+                self.store.apps.insert(app_id, settings);
+            }
+        }
+    }
+
+    /// Persist the settings to [SETTINGS_PATH]
+    fn save(&self) {
+        let mut config = ConfigFile::new_gd();
+        let sections = std::iter::once((DEFAULTS_SECTION.to_string(), self.store.defaults)).chain(
+            self.store
+                .apps
+                .iter()
+                .map(|(app_id, settings)| (format!("{APP_SECTION_PREFIX}{app_id}"), *settings)),
+        );
+        for (section, settings) in sections {
+            for key in SETTING_KEYS {
+                let Some(value) = settings.get(key) else {
+                    continue;
+                };
+                config.set_value(section.as_str(), key, &value.to_variant());
+            }
+        }
+        let err = config.save(SETTINGS_PATH);
+        if err != godot::global::Error::OK {
+            log::error!("Failed to save app settings to {SETTINGS_PATH}: {err:?}");
+        }
+    }
+}
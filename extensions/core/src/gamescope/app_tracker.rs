@@ -0,0 +1,487 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+use godot::{obj::WithBaseField, prelude::*};
+
+use godot::classes::Resource;
+
+use crate::resource::dispatcher::{channel, Receiver, Sender, TryRecvError};
+use crate::RUNTIME;
+
+use super::WAKEUP;
+
+/// How long to wait before checking again whether the processes of an app
+/// without any windows have exited
+const EXIT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Maximum number of parent processes to walk through when looking for the
+/// app that a process belongs to
+const MAX_PROCESS_DEPTH: usize = 64;
+
+/// Changes in the lifecycle of tracked apps
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppEvent {
+    /// The first window of the app was created
+    Started { app_id: u32, pids: Vec<u32> },
+    /// The app with the given id became the focused app
+    Focused { app_id: u32 },
+    /// The main window of the app changed
+    WindowChanged { app_id: u32, window_id: u32 },
+    /// All windows of the app were destroyed and its processes exited
+    Exited { app_id: u32 },
+}
+
+/// State of a single tracked app
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct App {
+    /// Process ids that belong to the app
+    pub pids: Vec<u32>,
+    /// Windows of the app in the order they were created. The first window is
+    /// the main window of the app.
+    pub windows: Vec<u32>,
+    /// Whether the app has created a window yet
+    pub started: bool,
+}
+
+/// Groups windows into apps and keeps track of their lifecycle. Windows are
+/// assigned to an app by their STEAM_GAME app id, or if they have none, by
+/// looking for a process in the window's process tree that belongs to an app.
+#[derive(Debug, Default)]
+pub struct AppModel {
+    apps: BTreeMap<u32, App>,
+    window_apps: HashMap<u32, u32>,
+    focused: u32,
+    focus_history: Vec<u32>,
+    events: Vec<AppEvent>,
+}
+
+impl AppModel {
+    /// Start tracking the given process as part of the given app, so windows
+    /// created by it or any of its child processes are assigned to the app
+    pub fn launched(&mut self, app_id: u32, pid: u32) {
+        let app = self.apps.entry(app_id).or_default();
+        if !app.pids.contains(&pid) {
+            app.pids.push(pid);
+        }
+    }
+
+    /// Track the given window that was created by the given processes. The
+    /// given function should return the parent process of a process.
+    pub fn window_created(
+        &mut self,
+        window_id: u32,
+        app_id: u32,
+        pids: &[u32],
+        parent_of: impl Fn(u32) -> Option<u32>,
+    ) {
+        if self.window_apps.contains_key(&window_id) {
+            return;
+        }
+        let Some(app_id) = self.find_app_for_window(app_id, pids, parent_of) else {
+            log::trace!("Window {window_id} does not belong to any app");
+            return;
+        };
+
+        let app = self.apps.entry(app_id).or_default();
+        for pid in pids {
+            if !app.pids.contains(pid) {
+                app.pids.push(*pid);
+            }
+        }
+        app.windows.push(window_id);
+        self.window_apps.insert(window_id, app_id);
+
+        if !app.started {
+            app.started = true;
+            let pids = app.pids.clone();
+            self.events.push(AppEvent::Started { app_id, pids });
+        }
+        if app.windows.len() == 1 {
+            self.events
+                .push(AppEvent::WindowChanged { app_id, window_id });
+        }
+    }
+
+    /// Stop tracking the given window. Apps are not considered exited until
+    /// [AppModel::check_exited] finds that their processes are gone.
+    pub fn window_destroyed(&mut self, window_id: u32) {
+        let Some(app_id) = self.window_apps.remove(&window_id) else {
+            return;
+        };
+        let Some(app) = self.apps.get_mut(&app_id) else {
+            return;
+        };
+        let was_main = app.windows.first() == Some(&window_id);
+        app.windows.retain(|id| *id != window_id);
+        if !was_main {
+            return;
+        }
+        if let Some(window_id) = app.windows.first().copied() {
+            self.events
+                .push(AppEvent::WindowChanged { app_id, window_id });
+        }
+    }
+
+    /// Assign the given window again after its app id changed, moving it to
+    /// the app with the new id. If the app id was removed, the window is
+    /// assigned by its processes like a newly created window.
+    pub fn app_id_changed(
+        &mut self,
+        window_id: u32,
+        app_id: u32,
+        pids: &[u32],
+        parent_of: impl Fn(u32) -> Option<u32>,
+    ) {
+        let found = self.find_app_for_window(app_id, pids, &parent_of);
+        if self.window_apps.get(&window_id) == found.as_ref() {
+            return;
+        }
+        self.window_destroyed(window_id);
+        let Some(app_id) = found else {
+            log::trace!("Window {window_id} does not belong to any app");
+            return;
+        };
+        self.window_created(window_id, app_id, pids, parent_of);
+    }
+
+    /// Update the currently focused app
+    pub fn focus_changed(&mut self, app_id: u32) {
+        if self.focused == app_id {
+            return;
+        }
+        self.focused = app_id;
+        if self.apps.contains_key(&app_id) {
+            self.focus_history.retain(|id| *id != app_id);
+            self.focus_history.push(app_id);
+        }
+        self.events.push(AppEvent::Focused { app_id });
+    }
+
+    /// Stop tracking any apps without windows whose processes are no longer
+    /// running. The given function should return true if a process is running.
+    pub fn check_exited(&mut self, is_running: impl Fn(u32) -> bool) {
+        let exited: Vec<u32> = self
+            .apps
+            .iter()
+            .filter(|(_, app)| app.windows.is_empty())
+            .filter(|(_, app)| !app.pids.iter().any(|pid| is_running(*pid)))
+            .map(|(app_id, _)| *app_id)
+            .collect();
+        for app_id in exited {
+            let Some(app) = self.apps.remove(&app_id) else {
+                continue;
+            };
+            self.focus_history.retain(|id| *id != app_id);
+            if app.started {
+                self.events.push(AppEvent::Exited { app_id });
+            }
+        }
+    }
+
+    /// Returns true if any app has no windows, which means
+    /// [AppModel::check_exited] needs to be called again later
+    pub fn has_windowless_apps(&self) -> bool {
+        self.apps.values().any(|app| app.windows.is_empty())
+    }
+
+    /// Returns the app with the given id
+    pub fn app(&self, app_id: u32) -> Option<&App> {
+        self.apps.get(&app_id)
+    }
+
+    /// Returns the ids of all apps that have started
+    pub fn running_apps(&self) -> Vec<u32> {
+        self.apps
+            .iter()
+            .filter(|(_, app)| app.started)
+            .map(|(app_id, _)| *app_id)
+            .collect()
+    }
+
+    /// Returns the id of the currently focused app
+    pub fn focused(&self) -> u32 {
+        self.focused
+    }
+
+    /// Returns the ids of tracked apps in the order they were last focused,
+    /// with the most recently focused app last
+    pub fn focus_history(&self) -> &[u32] {
+        self.focus_history.as_slice()
+    }
+
+    /// Returns all events that happened since the last call
+    pub fn take_events(&mut self) -> Vec<AppEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Returns the app that a window with the given app id and processes
+    /// belongs to. Windows without an app id belong to the app of their processes.
+    fn find_app_for_window(
+        &self,
+        app_id: u32,
+        pids: &[u32],
+        parent_of: impl Fn(u32) -> Option<u32>,
+    ) -> Option<u32> {
+        if app_id != 0 {
+            return Some(app_id);
+        }
+        pids.iter()
+            .find_map(|pid| self.find_app_for_pid(*pid, &parent_of))
+    }
+
+    /// Returns the app that the given process or any of its parents belong to
+    fn find_app_for_pid(&self, pid: u32, parent_of: impl Fn(u32) -> Option<u32>) -> Option<u32> {
+        let mut pid = pid;
+        for _ in 0..MAX_PROCESS_DEPTH {
+            if pid <= 1 {
+                return None;
+            }
+            let found = self.apps.iter().find(|(_, app)| app.pids.contains(&pid));
+            if let Some((app_id, _)) = found {
+                return Some(*app_id);
+            }
+            pid = parent_of(pid)?;
+        }
+        None
+    }
+}
+
+/// Signals that can be emitted
+#[derive(Debug)]
+enum Signal {
+    CheckExited,
+}
+
+/// Tracks running apps on the game XWayland display. Apps are identified by
+/// their STEAM_GAME app id. Windows without an app id are grouped with the app
+/// that launched them if the launched process was registered with
+/// [method track_launch].
+#[derive(GodotClass)]
+#[class(no_init, base=Resource)]
+pub struct GamescopeAppTracker {
+    base: Base<Resource>,
+    rx: Receiver<Signal>,
+    tx: Sender<Signal>,
+    model: AppModel,
+    exit_check_pending: bool,
+}
+
+#[godot_api]
+impl GamescopeAppTracker {
+    /// Emitted when the first window of an app is created
+    #[signal]
+    fn app_started(app_id: u32, pids: PackedInt64Array);
+
+    /// Emitted when the focused app changes
+    #[signal]
+    fn app_focused(app_id: u32);
+
+    /// Emitted when the main window of an app changes
+    #[signal]
+    fn app_window_changed(app_id: u32, window_id: u32);
+
+    /// Emitted when all windows of an app were destroyed and its processes exited
+    #[signal]
+    fn app_exited(app_id: u32);
+
+    /// Create a new [GamescopeAppTracker]
+    pub fn new() -> Gd<Self> {
+        let (tx, rx) = channel(&WAKEUP);
+        Gd::from_init_fn(|base| Self {
+            base,
+            rx,
+            tx,
+            model: Default::default(),
+            exit_check_pending: false,
+        })
+    }
+
+    /// Track the given launched process as part of the given app. Windows that
+    /// are created by the process or any of its child processes are assigned to
+    /// the app.
+    #[func]
+    pub fn track_launch(&mut self, app_id: u32, pid: u32) {
+        self.model.launched(app_id, pid);
+        self.schedule_exit_check();
+    }
+
+    /// Returns the ids of all running apps
+    #[func]
+    pub fn get_running_apps(&self) -> PackedInt64Array {
+        let apps: Vec<i64> = self
+            .model
+            .running_apps()
+            .into_iter()
+            .map(|id| id as i64)
+            .collect();
+        apps.into()
+    }
+
+    /// Returns the process ids that belong to the given app
+    #[func]
+    pub fn get_app_pids(&self, app_id: u32) -> PackedInt64Array {
+        let Some(app) = self.model.app(app_id) else {
+            return PackedInt64Array::new();
+        };
+        let pids: Vec<i64> = app.pids.iter().map(|pid| *pid as i64).collect();
+        pids.into()
+    }
+
+    /// Returns the windows of the given app in the order they were created
+    #[func]
+    pub fn get_app_windows(&self, app_id: u32) -> PackedInt64Array {
+        let Some(app) = self.model.app(app_id) else {
+            return PackedInt64Array::new();
+        };
+        let windows: Vec<i64> = app.windows.iter().map(|id| *id as i64).collect();
+        windows.into()
+    }
+
+    /// Returns the main window of the given app. Returns zero if the app has no
+    /// windows.
+    #[func]
+    pub fn get_app_window(&self, app_id: u32) -> u32 {
+        let Some(app) = self.model.app(app_id) else {
+            return 0;
+        };
+        app.windows.first().copied().unwrap_or_default()
+    }
+
+    /// Returns the id of the currently focused app
+    #[func]
+    pub fn get_focused_app(&self) -> u32 {
+        self.model.focused()
+    }
+
+    /// Returns the ids of running apps in the order they were last focused,
+    /// with the most recently focused app last
+    #[func]
+    pub fn get_focus_history(&self) -> PackedInt64Array {
+        let apps: Vec<i64> = self
+            .model
+            .focus_history()
+            .iter()
+            .map(|id| *id as i64)
+            .collect();
+        apps.into()
+    }
+
+    /// Track the given window, called by [GamescopeInstance]
+    pub fn window_created(&mut self, window_id: u32, app_id: u32, pids: &[u32]) {
+        self.model
+            .window_created(window_id, app_id, pids, get_parent_pid);
+        self.dispatch();
+    }
+
+    /// Stop tracking the given window, called by [GamescopeInstance]
+    pub fn window_destroyed(&mut self, window_id: u32) {
+        self.model.window_destroyed(window_id);
+        self.model.check_exited(is_pid_running);
+        self.schedule_exit_check();
+        self.dispatch();
+    }
+
+    /// Assign the given window again after its app id changed, called by
+    /// [GamescopeInstance]
+    pub fn app_id_changed(&mut self, window_id: u32, app_id: u32, pids: &[u32]) {
+        self.model
+            .app_id_changed(window_id, app_id, pids, get_parent_pid);
+        self.model.check_exited(is_pid_running);
+        self.schedule_exit_check();
+        self.dispatch();
+    }
+
+    /// Update the focused app, called by [GamescopeInstance]
+    pub fn focus_changed(&mut self, app_id: u32) {
+        self.model.focus_changed(app_id);
+        self.dispatch();
+    }
+
+    /// Dispatches signals, called by [GamescopeInstance]
+    pub fn process(&mut self) {
+        // Drain all messages from the channel to process them
+        loop {
+            let signal = match self.rx.try_recv() {
+                Ok(value) => value,
+                Err(e) => match e {
+                    TryRecvError::Empty => break,
+                    TryRecvError::Disconnected => {
+                        log::error!("Backend thread is not running!");
+                        return;
+                    }
+                },
+            };
+            match signal {
+                Signal::CheckExited => {
+                    self.exit_check_pending = false;
+                    self.model.check_exited(is_pid_running);
+                    self.schedule_exit_check();
+                }
+            }
+        }
+        self.dispatch();
+    }
+
+    /// Check again later whether apps without windows have exited
+    fn schedule_exit_check(&mut self) {
+        if self.exit_check_pending || !self.model.has_windowless_apps() {
+            return;
+        }
+        self.exit_check_pending = true;
+        let tx = self.tx.clone();
+        RUNTIME.spawn(async move {
+            tokio::time::sleep(EXIT_CHECK_INTERVAL).await;
+            if let Err(e) = tx.send(Signal::CheckExited) {
+                log::debug!("Failed to send exit check signal: {e:?}");
+            }
+        });
+    }
+
+    /// Emit signals for all app events that happened
+    fn dispatch(&mut self) {
+        for event in self.model.take_events() {
+            match event {
+                AppEvent::Started { app_id, pids } => {
+                    let pids: Vec<i64> = pids.into_iter().map(|pid| pid as i64).collect();
+                    let pids = PackedInt64Array::from(pids);
+                    self.base_mut()
+                        .emit_signal("app_started", &[app_id.to_variant(), pids.to_variant()]);
+                }
+                AppEvent::Focused { app_id } => {
+                    self.base_mut()
+                        .emit_signal("app_focused", &[app_id.to_variant()]);
+                }
+                AppEvent::WindowChanged { app_id, window_id } => {
+                    self.base_mut().emit_signal(
+                        "app_window_changed",
+                        &[app_id.to_variant(), window_id.to_variant()],
+                    );
+                }
+                AppEvent::Exited { app_id } => {
+                    self.base_mut()
+                        .emit_signal("app_exited", &[app_id.to_variant()]);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the parent process id of the given process
+fn get_parent_pid(pid: u32) -> Option<u32> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // The process name may contain spaces, so skip past it before splitting
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Returns true if the given process exists and is not a zombie
+fn is_pid_running(pid: u32) -> bool {
+    let Ok(stat) = std::fs::read_to_string(format!("/proc/{pid}/stat")) else {
+        return false;
+    };
+    let Some((_, fields)) = stat.rsplit_once(')') else {
+        return false;
+    };
+    !matches!(fields.split_whitespace().next(), Some("Z" | "X"))
+}
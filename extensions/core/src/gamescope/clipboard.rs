@@ -0,0 +1,635 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use x11rb::{
+    atom_manager,
+    connection::Connection,
+    errors::ReplyError,
+    protocol::{
+        xfixes::{ConnectionExt as _, SelectionEventMask},
+        xproto::{
+            Atom, AtomEnum, ClientMessageEvent, ConnectionExt, CreateWindowAux, EventMask,
+            PropMode, SelectionNotifyEvent, SelectionRequestEvent, Window, WindowClass,
+            SELECTION_NOTIFY_EVENT,
+        },
+        Event,
+    },
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+    COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT, CURRENT_TIME, NONE,
+};
+
+use godot::{obj::WithBaseField, prelude::*};
+
+use godot::classes::Resource;
+
+use crate::resource::dispatcher::{channel, Receiver, Sender, TryRecvError};
+use crate::RUNTIME;
+
+use super::WAKEUP;
+
+/// Maximum size in bytes of clipboard text that is bridged between displays.
+/// Larger selections have to be transferred incrementally, which is not
+/// supported.
+pub const MAX_TEXT_LENGTH: usize = 256 * 1024;
+
+atom_manager! {
+    /// Atoms used by [ClipboardDisplay]
+    Atoms: AtomsCookie {
+        CLIPBOARD,
+        UTF8_STRING,
+        TEXT,
+        TARGETS,
+        INCR,
+        OGUI_SELECTION,
+    }
+}
+
+/// X11 selections that are bridged between displays
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Selection {
+    /// The selection used by explicit copy and paste
+    Clipboard,
+    /// The selection holding the most recently selected text
+    Primary,
+}
+
+impl Selection {
+    /// All selections that are bridged
+    pub const ALL: [Selection; 2] = [Selection::Clipboard, Selection::Primary];
+}
+
+/// Events sent by a [ClipboardDisplay] from its event thread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardEvent {
+    /// Another client on the display put the given text into a selection
+    Changed {
+        display: String,
+        selection: Selection,
+        text: String,
+    },
+    /// The connection to the display was lost
+    Disconnected { display: String },
+}
+
+/// Decode the given ISO 8859-1 text of a STRING selection
+pub fn latin1_to_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| *byte as char).collect()
+}
+
+/// Encode the given text for a STRING selection. Characters outside of
+/// ISO 8859-1 are replaced with '?'.
+pub fn string_to_latin1(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| u8::try_from(c as u32).unwrap_or(b'?'))
+        .collect()
+}
+
+/// State shared between a [ClipboardDisplay] and its event thread
+struct Shared {
+    name: String,
+    conn: RustConnection,
+    window: Window,
+    atoms: Atoms,
+    running: AtomicBool,
+    /// Text of the selections that are currently owned by [Shared::window]
+    texts: Mutex<HashMap<Selection, String>>,
+}
+
+/// Owns the CLIPBOARD and PRIMARY selections on a single X11 display. Text
+/// that other clients on the display put into a selection is sent as a
+/// [ClipboardEvent], and text set with [ClipboardDisplay::set_text] is served
+/// to other clients until they take over the selection.
+pub struct ClipboardDisplay {
+    shared: Arc<Shared>,
+}
+
+impl ClipboardDisplay {
+    /// Connect to the X11 display with the given name (e.g. ":0") and send
+    /// selection changes to the given channel. The current text of each
+    /// selection is sent right away.
+    pub fn connect(name: &str, tx: Sender<ClipboardEvent>) -> Result<Self, Box<dyn Error>> {
+        let (conn, screen) = RustConnection::connect(Some(name))?;
+        let root = conn.setup().roots[screen].root;
+        let atoms = Atoms::new(&conn)?.reply()?;
+        conn.xfixes_query_version(5, 0)?.reply()?;
+
+        // Selections are owned by a window that is never mapped
+        let window = conn.generate_id()?;
+        conn.create_window(
+            COPY_DEPTH_FROM_PARENT,
+            window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            COPY_FROM_PARENT,
+            &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+        )?;
+        let mask = SelectionEventMask::SET_SELECTION_OWNER
+            | SelectionEventMask::SELECTION_WINDOW_DESTROY
+            | SelectionEventMask::SELECTION_CLIENT_CLOSE;
+
+        let shared = Arc::new(Shared {
+            name: name.to_string(),
+            conn,
+            window,
+            atoms,
+            running: AtomicBool::new(true),
+            texts: Default::default(),
+        });
+        for selection in Selection::ALL {
+            let atom = shared.atom(selection);
+            shared
+                .conn
+                .xfixes_select_selection_input(window, atom, mask)?;
+            shared.request_text(atom, CURRENT_TIME)?;
+        }
+        shared.conn.flush()?;
+
+        let thread_shared = shared.clone();
+        RUNTIME.spawn_blocking(move || thread_shared.run(tx));
+
+        Ok(Self { shared })
+    }
+
+    /// Returns the name of the display (e.g. ":0")
+    pub fn name(&self) -> &str {
+        self.shared.name.as_str()
+    }
+
+    /// Take over the given selection and serve the given text to clients that
+    /// paste it
+    pub fn set_text(&self, selection: Selection, text: &str) -> Result<(), ReplyError> {
+        let shared = &self.shared;
+        let atom = shared.atom(selection);
+        shared
+            .texts
+            .lock()
+            .unwrap()
+            .insert(selection, text.to_string());
+        shared
+            .conn
+            .set_selection_owner(shared.window, atom, CURRENT_TIME)?;
+        let owner = shared.conn.get_selection_owner(atom)?.reply()?.owner;
+        if owner != shared.window {
+            log::warn!(
+                "Failed to take over selection {selection:?} on display '{}'",
+                shared.name
+            );
+            shared.texts.lock().unwrap().remove(&selection);
+        }
+        Ok(())
+    }
+
+    /// Returns the text that is served for the given selection, or `None` if
+    /// another client owns the selection
+    pub fn text(&self, selection: Selection) -> Option<String> {
+        self.shared.texts.lock().unwrap().get(&selection).cloned()
+    }
+
+    /// Returns true if any client on the display owns the given selection
+    pub fn is_owned(&self, selection: Selection) -> Result<bool, ReplyError> {
+        let atom = self.shared.atom(selection);
+        let owner = self.shared.conn.get_selection_owner(atom)?.reply()?.owner;
+        Ok(owner != NONE)
+    }
+}
+
+impl Drop for ClipboardDisplay {
+    fn drop(&mut self) {
+        // Wake up the event thread so it stops, and give up the selections
+        let shared = &self.shared;
+        shared.running.store(false, Ordering::SeqCst);
+        let event = ClientMessageEvent::new(32, shared.window, AtomEnum::NONE, [0u32; 5]);
+        let result = shared
+            .conn
+            .send_event(false, shared.window, EventMask::NO_EVENT, event)
+            .and_then(|_| shared.conn.destroy_window(shared.window))
+            .and_then(|_| shared.conn.flush());
+        if let Err(e) = result {
+            log::debug!(
+                "Failed to close clipboard on display '{}': {e:?}",
+                shared.name
+            );
+        }
+    }
+}
+
+impl Shared {
+    /// Returns the atom of the given selection
+    fn atom(&self, selection: Selection) -> Atom {
+        match selection {
+            Selection::Clipboard => self.atoms.CLIPBOARD,
+            Selection::Primary => AtomEnum::PRIMARY.into(),
+        }
+    }
+
+    /// Returns the selection with the given atom
+    fn selection(&self, atom: Atom) -> Option<Selection> {
+        Selection::ALL
+            .into_iter()
+            .find(|selection| self.atom(*selection) == atom)
+    }
+
+    /// Handle events until the display is closed or the connection is lost
+    fn run(&self, tx: Sender<ClipboardEvent>) {
+        log::debug!("Started clipboard bridge on display '{}'", self.name);
+        while self.running.load(Ordering::SeqCst) {
+            let event = match self.conn.wait_for_event() {
+                Ok(event) => event,
+                Err(e) => {
+                    log::debug!("Lost clipboard connection to '{}': {e:?}", self.name);
+                    let display = self.name.clone();
+                    tx.send(ClipboardEvent::Disconnected { display }).ok();
+                    return;
+                }
+            };
+            if let Err(e) = self.handle_event(event, &tx) {
+                log::warn!("Failed to handle clipboard event on '{}': {e:?}", self.name);
+            }
+        }
+        log::debug!("Stopped clipboard bridge on display '{}'", self.name);
+    }
+
+    /// Handle the given event from the display
+    fn handle_event(&self, event: Event, tx: &Sender<ClipboardEvent>) -> Result<(), ReplyError> {
+        match event {
+            // Ask the new owner of a selection for its text
+            Event::XfixesSelectionNotify(event) => {
+                if event.owner == NONE || event.owner == self.window {
+                    return Ok(());
+                }
+                self.request_text(event.selection, event.selection_timestamp)?;
+                self.conn.flush()?;
+            }
+            // The text requested from another client has arrived
+            Event::SelectionNotify(event) => {
+                let Some(selection) = self.selection(event.selection) else {
+                    return Ok(());
+                };
+                if event.property == NONE {
+                    // Older clients only support STRING
+                    if event.target == self.atoms.UTF8_STRING {
+                        self.conn.convert_selection(
+                            self.window,
+                            event.selection,
+                            AtomEnum::STRING.into(),
+                            self.atoms.OGUI_SELECTION,
+                            event.time,
+                        )?;
+                        self.conn.flush()?;
+                    }
+                    return Ok(());
+                }
+                let Some(text) = self.read_text(event.property)? else {
+                    return Ok(());
+                };
+                let display = self.name.clone();
+                let event = ClipboardEvent::Changed {
+                    display,
+                    selection,
+                    text,
+                };
+                tx.send(event).ok();
+            }
+            // Another client wants to paste the text of a selection we own
+            Event::SelectionRequest(event) => {
+                self.answer_request(event)?;
+                self.conn.flush()?;
+            }
+            // Another client took over a selection
+            Event::SelectionClear(event) => {
+                if let Some(selection) = self.selection(event.selection) {
+                    self.texts.lock().unwrap().remove(&selection);
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Ask the owner of the given selection to convert it to UTF-8 text. The
+    /// text arrives with a SelectionNotify event.
+    fn request_text(&self, selection: Atom, time: u32) -> Result<(), ReplyError> {
+        let owner = self.conn.get_selection_owner(selection)?.reply()?.owner;
+        if owner == NONE || owner == self.window {
+            return Ok(());
+        }
+        self.conn.convert_selection(
+            self.window,
+            selection,
+            self.atoms.UTF8_STRING,
+            self.atoms.OGUI_SELECTION,
+            time,
+        )?;
+        Ok(())
+    }
+
+    /// Read and delete the text that another client stored in the given
+    /// property of our window
+    fn read_text(&self, property: Atom) -> Result<Option<String>, ReplyError> {
+        let length = (MAX_TEXT_LENGTH / 4) as u32;
+        let reply = self
+            .conn
+            .get_property(true, self.window, property, AtomEnum::ANY, 0, length)?
+            .reply()?;
+        if reply.type_ == self.atoms.INCR || reply.bytes_after > 0 {
+            log::debug!("Ignoring clipboard text larger than {MAX_TEXT_LENGTH} bytes");
+            return Ok(None);
+        }
+        if reply.format != 8 {
+            return Ok(None);
+        }
+        let text = if reply.type_ == u32::from(AtomEnum::STRING) {
+            latin1_to_string(&reply.value)
+        } else {
+            String::from_utf8_lossy(&reply.value).into_owned()
+        };
+        Ok(Some(text))
+    }
+
+    /// Store the text of a selection we own in the property the requestor
+    /// asked for and tell it whether the conversion succeeded
+    fn answer_request(&self, request: SelectionRequestEvent) -> Result<(), ReplyError> {
+        let text = self
+            .selection(request.selection)
+            .and_then(|selection| self.texts.lock().unwrap().get(&selection).cloned());
+        // Obsolete clients don't set a property and expect the target to be used
+        let property = if request.property == NONE {
+            request.target
+        } else {
+            request.property
+        };
+
+        let target = request.target;
+        let requestor = request.requestor;
+        let converted = match text {
+            None => false,
+            Some(_) if target == self.atoms.TARGETS => {
+                let targets = [
+                    self.atoms.TARGETS,
+                    self.atoms.UTF8_STRING,
+                    self.atoms.TEXT,
+                    AtomEnum::STRING.into(),
+                ];
+                self.conn.change_property32(
+                    PropMode::REPLACE,
+                    requestor,
+                    property,
+                    AtomEnum::ATOM,
+                    &targets,
+                )?;
+                true
+            }
+            Some(text) if target == self.atoms.UTF8_STRING || target == self.atoms.TEXT => {
+                self.conn.change_property8(
+                    PropMode::REPLACE,
+                    requestor,
+                    property,
+                    self.atoms.UTF8_STRING,
+                    text.as_bytes(),
+                )?;
+                true
+            }
+            Some(text) if target == u32::from(AtomEnum::STRING) => {
+                self.conn.change_property8(
+                    PropMode::REPLACE,
+                    requestor,
+                    property,
+                    AtomEnum::STRING,
+                    &string_to_latin1(&text),
+                )?;
+                true
+            }
+            Some(_) => false,
+        };
+
+        let event = SelectionNotifyEvent {
+            response_type: SELECTION_NOTIFY_EVENT,
+            sequence: 0,
+            time: request.time,
+            requestor,
+            selection: request.selection,
+            target,
+            property: if converted { property } else { NONE },
+        };
+        self.conn
+            .send_event(false, requestor, EventMask::NO_EVENT, event)?;
+        Ok(())
+    }
+}
+
+/// Bridges the clipboard between linked XWayland displays, so text copied on
+/// one display (e.g. a 2FA code in a game) can be pasted on the others.
+#[derive(GodotClass)]
+#[class(no_init, base=Resource)]
+pub struct GamescopeClipboard {
+    base: Base<Resource>,
+    rx: Receiver<ClipboardEvent>,
+    tx: Sender<ClipboardEvent>,
+    displays: HashMap<String, ClipboardDisplay>,
+    texts: HashMap<Selection, String>,
+}
+
+#[godot_api]
+impl GamescopeClipboard {
+    /// The selection used by explicit copy and paste
+    #[constant]
+    const SELECTION_CLIPBOARD: u32 = 0;
+
+    /// The selection holding the most recently selected text, pasted with the
+    /// middle mouse button
+    #[constant]
+    const SELECTION_PRIMARY: u32 = 1;
+
+    /// Emitted when the text of a selection changes on any linked display
+    #[signal]
+    fn clipboard_changed(selection: u32, text: GString);
+
+    /// Emitted when a display is no longer linked because its connection was
+    /// lost
+    #[signal]
+    fn display_unlinked(name: GString);
+
+    /// Create a new [GamescopeClipboard] without any linked displays
+    pub fn new() -> Gd<Self> {
+        let (tx, rx) = channel(&WAKEUP);
+        Gd::from_init_fn(|base| Self {
+            base,
+            rx,
+            tx,
+            displays: Default::default(),
+            texts: Default::default(),
+        })
+    }
+
+    /// Start bridging the clipboard of the given display (e.g. ":1") with the
+    /// other linked displays
+    #[func]
+    pub fn link_display(&mut self, name: GString) -> i32 {
+        let name = name.to_string();
+        if self.displays.contains_key(&name) {
+            return 0;
+        }
+        let display = match ClipboardDisplay::connect(name.as_str(), self.tx.clone()) {
+            Ok(display) => display,
+            Err(e) => {
+                log::error!("Failed to link clipboard of display '{name}': {e:?}");
+                return -1;
+            }
+        };
+        log::debug!("Linked clipboard of display '{name}'");
+
+        // Displays without any clipboard text get the text of the others
+        for (selection, text) in self.texts.iter() {
+            if !display.is_owned(*selection).unwrap_or(true) {
+                if let Err(e) = display.set_text(*selection, text) {
+                    log::warn!("Failed to set clipboard on display '{name}': {e:?}");
+                }
+            }
+        }
+        self.displays.insert(name, display);
+        0
+    }
+
+    /// Stop bridging the clipboard of the given display
+    #[func]
+    pub fn unlink_display(&mut self, name: GString) {
+        if self.displays.remove(&name.to_string()).is_some() {
+            log::debug!("Unlinked clipboard of display '{name}'");
+        }
+    }
+
+    /// Returns true if the clipboard of the given display is bridged
+    #[func]
+    pub fn is_linked(&self, name: GString) -> bool {
+        self.displays.contains_key(&name.to_string())
+    }
+
+    /// Returns the names of all linked displays
+    #[func]
+    pub fn get_linked_displays(&self) -> PackedStringArray {
+        let mut names: Vec<&String> = self.displays.keys().collect();
+        names.sort();
+        names.into_iter().map(|name| name.as_str().into()).collect()
+    }
+
+    /// Returns the text of the CLIPBOARD selection
+    #[func]
+    pub fn get_clipboard_text(&self) -> GString {
+        self.get_selection_text(Self::SELECTION_CLIPBOARD)
+    }
+
+    /// Put the given text into the CLIPBOARD selection of all linked displays
+    #[func]
+    pub fn set_clipboard_text(&mut self, text: GString) -> i32 {
+        self.set_selection_text(Self::SELECTION_CLIPBOARD, text)
+    }
+
+    /// Returns the text of the given SELECTION_*
+    #[func]
+    pub fn get_selection_text(&self, selection: u32) -> GString {
+        let Some(selection) = Self::selection_from_u32(selection) else {
+            return GString::new();
+        };
+        let text = self.texts.get(&selection);
+        text.map(|text| text.as_str().into()).unwrap_or_default()
+    }
+
+    /// Put the given text into the given SELECTION_* of all linked displays
+    #[func]
+    pub fn set_selection_text(&mut self, selection: u32, text: GString) -> i32 {
+        let Some(selection) = Self::selection_from_u32(selection) else {
+            log::error!("Invalid selection: {selection}");
+            return -1;
+        };
+        let text = text.to_string();
+        if text.len() > MAX_TEXT_LENGTH {
+            log::error!("Clipboard text is larger than {MAX_TEXT_LENGTH} bytes");
+            return -1;
+        }
+        self.update_text(selection, text, None)
+    }
+
+    /// Dispatches signals, called by [GamescopeInstance]
+    pub fn process(&mut self) {
+        // Drain all messages from the channel to process them
+        loop {
+            let event = match self.rx.try_recv() {
+                Ok(value) => value,
+                Err(e) => match e {
+                    TryRecvError::Empty => break,
+                    TryRecvError::Disconnected => {
+                        log::error!("Backend thread is not running!");
+                        return;
+                    }
+                },
+            };
+            match event {
+                ClipboardEvent::Changed {
+                    display,
+                    selection,
+                    text,
+                } => {
+                    if self.texts.get(&selection) == Some(&text) {
+                        continue;
+                    }
+                    log::trace!("Clipboard {selection:?} changed on display '{display}'");
+                    self.update_text(selection, text, Some(display.as_str()));
+                }
+                ClipboardEvent::Disconnected { display } => {
+                    if self.displays.remove(&display).is_none() {
+                        continue;
+                    }
+                    self.base_mut()
+                        .emit_signal("display_unlinked", &[display.to_godot().to_variant()]);
+                }
+            }
+        }
+    }
+
+    /// Store the given text of a selection, serve it on all linked displays
+    /// except the one it came from and emit [signal clipboard_changed]
+    fn update_text(&mut self, selection: Selection, text: String, source: Option<&str>) -> i32 {
+        let mut result = 0;
+        for (name, display) in self.displays.iter() {
+            if Some(name.as_str()) == source {
+                continue;
+            }
+            if let Err(e) = display.set_text(selection, text.as_str()) {
+                log::error!("Failed to set clipboard on display '{name}': {e:?}");
+                result = -1;
+            }
+        }
+        let value = match selection {
+            Selection::Clipboard => Self::SELECTION_CLIPBOARD,
+            Selection::Primary => Self::SELECTION_PRIMARY,
+        };
+        let godot_text = GString::from(text.as_str());
+        self.texts.insert(selection, text);
+        self.base_mut().emit_signal(
+            "clipboard_changed",
+            &[value.to_variant(), godot_text.to_variant()],
+        );
+        result
+    }
+
+    /// Returns the [Selection] for the given SELECTION_*
+    fn selection_from_u32(selection: u32) -> Option<Selection> {
+        match selection {
+            Self::SELECTION_CLIPBOARD => Some(Selection::Clipboard),
+            Self::SELECTION_PRIMARY => Some(Selection::Primary),
+            _ => None,
+        }
+    }
+}
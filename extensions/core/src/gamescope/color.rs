@@ -0,0 +1,718 @@
+use std::{
+    collections::HashSet,
+    fmt::Write,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use godot::{obj::WithBaseField, prelude::*};
+
+use godot::classes::{ConfigFile, Resource, Time};
+use tokio::task::AbortHandle;
+
+use crate::resource::dispatcher::{channel, Receiver, Sender, TryRecvError};
+use crate::RUNTIME;
+
+use super::x11_connection::X11Connection;
+use super::WAKEUP;
+
+/// Path to the file where colour settings are persisted
+const SETTINGS_PATH: &str = "user://gamescope_color.cfg";
+/// Section in the settings file where colour settings are stored
+const SETTINGS_SECTION: &str = "color";
+
+/// Path of a 1D shaper LUT file that Gamescope should use instead of its own
+const ATOM_SHAPER_LUT: &str = "GAMESCOPE_COLOR_SHAPERLUT_OVERRIDE";
+/// Path of a 3D LUT file that Gamescope should use instead of its own
+const ATOM_3D_LUT: &str = "GAMESCOPE_COLOR_3DLUT_OVERRIDE";
+/// How much to widen the gamut of SDR content, stored as the bits of an f32
+const ATOM_SDR_GAMUT_WIDENESS: &str = "GAMESCOPE_COLOR_SDR_GAMUT_WIDENESS";
+/// Night mode amount, hue and saturation, each stored as the bits of an f32
+const ATOM_NIGHT_MODE: &str = "GAMESCOPE_COLOR_NIGHTMODE";
+
+/// Number of entries in generated shaper LUTs
+const LUT_1D_SIZE: usize = 4096;
+/// Colour temperature that leaves colours unchanged
+pub const NEUTRAL_TEMPERATURE: u32 = 6500;
+/// Lowest and highest supported colour temperature
+const TEMPERATURE_RANGE: (u32, u32) = (1000, 10000);
+/// Lowest and highest supported gamma
+const GAMMA_RANGE: (f32, f32) = (0.5, 2.5);
+/// Number of minutes in a day
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// How often to update the night-light while it is enabled
+const NIGHT_LIGHT_INTERVAL: Duration = Duration::from_secs(30);
+/// If ticks arrive this much later than expected, the system was most likely
+/// suspended and all colour settings are applied again.
+const RESUME_THRESHOLD: Duration = Duration::from_secs(90);
+
+/// Signals that can be emitted
+#[derive(Debug)]
+enum Signal {
+    NightLightTick,
+}
+
+/// Time window in which the night-light is active. Times are given in minutes
+/// since midnight and the window may wrap past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NightLightSchedule {
+    /// Minute of the day when the night-light starts fading in
+    pub start: u32,
+    /// Minute of the day when the night-light has fully faded out
+    pub end: u32,
+    /// Number of minutes it takes to fade in or out
+    pub transition: u32,
+}
+
+impl NightLightSchedule {
+    /// Returns how strongly the night-light should be applied at the given
+    /// minute of the day, from 0.0 (off) to 1.0 (full strength). The
+    /// night-light fades in over `transition` minutes after `start` and fades
+    /// out over `transition` minutes before `end`.
+    pub fn amount(&self, minute: f32) -> f32 {
+        let day = MINUTES_PER_DAY as f32;
+        let start = (self.start % MINUTES_PER_DAY) as f32;
+        let end = (self.end % MINUTES_PER_DAY) as f32;
+        let length = (end - start).rem_euclid(day);
+        if length == 0.0 {
+            return 0.0;
+        }
+        let elapsed = (minute - start).rem_euclid(day);
+        if elapsed >= length {
+            return 0.0;
+        }
+
+        let transition = (self.transition as f32).min(length / 2.0);
+        if transition <= 0.0 {
+            return 1.0;
+        }
+        let fade_in = elapsed / transition;
+        let fade_out = (length - elapsed) / transition;
+        fade_in.min(fade_out).min(1.0)
+    }
+}
+
+/// Returns the red, green and blue gains that tint white to the given colour
+/// temperature in Kelvin. The gains are relative to [NEUTRAL_TEMPERATURE], so
+/// that temperature returns 1.0 for every channel.
+pub fn temperature_gains(kelvin: u32) -> [f32; 3] {
+    let color = temperature_to_rgb(kelvin);
+    let neutral = temperature_to_rgb(NEUTRAL_TEMPERATURE);
+    [
+        (color[0] / neutral[0]).clamp(0.0, 1.0),
+        (color[1] / neutral[1]).clamp(0.0, 1.0),
+        (color[2] / neutral[2]).clamp(0.0, 1.0),
+    ]
+}
+
+/// Approximates the colour of a black body at the given temperature in Kelvin
+/// as RGB values from 0 to 255.
+fn temperature_to_rgb(kelvin: u32) -> [f32; 3] {
+    let kelvin = kelvin.clamp(TEMPERATURE_RANGE.0, TEMPERATURE_RANGE.1);
+    let t = kelvin as f32 / 100.0;
+    let red = if t <= 66.0 {
+        255.0
+    } else {
+        329.69873 * (t - 60.0).powf(-0.13320476)
+    };
+    let green = if t <= 66.0 {
+        99.4708 * t.ln() - 161.11957
+    } else {
+        288.12216 * (t - 60.0).powf(-0.07551485)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.51773 * (t - 10.0).ln() - 305.0448
+    };
+    [
+        red.clamp(0.0, 255.0),
+        green.clamp(0.0, 255.0),
+        blue.clamp(0.0, 255.0),
+    ]
+}
+
+/// Returns the contents of a 1D shaper LUT in the ".cube" format with the
+/// given number of entries. Each channel is scaled by its gain and then
+/// raised to `1 / gamma`, so a gamma above 1.0 brightens the image.
+pub fn shaper_lut(gamma: f32, gains: [f32; 3], size: usize) -> String {
+    let size = size.max(2);
+    let exponent = 1.0 / gamma.clamp(GAMMA_RANGE.0, GAMMA_RANGE.1);
+    let mut lut = format!("LUT_1D_SIZE {size}\n");
+    for i in 0..size {
+        let x = i as f32 / (size - 1) as f32;
+        let [r, g, b] = gains.map(|gain| (x * gain).clamp(0.0, 1.0).powf(exponent));
+        let _ = writeln!(lut, "{r:.6} {g:.6} {b:.6}");
+    }
+    lut
+}
+
+/// Controls the colour management of Gamescope on the primary XWayland
+/// display: gamma, colour temperature, saturation, a custom 3D LUT and a
+/// scheduled night-light. Settings are persisted and applied again whenever
+/// the display changes or the system resumes.
+#[derive(GodotClass)]
+#[class(no_init, base=Resource)]
+pub struct GamescopeColor {
+    base: Base<Resource>,
+    rx: Receiver<Signal>,
+    tx: Sender<Signal>,
+    x11: Option<X11Connection>,
+    night_light_task: Option<AbortHandle>,
+    last_tick: Option<Instant>,
+    /// Atoms that were set on the current display. Only these are removed
+    /// again when a setting goes back to its default.
+    overrides: HashSet<&'static str>,
+    /// Shaper LUT file that Gamescope currently points to
+    shaper_lut_file: Option<PathBuf>,
+
+    /// Name of the XWayland display the settings are applied to (e.g. ":0")
+    #[var(get)]
+    display: GString,
+    /// Gamma correction to apply. Values above 1.0 brighten the image.
+    #[var(get, set = set_gamma)]
+    gamma: f32,
+    /// Colour temperature in Kelvin. 6500 leaves colours unchanged and lower
+    /// values make the image warmer.
+    #[var(get, set = set_temperature)]
+    temperature: u32,
+    /// How much to widen the gamut of SDR content, from 0.0 to 1.0. Negative
+    /// values leave the Gamescope default in place.
+    #[var(get, set = set_saturation)]
+    saturation: f32,
+    /// Path to a 3D LUT file in the ".cube" format to use instead of the
+    /// Gamescope default. Empty to use the default.
+    #[var(get, set = set_lut_path)]
+    lut_path: GString,
+    /// Whether the scheduled night-light is enabled
+    #[var(get, set = set_night_light_enabled)]
+    night_light_enabled: bool,
+    /// Strength of the night-light at its peak, from 0.0 to 1.0
+    #[var(get, set = set_night_light_strength)]
+    night_light_strength: f32,
+    /// Hue of the night-light tint, from 0.0 to 1.0
+    #[var(get, set = set_night_light_hue)]
+    night_light_hue: f32,
+    /// Saturation of the night-light tint, from 0.0 to 1.0
+    #[var(get, set = set_night_light_saturation)]
+    night_light_saturation: f32,
+    /// Minute of the day when the night-light starts
+    #[var(get, set = set_night_light_start)]
+    night_light_start: u32,
+    /// Minute of the day when the night-light ends
+    #[var(get, set = set_night_light_end)]
+    night_light_end: u32,
+    /// Number of minutes it takes the night-light to fade in or out
+    #[var(get, set = set_night_light_transition)]
+    night_light_transition: u32,
+    /// Night-light amount that is currently applied, from 0.0 to 1.0
+    #[var(get)]
+    night_light_amount: f32,
+}
+
+#[godot_api]
+impl GamescopeColor {
+    /// Emitted when the applied night-light amount changes
+    #[signal]
+    fn night_light_amount_updated(from: f32, to: f32);
+
+    /// Emitted after all colour settings were applied to the display
+    #[signal]
+    fn applied(display: GString);
+
+    /// Create a new [GamescopeColor] with the persisted settings
+    pub fn new() -> Gd<Self> {
+        let (tx, rx) = channel(&WAKEUP);
+        let mut color = Gd::from_init_fn(|base| Self {
+            base,
+            rx,
+            tx,
+            x11: None,
+            night_light_task: None,
+            last_tick: None,
+            overrides: Default::default(),
+            shaper_lut_file: None,
+            display: Default::default(),
+            gamma: 1.0,
+            temperature: NEUTRAL_TEMPERATURE,
+            saturation: -1.0,
+            lut_path: Default::default(),
+            night_light_enabled: false,
+            night_light_strength: 0.5,
+            night_light_hue: 0.05,
+            night_light_saturation: 1.0,
+            night_light_start: 21 * 60,
+            night_light_end: 7 * 60,
+            night_light_transition: 30,
+            night_light_amount: 0.0,
+        });
+        color.bind_mut().load();
+        color
+    }
+
+    /// Set the XWayland display to apply colour settings to and apply them.
+    /// An empty name stops applying settings until a display is set again.
+    pub fn set_display(&mut self, name: &str) {
+        self.display = name.into();
+        self.x11 = None;
+        self.overrides.clear();
+        if name.is_empty() {
+            return;
+        }
+        self.x11 = match X11Connection::connect(name) {
+            Ok(x11) => Some(x11),
+            Err(e) => {
+                log::error!("Failed to connect to display '{name}' for colour management: {e}");
+                None
+            }
+        };
+        self.apply();
+    }
+
+    /// Apply all colour settings that differ from the Gamescope defaults to
+    /// the display again. Settings that were changed back to their defaults
+    /// have their atoms removed.
+    #[func]
+    pub fn apply(&mut self) {
+        if self.x11.is_none() {
+            return;
+        }
+        self.apply_shaper_lut();
+        self.apply_lut_path();
+        self.apply_saturation();
+        self.update_night_light(true);
+        let display = self.display.clone();
+        self.base_mut()
+            .emit_signal("applied", &[display.to_variant()]);
+    }
+
+    /// Reset all colour settings to their defaults
+    #[func]
+    pub fn reset(&mut self) {
+        self.gamma = 1.0;
+        self.temperature = NEUTRAL_TEMPERATURE;
+        self.saturation = -1.0;
+        self.lut_path = Default::default();
+        self.night_light_enabled = false;
+        self.update_night_light_task();
+        self.save();
+        self.apply();
+    }
+
+    /// Returns how strongly the night-light would be applied at the given
+    /// minute of the day with the current schedule
+    #[func]
+    pub fn get_night_light_amount_at(&self, minute: u32) -> f32 {
+        self.schedule().amount(minute as f32) * self.night_light_strength
+    }
+
+    /// Sets the gamma correction. Values are clamped to 0.5-2.5.
+    #[func]
+    pub fn set_gamma(&mut self, value: f32) {
+        self.gamma = value.clamp(GAMMA_RANGE.0, GAMMA_RANGE.1);
+        self.save();
+        self.apply_shaper_lut();
+    }
+
+    /// Sets the colour temperature in Kelvin. Values are clamped to 1000-10000.
+    #[func]
+    pub fn set_temperature(&mut self, value: u32) {
+        self.temperature = value.clamp(TEMPERATURE_RANGE.0, TEMPERATURE_RANGE.1);
+        self.save();
+        self.apply_shaper_lut();
+    }
+
+    /// Sets the SDR gamut wideness. Negative values restore the Gamescope default.
+    #[func]
+    pub fn set_saturation(&mut self, value: f32) {
+        self.saturation = value.min(1.0);
+        self.save();
+        self.apply_saturation();
+    }
+
+    /// Sets the path to a 3D LUT file, or an empty path to use the Gamescope default
+    #[func]
+    pub fn set_lut_path(&mut self, value: GString) {
+        self.lut_path = value;
+        self.save();
+        self.apply_lut_path();
+    }
+
+    /// Enables or disables the scheduled night-light
+    #[func]
+    pub fn set_night_light_enabled(&mut self, value: bool) {
+        self.night_light_enabled = value;
+        self.save();
+        self.update_night_light_task();
+        self.update_night_light(false);
+    }
+
+    /// Sets the peak strength of the night-light. Values are clamped to 0.0-1.0.
+    #[func]
+    pub fn set_night_light_strength(&mut self, value: f32) {
+        self.night_light_strength = value.clamp(0.0, 1.0);
+        self.save();
+        self.update_night_light(false);
+    }
+
+    /// Sets the hue of the night-light tint. Values are clamped to 0.0-1.0.
+    #[func]
+    pub fn set_night_light_hue(&mut self, value: f32) {
+        self.night_light_hue = value.clamp(0.0, 1.0);
+        self.save();
+        self.update_night_light(true);
+    }
+
+    /// Sets the saturation of the night-light tint. Values are clamped to 0.0-1.0.
+    #[func]
+    pub fn set_night_light_saturation(&mut self, value: f32) {
+        self.night_light_saturation = value.clamp(0.0, 1.0);
+        self.save();
+        self.update_night_light(true);
+    }
+
+    /// Sets the minute of the day when the night-light starts
+    #[func]
+    pub fn set_night_light_start(&mut self, value: u32) {
+        self.night_light_start = value % MINUTES_PER_DAY;
+        self.save();
+        self.update_night_light(false);
+    }
+
+    /// Sets the minute of the day when the night-light ends
+    #[func]
+    pub fn set_night_light_end(&mut self, value: u32) {
+        self.night_light_end = value % MINUTES_PER_DAY;
+        self.save();
+        self.update_night_light(false);
+    }
+
+    /// Sets the number of minutes the night-light takes to fade in or out
+    #[func]
+    pub fn set_night_light_transition(&mut self, value: u32) {
+        self.night_light_transition = value;
+        self.save();
+        self.update_night_light(false);
+    }
+
+    /// Dispatches signals, called by [GamescopeInstance]
+    pub fn process(&mut self) {
+        // Drain all messages from the channel to process them
+        loop {
+            let signal = match self.rx.try_recv() {
+                Ok(value) => value,
+                Err(e) => match e {
+                    TryRecvError::Empty => break,
+                    TryRecvError::Disconnected => {
+                        log::error!("Backend thread is not running!");
+                        return;
+                    }
+                },
+            };
+            match signal {
+                Signal::NightLightTick => {
+                    // Gamescope may have lost its colour state while the
+                    // system was suspended, so apply everything again.
+                    let now = Instant::now();
+                    let resumed = self
+                        .last_tick
+                        .is_some_and(|last| now.duration_since(last) > RESUME_THRESHOLD);
+                    self.last_tick = Some(now);
+                    if resumed {
+                        log::debug!("Detected resume, applying colour settings again");
+                        self.apply();
+                    } else {
+                        self.update_night_light(false);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the current night-light schedule
+    fn schedule(&self) -> NightLightSchedule {
+        NightLightSchedule {
+            start: self.night_light_start,
+            end: self.night_light_end,
+            transition: self.night_light_transition,
+        }
+    }
+
+    /// Start or stop the task that periodically updates the night-light
+    fn update_night_light_task(&mut self) {
+        if !self.night_light_enabled {
+            if let Some(task) = self.night_light_task.take() {
+                task.abort();
+            }
+            self.last_tick = None;
+            return;
+        }
+        if self.night_light_task.is_some() {
+            return;
+        }
+        let tx = self.tx.clone();
+        let task = RUNTIME.spawn(async move {
+            loop {
+                tokio::time::sleep(NIGHT_LIGHT_INTERVAL).await;
+                if tx.send(Signal::NightLightTick).is_err() {
+                    log::debug!("Colour settings were dropped, stopping night-light");
+                    return;
+                }
+            }
+        });
+        self.night_light_task = Some(task.abort_handle());
+        self.last_tick = Some(Instant::now());
+    }
+
+    /// Update the night-light amount for the current time of day. The atom is
+    /// only written if the amount changed, unless `force` is set.
+    fn update_night_light(&mut self, force: bool) {
+        let amount = if self.night_light_enabled {
+            self.current_night_light_amount()
+        } else {
+            0.0
+        };
+        let from = self.night_light_amount;
+        if !force && (amount - from).abs() < f32::EPSILON {
+            return;
+        }
+        self.night_light_amount = amount;
+
+        if amount <= 0.0 {
+            self.remove_override(ATOM_NIGHT_MODE);
+        } else if let Some(x11) = self.x11.as_ref() {
+            let values = [
+                amount.to_bits(),
+                self.night_light_hue.to_bits(),
+                self.night_light_saturation.to_bits(),
+            ];
+            match x11.set_root_cardinals(ATOM_NIGHT_MODE, &values) {
+                Ok(_) => {
+                    self.overrides.insert(ATOM_NIGHT_MODE);
+                }
+                Err(e) => log::error!("Failed to set night mode: {e:?}"),
+            }
+        }
+
+        if (amount - from).abs() >= f32::EPSILON {
+            self.base_mut().emit_signal(
+                "night_light_amount_updated",
+                &[from.to_variant(), amount.to_variant()],
+            );
+        }
+    }
+
+    /// Returns the night-light amount for the current local time of day
+    fn current_night_light_amount(&self) -> f32 {
+        let time = Time::singleton().get_time_dict_from_system();
+        let get = |key: &str| -> f32 {
+            time.get(key)
+                .and_then(|value| value.try_to::<i64>().ok())
+                .unwrap_or_default() as f32
+        };
+        let minute = get("hour") * 60.0 + get("minute") + get("second") / 60.0;
+        self.schedule().amount(minute) * self.night_light_strength
+    }
+
+    /// Write a shaper LUT for the current gamma and colour temperature and
+    /// point Gamescope to it
+    fn apply_shaper_lut(&mut self) {
+        if self.x11.is_none() {
+            return;
+        }
+        let is_identity =
+            (self.gamma - 1.0).abs() < f32::EPSILON && self.temperature == NEUTRAL_TEMPERATURE;
+        if is_identity {
+            self.remove_override(ATOM_SHAPER_LUT);
+            self.remove_shaper_lut_file();
+            return;
+        }
+
+        let gains = temperature_gains(self.temperature);
+        let lut = shaper_lut(self.gamma, gains, LUT_1D_SIZE);
+        let path = Self::shaper_lut_path(self.gamma, self.temperature);
+        if let Err(e) = std::fs::write(&path, lut) {
+            log::error!("Failed to write shaper LUT to {path:?}: {e:?}");
+            return;
+        }
+        let Some(x11) = self.x11.as_ref() else {
+            return;
+        };
+        let value = path.to_string_lossy();
+        if let Err(e) = x11.set_root_string(ATOM_SHAPER_LUT, &value) {
+            log::error!("Failed to set shaper LUT to {value}: {e:?}");
+            return;
+        }
+        self.overrides.insert(ATOM_SHAPER_LUT);
+
+        // Gamescope has read the new file, so the previous one is not needed
+        if self.shaper_lut_file.as_ref() != Some(&path) {
+            self.remove_shaper_lut_file();
+            self.shaper_lut_file = Some(path);
+        }
+    }
+
+    /// Returns the path to write the shaper LUT for the given settings to.
+    /// The name includes the settings so Gamescope notices when it changes.
+    fn shaper_lut_path(gamma: f32, temperature: u32) -> PathBuf {
+        let dir = std::env::var("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| std::env::temp_dir());
+        dir.join(format!(
+            "opengamepadui-shaper-{gamma:.2}-{temperature}.cube"
+        ))
+    }
+
+    /// Delete the shaper LUT file that was last written
+    fn remove_shaper_lut_file(&mut self) {
+        let Some(path) = self.shaper_lut_file.take() else {
+            return;
+        };
+        if let Err(e) = std::fs::remove_file(&path) {
+            log::debug!("Failed to remove shaper LUT {path:?}: {e:?}");
+        }
+    }
+
+    /// Point Gamescope to the configured 3D LUT, or remove the override
+    fn apply_lut_path(&mut self) {
+        let path = self.lut_path.to_string();
+        if path.is_empty() {
+            self.remove_override(ATOM_3D_LUT);
+            return;
+        }
+        let Some(x11) = self.x11.as_ref() else {
+            return;
+        };
+        match x11.set_root_string(ATOM_3D_LUT, &path) {
+            Ok(_) => {
+                self.overrides.insert(ATOM_3D_LUT);
+            }
+            Err(e) => log::error!("Failed to set 3D LUT to '{path}': {e:?}"),
+        }
+    }
+
+    /// Set the SDR gamut wideness, or remove it to use the Gamescope default
+    fn apply_saturation(&mut self) {
+        if self.saturation < 0.0 {
+            self.remove_override(ATOM_SDR_GAMUT_WIDENESS);
+            return;
+        }
+        let Some(x11) = self.x11.as_ref() else {
+            return;
+        };
+        match x11.set_root_cardinals(ATOM_SDR_GAMUT_WIDENESS, &[self.saturation.to_bits()]) {
+            Ok(_) => {
+                self.overrides.insert(ATOM_SDR_GAMUT_WIDENESS);
+            }
+            Err(e) => log::error!("Failed to set saturation to {}: {e:?}", self.saturation),
+        }
+    }
+
+    /// Remove the given atom if it was set on the current display, so
+    /// Gamescope goes back to its own default. Atoms that were never set are
+    /// left alone.
+    fn remove_override(&mut self, atom: &'static str) {
+        if !self.overrides.remove(atom) {
+            return;
+        }
+        let Some(x11) = self.x11.as_ref() else {
+            return;
+        };
+        if let Err(e) = x11.remove_root_property(atom) {
+            log::error!("Failed to remove {atom}: {e:?}");
+        }
+    }
+
+    /// Load persisted settings from [SETTINGS_PATH]
+    fn load(&mut self) {
+        let mut config = ConfigFile::new_gd();
+        if config.load(SETTINGS_PATH) != godot::global::Error::OK {
+            return;
+        }
+        let get = |key: &str| -> Option<Variant> {
+            if !config.has_section_key(SETTINGS_SECTION, key) {
+                return None;
+            }
+            Some(config.get_value(SETTINGS_SECTION, key))
+        };
+        if let Some(value) = get("gamma").and_then(|v| v.try_to::<f32>().ok()) {
+            self.gamma = value.clamp(GAMMA_RANGE.0, GAMMA_RANGE.1);
+        }
+        if let Some(value) = get("temperature").and_then(|v| v.try_to::<u32>().ok()) {
+            self.temperature = value.clamp(TEMPERATURE_RANGE.0, TEMPERATURE_RANGE.1);
+        }
+        if let Some(value) = get("saturation").and_then(|v| v.try_to::<f32>().ok()) {
+            self.saturation = value.min(1.0);
+        }
+        if let Some(value) = get("lut_path").and_then(|v| v.try_to::<GString>().ok()) {
+            self.lut_path = value;
+        }
+        if let Some(value) = get("night_light_enabled").and_then(|v| v.try_to::<bool>().ok()) {
+            self.night_light_enabled = value;
+        }
+        if let Some(value) = get("night_light_strength").and_then(|v| v.try_to::<f32>().ok()) {
+            self.night_light_strength = value.clamp(0.0, 1.0);
+        }
+        if let Some(value) = get("night_light_hue").and_then(|v| v.try_to::<f32>().ok()) {
+            self.night_light_hue = value.clamp(0.0, 1.0);
+        }
+        if let Some(value) = get("night_light_saturation").and_then(|v| v.try_to::<f32>().ok()) {
+            self.night_light_saturation = value.clamp(0.0, 1.0);
+        }
+        if let Some(value) = get("night_light_start").and_then(|v| v.try_to::<u32>().ok()) {
+            self.night_light_start = value % MINUTES_PER_DAY;
+        }
+        if let Some(value) = get("night_light_end").and_then(|v| v.try_to::<u32>().ok()) {
+            self.night_light_end = value % MINUTES_PER_DAY;
+        }
+        if let Some(value) = get("night_light_transition").and_then(|v| v.try_to::<u32>().ok()) {
+            self.night_light_transition = value;
+        }
+        self.update_night_light_task();
+    }
+
+    /// Persist the current settings to [SETTINGS_PATH]
+    fn save(&self) {
+        let mut config = ConfigFile::new_gd();
+        let values = [
+            ("gamma", self.gamma.to_variant()),
+            ("temperature", self.temperature.to_variant()),
+            ("saturation", self.saturation.to_variant()),
+            ("lut_path", self.lut_path.to_variant()),
+            ("night_light_enabled", self.night_light_enabled.to_variant()),
+            (
+                "night_light_strength",
+                self.night_light_strength.to_variant(),
+            ),
+            ("night_light_hue", self.night_light_hue.to_variant()),
+            (
+                "night_light_saturation",
+                self.night_light_saturation.to_variant(),
+            ),
+            ("night_light_start", self.night_light_start.to_variant()),
+            ("night_light_end", self.night_light_end.to_variant()),
+            (
+                "night_light_transition",
+                self.night_light_transition.to_variant(),
+            ),
+        ];
+        for (key, value) in values {
+            config.set_value(SETTINGS_SECTION, key, &value);
+        }
+        let err = config.save(SETTINGS_PATH);
+        if err != godot::global::Error::OK {
+            log::error!("Failed to save colour settings to {SETTINGS_PATH}: {err:?}");
+        }
+    }
+}
+
+impl Drop for GamescopeColor {
+    fn drop(&mut self) {
+        if let Some(task) = self.night_light_task.take() {
+            task.abort();
+        }
+    }
+}
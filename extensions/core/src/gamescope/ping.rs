@@ -0,0 +1,375 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use x11rb::{
+    atom_manager,
+    connection::Connection,
+    errors::ReplyError,
+    protocol::{
+        xproto::{
+            AtomEnum, ChangeWindowAttributesAux, ClientMessageEvent, ConnectionExt,
+            CreateWindowAux, EventMask, InputFocus, Window, WindowClass,
+        },
+        Event,
+    },
+    rust_connection::RustConnection,
+    COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT, CURRENT_TIME, NONE,
+};
+
+use crate::resource::dispatcher::Sender;
+use crate::RUNTIME;
+
+/// How often the focused window is pinged
+const PING_INTERVAL: Duration = Duration::from_secs(1);
+/// Time after which a window that didn't answer a ping is unresponsive
+pub const DEFAULT_PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+atom_manager! {
+    /// Atoms used by [WindowPinger]
+    Atoms: AtomsCookie {
+        WM_PROTOCOLS,
+        _NET_WM_PING,
+    }
+}
+
+/// Changes in whether a window answers pings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PingEvent {
+    /// The window didn't answer a ping within the timeout
+    Unresponsive(u32),
+    /// The window answered a ping again after it was unresponsive
+    Responsive(u32),
+}
+
+/// Ping state of a single window
+#[derive(Debug, Default)]
+struct PingState {
+    /// When the unanswered ping was sent
+    sent: Option<Instant>,
+    unresponsive: bool,
+}
+
+/// Decides which windows to ping and when they become unresponsive. The
+/// focused window is pinged whenever it answered the previous ping. Windows
+/// that lose focus while unresponsive keep being tracked until they answer,
+/// so it is noticed when they recover.
+#[derive(Debug)]
+pub struct PingTracker {
+    timeout: Duration,
+    focused: Option<u32>,
+    windows: HashMap<u32, PingState>,
+}
+
+impl PingTracker {
+    /// Create a new tracker that considers windows unresponsive once a ping
+    /// wasn't answered within the given timeout
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            focused: None,
+            windows: Default::default(),
+        }
+    }
+
+    /// Sets the time after which an unanswered ping makes a window
+    /// unresponsive
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Returns the window that is currently pinged because it is focused
+    pub fn focused(&self) -> Option<u32> {
+        self.focused
+    }
+
+    /// Returns true if the given window didn't answer its last ping in time
+    pub fn is_unresponsive(&self, window: u32) -> bool {
+        self.windows
+            .get(&window)
+            .is_some_and(|state| state.unresponsive)
+    }
+
+    /// Start pinging the given focused window, or stop pinging the previously
+    /// focused window if it is responsive
+    pub fn set_focused(&mut self, window: Option<u32>) {
+        self.focused = window;
+        if let Some(window) = window {
+            self.windows.entry(window).or_default();
+        }
+        self.windows
+            .retain(|id, state| Some(*id) == window || state.unresponsive);
+    }
+
+    /// Stop tracking the given window
+    pub fn window_destroyed(&mut self, window: u32) {
+        self.windows.remove(&window);
+        if self.focused == Some(window) {
+            self.focused = None;
+        }
+    }
+
+    /// Returns the windows that should be pinged now, and the events of
+    /// windows whose ping is overdue
+    pub fn tick(&mut self, now: Instant) -> (Vec<u32>, Vec<PingEvent>) {
+        let mut pings = Vec::new();
+        let mut events = Vec::new();
+        for (window, state) in self.windows.iter_mut() {
+            match state.sent {
+                None => {
+                    state.sent = Some(now);
+                    pings.push(*window);
+                }
+                Some(sent) if !state.unresponsive && now - sent >= self.timeout => {
+                    state.unresponsive = true;
+                    events.push(PingEvent::Unresponsive(*window));
+                }
+                _ => (),
+            }
+        }
+        pings.sort_unstable();
+        (pings, events)
+    }
+
+    /// Handle the answer of the given window to a ping
+    pub fn pong(&mut self, window: u32) -> Option<PingEvent> {
+        let state = self.windows.get_mut(&window)?;
+        state.sent = None;
+        let recovered = std::mem::take(&mut state.unresponsive);
+        if self.focused != Some(window) {
+            self.windows.remove(&window);
+        }
+        recovered.then_some(PingEvent::Responsive(window))
+    }
+}
+
+/// State shared between a [WindowPinger] and its threads
+struct Shared {
+    name: String,
+    conn: RustConnection,
+    root: Window,
+    /// Window that receives the event which stops the event thread
+    window: Window,
+    atoms: Atoms,
+    running: AtomicBool,
+    tracker: Mutex<PingTracker>,
+}
+
+/// Pings the focused window of an X11 display with _NET_WM_PING and sends a
+/// [PingEvent] when it stops or resumes answering. Windows that don't support
+/// _NET_WM_PING are not pinged.
+pub struct WindowPinger {
+    shared: Arc<Shared>,
+}
+
+impl WindowPinger {
+    /// Connect to the X11 display with the given name (e.g. ":1") and start
+    /// pinging its focused window
+    pub fn start(
+        name: &str,
+        timeout: Duration,
+        tx: Sender<PingEvent>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let (conn, screen) = RustConnection::connect(Some(name))?;
+        let root = conn.setup().roots[screen].root;
+        let atoms = Atoms::new(&conn)?.reply()?;
+
+        // Clients answer pings by sending them to the root window
+        let events = ChangeWindowAttributesAux::new().event_mask(EventMask::SUBSTRUCTURE_NOTIFY);
+        conn.change_window_attributes(root, &events)?.check()?;
+        let window = conn.generate_id()?;
+        conn.create_window(
+            COPY_DEPTH_FROM_PARENT,
+            window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            COPY_FROM_PARENT,
+            &CreateWindowAux::new(),
+        )?;
+        conn.flush()?;
+
+        let shared = Arc::new(Shared {
+            name: name.to_string(),
+            conn,
+            root,
+            window,
+            atoms,
+            running: AtomicBool::new(true),
+            tracker: Mutex::new(PingTracker::new(timeout)),
+        });
+        let events_shared = shared.clone();
+        let events_tx = tx.clone();
+        RUNTIME.spawn_blocking(move || events_shared.handle_events(events_tx));
+        let ping_shared = shared.clone();
+        RUNTIME.spawn_blocking(move || ping_shared.ping_loop(tx));
+
+        Ok(Self { shared })
+    }
+
+    /// Sets the time after which an unanswered ping makes a window
+    /// unresponsive
+    pub fn set_timeout(&self, timeout: Duration) {
+        self.shared.tracker.lock().unwrap().set_timeout(timeout);
+    }
+
+    /// Stop tracking the given window after it was destroyed
+    pub fn window_destroyed(&self, window: u32) {
+        self.shared.tracker.lock().unwrap().window_destroyed(window);
+    }
+
+    /// Returns true if the given window didn't answer its last ping in time
+    pub fn is_unresponsive(&self, window: u32) -> bool {
+        self.shared.tracker.lock().unwrap().is_unresponsive(window)
+    }
+}
+
+impl Drop for WindowPinger {
+    fn drop(&mut self) {
+        // Wake up the event thread so it stops. The ping thread stops on its
+        // next tick.
+        let shared = &self.shared;
+        shared.running.store(false, Ordering::SeqCst);
+        let event = ClientMessageEvent::new(32, shared.window, AtomEnum::NONE, [0u32; 5]);
+        let result = shared
+            .conn
+            .send_event(false, shared.window, EventMask::NO_EVENT, event)
+            .and_then(|_| shared.conn.destroy_window(shared.window))
+            .and_then(|_| shared.conn.flush());
+        if let Err(e) = result {
+            log::debug!("Failed to stop pinging windows on '{}': {e:?}", shared.name);
+        }
+    }
+}
+
+impl Shared {
+    /// Handle answers to pings until the pinger is dropped or the connection
+    /// is lost
+    fn handle_events(&self, tx: Sender<PingEvent>) {
+        while self.running.load(Ordering::SeqCst) {
+            let event = match self.conn.wait_for_event() {
+                Ok(event) => event,
+                Err(e) => {
+                    log::debug!("Lost ping connection to '{}': {e:?}", self.name);
+                    self.running.store(false, Ordering::SeqCst);
+                    return;
+                }
+            };
+            // Errors from pinging windows that were just destroyed are ignored
+            let Event::ClientMessage(event) = event else {
+                continue;
+            };
+            if event.type_ != self.atoms.WM_PROTOCOLS || event.window != self.root {
+                continue;
+            }
+            let data = event.data.as_data32();
+            if data[0] != self.atoms._NET_WM_PING {
+                continue;
+            }
+            let window = data[2];
+            let Some(ping_event) = self.tracker.lock().unwrap().pong(window) else {
+                continue;
+            };
+            log::debug!("Window {window} on '{}' is responsive again", self.name);
+            if tx.send(ping_event).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Ping the focused window every [PING_INTERVAL] until the pinger is
+    /// dropped or the connection is lost
+    fn ping_loop(&self, tx: Sender<PingEvent>) {
+        log::debug!("Started pinging windows on display '{}'", self.name);
+        loop {
+            std::thread::sleep(PING_INTERVAL);
+            if !self.running.load(Ordering::SeqCst) {
+                break;
+            }
+            let events = match self.ping() {
+                Ok(events) => events,
+                Err(e) => {
+                    log::debug!("Failed to ping windows on '{}': {e:?}", self.name);
+                    continue;
+                }
+            };
+            for event in events {
+                if let PingEvent::Unresponsive(window) = event {
+                    log::debug!("Window {window} on '{}' is unresponsive", self.name);
+                }
+                if tx.send(event).is_err() {
+                    return;
+                }
+            }
+        }
+        log::debug!("Stopped pinging windows on display '{}'", self.name);
+    }
+
+    /// Ping the windows that are due and return the events of windows that
+    /// became unresponsive
+    fn ping(&self) -> Result<Vec<PingEvent>, ReplyError> {
+        let focused = match self.focused_window()? {
+            Some(window) if self.supports_ping(window)? => Some(window),
+            _ => None,
+        };
+        let (pings, events) = {
+            let mut tracker = self.tracker.lock().unwrap();
+            tracker.set_focused(focused);
+            tracker.tick(Instant::now())
+        };
+        for window in pings {
+            let data = [self.atoms._NET_WM_PING, CURRENT_TIME, window, 0, 0];
+            let event = ClientMessageEvent::new(32, window, self.atoms.WM_PROTOCOLS, data);
+            self.conn
+                .send_event(false, window, EventMask::NO_EVENT, event)?;
+        }
+        self.conn.flush()?;
+        Ok(events)
+    }
+
+    /// Returns the top-level window that has the input focus
+    fn focused_window(&self) -> Result<Option<Window>, ReplyError> {
+        let focus = self.conn.get_input_focus()?.reply()?.focus;
+        let pointer_root: Window = InputFocus::POINTER_ROOT.into();
+        if focus == NONE || focus == pointer_root || focus == self.root {
+            return Ok(None);
+        }
+        let mut window = focus;
+        loop {
+            let parent = self.conn.query_tree(window)?.reply()?.parent;
+            if parent == self.root || parent == NONE {
+                return Ok(Some(window));
+            }
+            window = parent;
+        }
+    }
+
+    /// Returns true if the given window lists _NET_WM_PING in WM_PROTOCOLS
+    fn supports_ping(&self, window: Window) -> Result<bool, ReplyError> {
+        let reply = self
+            .conn
+            .get_property(
+                false,
+                window,
+                self.atoms.WM_PROTOCOLS,
+                AtomEnum::ATOM,
+                0,
+                32,
+            )?
+            .reply()?;
+        let Some(mut protocols) = reply.value32() else {
+            return Ok(false);
+        };
+        Ok(protocols.any(|atom| atom == self.atoms._NET_WM_PING))
+    }
+}
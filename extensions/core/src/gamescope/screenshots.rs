@@ -0,0 +1,638 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use futures_util::StreamExt;
+use inotify::{Inotify, WatchMask};
+use tokio::task::AbortHandle;
+
+use godot::{obj::WithBaseField, prelude::*};
+
+use godot::classes::{Image, ProjectSettings, Resource};
+
+use crate::resource::dispatcher::{channel, Receiver, Sender, TryRecvError};
+use crate::RUNTIME;
+
+use super::WAKEUP;
+
+/// Directory Gamescope writes screenshots to by default
+const DEFAULT_SCREENSHOT_DIR: &str = "/tmp";
+/// Directory screenshots are collected in, with a sub-directory for each app
+const DEFAULT_LIBRARY_DIR: &str = "user://screenshots";
+/// Prefix of the screenshot files written by Gamescope
+const SCREENSHOT_PREFIX: &str = "gamescope_";
+/// File extensions of the screenshots written by Gamescope
+const SCREENSHOT_EXTENSIONS: [&str; 2] = ["png", "avif"];
+/// Name of the directory next to the screenshots of an app with thumbnails
+const THUMBNAIL_DIR: &str = "thumbnails";
+/// Width of generated thumbnails
+const THUMBNAIL_WIDTH: i32 = 320;
+/// How long Gamescope may take to write a requested screenshot
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Screenshots requested with [request_screenshot] that were not written yet
+static REQUESTS: Mutex<ScreenshotRequests> = Mutex::new(ScreenshotRequests::new());
+
+/// Signals that can be emitted
+#[derive(Debug)]
+enum Signal {
+    ScreenshotWritten { path: PathBuf },
+    ScreenshotAdded { info: ScreenshotInfo },
+}
+
+/// Screenshots that were requested from Gamescope and not written yet
+#[derive(Debug, Default)]
+pub struct ScreenshotRequests {
+    pending: Vec<Instant>,
+}
+
+impl ScreenshotRequests {
+    /// Create a new [ScreenshotRequests] without any pending requests
+    pub const fn new() -> Self {
+        Self {
+            pending: Vec::new(),
+        }
+    }
+
+    /// Remember that a screenshot was requested at the given time
+    pub fn request(&mut self, now: Instant) {
+        self.pending.push(now);
+    }
+
+    /// Returns true if a screenshot written at the given time was requested,
+    /// consuming the oldest pending request. Requests older than `timeout`
+    /// are dropped.
+    pub fn take(&mut self, now: Instant, timeout: Duration) -> bool {
+        self.pending
+            .retain(|requested| now.saturating_duration_since(*requested) <= timeout);
+        if self.pending.is_empty() {
+            return false;
+        }
+        self.pending.remove(0);
+        true
+    }
+}
+
+/// Remember that a screenshot was requested from Gamescope, so the written
+/// screenshot is moved into the library. Called by [GamescopeXWayland].
+pub fn request_screenshot() {
+    REQUESTS.lock().unwrap().request(Instant::now());
+}
+
+/// Metadata of a screenshot in the [ScreenshotLibrary]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenshotInfo {
+    /// Path to the screenshot
+    pub path: PathBuf,
+    /// Id of the app that was focused when the screenshot was taken
+    pub app_id: u32,
+    /// When the screenshot was taken, in seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Width of the screenshot, or 0 if unknown
+    pub width: u32,
+    /// Height of the screenshot, or 0 if unknown
+    pub height: u32,
+    /// Size of the screenshot file in bytes
+    pub size: u64,
+    /// Path to the thumbnail of the screenshot. The file may not exist yet.
+    pub thumbnail: PathBuf,
+}
+
+/// Screenshots collected in a directory, with a sub-directory named after the
+/// id of the app each screenshot belongs to
+#[derive(Debug, Clone)]
+pub struct ScreenshotLibrary {
+    dir: PathBuf,
+}
+
+impl ScreenshotLibrary {
+    /// Create a new [ScreenshotLibrary] in the given directory
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the directory of the library
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Move the given screenshot into the library as a screenshot of the
+    /// given app. If the library already has a screenshot with the same name,
+    /// a number is appended to the name of the new one.
+    pub fn import(&self, source: &Path, app_id: u32) -> io::Result<ScreenshotInfo> {
+        let path = self.import_path(source, app_id)?;
+
+        // Renaming fails if the library is on another filesystem
+        if let Err(e) = fs::rename(source, &path) {
+            log::trace!("Failed to move screenshot {source:?}, copying instead: {e:?}");
+            fs::copy(source, &path)?;
+            fs::remove_file(source)?;
+        }
+        Self::info(&path, app_id)
+    }
+
+    /// Copy the given screenshot into the library as a screenshot of the
+    /// given app, leaving the original in place. Names are made unique like
+    /// with [ScreenshotLibrary::import].
+    pub fn copy(&self, source: &Path, app_id: u32) -> io::Result<ScreenshotInfo> {
+        let path = self.import_path(source, app_id)?;
+        fs::copy(source, &path)?;
+        Self::info(&path, app_id)
+    }
+
+    /// Returns the path in the library to add the given screenshot of the
+    /// given app at, creating the directory of the app
+    fn import_path(&self, source: &Path, app_id: u32) -> io::Result<PathBuf> {
+        if source.file_name().is_none() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a file"));
+        }
+        let app_dir = self.dir.join(app_id.to_string());
+        fs::create_dir_all(&app_dir)?;
+        Ok(unique_path(&app_dir, source))
+    }
+
+    /// Returns all screenshots in the library by app id, newest first
+    pub fn scan(&self) -> io::Result<BTreeMap<u32, Vec<ScreenshotInfo>>> {
+        let mut screenshots = BTreeMap::new();
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(screenshots),
+            Err(e) => return Err(e),
+        };
+        for entry in entries {
+            let entry = entry?;
+            let Some(app_id) = entry.file_name().to_str().and_then(|id| id.parse().ok()) else {
+                continue;
+            };
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+
+            let mut app_screenshots = Vec::new();
+            for file in fs::read_dir(entry.path())? {
+                let path = file?.path();
+                if !path.is_file() || !has_screenshot_extension(&path) {
+                    continue;
+                }
+                match Self::info(&path, app_id) {
+                    Ok(info) => app_screenshots.push(info),
+                    Err(e) => log::warn!("Failed to read screenshot {path:?}: {e:?}"),
+                }
+            }
+            if app_screenshots.is_empty() {
+                continue;
+            }
+            sort_newest_first(&mut app_screenshots);
+            screenshots.insert(app_id, app_screenshots);
+        }
+        Ok(screenshots)
+    }
+
+    /// Delete the given screenshot and its thumbnail
+    pub fn delete(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)?;
+        match fs::remove_file(thumbnail_path(path)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the metadata of the given screenshot
+    fn info(path: &Path, app_id: u32) -> io::Result<ScreenshotInfo> {
+        let metadata = fs::metadata(path)?;
+        let timestamp = metadata
+            .modified()
+            .unwrap_or_else(|_| SystemTime::now())
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+
+        let mut header = [0; 24];
+        let mut file = fs::File::open(path)?;
+        let read = file.read(&mut header)?;
+        let (width, height) = png_dimensions(&header[..read]).unwrap_or_default();
+
+        Ok(ScreenshotInfo {
+            path: path.to_path_buf(),
+            app_id,
+            timestamp,
+            width,
+            height,
+            size: metadata.len(),
+            thumbnail: thumbnail_path(path),
+        })
+    }
+}
+
+/// Returns true if the given file name looks like a screenshot written by
+/// Gamescope
+pub fn is_screenshot_name(name: &str) -> bool {
+    name.starts_with(SCREENSHOT_PREFIX) && has_screenshot_extension(Path::new(name))
+}
+
+/// Returns the width and height of a PNG image from the start of its file, or
+/// `None` if it is not a PNG image
+pub fn png_dimensions(header: &[u8]) -> Option<(u32, u32)> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    if header.len() < 24 || !header.starts_with(SIGNATURE) || &header[12..16] != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(header[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(header[20..24].try_into().ok()?);
+    Some((width, height))
+}
+
+/// Returns the path of the thumbnail for the given screenshot
+pub fn thumbnail_path(path: &Path) -> PathBuf {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let name = path.file_stem().unwrap_or_default();
+    dir.join(THUMBNAIL_DIR).join(name).with_extension("png")
+}
+
+/// Returns a path in the given directory with the file name of the given
+/// screenshot that no file exists at yet
+fn unique_path(dir: &Path, source: &Path) -> PathBuf {
+    let path = dir.join(source.file_name().unwrap_or_default());
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = match source.extension() {
+        Some(extension) => format!(".{}", extension.to_string_lossy()),
+        None => String::new(),
+    };
+    let mut unique = path;
+    let mut number = 1;
+    while unique.exists() {
+        unique = dir.join(format!("{stem}-{number}{extension}"));
+        number += 1;
+    }
+    unique
+}
+
+/// Returns true if the given path has the extension of a screenshot
+fn has_screenshot_extension(path: &Path) -> bool {
+    let extension = path.extension().and_then(|ext| ext.to_str());
+    extension.is_some_and(|ext| SCREENSHOT_EXTENSIONS.contains(&ext))
+}
+
+/// Sort the given screenshots from newest to oldest
+fn sort_newest_first(screenshots: &mut [ScreenshotInfo]) {
+    screenshots.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then(b.path.cmp(&a.path)));
+}
+
+/// Watches the directory Gamescope writes screenshots to and collects new
+/// screenshots in a library, grouped by the app that was focused when they
+/// were taken. Screenshots requested with
+/// [method GamescopeXWayland.request_screenshot] are moved into the library.
+/// Other screenshots, like those taken by Steam or with the Gamescope
+/// shortcut, are copied and left where they were written.
+#[derive(GodotClass)]
+#[class(no_init, base=Resource)]
+pub struct GamescopeScreenshots {
+    base: Base<Resource>,
+    rx: Receiver<Signal>,
+    tx: Sender<Signal>,
+    watch_task: Option<AbortHandle>,
+    library: ScreenshotLibrary,
+    index: BTreeMap<u32, Vec<ScreenshotInfo>>,
+    focused_app: u32,
+
+    /// Directory that Gamescope writes screenshots to
+    #[var(get, set = set_screenshot_dir)]
+    screenshot_dir: GString,
+    /// Directory that screenshots are collected in
+    #[var(get, set = set_library_dir)]
+    library_dir: GString,
+}
+
+#[godot_api]
+impl GamescopeScreenshots {
+    /// Emitted when a new screenshot was added to the library
+    #[signal]
+    fn screenshot_taken(path: GString, app_id: u32);
+
+    /// Emitted when a screenshot was deleted from the library
+    #[signal]
+    fn screenshot_deleted(path: GString, app_id: u32);
+
+    /// Create a new [GamescopeScreenshots] and start watching for screenshots
+    pub fn new() -> Gd<Self> {
+        let (tx, rx) = channel(&WAKEUP);
+        let library_dir = ProjectSettings::singleton().globalize_path(DEFAULT_LIBRARY_DIR);
+        let mut screenshots = Gd::from_init_fn(|base| Self {
+            base,
+            rx,
+            tx,
+            watch_task: None,
+            library: ScreenshotLibrary::new(library_dir.to_string()),
+            index: Default::default(),
+            focused_app: 0,
+            screenshot_dir: DEFAULT_SCREENSHOT_DIR.into(),
+            library_dir,
+        });
+        {
+            let mut screenshots = screenshots.bind_mut();
+            screenshots.rescan();
+            screenshots.watch();
+        }
+        screenshots
+    }
+
+    /// Sets the directory that Gamescope writes screenshots to
+    #[func]
+    pub fn set_screenshot_dir(&mut self, dir: GString) {
+        self.screenshot_dir = dir;
+        self.watch();
+    }
+
+    /// Sets the directory that screenshots are collected in
+    #[func]
+    pub fn set_library_dir(&mut self, dir: GString) {
+        let path = ProjectSettings::singleton().globalize_path(&dir);
+        self.library = ScreenshotLibrary::new(path.to_string());
+        self.library_dir = path;
+        self.rescan();
+    }
+
+    /// Read all screenshots in the library again
+    #[func]
+    pub fn rescan(&mut self) {
+        self.index = match self.library.scan() {
+            Ok(index) => index,
+            Err(e) => {
+                log::error!(
+                    "Failed to read screenshots in {:?}: {e:?}",
+                    self.library.dir()
+                );
+                Default::default()
+            }
+        };
+    }
+
+    /// Returns the ids of all apps that have screenshots
+    #[func]
+    pub fn get_apps(&self) -> PackedInt64Array {
+        let apps: Vec<i64> = self.index.keys().map(|id| *id as i64).collect();
+        apps.into()
+    }
+
+    /// Returns the screenshots of the given app, newest first, as dictionaries
+    /// with "path", "app_id", "timestamp", "width", "height", "size" and
+    /// "thumbnail" keys
+    #[func]
+    pub fn get_screenshots(&self, app_id: u32) -> Array<Dictionary> {
+        let mut screenshots = array![];
+        let Some(infos) = self.index.get(&app_id) else {
+            return screenshots;
+        };
+        for info in infos {
+            screenshots.push(&Self::info_to_dictionary(info));
+        }
+        screenshots
+    }
+
+    /// Returns the path to the thumbnail of the given screenshot, generating
+    /// the thumbnail if it doesn't exist yet. Returns an empty string if no
+    /// thumbnail could be generated.
+    #[func]
+    pub fn get_thumbnail(&self, path: GString) -> GString {
+        let path = PathBuf::from(path.to_string());
+        let thumbnail = thumbnail_path(&path);
+        if !thumbnail.exists() && !Self::generate_thumbnail(&path, &thumbnail) {
+            return GString::new();
+        }
+        thumbnail.to_string_lossy().as_ref().into()
+    }
+
+    /// Delete the given screenshot from the library
+    #[func]
+    pub fn delete_screenshot(&mut self, path: GString) -> bool {
+        let path_buf = PathBuf::from(path.to_string());
+        let mut app_id = None;
+        for (id, infos) in self.index.iter_mut() {
+            let Some(position) = infos.iter().position(|info| info.path == path_buf) else {
+                continue;
+            };
+            infos.remove(position);
+            app_id = Some(*id);
+            break;
+        }
+        let Some(app_id) = app_id else {
+            log::error!("Screenshot is not in the library: {path}");
+            return false;
+        };
+        if self
+            .index
+            .get(&app_id)
+            .is_some_and(|infos| infos.is_empty())
+        {
+            self.index.remove(&app_id);
+        }
+
+        if let Err(e) = self.library.delete(&path_buf) {
+            log::error!("Failed to delete screenshot {path}: {e:?}");
+            return false;
+        }
+        self.base_mut().emit_signal(
+            "screenshot_deleted",
+            &[path.to_variant(), app_id.to_variant()],
+        );
+        true
+    }
+
+    /// Set the app that new screenshots belong to. Called by
+    /// [GamescopeInstance] when the focused app changes.
+    pub fn set_focused_app(&mut self, app_id: u32) {
+        self.focused_app = app_id;
+    }
+
+    /// Dispatches signals, called by [GamescopeInstance]
+    pub fn process(&mut self) {
+        // Drain all messages from the channel to process them
+        loop {
+            let signal = match self.rx.try_recv() {
+                Ok(value) => value,
+                Err(e) => match e {
+                    TryRecvError::Empty => break,
+                    TryRecvError::Disconnected => {
+                        log::error!("Backend thread is not running!");
+                        return;
+                    }
+                },
+            };
+            match signal {
+                Signal::ScreenshotWritten { path } => self.import_screenshot(path),
+                Signal::ScreenshotAdded { info } => self.add_screenshot(info),
+            }
+        }
+    }
+
+    /// Add the given screenshot to the library as a screenshot of the focused
+    /// app and generate its thumbnail in the background. Requested screenshots
+    /// are moved, others are copied.
+    fn import_screenshot(&mut self, source: PathBuf) {
+        let app_id = self.focused_app;
+        let library = self.library.clone();
+        let tx = self.tx.clone();
+        let requested = REQUESTS
+            .lock()
+            .unwrap()
+            .take(Instant::now(), REQUEST_TIMEOUT);
+        RUNTIME.spawn_blocking(move || {
+            let result = if requested {
+                library.import(&source, app_id)
+            } else {
+                library.copy(&source, app_id)
+            };
+            let info = match result {
+                Ok(info) => info,
+                Err(e) => {
+                    log::error!("Failed to add screenshot {source:?} to the library: {e:?}");
+                    return;
+                }
+            };
+            Self::generate_thumbnail(&info.path, &info.thumbnail);
+            if tx.send(Signal::ScreenshotAdded { info }).is_err() {
+                log::debug!("Screenshots were dropped before the screenshot was added");
+            }
+        });
+    }
+
+    /// Add the given imported screenshot to the index and emit
+    /// [signal screenshot_taken]
+    fn add_screenshot(&mut self, info: ScreenshotInfo) {
+        let app_id = info.app_id;
+        log::debug!("Screenshot taken of app {app_id}: {:?}", info.path);
+        if !info.path.starts_with(self.library.dir()) {
+            // The library directory changed while the screenshot was imported
+            log::debug!("Screenshot {:?} is not in the library anymore", info.path);
+            return;
+        }
+
+        let path: GString = info.path.to_string_lossy().as_ref().into();
+        let infos = self.index.entry(app_id).or_default();
+        infos.retain(|existing| existing.path != info.path);
+        infos.push(info);
+        sort_newest_first(infos);
+
+        self.base_mut().emit_signal(
+            "screenshot_taken",
+            &[path.to_variant(), app_id.to_variant()],
+        );
+    }
+
+    /// Start watching the screenshot directory, stopping any earlier watch
+    fn watch(&mut self) {
+        if let Some(task) = self.watch_task.take() {
+            task.abort();
+        }
+        let dir = PathBuf::from(self.screenshot_dir.to_string());
+        let task = RUNTIME.spawn(Self::watch_dir(dir, self.tx.clone()));
+        self.watch_task = Some(task.abort_handle());
+    }
+
+    /// Send a signal for every screenshot that is written to the given directory
+    async fn watch_dir(dir: PathBuf, tx: Sender<Signal>) {
+        let inotify = match Inotify::init() {
+            Ok(inotify) => inotify,
+            Err(e) => {
+                log::error!("Failed to initialize inotify: {e:?}");
+                return;
+            }
+        };
+        // Files moved into the directory are complete, others once they are closed
+        let mask = WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO;
+        if let Err(e) = inotify.watches().add(&dir, mask) {
+            log::error!("Failed to watch screenshot directory {dir:?}: {e:?}");
+            return;
+        }
+        let mut events = match inotify.into_event_stream([0; 4096]) {
+            Ok(events) => events,
+            Err(e) => {
+                log::error!("Failed to read events for {dir:?}: {e:?}");
+                return;
+            }
+        };
+
+        while let Some(event) = events.next().await {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    log::error!("Failed to read events for {dir:?}: {e:?}");
+                    return;
+                }
+            };
+            let Some(name) = event.name else {
+                continue;
+            };
+            let Some(name) = name.to_str() else {
+                continue;
+            };
+            if !is_screenshot_name(name) {
+                continue;
+            }
+            let signal = Signal::ScreenshotWritten {
+                path: dir.join(name),
+            };
+            if tx.send(signal).is_err() {
+                log::debug!("Screenshots were dropped, stopping screenshot watch");
+                return;
+            }
+        }
+    }
+
+    /// Write a thumbnail of the given screenshot. Returns false if the
+    /// screenshot could not be loaded or the thumbnail not written.
+    fn generate_thumbnail(path: &Path, thumbnail: &Path) -> bool {
+        let Some(mut image) = Image::load_from_file(path.to_string_lossy().as_ref()) else {
+            log::warn!("Failed to load screenshot {path:?} to generate a thumbnail");
+            return false;
+        };
+        let size = image.get_size();
+        if size.x <= 0 || size.y <= 0 {
+            return false;
+        }
+        let height = (size.y as i64 * THUMBNAIL_WIDTH as i64 / size.x as i64).max(1) as i32;
+        image.resize(THUMBNAIL_WIDTH, height);
+
+        if let Some(dir) = thumbnail.parent() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                log::error!("Failed to create thumbnail directory {dir:?}: {e:?}");
+                return false;
+            }
+        }
+        let err = image.save_png(thumbnail.to_string_lossy().as_ref());
+        if err != godot::global::Error::OK {
+            log::error!("Failed to save thumbnail {thumbnail:?}: {err:?}");
+            return false;
+        }
+        true
+    }
+
+    /// Returns the given screenshot metadata as a [Dictionary]
+    fn info_to_dictionary(info: &ScreenshotInfo) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("path", info.path.to_string_lossy().to_string().to_godot());
+        dict.set("app_id", info.app_id);
+        dict.set("timestamp", info.timestamp as i64);
+        dict.set("width", info.width);
+        dict.set("height", info.height);
+        dict.set("size", info.size as i64);
+        dict.set(
+            "thumbnail",
+            info.thumbnail.to_string_lossy().to_string().to_godot(),
+        );
+        dict
+    }
+}
+
+impl Drop for GamescopeScreenshots {
+    fn drop(&mut self) {
+        if let Some(task) = self.watch_task.take() {
+            task.abort();
+        }
+    }
+}
@@ -0,0 +1,172 @@
+/// Layout of the pixels of an X11 image in ZPixmap format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelFormat {
+    /// Number of bits that each pixel uses (16, 24 or 32)
+    pub bits_per_pixel: u8,
+    /// Number of bits that each row is padded to
+    pub scanline_pad: u8,
+    /// Bits of each pixel value that hold the red, green and blue channels
+    pub red_mask: u32,
+    pub green_mask: u32,
+    pub blue_mask: u32,
+    /// Whether pixel values are stored with the most significant byte first
+    pub big_endian: bool,
+}
+
+impl PixelFormat {
+    /// Format of 32-bit little-endian pixels with 8 bits per channel, as used
+    /// by XWayland
+    pub const BGRX: PixelFormat = PixelFormat {
+        bits_per_pixel: 32,
+        scanline_pad: 32,
+        red_mask: 0xff0000,
+        green_mask: 0x00ff00,
+        blue_mask: 0x0000ff,
+        big_endian: false,
+    };
+}
+
+/// Contents of a window as returned by the X server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowImage {
+    pub width: u32,
+    pub height: u32,
+    pub format: PixelFormat,
+    pub data: Vec<u8>,
+}
+
+impl WindowImage {
+    /// Returns the number of bytes of each row
+    pub fn stride(&self) -> usize {
+        let pad = self.format.scanline_pad.max(8) as usize;
+        let bits = self.width as usize * self.format.bits_per_pixel as usize;
+        bits.div_ceil(pad) * pad / 8
+    }
+
+    /// Returns the red, green and blue values of the given pixel, or `None`
+    /// if it is outside of the image or the format is not supported
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 3]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let bytes = match self.format.bits_per_pixel {
+            16 | 24 | 32 => self.format.bits_per_pixel as usize / 8,
+            _ => return None,
+        };
+        let offset = y as usize * self.stride() + x as usize * bytes;
+        let raw = self.data.get(offset..offset + bytes)?;
+        let value = if self.format.big_endian {
+            raw.iter()
+                .fold(0u32, |value, byte| value << 8 | *byte as u32)
+        } else {
+            raw.iter()
+                .rev()
+                .fold(0u32, |value, byte| value << 8 | *byte as u32)
+        };
+        Some([
+            channel(value, self.format.red_mask),
+            channel(value, self.format.green_mask),
+            channel(value, self.format.blue_mask),
+        ])
+    }
+
+    /// Downscale the image to fit within the given size, averaging the pixels
+    /// that are combined into each thumbnail pixel. Images that already fit
+    /// are only converted. Returns `None` if the format is not supported.
+    pub fn thumbnail(&self, max_width: u32, max_height: u32) -> Option<Thumbnail> {
+        let (width, height) = thumbnail_size(self.width, self.height, max_width, max_height);
+        if width == 0 || height == 0 {
+            return None;
+        }
+        self.pixel(0, 0)?;
+
+        let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+        for ty in 0..height {
+            let y0 = source_start(ty, height, self.height);
+            let y1 = source_start(ty + 1, height, self.height).max(y0 + 1);
+            for tx in 0..width {
+                let x0 = source_start(tx, width, self.width);
+                let x1 = source_start(tx + 1, width, self.width).max(x0 + 1);
+
+                let mut sum = [0u64; 3];
+                let mut count = 0u64;
+                for y in y0..y1 {
+                    for x in x0..x1 {
+                        let Some(pixel) = self.pixel(x, y) else {
+                            continue;
+                        };
+                        for (sum, value) in sum.iter_mut().zip(pixel) {
+                            *sum += value as u64;
+                        }
+                        count += 1;
+                    }
+                }
+                let count = count.max(1);
+                rgba.extend(sum.iter().map(|sum| (sum / count) as u8));
+                rgba.push(u8::MAX);
+            }
+        }
+
+        Some(Thumbnail {
+            width,
+            height,
+            rgba,
+        })
+    }
+}
+
+/// Downscaled window contents with 8-bit RGBA pixels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thumbnail {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+/// Returns the size of a thumbnail of an image with the given size that fits
+/// within the given maximum size and keeps the aspect ratio. Images are never
+/// upscaled, and a maximum size of zero doesn't limit that dimension.
+pub fn thumbnail_size(width: u32, height: u32, max_width: u32, max_height: u32) -> (u32, u32) {
+    if width == 0 || height == 0 {
+        return (0, 0);
+    }
+    let max_width = if max_width == 0 {
+        width
+    } else {
+        max_width.min(width)
+    };
+    let max_height = if max_height == 0 {
+        height
+    } else {
+        max_height.min(height)
+    };
+
+    // Scale by whichever dimension needs the smaller factor
+    let (width, height) = (width as u64, height as u64);
+    if max_width as u64 * height <= max_height as u64 * width {
+        let scaled = (height * max_width as u64 / width).max(1);
+        (max_width, scaled as u32)
+    } else {
+        let scaled = (width * max_height as u64 / height).max(1);
+        (scaled as u32, max_height)
+    }
+}
+
+/// Returns the first source pixel of the given thumbnail pixel
+fn source_start(index: u32, size: u32, source_size: u32) -> u32 {
+    (index as u64 * source_size as u64 / size as u64) as u32
+}
+
+/// Extract the channel with the given mask from a pixel value as an 8-bit value
+fn channel(value: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let bits = mask.count_ones();
+    let value = (value & mask) >> mask.trailing_zeros();
+    if bits >= 8 {
+        (value >> (bits - 8)) as u8
+    } else {
+        (value * 255 / ((1 << bits) - 1)) as u8
+    }
+}
@@ -2,26 +2,80 @@ use gamescope_x11_client::{
     atoms::GamescopeAtom,
     xwayland::{BlurMode, Primary, WindowLifecycleEvent, XWayland},
 };
-use std::{
-    collections::HashMap,
-    sync::mpsc::{channel, Receiver, Sender, TryRecvError},
-    time::Duration,
-};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::task::AbortHandle;
+use x11rb::errors::ReplyError;
 
 use godot::{obj::WithBaseField, prelude::*};
 
-use godot::classes::{Resource, ResourceLoader};
+use godot::classes::{image::Format, Image, ImageTexture, Resource, ResourceLoader};
 
+use crate::resource::dispatcher::{channel, Receiver, Sender, TryRecvError};
 use crate::RUNTIME;
 
+use super::ping::{PingEvent, WindowPinger, DEFAULT_PING_TIMEOUT};
+use super::screenshots;
+use super::thumbnail::Thumbnail;
+use super::x11_connection::{parse_display_modes, DisplayMode, X11Connection};
+use super::x11_input::{self, key_name_to_keysym};
+use super::WAKEUP;
+
+/// Root window atom for the upscale filter
+const ATOM_UPSCALE_FILTER: &str = "GAMESCOPE_NEW_SCALING_FILTER";
+/// Root window atom for the upscale scaler mode
+const ATOM_UPSCALE_SCALER: &str = "GAMESCOPE_NEW_SCALING_SCALER";
+/// Root window atom for the upscale sharpness
+const ATOM_SHARPNESS: &str = "GAMESCOPE_SHARPNESS";
+/// Root window atom to enable HDR output
+const ATOM_HDR_ENABLED: &str = "GAMESCOPE_DISPLAY_HDR_ENABLED";
+/// Root window atom that Gamescope sets if the display supports HDR
+const ATOM_HDR_SUPPORTED: &str = "GAMESCOPE_DISPLAY_SUPPORTS_HDR";
+/// Root window atom for the brightness of SDR content in HDR mode, as the bits
+/// of a 32-bit float
+const ATOM_SDR_CONTENT_BRIGHTNESS: &str = "GAMESCOPE_SDR_ON_HDR_CONTENT_BRIGHTNESS";
+/// Root window atom to change the resolution of an XWayland server, as
+/// [server index, width, height, allow super resolution]
+const ATOM_XWAYLAND_MODE_CONTROL: &str = "GAMESCOPE_XWAYLAND_MODE_CONTROL";
+/// Root window atom for the refresh rate that Gamescope should switch the
+/// display to. 0 uses the native refresh rate.
+const ATOM_DYNAMIC_REFRESH: &str = "GAMESCOPE_DYNAMIC_REFRESH";
+/// Root window atom that Gamescope sets to the current refresh rate
+const ATOM_REFRESH_RATE_FEEDBACK: &str = "GAMESCOPE_DISPLAY_REFRESH_RATE_FEEDBACK";
+/// Root window atom that Gamescope sets to the modes the display supports
+const ATOM_DISPLAY_MODES: &str = "GAMESCOPE_DISPLAY_MODE_LIST_EXTERNAL";
+
 /// Signals that can be emitted
 #[derive(Debug)]
 enum Signal {
-    WindowCreated { window_id: u32 },
-    WindowDestroyed { window_id: u32 },
-    WindowPropertyChanged { window_id: u32, property: String },
-    PropertyChanged { property: String },
+    WindowCreated {
+        window_id: u32,
+    },
+    WindowDestroyed {
+        window_id: u32,
+    },
+    WindowPropertyChanged {
+        window_id: u32,
+        property: String,
+    },
+    PropertyChanged {
+        property: String,
+    },
+    ThumbnailCaptured {
+        window_id: u32,
+        thumbnail: Option<Thumbnail>,
+    },
+    Disconnected,
+}
+
+/// Window, focus and baselayer changes that are handled by [GamescopeInstance]
+#[derive(Debug, Clone, Copy)]
+pub enum WindowEvent {
+    Created(u32),
+    Destroyed(u32),
+    FocusedApp(u32),
+    BaselayerChanged,
+    /// The STEAM_GAME app id of a watched window changed
+    AppIdChanged(u32),
 }
 
 #[derive(GodotClass)]
@@ -31,7 +85,16 @@ pub struct GamescopeXWayland {
     rx: Receiver<Signal>,
     tx: Sender<Signal>,
     xwayland: XWayland,
+    x11: Option<Arc<X11Connection>>,
+    /// Textures to update with the thumbnails of windows that are being
+    /// captured
+    thumbnail_requests: HashMap<u32, Vec<Gd<ImageTexture>>>,
+    pinger: Option<WindowPinger>,
+    ping_rx: Receiver<PingEvent>,
+    ping_tx: Sender<PingEvent>,
     window_watch_handles: HashMap<u32, AbortHandle>,
+    connected: bool,
+    window_events: Vec<WindowEvent>,
 
     /// The name of the XWayland instance (e.g. ":0")
     #[var]
@@ -84,6 +147,39 @@ pub struct GamescopeXWayland {
     /// Current manually focused app
     #[var(get = get_baselayer_app, set = set_baselayer_app)]
     baselayer_app: u32,
+    /// Gamescope upscale filter (0 - linear, 1 - nearest, 2 - FSR, 3 - NIS, 4 - pixel)
+    #[var(get = get_upscale_filter, set = set_upscale_filter)]
+    upscale_filter: u32,
+    /// Gamescope upscale scaler (0 - auto, 1 - integer, 2 - fit, 3 - fill, 4 - stretch)
+    #[var(get = get_upscale_scaler, set = set_upscale_scaler)]
+    upscale_scaler: u32,
+    /// Gamescope upscale sharpness (0 - sharpest, 20 - softest)
+    #[var(get = get_sharpness, set = set_sharpness)]
+    sharpness: u32,
+    /// Whether or not Gamescope HDR output is enabled
+    #[var(get = get_hdr_enabled, set = set_hdr_enabled)]
+    hdr_enabled: bool,
+    /// Whether or not the display supports HDR output
+    #[var(get = get_hdr_supported)]
+    hdr_supported: bool,
+    /// Brightness of SDR content in nits while HDR output is enabled
+    #[var(get = get_sdr_content_brightness, set = set_sdr_content_brightness)]
+    sdr_content_brightness: f32,
+    /// Refresh rate that Gamescope was asked to use, or 0 for the native
+    /// refresh rate of the display
+    #[var(get = get_refresh_rate, set = set_refresh_rate)]
+    refresh_rate: u32,
+    /// Refresh rate the display is currently running at
+    #[var(get = get_current_refresh_rate)]
+    current_refresh_rate: u32,
+    /// Whether the focused window is pinged to detect when it stops
+    /// responding
+    #[var(get, set = set_ping_enabled)]
+    ping_enabled: bool,
+    /// Time in milliseconds after which a window that didn't answer a ping is
+    /// unresponsive
+    #[var(get, set = set_ping_timeout)]
+    ping_timeout: u32,
 }
 
 #[godot_api]
@@ -95,6 +191,35 @@ impl GamescopeXWayland {
     #[constant]
     const BLUR_MODE_ALWAYS: u32 = 2;
 
+    #[constant]
+    const UPSCALE_FILTER_LINEAR: u32 = 0;
+    #[constant]
+    const UPSCALE_FILTER_NEAREST: u32 = 1;
+    #[constant]
+    const UPSCALE_FILTER_FSR: u32 = 2;
+    #[constant]
+    const UPSCALE_FILTER_NIS: u32 = 3;
+    #[constant]
+    const UPSCALE_FILTER_PIXEL: u32 = 4;
+
+    #[constant]
+    const UPSCALE_SCALER_AUTO: u32 = 0;
+    #[constant]
+    const UPSCALE_SCALER_INTEGER: u32 = 1;
+    #[constant]
+    const UPSCALE_SCALER_FIT: u32 = 2;
+    #[constant]
+    const UPSCALE_SCALER_FILL: u32 = 3;
+    #[constant]
+    const UPSCALE_SCALER_STRETCH: u32 = 4;
+
+    #[constant]
+    const MOUSE_BUTTON_LEFT: u32 = x11_input::BUTTON_LEFT as u32;
+    #[constant]
+    const MOUSE_BUTTON_MIDDLE: u32 = x11_input::BUTTON_MIDDLE as u32;
+    #[constant]
+    const MOUSE_BUTTON_RIGHT: u32 = x11_input::BUTTON_RIGHT as u32;
+
     #[signal]
     fn window_created(window_id: u32);
 
@@ -125,18 +250,73 @@ impl GamescopeXWayland {
     #[signal]
     fn baselayer_app_updated(from: u32, to: u32);
 
+    #[signal]
+    fn upscale_filter_updated(from: u32, to: u32);
+
+    #[signal]
+    fn upscale_scaler_updated(from: u32, to: u32);
+
+    #[signal]
+    fn sharpness_updated(from: u32, to: u32);
+
+    #[signal]
+    fn hdr_enabled_updated(from: bool, to: bool);
+
+    #[signal]
+    fn sdr_content_brightness_updated(from: f32, to: f32);
+
+    #[signal]
+    fn current_refresh_rate_updated(from: u32, to: u32);
+
+    /// Emitted when the focused window stops answering pings, e.g. because the
+    /// game froze. Only emitted while [member ping_enabled] is true.
+    #[signal]
+    fn window_unresponsive(window_id: u32, pids: PackedInt64Array);
+
+    /// Emitted when a window that was unresponsive answers pings again
+    #[signal]
+    fn window_responsive(window_id: u32);
+
+    /// Emitted when the modes supported by the display change
+    #[signal]
+    fn display_modes_updated();
+
+    /// Emitted when a thumbnail requested with [method request_window_thumbnail]
+    /// was captured
+    #[signal]
+    fn window_thumbnail_captured(window_id: u32, image: Gd<Image>);
+
+    /// Emitted when the connection to the XWayland display is lost (e.g. when
+    /// Gamescope exits)
+    #[signal]
+    fn disconnected();
+
     /// Create a new [GamescopeXWayland] with the given name (e.g. ":0")
     pub fn from_name(name: GString) -> Gd<Self> {
         // Create a channel to communicate with the signals task
         log::debug!("Gamescope XWayland created with name: {name}");
-        let (tx, rx) = channel();
+        let (tx, rx) = channel(&WAKEUP);
+        let (ping_tx, ping_rx) = channel(&WAKEUP);
 
         // Create an XWayland client instance for this display
         let mut xwayland = XWayland::new(name.clone().into());
-        if let Err(e) = xwayland.connect() {
-            log::error!("Failed to connect to XWayland display '{name}': {e:?}");
-        }
+        let connected = match xwayland.connect() {
+            Ok(_) => true,
+            Err(e) => {
+                log::error!("Failed to connect to XWayland display '{name}': {e:?}");
+                false
+            }
+        };
         let is_primary = xwayland.is_primary_instance().unwrap_or_default();
+
+        // Open a second connection for requests the XWayland client doesn't support
+        let x11 = match X11Connection::connect(name.to_string().as_str()) {
+            Ok(x11) => Some(Arc::new(x11)),
+            Err(e) => {
+                log::error!("Failed to open X11 connection to display '{name}': {e:?}");
+                None
+            }
+        };
         let root_window_id = xwayland.get_root_window_id().unwrap_or_default();
 
         // If this XWayland instance is a primary instance, listen for signals
@@ -169,9 +349,13 @@ impl GamescopeXWayland {
                     };
                     if let Err(e) = signals_tx.send(signal) {
                         log::error!("Error sending window signal: {e:?}");
-                        break;
+                        return;
                     }
                 }
+                // The listener stops when the connection to the display is lost
+                if let Err(e) = signals_tx.send(Signal::Disconnected) {
+                    log::debug!("Error sending disconnected signal: {e:?}");
+                }
             });
         } else {
             log::error!("Failed to listen for XWayland windows created/destroyed");
@@ -186,10 +370,17 @@ impl GamescopeXWayland {
                 tx,
                 name,
                 xwayland,
+                x11,
+                thumbnail_requests: Default::default(),
+                pinger: None,
+                ping_rx,
+                ping_tx,
                 is_primary,
                 root_window_id,
                 watched_windows: Default::default(),
                 window_watch_handles: Default::default(),
+                connected,
+                window_events: Default::default(),
                 focusable_apps: Default::default(),
                 focusable_windows: Default::default(),
                 focusable_window_names: Default::default(),
@@ -203,6 +394,16 @@ impl GamescopeXWayland {
                 allow_tearing: Default::default(),
                 baselayer_window: Default::default(),
                 baselayer_app: Default::default(),
+                upscale_filter: Default::default(),
+                upscale_scaler: Default::default(),
+                sharpness: Default::default(),
+                hdr_enabled: Default::default(),
+                hdr_supported: Default::default(),
+                sdr_content_brightness: Default::default(),
+                refresh_rate: Default::default(),
+                current_refresh_rate: Default::default(),
+                ping_enabled: false,
+                ping_timeout: DEFAULT_PING_TIMEOUT.as_millis() as u32,
             }
         })
     }
@@ -232,6 +433,13 @@ impl GamescopeXWayland {
         }
     }
 
+    /// Returns true if the XWayland display is connected. Once the connection
+    /// is lost, [GamescopeInstance] stops tracking this instance.
+    #[func]
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
     /// Returns the list of currently watched windows.
     #[func]
     pub fn get_watched_windows(&self) -> PackedInt64Array {
@@ -383,7 +591,7 @@ impl GamescopeXWayland {
 
     /// Recursively returns all child windows of the given window id
     #[func]
-    fn get_all_windows(&self, window_id: u32) -> PackedInt64Array {
+    pub fn get_all_windows(&self, window_id: u32) -> PackedInt64Array {
         let windows = match self.xwayland.get_all_windows(window_id) {
             Ok(windows) => windows,
             Err(e) => {
@@ -396,10 +604,111 @@ impl GamescopeXWayland {
         windows.into()
     }
 
+    /// Returns the metadata of the given window as a dictionary with "name",
+    /// "wm_class_instance", "wm_class", "pid", "window_types", "x", "y",
+    /// "width", "height", "mapped", "visible", "transient_for", "fullscreen"
+    /// and "hidden" keys. The window types are the _NET_WM_WINDOW_TYPE types
+    /// without their prefix, such as "normal" or "splash", and the position is
+    /// relative to the root window. Returns an empty dictionary if the window
+    /// does not exist.
+    #[func]
+    pub fn get_window_info(&self, window_id: u32) -> Dictionary {
+        let Some(x11) = self.x11.as_ref() else {
+            log::error!("No X11 connection to display '{}'", self.name);
+            return Dictionary::new();
+        };
+        let info = match x11.get_window_info(window_id) {
+            Ok(info) => info,
+            Err(e) => {
+                log::error!("Failed to get window info for window '{window_id}': {e:?}");
+                return Dictionary::new();
+            }
+        };
+        let window_types: Vec<GString> = info
+            .window_types
+            .iter()
+            .map(|t| GString::from(t.as_str()))
+            .collect();
+
+        let mut dict = Dictionary::new();
+        dict.set("window_id", window_id);
+        dict.set("name", info.name.to_godot());
+        dict.set("wm_class_instance", info.wm_class_instance.to_godot());
+        dict.set("wm_class", info.wm_class.to_godot());
+        dict.set("pid", info.pid);
+        dict.set("window_types", PackedStringArray::from(window_types));
+        dict.set("x", info.x);
+        dict.set("y", info.y);
+        dict.set("width", info.width);
+        dict.set("height", info.height);
+        dict.set("mapped", info.mapped);
+        dict.set("visible", info.visible);
+        dict.set("transient_for", info.transient_for);
+        dict.set("fullscreen", info.fullscreen);
+        dict.set("hidden", info.hidden);
+        dict
+    }
+
+    /// Capture a thumbnail of the given window in the background that fits
+    /// within the given size and keeps the aspect ratio of the window. A size
+    /// of zero doesn't limit that dimension. [signal window_thumbnail_captured]
+    /// is emitted once the thumbnail is ready, unless the window isn't visible
+    /// or can't be captured. Requests for a window that is still being
+    /// captured share that capture. Returns false if there is no connection to
+    /// the display.
+    #[func]
+    pub fn request_window_thumbnail(&mut self, window_id: u32, max_size: Vector2i) -> bool {
+        let Some(x11) = self.x11.clone() else {
+            log::error!("No X11 connection to display '{}'", self.name);
+            return false;
+        };
+        if self.thumbnail_requests.contains_key(&window_id) {
+            return true;
+        }
+        self.thumbnail_requests.insert(window_id, Vec::new());
+
+        // Capturing and downscaling large windows takes too long for a frame
+        let max_width = max_size.x.max(0) as u32;
+        let max_height = max_size.y.max(0) as u32;
+        let tx = self.tx.clone();
+        RUNTIME.spawn_blocking(move || {
+            let thumbnail = capture_thumbnail(&x11, window_id, max_width, max_height);
+            let signal = Signal::ThumbnailCaptured {
+                window_id,
+                thumbnail,
+            };
+            if let Err(e) = tx.send(signal) {
+                log::debug!("Failed to send thumbnail of window '{window_id}': {e:?}");
+            }
+        });
+        true
+    }
+
+    /// Capture the given window again in the background and update the given
+    /// texture with the new thumbnail once it is ready, see
+    /// [method request_window_thumbnail]. Returns false if there is no
+    /// connection to the display.
+    #[func]
+    pub fn update_window_thumbnail(
+        &mut self,
+        texture: Gd<ImageTexture>,
+        window_id: u32,
+        max_size: Vector2i,
+    ) -> bool {
+        if !self.request_window_thumbnail(window_id, max_size) {
+            return false;
+        }
+        self.thumbnail_requests
+            .entry(window_id)
+            .or_default()
+            .push(texture);
+        true
+    }
+
     /// Returns the currently set app ID on the given window. Returns zero if no
     /// app id was found.
     #[func]
-    fn get_app_id(&self, window_id: u32) -> u32 {
+    pub fn get_app_id(&self, window_id: u32) -> u32 {
         match self.xwayland.get_app_id(window_id) {
             Ok(app_id) => app_id.unwrap_or_default(),
             Err(e) => {
@@ -581,7 +890,7 @@ impl GamescopeXWayland {
 
     /// Return the currently focused app id.
     #[func]
-    fn get_focused_app(&mut self) -> u32 {
+    pub fn get_focused_app(&mut self) -> u32 {
         if !self.is_primary {
             log::error!("XWayland instance is not primary!");
             return Default::default();
@@ -638,7 +947,7 @@ impl GamescopeXWayland {
 
     /// The current Gamescope FPS limit
     #[func]
-    fn get_fps_limit(&mut self) -> u32 {
+    pub fn get_fps_limit(&mut self) -> u32 {
         if !self.is_primary {
             log::error!("XWayland instance is not primary!");
             return Default::default();
@@ -657,7 +966,7 @@ impl GamescopeXWayland {
 
     /// Sets the current Gamescope FPS limit
     #[func]
-    fn set_fps_limit(&mut self, fps: u32) {
+    pub fn set_fps_limit(&mut self, fps: u32) {
         if !self.is_primary {
             log::error!("XWayland instance is not primary!");
             return;
@@ -670,7 +979,7 @@ impl GamescopeXWayland {
 
     /// The Gamescope blur mode (0 - off, 1 - cond, 2 - always)
     #[func]
-    fn get_blur_mode(&mut self) -> u32 {
+    pub fn get_blur_mode(&mut self) -> u32 {
         if !self.is_primary {
             log::error!("XWayland instance is not primary!");
             return Default::default();
@@ -697,7 +1006,7 @@ impl GamescopeXWayland {
 
     /// Sets the Gamescope blur mode
     #[func]
-    fn set_blur_mode(&mut self, mode: u32) {
+    pub fn set_blur_mode(&mut self, mode: u32) {
         if !self.is_primary {
             log::error!("XWayland instance is not primary!");
             return Default::default();
@@ -716,7 +1025,7 @@ impl GamescopeXWayland {
 
     // The blur radius size
     #[func]
-    fn get_blur_radius(&self) -> u32 {
+    pub fn get_blur_radius(&self) -> u32 {
         if !self.is_primary {
             log::error!("XWayland instance is not primary!");
             return Default::default();
@@ -726,7 +1035,7 @@ impl GamescopeXWayland {
 
     /// Sets the blur radius size
     #[func]
-    fn set_blur_radius(&mut self, radius: u32) {
+    pub fn set_blur_radius(&mut self, radius: u32) {
         if !self.is_primary {
             log::error!("XWayland instance is not primary!");
             return;
@@ -739,7 +1048,7 @@ impl GamescopeXWayland {
 
     /// Whether or not Gamescope should be allowed to screen tear
     #[func]
-    fn get_allow_tearing(&self) -> bool {
+    pub fn get_allow_tearing(&self) -> bool {
         if !self.is_primary {
             log::error!("XWayland instance is not primary!");
             return Default::default();
@@ -749,7 +1058,7 @@ impl GamescopeXWayland {
 
     /// Sets whether or not Gamescope should be allowed to screen tear
     #[func]
-    fn set_allow_tearing(&mut self, allow: bool) {
+    pub fn set_allow_tearing(&mut self, allow: bool) {
         if !self.is_primary {
             log::error!("XWayland instance is not primary!");
             return;
@@ -948,14 +1257,404 @@ impl GamescopeXWayland {
         self.baselayer_window = 0;
     }
 
-    /// Request a screenshot from Gamescope
+    /// Returns the Gamescope upscale filter
+    #[func]
+    pub fn get_upscale_filter(&mut self) -> u32 {
+        let Some(value) = self.get_root_value(ATOM_UPSCALE_FILTER) else {
+            return self.upscale_filter;
+        };
+        self.upscale_filter = value;
+        self.upscale_filter
+    }
+
+    /// Sets the Gamescope upscale filter
+    #[func]
+    pub fn set_upscale_filter(&mut self, filter: u32) {
+        if filter > GamescopeXWayland::UPSCALE_FILTER_PIXEL {
+            log::error!("Invalid upscale filter: {filter}");
+            return;
+        }
+        self.set_root_value(ATOM_UPSCALE_FILTER, filter);
+        self.upscale_filter = filter;
+    }
+
+    /// Returns the Gamescope upscale scaler
+    #[func]
+    pub fn get_upscale_scaler(&mut self) -> u32 {
+        let Some(value) = self.get_root_value(ATOM_UPSCALE_SCALER) else {
+            return self.upscale_scaler;
+        };
+        self.upscale_scaler = value;
+        self.upscale_scaler
+    }
+
+    /// Sets the Gamescope upscale scaler
+    #[func]
+    pub fn set_upscale_scaler(&mut self, scaler: u32) {
+        if scaler > GamescopeXWayland::UPSCALE_SCALER_STRETCH {
+            log::error!("Invalid upscale scaler: {scaler}");
+            return;
+        }
+        self.set_root_value(ATOM_UPSCALE_SCALER, scaler);
+        self.upscale_scaler = scaler;
+    }
+
+    /// Returns the Gamescope upscale sharpness
+    #[func]
+    pub fn get_sharpness(&mut self) -> u32 {
+        let Some(value) = self.get_root_value(ATOM_SHARPNESS) else {
+            return self.sharpness;
+        };
+        self.sharpness = value;
+        self.sharpness
+    }
+
+    /// Sets the Gamescope upscale sharpness. Values are clamped to 0-20.
+    #[func]
+    pub fn set_sharpness(&mut self, sharpness: u32) {
+        let sharpness = sharpness.min(20);
+        self.set_root_value(ATOM_SHARPNESS, sharpness);
+        self.sharpness = sharpness;
+    }
+
+    /// Returns whether or not Gamescope HDR output is enabled
+    #[func]
+    fn get_hdr_enabled(&mut self) -> bool {
+        let Some(value) = self.get_root_value(ATOM_HDR_ENABLED) else {
+            return self.hdr_enabled;
+        };
+        self.hdr_enabled = value != 0;
+        self.hdr_enabled
+    }
+
+    /// Sets whether or not Gamescope HDR output is enabled. This has no effect
+    /// if the display does not support HDR.
+    #[func]
+    fn set_hdr_enabled(&mut self, enabled: bool) {
+        self.set_root_value(ATOM_HDR_ENABLED, enabled as u32);
+        self.hdr_enabled = enabled;
+    }
+
+    /// Returns whether or not the display supports HDR output
+    #[func]
+    fn get_hdr_supported(&mut self) -> bool {
+        let Some(value) = self.get_root_value(ATOM_HDR_SUPPORTED) else {
+            return self.hdr_supported;
+        };
+        self.hdr_supported = value != 0;
+        self.hdr_supported
+    }
+
+    /// Returns the brightness of SDR content in nits while HDR output is enabled
+    #[func]
+    fn get_sdr_content_brightness(&mut self) -> f32 {
+        let Some(value) = self.get_root_value(ATOM_SDR_CONTENT_BRIGHTNESS) else {
+            return self.sdr_content_brightness;
+        };
+        self.sdr_content_brightness = f32::from_bits(value);
+        self.sdr_content_brightness
+    }
+
+    /// Sets the brightness of SDR content in nits while HDR output is enabled
+    #[func]
+    fn set_sdr_content_brightness(&mut self, nits: f32) {
+        if !nits.is_finite() || nits <= 0.0 {
+            log::error!("Invalid SDR content brightness: {nits}");
+            return;
+        }
+        self.set_root_value(ATOM_SDR_CONTENT_BRIGHTNESS, nits.to_bits());
+        self.sdr_content_brightness = nits;
+    }
+
+    /// Returns the refresh rate that Gamescope was asked to use, or 0 for the
+    /// native refresh rate of the display
+    #[func]
+    pub fn get_refresh_rate(&mut self) -> u32 {
+        let Some(x11) = self.x11.as_ref().filter(|_| self.is_primary) else {
+            return self.refresh_rate;
+        };
+        match x11.get_root_cardinal(ATOM_DYNAMIC_REFRESH) {
+            Ok(value) => self.refresh_rate = value.unwrap_or_default(),
+            Err(e) => log::error!("Failed to get {ATOM_DYNAMIC_REFRESH}: {e:?}"),
+        }
+        self.refresh_rate
+    }
+
+    /// Asks Gamescope to switch the display to the given refresh rate. Use 0
+    /// to go back to the native refresh rate.
+    #[func]
+    pub fn set_refresh_rate(&mut self, refresh_rate: u32) {
+        self.set_root_value(ATOM_DYNAMIC_REFRESH, refresh_rate);
+        self.refresh_rate = refresh_rate;
+    }
+
+    /// Returns the refresh rate the display is currently running at
+    #[func]
+    fn get_current_refresh_rate(&mut self) -> u32 {
+        let Some(value) = self.get_root_value(ATOM_REFRESH_RATE_FEEDBACK) else {
+            return self.current_refresh_rate;
+        };
+        self.current_refresh_rate = value;
+        self.current_refresh_rate
+    }
+
+    /// Returns the modes the display supports as dictionaries with "width",
+    /// "height" and "refresh_rate" keys
+    #[func]
+    fn get_display_modes(&self) -> Array<Dictionary> {
+        let mut modes = array![];
+        for mode in self.display_modes() {
+            let mut dict = Dictionary::new();
+            dict.set("width", mode.width);
+            dict.set("height", mode.height);
+            dict.set("refresh_rate", mode.refresh_rate);
+            modes.push(&dict);
+        }
+        modes
+    }
+
+    /// Returns the refresh rates the display supports in ascending order. If
+    /// the display doesn't publish its modes, only the current refresh rate is
+    /// returned.
+    #[func]
+    fn get_supported_refresh_rates(&mut self) -> PackedInt64Array {
+        let mut rates: Vec<i64> = self
+            .display_modes()
+            .into_iter()
+            .map(|mode| mode.refresh_rate as i64)
+            .collect();
+        if rates.is_empty() {
+            let current = self.get_current_refresh_rate();
+            if current > 0 {
+                rates.push(current as i64);
+            }
+        }
+        rates.sort_unstable();
+        rates.dedup();
+        rates.into()
+    }
+
+    /// Returns the resolution of this XWayland display
+    #[func]
+    pub fn get_resolution(&self) -> Vector2i {
+        let Some(x11) = self.x11.as_ref() else {
+            return Vector2i::ZERO;
+        };
+        match x11.get_root_size() {
+            Ok((width, height)) => Vector2i::new(width as i32, height as i32),
+            Err(e) => {
+                log::error!("Failed to get resolution of display '{}': {e:?}", self.name);
+                Vector2i::ZERO
+            }
+        }
+    }
+
+    /// Asks Gamescope to change the resolution of the XWayland server with the
+    /// given index. Server 0 is the primary XWayland. If `allow_super_res` is
+    /// true, the resolution may be higher than the output resolution.
+    #[func]
+    pub fn set_xwayland_resolution(
+        &self,
+        server: u32,
+        width: u32,
+        height: u32,
+        allow_super_res: bool,
+    ) -> i32 {
+        if !self.is_primary {
+            log::error!("XWayland instance is not primary!");
+            return -1;
+        }
+        let Some(x11) = self.x11.as_ref() else {
+            log::error!("No X11 connection to display '{}'", self.name);
+            return -1;
+        };
+        let values = [server, width, height, allow_super_res as u32];
+        if let Err(e) = x11.set_root_cardinals(ATOM_XWAYLAND_MODE_CONTROL, &values) {
+            log::error!("Failed to set resolution of XWayland server {server}: {e:?}");
+            return -1;
+        }
+        0
+    }
+
+    /// Returns the modes that Gamescope published for the display
+    fn display_modes(&self) -> Vec<DisplayMode> {
+        let Some(x11) = self.x11.as_ref().filter(|_| self.is_primary) else {
+            return Vec::new();
+        };
+        match x11.get_root_string(ATOM_DISPLAY_MODES) {
+            Ok(value) => parse_display_modes(value.unwrap_or_default().as_str()),
+            Err(e) => {
+                log::error!("Failed to get {ATOM_DISPLAY_MODES}: {e:?}");
+                Vec::new()
+            }
+        }
+    }
+
+    /// Returns the value of the given root window atom on the primary
+    /// XWayland, or `None` if it is not set or could not be read
+    fn get_root_value(&self, atom: &str) -> Option<u32> {
+        if !self.is_primary {
+            log::error!("XWayland instance is not primary!");
+            return None;
+        }
+        let x11 = self.x11.as_ref()?;
+        match x11.get_root_cardinal(atom) {
+            Ok(value) => value,
+            Err(e) => {
+                log::error!("Failed to get {atom}: {e:?}");
+                None
+            }
+        }
+    }
+
+    /// Sets the given root window atom on the primary XWayland
+    fn set_root_value(&self, atom: &str, value: u32) {
+        if !self.is_primary {
+            log::error!("XWayland instance is not primary!");
+            return;
+        }
+        let Some(x11) = self.x11.as_ref() else {
+            log::error!("No X11 connection to display '{}'", self.name);
+            return;
+        };
+        if let Err(e) = x11.set_root_cardinals(atom, &[value]) {
+            log::error!("Failed to set {atom} to {value}: {e:?}");
+        }
+    }
+
+    /// Request a screenshot from Gamescope. The screenshot is moved into the
+    /// library of [GamescopeScreenshots] once it is written.
     #[func]
     fn request_screenshot(&self) {
         if let Err(e) = self.xwayland.request_screenshot() {
             log::error!("Failed to request screenshot: {e:?}");
+            return;
+        }
+        screenshots::request_screenshot();
+    }
+
+    /// Press or release the key with the given evdev name (e.g. "KEY_A") on
+    /// this display using XTest. Unlike InputPlumber keyboard devices, this
+    /// works without InputPlumber running.
+    #[func]
+    pub fn send_key(&self, key: GString, pressed: bool) -> i32 {
+        let Some(keysym) = key_name_to_keysym(key.to_string().as_str()) else {
+            log::error!("Unknown key: {key}");
+            return -1;
+        };
+        self.send_keysym(keysym, pressed)
+    }
+
+    /// Press or release the key that produces the given X11 keysym on this
+    /// display using XTest
+    #[func]
+    pub fn send_keysym(&self, keysym: u32, pressed: bool) -> i32 {
+        self.inject_input("send key", |x11| x11.send_keysym(keysym, pressed))
+    }
+
+    /// Type the given text on this display using XTest
+    #[func]
+    pub fn type_text(&self, text: GString) -> i32 {
+        let text = text.to_string();
+        self.inject_input("type text", |x11| x11.type_text(text.as_str()))
+    }
+
+    /// Move the mouse cursor by the given amount on this display using XTest
+    #[func]
+    pub fn move_cursor(&self, x: i64, y: i64) -> i32 {
+        let (x, y) = (clamp_i16(x), clamp_i16(y));
+        self.inject_input("move cursor", |x11| x11.move_cursor_relative(x, y))
+    }
+
+    /// Move the mouse cursor to the given position on this display using XTest
+    #[func]
+    pub fn move_cursor_to(&self, x: i64, y: i64) -> i32 {
+        let (x, y) = (clamp_i16(x), clamp_i16(y));
+        self.inject_input("move cursor", |x11| x11.move_cursor(x, y))
+    }
+
+    /// Press or release the given MOUSE_BUTTON_* on this display using XTest
+    #[func]
+    pub fn send_mouse_button(&self, button: u32, pressed: bool) -> i32 {
+        let Ok(button) = u8::try_from(button) else {
+            log::error!("Invalid mouse button: {button}");
+            return -1;
+        };
+        self.inject_input("send mouse button", |x11| x11.send_button(button, pressed))
+    }
+
+    /// Press and release the given MOUSE_BUTTON_* on this display using XTest
+    #[func]
+    pub fn click(&self, button: u32) -> i32 {
+        let result = self.send_mouse_button(button, true);
+        if result != 0 {
+            return result;
+        }
+        self.send_mouse_button(button, false)
+    }
+
+    /// Scroll by the given number of steps on this display using XTest.
+    /// Positive values scroll right and down.
+    #[func]
+    pub fn scroll(&self, x: i32, y: i32) -> i32 {
+        self.inject_input("scroll", |x11| x11.scroll(x, y))
+    }
+
+    /// Run the given input injection on the X11 connection, returning 0 on
+    /// success and -1 on failure
+    fn inject_input(
+        &self,
+        action: &str,
+        inject: impl FnOnce(&X11Connection) -> Result<(), ReplyError>,
+    ) -> i32 {
+        let Some(x11) = self.x11.as_deref() else {
+            log::error!("No X11 connection to display '{}'", self.name);
+            return -1;
+        };
+        if let Err(e) = inject(x11) {
+            log::error!("Failed to {action} on display '{}': {e:?}", self.name);
+            return -1;
+        }
+        0
+    }
+
+    /// Sets whether the focused window is pinged to detect when it stops
+    /// responding
+    #[func]
+    pub fn set_ping_enabled(&mut self, enabled: bool) {
+        self.ping_enabled = enabled;
+        if !enabled {
+            self.pinger = None;
+            return;
+        }
+        if self.pinger.is_some() {
+            return;
+        }
+        let name = self.name.to_string();
+        let timeout = Duration::from_millis(self.ping_timeout as u64);
+        match WindowPinger::start(name.as_str(), timeout, self.ping_tx.clone()) {
+            Ok(pinger) => self.pinger = Some(pinger),
+            Err(e) => log::error!("Failed to start pinging windows on display '{name}': {e:?}"),
         }
     }
 
+    /// Sets the time in milliseconds after which a window that didn't answer
+    /// a ping is unresponsive
+    #[func]
+    pub fn set_ping_timeout(&mut self, timeout: u32) {
+        self.ping_timeout = timeout;
+        if let Some(pinger) = self.pinger.as_ref() {
+            pinger.set_timeout(Duration::from_millis(timeout as u64));
+        }
+    }
+
+    /// Returns true if the given window didn't answer its last ping in time
+    #[func]
+    pub fn is_window_unresponsive(&self, window_id: u32) -> bool {
+        let pinger = self.pinger.as_ref();
+        pinger.is_some_and(|pinger| pinger.is_unresponsive(window_id))
+    }
+
     /// Dispatches signals, called by [GamescopeInstance]
     pub fn process(&mut self) {
         // Drain all messages from the channel to process them
@@ -972,6 +1671,64 @@ impl GamescopeXWayland {
             };
             self.process_signal(signal);
         }
+
+        while let Ok(event) = self.ping_rx.try_recv() {
+            self.process_ping_event(event);
+        }
+    }
+
+    /// Emit the signal for the given change in whether a window answers pings
+    fn process_ping_event(&mut self, event: PingEvent) {
+        match event {
+            PingEvent::Unresponsive(window_id) => {
+                let pids = self.get_pids_for_window(window_id);
+                self.base_mut().emit_signal(
+                    "window_unresponsive",
+                    &[window_id.to_variant(), pids.to_variant()],
+                );
+            }
+            PingEvent::Responsive(window_id) => {
+                self.base_mut()
+                    .emit_signal("window_responsive", &[window_id.to_variant()]);
+            }
+        }
+    }
+
+    /// Update the textures waiting for the given captured thumbnail and emit
+    /// [signal window_thumbnail_captured]
+    fn process_thumbnail(&mut self, window_id: u32, thumbnail: Option<Thumbnail>) {
+        let textures = self
+            .thumbnail_requests
+            .remove(&window_id)
+            .unwrap_or_default();
+        let Some(thumbnail) = thumbnail else {
+            return;
+        };
+        let data = PackedByteArray::from(thumbnail.rgba.as_slice());
+        let (width, height) = (thumbnail.width as i32, thumbnail.height as i32);
+        let Some(image) = Image::create_from_data(width, height, false, Format::RGBA8, &data)
+        else {
+            log::warn!("Unable to create thumbnail image of window '{window_id}'");
+            return;
+        };
+        for mut texture in textures {
+            // Textures can only be updated in place if the size stays the same
+            if texture.get_size() == image.get_size().cast_float() {
+                texture.update(&image);
+            } else {
+                texture.set_image(&image);
+            }
+        }
+        self.base_mut().emit_signal(
+            "window_thumbnail_captured",
+            &[window_id.to_variant(), image.to_variant()],
+        );
+    }
+
+    /// Returns all window and focus changes since the last call, called by
+    /// [GamescopeInstance]
+    pub fn take_window_events(&mut self) -> Vec<WindowEvent> {
+        std::mem::take(&mut self.window_events)
     }
 
     /// Process and dispatch the given signal
@@ -979,10 +1736,15 @@ impl GamescopeXWayland {
         //log::trace!("Got signal: {signal:?}");
         match signal {
             Signal::WindowCreated { window_id } => {
+                self.window_events.push(WindowEvent::Created(window_id));
                 self.base_mut()
                     .emit_signal("window_created", &[window_id.to_variant()]);
             }
             Signal::WindowDestroyed { window_id } => {
+                if let Some(pinger) = self.pinger.as_ref() {
+                    pinger.window_destroyed(window_id);
+                }
+                self.window_events.push(WindowEvent::Destroyed(window_id));
                 self.base_mut()
                     .emit_signal("window_destroyed", &[window_id.to_variant()]);
             }
@@ -990,16 +1752,32 @@ impl GamescopeXWayland {
                 window_id,
                 property,
             } => {
+                if property == GamescopeAtom::SteamGame.to_string() {
+                    self.window_events
+                        .push(WindowEvent::AppIdChanged(window_id));
+                }
                 self.base_mut().emit_signal(
                     "window_property_updated",
                     &[window_id.to_variant(), property.to_godot().to_variant()],
                 );
             }
+            Signal::ThumbnailCaptured {
+                window_id,
+                thumbnail,
+            } => {
+                self.process_thumbnail(window_id, thumbnail);
+            }
+            Signal::Disconnected => {
+                log::debug!("XWayland display '{}' was disconnected", self.name);
+                self.connected = false;
+                self.base_mut().emit_signal("disconnected", &[]);
+            }
             Signal::PropertyChanged { property } => {
                 match property {
                     property if property == GamescopeAtom::FocusedApp.to_string() => {
                         let from = self.focused_app;
                         let to = self.get_focused_app();
+                        self.window_events.push(WindowEvent::FocusedApp(to));
                         self.base_mut().emit_signal(
                             "focused_app_updated",
                             &[from.to_variant(), to.to_variant()],
@@ -1040,6 +1818,7 @@ impl GamescopeXWayland {
                     property if property == GamescopeAtom::BaselayerWindow.to_string() => {
                         let from = self.baselayer_window;
                         let to = self.get_baselayer_window();
+                        self.window_events.push(WindowEvent::BaselayerChanged);
                         self.base_mut().emit_signal(
                             "baselayer_window_updated",
                             &[from.to_variant(), to.to_variant()],
@@ -1048,11 +1827,63 @@ impl GamescopeXWayland {
                     property if property == GamescopeAtom::BaselayerAppId.to_string() => {
                         let from = self.baselayer_app;
                         let to = self.get_baselayer_app();
+                        self.window_events.push(WindowEvent::BaselayerChanged);
                         self.base_mut().emit_signal(
                             "baselayer_app_updated",
                             &[from.to_variant(), to.to_variant()],
                         );
                     }
+                    property if property == ATOM_UPSCALE_FILTER => {
+                        let from = self.upscale_filter;
+                        let to = self.get_upscale_filter();
+                        self.base_mut().emit_signal(
+                            "upscale_filter_updated",
+                            &[from.to_variant(), to.to_variant()],
+                        );
+                    }
+                    property if property == ATOM_UPSCALE_SCALER => {
+                        let from = self.upscale_scaler;
+                        let to = self.get_upscale_scaler();
+                        self.base_mut().emit_signal(
+                            "upscale_scaler_updated",
+                            &[from.to_variant(), to.to_variant()],
+                        );
+                    }
+                    property if property == ATOM_SHARPNESS => {
+                        let from = self.sharpness;
+                        let to = self.get_sharpness();
+                        self.base_mut().emit_signal(
+                            "sharpness_updated",
+                            &[from.to_variant(), to.to_variant()],
+                        );
+                    }
+                    property if property == ATOM_HDR_ENABLED => {
+                        let from = self.hdr_enabled;
+                        let to = self.get_hdr_enabled();
+                        self.base_mut().emit_signal(
+                            "hdr_enabled_updated",
+                            &[from.to_variant(), to.to_variant()],
+                        );
+                    }
+                    property if property == ATOM_SDR_CONTENT_BRIGHTNESS => {
+                        let from = self.sdr_content_brightness;
+                        let to = self.get_sdr_content_brightness();
+                        self.base_mut().emit_signal(
+                            "sdr_content_brightness_updated",
+                            &[from.to_variant(), to.to_variant()],
+                        );
+                    }
+                    property if property == ATOM_REFRESH_RATE_FEEDBACK => {
+                        let from = self.current_refresh_rate;
+                        let to = self.get_current_refresh_rate();
+                        self.base_mut().emit_signal(
+                            "current_refresh_rate_updated",
+                            &[from.to_variant(), to.to_variant()],
+                        );
+                    }
+                    property if property == ATOM_DISPLAY_MODES => {
+                        self.base_mut().emit_signal("display_modes_updated", &[]);
+                    }
                     _ => {
                         // Unknown prop changed
                     }
@@ -1072,5 +1903,36 @@ impl IResource for GamescopeXWayland {
 impl Drop for GamescopeXWayland {
     fn drop(&mut self) {
         log::trace!("Gamescope XWayland '{}' is being destroyed!", self.name);
+        for task in self.window_watch_handles.values() {
+            task.abort();
+        }
+    }
+}
+
+/// Clamp the given value to the range of X11 coordinates
+fn clamp_i16(value: i64) -> i16 {
+    value.clamp(i16::MIN as i64, i16::MAX as i64) as i16
+}
+
+/// Capture the given window and downscale it to fit within the given size.
+/// Returns `None` if the window isn't visible or can't be captured.
+fn capture_thumbnail(
+    x11: &X11Connection,
+    window_id: u32,
+    max_width: u32,
+    max_height: u32,
+) -> Option<Thumbnail> {
+    let image = match x11.capture_window(window_id) {
+        Ok(Some(image)) => image,
+        Ok(None) => return None,
+        Err(e) => {
+            log::error!("Failed to capture window '{window_id}': {e:?}");
+            return None;
+        }
+    };
+    let thumbnail = image.thumbnail(max_width, max_height);
+    if thumbnail.is_none() {
+        log::warn!("Unable to create thumbnail of window '{window_id}'");
     }
+    thumbnail
 }
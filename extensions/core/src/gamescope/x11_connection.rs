@@ -0,0 +1,621 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    sync::Mutex,
+};
+
+use super::thumbnail::{PixelFormat, WindowImage};
+use super::x11_input::{
+    char_to_keysym, Keymap, BUTTON_SCROLL_DOWN, BUTTON_SCROLL_LEFT, BUTTON_SCROLL_RIGHT,
+    BUTTON_SCROLL_UP, KEYSYM_SHIFT_L, NO_SYMBOL,
+};
+
+use x11rb::{
+    atom_manager,
+    connection::Connection,
+    cookie::Cookie,
+    errors::{ReplyError, ReplyOrIdError},
+    protocol::{
+        composite::ConnectionExt as _,
+        xproto::{
+            Atom, AtomEnum, ConnectionExt, GetPropertyReply, ImageFormat, ImageOrder, MapState,
+            Pixmap, PropMode, Visualid, Window, WindowClass, BUTTON_PRESS_EVENT,
+            BUTTON_RELEASE_EVENT, KEY_PRESS_EVENT, KEY_RELEASE_EVENT, MOTION_NOTIFY_EVENT,
+        },
+        xtest::ConnectionExt as _,
+    },
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+};
+
+/// Maximum number of 32-bit values to read from a window property
+const MAX_PROPERTY_LENGTH: u32 = 1024;
+
+atom_manager! {
+    /// Atoms used by [X11Connection]
+    pub Atoms: AtomsCookie {
+        UTF8_STRING,
+        _NET_WM_NAME,
+        _NET_WM_PID,
+        _NET_WM_WINDOW_TYPE,
+        _NET_WM_WINDOW_TYPE_DESKTOP,
+        _NET_WM_WINDOW_TYPE_DOCK,
+        _NET_WM_WINDOW_TYPE_TOOLBAR,
+        _NET_WM_WINDOW_TYPE_MENU,
+        _NET_WM_WINDOW_TYPE_UTILITY,
+        _NET_WM_WINDOW_TYPE_SPLASH,
+        _NET_WM_WINDOW_TYPE_DIALOG,
+        _NET_WM_WINDOW_TYPE_DROPDOWN_MENU,
+        _NET_WM_WINDOW_TYPE_POPUP_MENU,
+        _NET_WM_WINDOW_TYPE_TOOLTIP,
+        _NET_WM_WINDOW_TYPE_NOTIFICATION,
+        _NET_WM_WINDOW_TYPE_COMBO,
+        _NET_WM_WINDOW_TYPE_DND,
+        _NET_WM_WINDOW_TYPE_NORMAL,
+        _NET_WM_STATE,
+        _NET_WM_STATE_FULLSCREEN,
+        _NET_WM_STATE_HIDDEN,
+    }
+}
+
+/// Metadata of a single X11 window
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WindowInfo {
+    /// Title of the window from _NET_WM_NAME or WM_NAME
+    pub name: String,
+    /// Instance part of WM_CLASS
+    pub wm_class_instance: String,
+    /// Class part of WM_CLASS
+    pub wm_class: String,
+    /// Process id from _NET_WM_PID, or zero if it is not set
+    pub pid: u32,
+    /// Window types from _NET_WM_WINDOW_TYPE without the prefix (e.g. "normal"
+    /// or "splash")
+    pub window_types: Vec<String>,
+    /// Position of the window relative to the root window
+    pub x: i32,
+    pub y: i32,
+    /// Size of the window
+    pub width: u32,
+    pub height: u32,
+    /// Whether the window is mapped
+    pub mapped: bool,
+    /// Whether the window and all of its ancestors are mapped
+    pub visible: bool,
+    /// Window from WM_TRANSIENT_FOR, or zero if it is not set
+    pub transient_for: u32,
+    /// Whether _NET_WM_STATE contains _NET_WM_STATE_FULLSCREEN
+    pub fullscreen: bool,
+    /// Whether _NET_WM_STATE contains _NET_WM_STATE_HIDDEN
+    pub hidden: bool,
+}
+
+/// Direct connection to an XWayland display for requests that are not
+/// provided by [gamescope_x11_client::xwayland::XWayland]
+pub struct X11Connection {
+    conn: RustConnection,
+    root: Window,
+    atoms: Atoms,
+    /// Atoms that were interned by name with [X11Connection::atom]
+    named_atoms: Mutex<HashMap<String, Atom>>,
+    /// Keyboard mapping used to inject keys, read on first use
+    keymap: Mutex<Option<Keymap>>,
+    /// Spare keycodes that keysyms were mapped to, restored once released
+    remapped_keycodes: Mutex<HashSet<u8>>,
+    /// Whether the display supports the Composite extension
+    composite: bool,
+}
+
+impl X11Connection {
+    /// Connect to the X11 display with the given name (e.g. ":0")
+    pub fn connect(name: &str) -> Result<Self, Box<dyn Error>> {
+        let (conn, screen) = RustConnection::connect(Some(name))?;
+        let root = conn.setup().roots[screen].root;
+        let atoms = Atoms::new(&conn)?.reply()?;
+        let composite = conn
+            .composite_query_version(0, 4)
+            .is_ok_and(|cookie| cookie.reply().is_ok());
+        Ok(Self {
+            conn,
+            root,
+            atoms,
+            named_atoms: Default::default(),
+            keymap: Default::default(),
+            remapped_keycodes: Default::default(),
+            composite,
+        })
+    }
+
+    /// Returns the root window of the display
+    pub fn root(&self) -> Window {
+        self.root
+    }
+
+    /// Returns the width and height of the root window, which is the
+    /// resolution of the display
+    pub fn get_root_size(&self) -> Result<(u32, u32), ReplyError> {
+        let geometry = self.conn.get_geometry(self.root)?.reply()?;
+        Ok((geometry.width as u32, geometry.height as u32))
+    }
+
+    /// Returns the atom with the given name, interning it on first use
+    pub fn atom(&self, name: &str) -> Result<Atom, ReplyError> {
+        if let Some(atom) = self.named_atoms.lock().unwrap().get(name) {
+            return Ok(*atom);
+        }
+        let atom = self.conn.intern_atom(false, name.as_bytes())?.reply()?.atom;
+        self.named_atoms
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), atom);
+        Ok(atom)
+    }
+
+    /// Returns the 32-bit values of the root window property with the given
+    /// name, or `None` if the property is not set
+    pub fn get_root_cardinals(&self, name: &str) -> Result<Option<Vec<u32>>, ReplyError> {
+        let atom = self.atom(name)?;
+        let reply = self
+            .get_property(self.root, atom, AtomEnum::CARDINAL.into())?
+            .reply()?;
+        if reply.value_len == 0 {
+            return Ok(None);
+        }
+        Ok(Some(values32(&reply)))
+    }
+
+    /// Returns the first 32-bit value of the root window property with the
+    /// given name, or `None` if the property is not set
+    pub fn get_root_cardinal(&self, name: &str) -> Result<Option<u32>, ReplyError> {
+        let values = self.get_root_cardinals(name)?;
+        Ok(values.and_then(|values| values.first().copied()))
+    }
+
+    /// Returns the string value of the root window property with the given
+    /// name, or `None` if the property is not set
+    pub fn get_root_string(&self, name: &str) -> Result<Option<String>, ReplyError> {
+        let atom = self.atom(name)?;
+        let reply = self
+            .get_property(self.root, atom, AtomEnum::ANY.into())?
+            .reply()?;
+        if reply.value_len == 0 {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(&reply.value).to_string()))
+    }
+
+    /// Set the root window property with the given name to the given 32-bit
+    /// values
+    pub fn set_root_cardinals(&self, name: &str, values: &[u32]) -> Result<(), ReplyError> {
+        let atom = self.atom(name)?;
+        self.conn
+            .change_property32(
+                PropMode::REPLACE,
+                self.root,
+                atom,
+                AtomEnum::CARDINAL,
+                values,
+            )?
+            .check()?;
+        Ok(())
+    }
+
+    /// Set the root window property with the given name to the given string
+    pub fn set_root_string(&self, name: &str, value: &str) -> Result<(), ReplyError> {
+        let atom = self.atom(name)?;
+        self.conn
+            .change_property8(
+                PropMode::REPLACE,
+                self.root,
+                atom,
+                AtomEnum::STRING,
+                value.as_bytes(),
+            )?
+            .check()?;
+        Ok(())
+    }
+
+    /// Remove the root window property with the given name
+    pub fn remove_root_property(&self, name: &str) -> Result<(), ReplyError> {
+        let atom = self.atom(name)?;
+        self.conn.delete_property(self.root, atom)?.check()?;
+        Ok(())
+    }
+
+    /// Press or release the key that produces the given keysym using XTest.
+    /// Shift is held while the key is pressed if the keysym needs it, and
+    /// keysyms that no key produces are mapped to a spare keycode until the
+    /// key is released.
+    pub fn send_keysym(&self, keysym: u32, pressed: bool) -> Result<(), ReplyError> {
+        let Some((keycode, shift)) = self.keycode_for(keysym)? else {
+            log::warn!("No keycode to send keysym {keysym:#x} with");
+            return Ok(());
+        };
+        let shift_keycode = match shift {
+            true => self
+                .keycode_for(KEYSYM_SHIFT_L)?
+                .map(|(keycode, _)| keycode),
+            false => None,
+        };
+        if pressed {
+            if let Some(shift_keycode) = shift_keycode {
+                self.fake_input(KEY_PRESS_EVENT, shift_keycode, 0, 0)?;
+            }
+            self.fake_input(KEY_PRESS_EVENT, keycode, 0, 0)?;
+        } else {
+            self.fake_input(KEY_RELEASE_EVENT, keycode, 0, 0)?;
+            if let Some(shift_keycode) = shift_keycode {
+                self.fake_input(KEY_RELEASE_EVENT, shift_keycode, 0, 0)?;
+            }
+            self.restore_keycode(keycode)?;
+        }
+        self.sync()
+    }
+
+    /// Type the given text by pressing and releasing a key for each character
+    pub fn type_text(&self, text: &str) -> Result<(), ReplyError> {
+        for c in text.chars() {
+            let keysym = char_to_keysym(c);
+            self.send_keysym(keysym, true)?;
+            self.send_keysym(keysym, false)?;
+        }
+        Ok(())
+    }
+
+    /// Move the mouse cursor to the given position on the root window
+    pub fn move_cursor(&self, x: i16, y: i16) -> Result<(), ReplyError> {
+        self.fake_input(MOTION_NOTIFY_EVENT, 0, x, y)?;
+        self.sync()
+    }
+
+    /// Move the mouse cursor by the given amount
+    pub fn move_cursor_relative(&self, dx: i16, dy: i16) -> Result<(), ReplyError> {
+        self.fake_input(MOTION_NOTIFY_EVENT, 1, dx, dy)?;
+        self.sync()
+    }
+
+    /// Press or release the given mouse button
+    pub fn send_button(&self, button: u8, pressed: bool) -> Result<(), ReplyError> {
+        let event = match pressed {
+            true => BUTTON_PRESS_EVENT,
+            false => BUTTON_RELEASE_EVENT,
+        };
+        self.fake_input(event, button, 0, 0)?;
+        self.sync()
+    }
+
+    /// Scroll by the given number of steps. Positive values scroll right and
+    /// down.
+    pub fn scroll(&self, dx: i32, dy: i32) -> Result<(), ReplyError> {
+        let horizontal = match dx > 0 {
+            true => BUTTON_SCROLL_RIGHT,
+            false => BUTTON_SCROLL_LEFT,
+        };
+        let vertical = match dy > 0 {
+            true => BUTTON_SCROLL_DOWN,
+            false => BUTTON_SCROLL_UP,
+        };
+        for (button, steps) in [(horizontal, dx), (vertical, dy)] {
+            for _ in 0..steps.unsigned_abs() {
+                self.fake_input(BUTTON_PRESS_EVENT, button, 0, 0)?;
+                self.fake_input(BUTTON_RELEASE_EVENT, button, 0, 0)?;
+            }
+        }
+        self.sync()
+    }
+
+    /// Returns the keycode that produces the given keysym and whether shift has
+    /// to be held for it, mapping the keysym to a spare keycode if needed.
+    /// Returns `None` if there is no spare keycode left.
+    fn keycode_for(&self, keysym: u32) -> Result<Option<(u8, bool)>, ReplyError> {
+        let mut keymap = self.keymap.lock().unwrap();
+        if keymap.is_none() {
+            let setup = self.conn.setup();
+            let count = setup.max_keycode - setup.min_keycode + 1;
+            let reply = self
+                .conn
+                .get_keyboard_mapping(setup.min_keycode, count)?
+                .reply()?;
+            *keymap = Some(Keymap {
+                min_keycode: setup.min_keycode,
+                keysyms_per_keycode: reply.keysyms_per_keycode,
+                keysyms: reply.keysyms,
+            });
+        }
+        let keymap = keymap.as_mut().unwrap();
+        if let Some(found) = keymap.find(keysym) {
+            return Ok(Some(found));
+        }
+
+        let Some(keycode) = keymap.spare_keycode() else {
+            return Ok(None);
+        };
+        self.remap_keycode(keymap, keycode, keysym)?;
+        self.remapped_keycodes.lock().unwrap().insert(keycode);
+        Ok(Some((keycode, false)))
+    }
+
+    /// Unmap the given keycode again if a keysym was mapped to it by
+    /// [X11Connection::keycode_for], so spare keycodes are not used up
+    fn restore_keycode(&self, keycode: u8) -> Result<(), ReplyError> {
+        if !self.remapped_keycodes.lock().unwrap().remove(&keycode) {
+            return Ok(());
+        }
+        let mut keymap = self.keymap.lock().unwrap();
+        let Some(keymap) = keymap.as_mut() else {
+            return Ok(());
+        };
+        self.remap_keycode(keymap, keycode, NO_SYMBOL)
+    }
+
+    /// Map the given keycode to the given keysym on the display
+    fn remap_keycode(
+        &self,
+        keymap: &mut Keymap,
+        keycode: u8,
+        keysym: u32,
+    ) -> Result<(), ReplyError> {
+        keymap.set(keycode, keysym);
+        let per_keycode = keymap.keysyms_per_keycode as usize;
+        let start = (keycode - keymap.min_keycode) as usize * per_keycode;
+        let keysyms = &keymap.keysyms[start..start + per_keycode];
+        self.conn
+            .change_keyboard_mapping(1, keycode, keymap.keysyms_per_keycode, keysyms)?
+            .check()
+    }
+
+    /// Send a fake input event to the display with XTest
+    fn fake_input(&self, event: u8, detail: u8, x: i16, y: i16) -> Result<(), ReplyError> {
+        // Absolute motion is relative to the root window, everything else
+        // ignores the window
+        let root = match event == MOTION_NOTIFY_EVENT && detail == 0 {
+            true => self.root,
+            false => x11rb::NONE,
+        };
+        self.conn
+            .xtest_fake_input(event, detail, x11rb::CURRENT_TIME, root, x, y, 0)?;
+        Ok(())
+    }
+
+    /// Wait until the display has processed all requests
+    fn sync(&self) -> Result<(), ReplyError> {
+        self.conn.get_input_focus()?.reply()?;
+        Ok(())
+    }
+
+    /// Returns the metadata of the given window. All requests are sent before
+    /// waiting for any replies, so this only takes a single round-trip.
+    pub fn get_window_info(&self, window: Window) -> Result<WindowInfo, ReplyError> {
+        let conn = &self.conn;
+        let atoms = &self.atoms;
+        let net_wm_name = self.get_property(window, atoms._NET_WM_NAME, atoms.UTF8_STRING)?;
+        let wm_name =
+            self.get_property(window, AtomEnum::WM_NAME.into(), AtomEnum::STRING.into())?;
+        let wm_class =
+            self.get_property(window, AtomEnum::WM_CLASS.into(), AtomEnum::STRING.into())?;
+        let pid = self.get_property(window, atoms._NET_WM_PID, AtomEnum::CARDINAL.into())?;
+        let window_type =
+            self.get_property(window, atoms._NET_WM_WINDOW_TYPE, AtomEnum::ATOM.into())?;
+        let state = self.get_property(window, atoms._NET_WM_STATE, AtomEnum::ATOM.into())?;
+        let transient_for = self.get_property(
+            window,
+            AtomEnum::WM_TRANSIENT_FOR.into(),
+            AtomEnum::WINDOW.into(),
+        )?;
+        let geometry = conn.get_geometry(window)?;
+        let attributes = conn.get_window_attributes(window)?;
+        let position = conn.translate_coordinates(window, self.root, 0, 0)?;
+
+        let net_wm_name = net_wm_name.reply()?;
+        let wm_name = wm_name.reply()?;
+        let name = if net_wm_name.value.is_empty() {
+            String::from_utf8_lossy(&wm_name.value).to_string()
+        } else {
+            String::from_utf8_lossy(&net_wm_name.value).to_string()
+        };
+        let (wm_class_instance, wm_class) = parse_wm_class(&wm_class.reply()?.value);
+        let pid = first_value32(&pid.reply()?).unwrap_or_default();
+        let window_types = values32(&window_type.reply()?)
+            .into_iter()
+            .filter_map(|atom| self.window_type_name(atom))
+            .map(String::from)
+            .collect();
+        let state = values32(&state.reply()?);
+        let transient_for = first_value32(&transient_for.reply()?).unwrap_or_default();
+        let geometry = geometry.reply()?;
+        let attributes = attributes.reply()?;
+        let position = position.reply()?;
+
+        Ok(WindowInfo {
+            name,
+            wm_class_instance,
+            wm_class,
+            pid,
+            window_types,
+            x: position.dst_x as i32,
+            y: position.dst_y as i32,
+            width: geometry.width as u32,
+            height: geometry.height as u32,
+            mapped: attributes.map_state != MapState::UNMAPPED,
+            visible: attributes.map_state == MapState::VIEWABLE,
+            transient_for,
+            fullscreen: state.contains(&atoms._NET_WM_STATE_FULLSCREEN),
+            hidden: state.contains(&atoms._NET_WM_STATE_HIDDEN),
+        })
+    }
+
+    /// Returns the contents of the given window, or `None` if the window is
+    /// not viewable. Windows that are redirected by a compositor such as
+    /// Gamescope are read from their offscreen pixmap, so they are captured
+    /// even while other windows cover them.
+    pub fn capture_window(&self, window: Window) -> Result<Option<WindowImage>, ReplyOrIdError> {
+        let conn = &self.conn;
+        let attributes = conn.get_window_attributes(window)?;
+        let geometry = conn.get_geometry(window)?;
+        let attributes = attributes.reply()?;
+        let geometry = geometry.reply()?;
+        let viewable = attributes.map_state == MapState::VIEWABLE;
+        if !viewable || attributes.class == WindowClass::INPUT_ONLY {
+            return Ok(None);
+        }
+        let Some(format) = self.pixel_format(geometry.depth, attributes.visual) else {
+            log::warn!(
+                "Unsupported pixel format of window {window} with depth {}",
+                geometry.depth
+            );
+            return Ok(None);
+        };
+
+        // The window pixmap includes the border of the window
+        let pixmap = self.name_window_pixmap(window)?;
+        let (drawable, offset) = match pixmap {
+            Some(pixmap) => (pixmap, geometry.border_width as i16),
+            None => (window, 0),
+        };
+        let (width, height) = (geometry.width, geometry.height);
+        let image = conn.get_image(
+            ImageFormat::Z_PIXMAP,
+            drawable,
+            offset,
+            offset,
+            width,
+            height,
+            u32::MAX,
+        );
+        if let Some(pixmap) = pixmap {
+            conn.free_pixmap(pixmap)?;
+        }
+        let image = image?.reply()?;
+
+        Ok(Some(WindowImage {
+            width: width as u32,
+            height: height as u32,
+            format,
+            data: image.data,
+        }))
+    }
+
+    /// Returns a new pixmap with the offscreen contents of the given window,
+    /// or `None` if the window is not redirected. The pixmap has to be freed
+    /// by the caller.
+    fn name_window_pixmap(&self, window: Window) -> Result<Option<Pixmap>, ReplyOrIdError> {
+        if !self.composite {
+            return Ok(None);
+        }
+        let pixmap = self.conn.generate_id()?;
+        let cookie = self.conn.composite_name_window_pixmap(window, pixmap)?;
+        match cookie.check() {
+            Ok(()) => Ok(Some(pixmap)),
+            // Windows that are not redirected fail with BadMatch
+            Err(ReplyError::X11Error(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns the layout of images of windows with the given depth and visual
+    fn pixel_format(&self, depth: u8, visual: Visualid) -> Option<PixelFormat> {
+        let setup = self.conn.setup();
+        let format = setup
+            .pixmap_formats
+            .iter()
+            .find(|format| format.depth == depth)?;
+        let visual = setup
+            .roots
+            .iter()
+            .flat_map(|screen| &screen.allowed_depths)
+            .flat_map(|depth| &depth.visuals)
+            .find(|info| info.visual_id == visual)?;
+        Some(PixelFormat {
+            bits_per_pixel: format.bits_per_pixel,
+            scanline_pad: format.scanline_pad,
+            red_mask: visual.red_mask,
+            green_mask: visual.green_mask,
+            blue_mask: visual.blue_mask,
+            big_endian: setup.image_byte_order == ImageOrder::MSB_FIRST,
+        })
+    }
+
+    /// Send a request for the given window property without waiting for the
+    /// reply
+    fn get_property(
+        &self,
+        window: Window,
+        property: Atom,
+        type_: Atom,
+    ) -> Result<Cookie<'_, RustConnection, GetPropertyReply>, ReplyError> {
+        let conn = &self.conn;
+        let cookie = conn.get_property(false, window, property, type_, 0, MAX_PROPERTY_LENGTH)?;
+        Ok(cookie)
+    }
+
+    /// Returns the short name of the given _NET_WM_WINDOW_TYPE_* atom
+    fn window_type_name(&self, atom: Atom) -> Option<&'static str> {
+        let atoms = &self.atoms;
+        let name = match atom {
+            atom if atom == atoms._NET_WM_WINDOW_TYPE_DESKTOP => "desktop",
+            atom if atom == atoms._NET_WM_WINDOW_TYPE_DOCK => "dock",
+            atom if atom == atoms._NET_WM_WINDOW_TYPE_TOOLBAR => "toolbar",
+            atom if atom == atoms._NET_WM_WINDOW_TYPE_MENU => "menu",
+            atom if atom == atoms._NET_WM_WINDOW_TYPE_UTILITY => "utility",
+            atom if atom == atoms._NET_WM_WINDOW_TYPE_SPLASH => "splash",
+            atom if atom == atoms._NET_WM_WINDOW_TYPE_DIALOG => "dialog",
+            atom if atom == atoms._NET_WM_WINDOW_TYPE_DROPDOWN_MENU => "dropdown_menu",
+            atom if atom == atoms._NET_WM_WINDOW_TYPE_POPUP_MENU => "popup_menu",
+            atom if atom == atoms._NET_WM_WINDOW_TYPE_TOOLTIP => "tooltip",
+            atom if atom == atoms._NET_WM_WINDOW_TYPE_NOTIFICATION => "notification",
+            atom if atom == atoms._NET_WM_WINDOW_TYPE_COMBO => "combo",
+            atom if atom == atoms._NET_WM_WINDOW_TYPE_DND => "dnd",
+            atom if atom == atoms._NET_WM_WINDOW_TYPE_NORMAL => "normal",
+            _ => return None,
+        };
+        Some(name)
+    }
+}
+
+/// Split the value of a WM_CLASS property into its instance and class parts
+pub fn parse_wm_class(value: &[u8]) -> (String, String) {
+    let mut parts = value
+        .split(|byte| *byte == 0)
+        .map(|part| String::from_utf8_lossy(part).to_string());
+    let instance = parts.next().unwrap_or_default();
+    let class = parts.next().unwrap_or_default();
+    (instance, class)
+}
+
+/// A mode that an output supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayMode {
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate: u32,
+}
+
+/// Parse a space-separated list of modes in the "WIDTHxHEIGHT@REFRESH" format,
+/// as published by Gamescope. Invalid modes are skipped.
+pub fn parse_display_modes(value: &str) -> Vec<DisplayMode> {
+    value
+        .split_whitespace()
+        .filter_map(|mode| {
+            let (size, refresh_rate) = mode.split_once('@')?;
+            let (width, height) = size.split_once('x')?;
+            Some(DisplayMode {
+                width: width.parse().ok()?,
+                height: height.parse().ok()?,
+                refresh_rate: refresh_rate.parse::<f32>().ok()?.round() as u32,
+            })
+        })
+        .collect()
+}
+
+/// Returns all 32-bit values of the given property
+fn values32(reply: &GetPropertyReply) -> Vec<u32> {
+    reply
+        .value32()
+        .map(|values| values.collect())
+        .unwrap_or_default()
+}
+
+/// Returns the first 32-bit value of the given property
+fn first_value32(reply: &GetPropertyReply) -> Option<u32> {
+    reply.value32()?.next()
+}
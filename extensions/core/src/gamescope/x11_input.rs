@@ -0,0 +1,186 @@
+/// Keysym that no key produces
+pub const NO_SYMBOL: u32 = 0;
+/// Keysym of the left shift key
+pub const KEYSYM_SHIFT_L: u32 = 0xffe1;
+
+/// Mouse button numbers used by X11
+pub const BUTTON_LEFT: u8 = 1;
+pub const BUTTON_MIDDLE: u8 = 2;
+pub const BUTTON_RIGHT: u8 = 3;
+pub const BUTTON_SCROLL_UP: u8 = 4;
+pub const BUTTON_SCROLL_DOWN: u8 = 5;
+pub const BUTTON_SCROLL_LEFT: u8 = 6;
+pub const BUTTON_SCROLL_RIGHT: u8 = 7;
+
+/// Returns the X11 keysym for the given evdev key name (e.g. "KEY_A"), as used
+/// by InputPlumber keyboard devices
+pub fn key_name_to_keysym(name: &str) -> Option<u32> {
+    let key = name.strip_prefix("KEY_")?;
+
+    // Letters and digits map to their lowercase ASCII keysym
+    if key.len() == 1 {
+        let c = key.chars().next()?;
+        if c.is_ascii_alphanumeric() {
+            return Some(c.to_ascii_lowercase() as u32);
+        }
+    }
+    // Function keys are consecutive keysyms starting at F1
+    if let Some(number) = key.strip_prefix('F').and_then(|n| n.parse::<u32>().ok()) {
+        if (1..=35).contains(&number) {
+            return Some(0xffbe + number - 1);
+        }
+    }
+    // Keypad digits are consecutive keysyms starting at KP_0
+    if let Some(number) = key.strip_prefix("KP").and_then(|n| n.parse::<u32>().ok()) {
+        if number <= 9 {
+            return Some(0xffb0 + number);
+        }
+    }
+
+    let keysym = match key {
+        "ESC" => 0xff1b,
+        "ENTER" => 0xff0d,
+        "BACKSPACE" => 0xff08,
+        "TAB" => 0xff09,
+        "SPACE" => 0x20,
+        "MINUS" => 0x2d,
+        "EQUAL" => 0x3d,
+        "LEFTBRACE" => 0x5b,
+        "RIGHTBRACE" => 0x5d,
+        "BACKSLASH" => 0x5c,
+        "SEMICOLON" => 0x3b,
+        "APOSTROPHE" => 0x27,
+        "GRAVE" => 0x60,
+        "COMMA" => 0x2c,
+        "DOT" => 0x2e,
+        "SLASH" => 0x2f,
+        "ASTERISK" => 0x2a,
+        "CAPSLOCK" => 0xffe5,
+        "SHIFT" | "LEFTSHIFT" => KEYSYM_SHIFT_L,
+        "RIGHTSHIFT" => 0xffe2,
+        "CTRL" | "LEFTCTRL" => 0xffe3,
+        "RIGHTCTRL" => 0xffe4,
+        "ALT" | "LEFTALT" => 0xffe9,
+        "RIGHTALT" => 0xffea,
+        "LEFTMETA" => 0xffeb,
+        "RIGHTMETA" => 0xffec,
+        "COMPOSE" => 0xff67,
+        "HOME" => 0xff50,
+        "LEFT" => 0xff51,
+        "UP" => 0xff52,
+        "RIGHT" => 0xff53,
+        "DOWN" => 0xff54,
+        "PAGEUP" => 0xff55,
+        "PAGEDOWN" => 0xff56,
+        "END" => 0xff57,
+        "INSERT" => 0xff63,
+        "DELETE" => 0xffff,
+        "HELP" => 0xff6a,
+        "PAUSE" => 0xff13,
+        "SCROLLLOCK" => 0xff14,
+        "SYSRQ" | "SYSREQ" => 0xff15,
+        "NUMLOCK" => 0xff7f,
+        "KPDOT" => 0xffae,
+        "KPENTER" => 0xff8d,
+        "KPMINUS" => 0xffad,
+        "KPPLUS" => 0xffab,
+        "KPSLASH" => 0xffaf,
+        "KPASTERISK" => 0xffaa,
+        "KATAKANAHIRAGANA" => 0xff27,
+        "VOLUMEDOWN" => 0x1008ff11,
+        "MUTE" => 0x1008ff12,
+        "VOLUMEUP" => 0x1008ff13,
+        "MEDIAPLAY" | "PLAYPAUSE" => 0x1008ff14,
+        "MEDIASTOP" | "STOPCD" => 0x1008ff15,
+        "MEDIAPREVIOUS" | "PREVIOUSSONG" => 0x1008ff16,
+        "MEDIANEXT" | "NEXTSONG" => 0x1008ff17,
+        "BACK" => 0x1008ff26,
+        "FORWARD" => 0x1008ff27,
+        "STOP" => 0x1008ff28,
+        "REFRESH" => 0x1008ff29,
+        _ => return None,
+    };
+    Some(keysym)
+}
+
+/// Returns the X11 keysym that types the given character
+pub fn char_to_keysym(c: char) -> u32 {
+    match c {
+        '\n' | '\r' => 0xff0d,
+        '\t' => 0xff09,
+        '\u{8}' => 0xff08,
+        // Latin-1 characters have the same keysym as their code point
+        ' '..='~' | '\u{a0}'..='\u{ff}' => c as u32,
+        // Other characters use the Unicode keysym range
+        _ => 0x0100_0000 + c as u32,
+    }
+}
+
+/// Keyboard mapping of an X11 display
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Keymap {
+    /// Keycode of the first entry in `keysyms`
+    pub min_keycode: u8,
+    /// Number of keysyms for each keycode
+    pub keysyms_per_keycode: u8,
+    /// Keysyms of all keycodes, `keysyms_per_keycode` at a time
+    pub keysyms: Vec<u32>,
+}
+
+impl Keymap {
+    /// Returns the keycode that produces the given keysym and whether shift
+    /// has to be held for it. Keycodes that produce the keysym without shift
+    /// are preferred.
+    pub fn find(&self, keysym: u32) -> Option<(u8, bool)> {
+        let per_keycode = self.keysyms_per_keycode as usize;
+        if per_keycode == 0 || keysym == NO_SYMBOL {
+            return None;
+        }
+        let mut shifted = None;
+        for (i, keysyms) in self.keysyms.chunks(per_keycode).enumerate() {
+            let keycode = self.min_keycode as usize + i;
+            let Ok(keycode) = u8::try_from(keycode) else {
+                break;
+            };
+            match keysyms.iter().position(|sym| *sym == keysym) {
+                Some(0) => return Some((keycode, false)),
+                Some(1) if shifted.is_none() => shifted = Some((keycode, true)),
+                _ => (),
+            }
+        }
+        shifted
+    }
+
+    /// Returns a keycode that doesn't produce any keysym and can be remapped,
+    /// searching from the highest keycode down
+    pub fn spare_keycode(&self) -> Option<u8> {
+        let per_keycode = self.keysyms_per_keycode as usize;
+        if per_keycode == 0 {
+            return None;
+        }
+        self.keysyms
+            .chunks(per_keycode)
+            .enumerate()
+            .rev()
+            .filter(|(_, keysyms)| keysyms.iter().all(|sym| *sym == NO_SYMBOL))
+            .find_map(|(i, _)| u8::try_from(self.min_keycode as usize + i).ok())
+    }
+
+    /// Map the given keycode to produce the given keysym with and without shift
+    pub fn set(&mut self, keycode: u8, keysym: u32) {
+        let per_keycode = self.keysyms_per_keycode as usize;
+        if per_keycode == 0 {
+            return;
+        }
+        let Some(index) = (keycode as usize).checked_sub(self.min_keycode as usize) else {
+            return;
+        };
+        let Some(keysyms) = self.keysyms.chunks_mut(per_keycode).nth(index) else {
+            return;
+        };
+        keysyms.fill(NO_SYMBOL);
+        for sym in keysyms.iter_mut().take(2) {
+            *sym = keysym;
+        }
+    }
+}
@@ -5,29 +5,28 @@ pub mod keyboard_device;
 pub mod mouse_device;
 
 use dbus_device::DBusDevice;
-use futures_util::stream::StreamExt;
 use std::collections::HashMap;
-use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
-use std::time::Duration;
 
 use composite_device::CompositeDevice;
 use godot::prelude::*;
+use once_cell::sync::Lazy;
 
 use godot::classes::{Engine, Resource};
-use zbus::fdo::ObjectManagerProxy;
 use zbus::names::BusName;
 
 use crate::dbus::inputplumber::input_manager::InputManagerProxyBlocking;
-use crate::dbus::RunError;
-use crate::{get_dbus_system, get_dbus_system_blocking, RUNTIME};
+use crate::dbus::object_watcher::{ObjectEvent, ObjectKind, ObjectWatcher};
+use crate::dbus::property_cache::PropertyCache;
+use crate::dbus::{impl_last_error, GodotVariant, LastError};
+use crate::{get_dbus_system_blocking, resource::dispatcher::Wakeup, DBusBus};
 
 const INPUT_PLUMBER_BUS: &str = "org.shadowblip.InputPlumber";
 const INPUT_PLUMBER_PATH: &str = "/org/shadowblip/InputPlumber";
+const INPUT_PLUMBER_MANAGER_PATH: &str = "/org/shadowblip/InputPlumber/Manager";
 
 /// Supported InputPlumber DBus objects
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum ObjectType {
-    Unknown,
     CompositeDevice,
     SourceEventDevice,
     SourceHidRawDevice,
@@ -38,44 +37,25 @@ enum ObjectType {
     TargetMouseDevice,
 }
 
-impl ObjectType {
-    fn from_dbus_path(path: &str) -> Self {
-        if path.contains("CompositeDevice") {
-            return Self::CompositeDevice;
+impl ObjectKind for ObjectType {
+    fn from_interface(iface: &str) -> Option<Self> {
+        match iface {
+            "org.shadowblip.Input.CompositeDevice" => Some(Self::CompositeDevice),
+            "org.shadowblip.Input.Source.EventDevice" => Some(Self::SourceEventDevice),
+            "org.shadowblip.Input.Source.HIDRawDevice" => Some(Self::SourceHidRawDevice),
+            "org.shadowblip.Input.Source.IIOIMUDevice" => Some(Self::SourceIioDevice),
+            "org.shadowblip.Input.DBusDevice" => Some(Self::TargetDBusDevice),
+            "org.shadowblip.Input.Gamepad" => Some(Self::TargetGamepadDevice),
+            "org.shadowblip.Input.Keyboard" => Some(Self::TargetKeyboardDevice),
+            "org.shadowblip.Input.Mouse" => Some(Self::TargetMouseDevice),
+            _ => None,
         }
-        if path.contains("dbus") {
-            return Self::TargetDBusDevice;
-        }
-        if path.contains("target") && path.contains("mouse") {
-            return Self::TargetMouseDevice;
-        }
-        if path.contains("target") && path.contains("keyboard") {
-            return Self::TargetKeyboardDevice;
-        }
-        if path.contains("target") && path.contains("gamepad") {
-            return Self::TargetGamepadDevice;
-        }
-        if path.contains("source") && path.contains("event") {
-            return Self::SourceEventDevice;
-        }
-        if path.contains("source") && path.contains("hidraw") {
-            return Self::SourceHidRawDevice;
-        }
-        if path.contains("source") && path.contains("iio") {
-            return Self::SourceIioDevice;
-        }
-        Self::Unknown
     }
 }
 
-/// Signals that can be emitted
-#[derive(Debug)]
-enum Signal {
-    Started,
-    Stopped,
-    ObjectAdded { path: String, kind: ObjectType },
-    ObjectRemoved { path: String, kind: ObjectType },
-}
+/// Raised when the InputPlumber manager or any of its objects has events waiting to
+/// be dispatched by [InputPlumberInstance::process]
+static WAKEUP: Lazy<Wakeup> = Lazy::new(Wakeup::default);
 
 /// Instance representing a client connection to InputPlumber over DBus. This
 /// is represented as a resource so it can be accessed from anywhere in the scene
@@ -85,8 +65,10 @@ enum Signal {
 #[class(base=Resource)]
 pub struct InputPlumberInstance {
     base: Base<Resource>,
-    rx: Receiver<Signal>,
+    objects: ObjectWatcher<ObjectType>,
     conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<InputManagerProxyBlocking<'static>>,
+    last_error: LastError,
     /// Map of DBus path to composite device resource. E.g.
     /// {"/org/shadowblip/InputPlumber/CompositeDevice0": <CompositeDevice>}
     composite_devices: HashMap<String, Gd<CompositeDevice>>,
@@ -108,6 +90,8 @@ pub struct InputPlumberInstance {
     manage_all_devices: bool,
 }
 
+impl_last_error!(InputPlumberInstance);
+
 #[godot_api]
 impl InputPlumberInstance {
     #[constant]
@@ -133,13 +117,20 @@ impl InputPlumberInstance {
     #[signal]
     fn composite_device_removed(dbus_path: GString);
 
-    /// Return a proxy instance to the input manager
+    /// Emitted when a property of the input manager changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
+    /// Return a proxy instance to the input manager whose property getters read
+    /// from the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<InputManagerProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            InputManagerProxyBlocking::builder(conn).build().ok()
-        } else {
-            None
-        }
+        self.cache.read_proxy()
+    }
+
+    /// Return a proxy instance to call methods on the input manager
+    fn get_call_proxy(&self) -> Option<InputManagerProxyBlocking> {
+        let result = self.cache.call_proxy();
+        self.last_error.check(self, result.map(Some))
     }
 
     /// Returns true if the InputPlumber service is currently running
@@ -167,22 +158,12 @@ impl InputPlumberInstance {
 
     /// Return all current composite devices
     #[func]
-    fn get_composite_devices(&mut self) -> Array<Gd<CompositeDevice>> {
+    fn get_composite_devices(&self) -> Array<Gd<CompositeDevice>> {
         let mut devices = array![];
-        let objects = match self.get_managed_objects() {
-            Ok(paths) => paths,
-            Err(e) => {
-                log::error!("Failed to get managed objects: {e:?}");
-                return devices;
+        for path in self.objects.paths(ObjectType::CompositeDevice) {
+            if let Some(device) = self.composite_devices.get(&path) {
+                devices.push(device);
             }
-        };
-
-        for path in objects {
-            if !path.contains("CompositeDevice") {
-                continue;
-            }
-            let device = CompositeDevice::new(path.as_str());
-            devices.push(&device);
         }
 
         devices
@@ -199,69 +180,41 @@ impl InputPlumberInstance {
 
     /// Return all current dbus devices
     #[func]
-    fn get_dbus_devices(&mut self) -> Array<Gd<DBusDevice>> {
+    fn get_dbus_devices(&self) -> Array<Gd<DBusDevice>> {
         let mut devices = array![];
-        let objects = match self.get_managed_objects() {
-            Ok(paths) => paths,
-            Err(e) => {
-                log::error!("Failed to get managed objects: {e:?}");
-                return devices;
+        for path in self.objects.paths(ObjectType::TargetDBusDevice) {
+            if let Some(device) = self.dbus_devices.get(&path) {
+                devices.push(device);
             }
-        };
-
-        for path in objects {
-            if !path.contains("target/dbus") {
-                continue;
-            }
-            let device = DBusDevice::new(path.as_str());
-            devices.push(&device);
         }
 
         devices
     }
 
-    /// Get managed objects
-    fn get_managed_objects(&self) -> Result<Vec<String>, zbus::fdo::Error> {
-        let Some(conn) = self.conn.as_ref() else {
-            return Err(zbus::fdo::Error::Disconnected(
-                "No DBus connection found".into(),
-            ));
-        };
+    /// Process InputPlumber signals and emit them as Godot signals. This method is
+    /// called by the [ResourceProcessor] on frames where any of the objects of
+    /// this manager sent events, once the manager is registered with
+    /// [method ResourceRegistry.register_on_event].
+    #[func]
+    fn process(&mut self, _delta: f64) {
+        // Report this manager to the registry whenever its objects send events
+        WAKEUP.set_owner(self.base().instance_id().to_i64());
 
-        let bus = BusName::from_static_str(INPUT_PLUMBER_BUS).unwrap();
-        let object_manager = zbus::blocking::fdo::ObjectManagerProxy::builder(conn)
-            .destination(bus)
-            .ok()
-            .and_then(|builder| builder.path(INPUT_PLUMBER_PATH).ok())
-            .and_then(|builder| builder.build().ok());
-        let Some(object_manager) = object_manager else {
-            return Ok(Vec::new());
-        };
+        // Skip frames where none of the objects of this manager sent any events
+        if !WAKEUP.take() {
+            return;
+        }
 
-        Ok(object_manager
-            .get_managed_objects()?
-            .keys()
-            .map(|v| v.to_string())
-            .collect())
-    }
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
 
-    /// Process InputPlumber signals and emit them as Godot signals. This method
-    /// should be called every frame in the "_process" loop of a node.
-    #[func]
-    fn process(&mut self) {
-        // Drain all messages from the channel to process them
-        loop {
-            let signal = match self.rx.try_recv() {
-                Ok(value) => value,
-                Err(e) => match e {
-                    TryRecvError::Empty => break,
-                    TryRecvError::Disconnected => {
-                        log::error!("Backend thread is not running!");
-                        return;
-                    }
-                },
-            };
-            self.process_signal(signal);
+        // Dispatch any objects that were added or removed
+        for event in self.objects.take_events() {
+            self.process_event(event);
         }
 
         // Process signals from tracked devices
@@ -279,16 +232,17 @@ impl InputPlumberInstance {
         let Some(proxy) = self.get_proxy() else {
             return false;
         };
-        proxy.manage_all_devices().unwrap_or_default()
+        self.last_error.check(self, proxy.manage_all_devices())
     }
 
     /// Sets whether or not InputPlumber should automatically manage all supported devices
     #[func]
     fn set_manage_all_devices(&self, value: bool) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return;
         };
-        proxy.set_manage_all_devices(value).unwrap_or_default()
+        self.last_error
+            .check(self, proxy.set_manage_all_devices(value))
     }
 
     /// Gets the current intercept mode for all composite devices
@@ -346,22 +300,21 @@ impl InputPlumberInstance {
         }
     }
 
-    /// Process and dispatch the given signal
-    fn process_signal(&mut self, signal: Signal) {
-        match signal {
-            Signal::Started => {
+    /// Process and dispatch the given object event
+    fn process_event(&mut self, event: ObjectEvent<ObjectType>) {
+        match event {
+            ObjectEvent::Started => {
+                // The service or the bus may have restarted, so reconnect
+                self.conn = get_dbus_system_blocking().ok();
                 self.base_mut().emit_signal("started", &[]);
             }
-            Signal::Stopped => {
-                // Clear all known devices
-                self.composite_devices.clear();
-                self.dbus_devices.clear();
+            ObjectEvent::Stopped => {
                 self.base_mut().emit_signal("stopped", &[]);
             }
-            Signal::ObjectAdded { path, kind } => {
+            ObjectEvent::Added { path, kind } => {
                 self.on_object_added(path, kind);
             }
-            Signal::ObjectRemoved { path, kind } => {
+            ObjectEvent::Removed { path, kind } => {
                 self.on_object_removed(path, kind);
             }
         }
@@ -370,7 +323,6 @@ impl InputPlumberInstance {
     /// Track the given object and emit signals
     fn on_object_added(&mut self, path: String, kind: ObjectType) {
         match kind {
-            ObjectType::Unknown => (),
             ObjectType::CompositeDevice => {
                 log::debug!("CompositeDevice added: {path}");
                 let device = CompositeDevice::new(path.as_str());
@@ -395,7 +347,6 @@ impl InputPlumberInstance {
     /// Remove the given object and emit signals
     fn on_object_removed(&mut self, path: String, kind: ObjectType) {
         match kind {
-            ObjectType::Unknown => (),
             ObjectType::CompositeDevice => {
                 log::debug!("CompositeDevice device removed: {path}");
                 self.composite_devices.remove(&path);
@@ -424,17 +375,18 @@ impl IResource for InputPlumberInstance {
     fn init(base: Base<Self::Base>) -> Self {
         log::debug!("Initializing InputPlumber instance");
 
-        // Create a channel to communicate with the service
-        let (tx, rx) = channel();
         let conn = get_dbus_system_blocking().ok();
+        let cache = PropertyCache::new(INPUT_PLUMBER_MANAGER_PATH, &WAKEUP);
 
         // Don't run in the editor
         let engine = Engine::singleton();
         if engine.is_editor_hint() {
             return Self {
                 base,
-                rx,
+                objects: Default::default(),
                 conn,
+                cache,
+                last_error: Default::default(),
                 composite_devices: Default::default(),
                 dbus_devices: Default::default(),
                 intercept_mode: Default::default(),
@@ -444,18 +396,21 @@ impl IResource for InputPlumberInstance {
             };
         }
 
-        // Spawn a task using the shared tokio runtime to listen for signals
-        RUNTIME.spawn(async move {
-            if let Err(e) = run(tx).await {
-                log::error!("Failed to run InputPlumber task: ${e:?}");
-            }
-        });
+        // Watch for composite and target devices
+        let objects = ObjectWatcher::new(
+            DBusBus::System,
+            INPUT_PLUMBER_BUS,
+            INPUT_PLUMBER_PATH,
+            &WAKEUP,
+        );
 
         // Create a new InputPlumber instance
         let mut instance = Self {
             base,
-            rx,
+            objects,
             conn,
+            cache,
+            last_error: Default::default(),
             composite_devices: HashMap::new(),
             dbus_devices: HashMap::new(),
             intercept_mode: 0,
@@ -464,115 +419,25 @@ impl IResource for InputPlumberInstance {
             manage_all_devices: Default::default(),
         };
 
-        // Do initial device discovery
-        let devices = instance.get_composite_devices();
-        for device in devices.iter_shared() {
-            let path = device.bind().get_dbus_path();
-            instance.composite_devices.insert(path.into(), device);
-        }
-        let dbus_devices = instance.get_dbus_devices();
-        for dbus_device in dbus_devices.iter_shared() {
-            let path = dbus_device.bind().get_dbus_path();
-            instance
-                .dbus_devices
-                .insert(path.into(), dbus_device.clone());
-        }
-        instance
-    }
-}
-
-/// Runs InputPlumber tasks in Tokio to listen for DBus signals and send them
-/// over the given channel so they can be processed during each engine frame.
-async fn run(tx: Sender<Signal>) -> Result<(), RunError> {
-    log::debug!("Spawning inputplumber");
-    // Establish a connection to the system bus
-    let conn = get_dbus_system().await?;
-
-    // Spawn a task to listen for InputPlumber start/stop
-    let dbus_conn = conn.clone();
-    let signals_tx = tx.clone();
-    RUNTIME.spawn(async move {
-        let bus = BusName::from_static_str(INPUT_PLUMBER_BUS).unwrap();
-        let mut is_running = {
-            let dbus = zbus::fdo::DBusProxy::new(&dbus_conn).await.ok();
-            let Some(dbus) = dbus else {
-                return;
-            };
-            dbus.name_has_owner(bus.clone()).await.unwrap_or_default()
-        };
-
-        loop {
-            let dbus = zbus::fdo::DBusProxy::new(&dbus_conn).await.ok();
-            let Some(dbus) = dbus else {
-                break;
-            };
-            let running = dbus.name_has_owner(bus.clone()).await.unwrap_or_default();
-            if running != is_running {
-                let signal = if running {
-                    Signal::Started
-                } else {
-                    Signal::Stopped
-                };
-                if signals_tx.send(signal).is_err() {
-                    break;
+        // Track the devices that were discovered
+        let objects: Vec<(String, ObjectType)> = instance
+            .objects
+            .objects()
+            .map(|(path, kind)| (path.to_string(), kind))
+            .collect();
+        for (path, kind) in objects {
+            match kind {
+                ObjectType::CompositeDevice => {
+                    let device = CompositeDevice::new(path.as_str());
+                    instance.composite_devices.insert(path, device);
                 }
-            }
-            is_running = running;
-            tokio::time::sleep(Duration::from_secs(5)).await;
-        }
-    });
-
-    // Get a proxy instance to ObjectManager
-    let bus = BusName::from_static_str(INPUT_PLUMBER_BUS).unwrap();
-    let object_manager: ObjectManagerProxy = ObjectManagerProxy::builder(&conn)
-        .destination(bus)?
-        .path(INPUT_PLUMBER_PATH)?
-        .build()
-        .await?;
-
-    // Spawn a task to listen for objects added
-    let mut ifaces_added = object_manager.receive_interfaces_added().await?;
-    let signals_tx = tx.clone();
-    RUNTIME.spawn(async move {
-        while let Some(signal) = ifaces_added.next().await {
-            let args = match signal.args() {
-                Ok(args) => args,
-                Err(e) => {
-                    log::warn!("Failed to get signal args: ${e:?}");
-                    continue;
-                }
-            };
-
-            let path = args.object_path.to_string();
-            let kind = ObjectType::from_dbus_path(path.as_str());
-            let signal = Signal::ObjectAdded { path, kind };
-            if signals_tx.send(signal).is_err() {
-                break;
-            }
-        }
-    });
-
-    // Spawn a task to listen for objects removed
-    let mut ifaces_removed = object_manager.receive_interfaces_removed().await?;
-    let signals_tx = tx.clone();
-    RUNTIME.spawn(async move {
-        while let Some(signal) = ifaces_removed.next().await {
-            let args = match signal.args() {
-                Ok(args) => args,
-                Err(e) => {
-                    log::warn!("Failed to get signal args: ${e:?}");
-                    continue;
+                ObjectType::TargetDBusDevice => {
+                    let device = DBusDevice::new(path.as_str());
+                    instance.dbus_devices.insert(path, device);
                 }
-            };
-
-            let path = args.object_path.to_string();
-            let kind = ObjectType::from_dbus_path(path.as_str());
-            let signal = Signal::ObjectRemoved { path, kind };
-            if signals_tx.send(signal).is_err() {
-                break;
+                _ => (),
             }
         }
-    });
-
-    Ok(())
+        instance
+    }
 }
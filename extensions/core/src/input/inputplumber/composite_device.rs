@@ -3,20 +3,21 @@ use godot::prelude::*;
 use godot::classes::{ProjectSettings, Resource, ResourceLoader};
 
 use crate::dbus::inputplumber::composite_device::CompositeDeviceProxyBlocking;
-use crate::dbus::DBusVariant;
-use crate::get_dbus_system_blocking;
+use crate::dbus::property_cache::PropertyCache;
+use crate::dbus::{impl_last_error, DBusVariant, GodotVariant, LastError};
 
 use super::dbus_device::DBusDevice;
 use super::keyboard_device::KeyboardDevice;
 use super::mouse_device::MouseDevice;
-use super::INPUT_PLUMBER_BUS;
+use super::{INPUT_PLUMBER_BUS, WAKEUP};
 
 #[derive(GodotClass)]
 #[class(no_init, base=Resource)]
 pub struct CompositeDevice {
     base: Base<Resource>,
 
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<CompositeDeviceProxyBlocking<'static>>,
+    last_error: LastError,
     path: String,
 
     /// The DBus path of the [CompositeDevice]
@@ -53,17 +54,23 @@ pub struct CompositeDevice {
     source_device_paths: PackedStringArray,
 }
 
+impl_last_error!(CompositeDevice);
+
 #[godot_api]
 impl CompositeDevice {
+    /// Emitted when a property of the composite device changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Create a new [CompositeDevice] with the given DBus path
     pub fn from_path(path: GString) -> Gd<Self> {
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
+            let cache = PropertyCache::new(path.to_string().as_str(), &WAKEUP);
 
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
-                conn,
+                cache,
+                last_error: Default::default(),
                 path: path.clone().into(), // Convert GString -> String.
                 dbus_path: path,
                 name: Default::default(),
@@ -78,16 +85,16 @@ impl CompositeDevice {
         })
     }
 
-    /// Return a proxy instance to the composite device
+    /// Return a proxy instance to the composite device whose property getters
+    /// read from the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<CompositeDeviceProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            CompositeDeviceProxyBlocking::builder(conn)
-                .path(self.path.clone())
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
+    }
+
+    /// Return a proxy instance to call methods on the composite device
+    fn get_call_proxy(&self) -> Option<CompositeDeviceProxyBlocking> {
+        let result = self.cache.call_proxy();
+        self.last_error.check(self, result.map(Some))
     }
 
     /// Get or create a [CompositeDevice] with the given DBus path. If an instance
@@ -121,7 +128,7 @@ impl CompositeDevice {
         let Some(proxy) = self.get_proxy() else {
             return "".into();
         };
-        proxy.name().ok().unwrap_or_default().into()
+        self.last_error.check(self, proxy.name()).into()
     }
 
     #[func]
@@ -129,7 +136,7 @@ impl CompositeDevice {
         let Some(proxy) = self.get_proxy() else {
             return "".into();
         };
-        proxy.profile_name().ok().unwrap_or_default().into()
+        self.last_error.check(self, proxy.profile_name()).into()
     }
 
     /// Get the intercept mode of the composite device
@@ -138,13 +145,13 @@ impl CompositeDevice {
         let Some(proxy) = self.get_proxy() else {
             return -1;
         };
-        proxy.intercept_mode().ok().unwrap_or_default() as i32
+        self.last_error.check(self, proxy.intercept_mode()) as i32
     }
 
     /// Set the intercept mode of the composite device
     #[func]
     pub fn set_intercept_mode(&self, mode: i32) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return;
         };
         let mode = mode as u32;
@@ -157,10 +164,9 @@ impl CompositeDevice {
         let Some(proxy) = self.get_proxy() else {
             return PackedStringArray::new();
         };
-        let caps: Vec<GString> = proxy
-            .capabilities()
-            .ok()
-            .unwrap_or_default()
+        let caps: Vec<GString> = self
+            .last_error
+            .check(self, proxy.capabilities())
             .into_iter()
             .map(GString::from)
             .collect();
@@ -173,10 +179,9 @@ impl CompositeDevice {
         let Some(proxy) = self.get_proxy() else {
             return PackedStringArray::new();
         };
-        let caps: Vec<GString> = proxy
-            .target_capabilities()
-            .ok()
-            .unwrap_or_default()
+        let caps: Vec<GString> = self
+            .last_error
+            .check(self, proxy.target_capabilities())
             .into_iter()
             .map(GString::from)
             .collect();
@@ -200,10 +205,9 @@ impl CompositeDevice {
         let Some(proxy) = self.get_proxy() else {
             return PackedStringArray::new();
         };
-        let values: Vec<GString> = proxy
-            .dbus_devices()
-            .ok()
-            .unwrap_or_default()
+        let values: Vec<GString> = self
+            .last_error
+            .check(self, proxy.dbus_devices())
             .into_iter()
             .map(GString::from)
             .collect();
@@ -216,10 +220,9 @@ impl CompositeDevice {
         let Some(proxy) = self.get_proxy() else {
             return PackedStringArray::new();
         };
-        let values: Vec<GString> = proxy
-            .source_device_paths()
-            .ok()
-            .unwrap_or_default()
+        let values: Vec<GString> = self
+            .last_error
+            .check(self, proxy.source_device_paths())
             .into_iter()
             .map(GString::from)
             .collect();
@@ -232,7 +235,7 @@ impl CompositeDevice {
         let Some(proxy) = self.get_proxy() else {
             return array![];
         };
-        let values = proxy.target_devices().ok().unwrap_or_default();
+        let values = self.last_error.check(self, proxy.target_devices());
         let mut target_devices = array![];
 
         // Build the Godot object based on the path
@@ -264,7 +267,7 @@ impl CompositeDevice {
     /// set the target device types for the composite device (e.g. "keyboard", "mouse", etc.)
     #[func]
     pub fn set_target_devices(&self, devices: PackedStringArray) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return;
         };
         let device_types: Vec<String> = devices.to_vec().into_iter().map(|v| v.into()).collect();
@@ -281,7 +284,7 @@ impl CompositeDevice {
     /// Load the device profile from the given path
     #[func]
     pub fn load_profile_path(&self, path: GString) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return;
         };
         let path = String::from(path);
@@ -298,7 +301,7 @@ impl CompositeDevice {
     /// logic.
     #[func]
     pub fn send_event(&self, action: GString, value: Variant) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return;
         };
         let Some(value) = value.as_zvariant() else {
@@ -311,7 +314,7 @@ impl CompositeDevice {
     /// Write the given set of events as a button chord
     #[func]
     pub fn send_button_chord(&self, actions: PackedStringArray) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return;
         };
         let values: Vec<String> = actions.to_vec().into_iter().map(|v| v.into()).collect();
@@ -335,5 +338,12 @@ impl CompositeDevice {
     }
 
     /// Dispatches signals
-    pub fn process(&mut self) {}
+    pub fn process(&mut self) {
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+    }
 }
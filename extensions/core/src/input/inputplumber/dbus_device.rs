@@ -1,14 +1,16 @@
-use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
-
 use futures_util::StreamExt;
 use godot::prelude::*;
 
 use godot::classes::{Resource, ResourceLoader};
 
 use crate::dbus::inputplumber::dbus_device::DBusDeviceProxy;
-use crate::{get_dbus_system, RUNTIME};
+use crate::{
+    get_dbus_system,
+    resource::dispatcher::{channel, Receiver, Sender, TryRecvError},
+    RUNTIME,
+};
 
-use super::{RunError, INPUT_PLUMBER_BUS};
+use super::{RunError, INPUT_PLUMBER_BUS, WAKEUP};
 
 /// Signals that can be emitted
 #[derive(Debug)]
@@ -58,7 +60,7 @@ impl DBusDevice {
     pub fn from_path(path: GString) -> Gd<Self> {
         // Create a channel to communicate with the signals task
         log::debug!("DBusDevice created with path: {path}");
-        let (tx, rx) = channel();
+        let (tx, rx) = channel(&WAKEUP);
         let dbus_path = path.clone().into();
 
         // Spawn a task using the shared tokio runtime to listen for signals
@@ -1,15 +1,18 @@
 use godot::{classes::ResourceLoader, prelude::*};
 
-use crate::{dbus::inputplumber::event_device::EventDeviceProxyBlocking, get_dbus_system_blocking};
+use crate::dbus::inputplumber::event_device::EventDeviceProxyBlocking;
+use crate::dbus::property_cache::PropertyCache;
+use crate::dbus::{impl_last_error, LastError};
 
-use super::INPUT_PLUMBER_BUS;
+use super::{INPUT_PLUMBER_BUS, WAKEUP};
 
 #[derive(GodotClass)]
 #[class(no_init, base=Resource)]
 pub struct EventDevice {
     base: Base<Resource>,
     path: String,
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<EventDeviceProxyBlocking<'static>>,
+    last_error: LastError,
 
     #[allow(dead_code)]
     #[var(get = get_dbus_path)]
@@ -31,18 +34,20 @@ pub struct EventDevice {
     unique_id: GString,
 }
 
+impl_last_error!(EventDevice);
+
 #[godot_api]
 impl EventDevice {
     /// Create a new [EventDevice] with the given DBus path
     fn from_path(path: GString) -> Gd<Self> {
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
+            let cache = PropertyCache::new(path.to_string().as_str(), &WAKEUP);
 
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
-                conn,
+                cache,
+                last_error: Default::default(),
                 path: path.clone().into(),
                 dbus_path: path,
                 name: Default::default(),
@@ -54,16 +59,10 @@ impl EventDevice {
         })
     }
 
-    /// Return a proxy instance to the composite device
+    /// Return a proxy instance to the composite device whose property getters
+    /// read from the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<EventDeviceProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            EventDeviceProxyBlocking::builder(conn)
-                .path(self.path.clone())
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
     }
 
     /// Get or create a [DBusDevice] with the given DBus path. If an instance
@@ -102,7 +101,7 @@ impl EventDevice {
         let Some(proxy) = self.get_proxy() else {
             return "".into();
         };
-        proxy.name().unwrap_or_default().into()
+        self.last_error.check(self, proxy.name()).into()
     }
 
     #[func]
@@ -110,7 +109,7 @@ impl EventDevice {
         let Some(proxy) = self.get_proxy() else {
             return "".into();
         };
-        proxy.device_path().unwrap_or_default().into()
+        self.last_error.check(self, proxy.device_path()).into()
     }
 
     #[func]
@@ -118,7 +117,7 @@ impl EventDevice {
         let Some(proxy) = self.get_proxy() else {
             return "".into();
         };
-        proxy.phys_path().unwrap_or_default().into()
+        self.last_error.check(self, proxy.phys_path()).into()
     }
 
     #[func]
@@ -126,7 +125,7 @@ impl EventDevice {
         let Some(proxy) = self.get_proxy() else {
             return "".into();
         };
-        proxy.sysfs_path().unwrap_or_default().into()
+        self.last_error.check(self, proxy.sysfs_path()).into()
     }
 
     #[func]
@@ -134,6 +133,6 @@ impl EventDevice {
         let Some(proxy) = self.get_proxy() else {
             return "".into();
         };
-        proxy.unique_id().unwrap_or_default().into()
+        self.last_error.check(self, proxy.unique_id()).into()
     }
 }
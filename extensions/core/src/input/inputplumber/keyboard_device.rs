@@ -1,15 +1,18 @@
 use godot::{classes::ResourceLoader, prelude::*};
 
-use crate::{dbus::inputplumber::keyboard::KeyboardProxyBlocking, get_dbus_system_blocking};
+use crate::dbus::inputplumber::keyboard::KeyboardProxyBlocking;
+use crate::dbus::property_cache::PropertyCache;
+use crate::dbus::{impl_last_error, LastError};
 
-use super::INPUT_PLUMBER_BUS;
+use super::{INPUT_PLUMBER_BUS, WAKEUP};
 
 #[derive(GodotClass)]
 #[class(no_init, base=Resource)]
 pub struct KeyboardDevice {
     base: Base<Resource>,
     path: String,
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<KeyboardProxyBlocking<'static>>,
+    last_error: LastError,
 
     #[allow(dead_code)]
     #[var(get = get_dbus_path)]
@@ -19,18 +22,20 @@ pub struct KeyboardDevice {
     name: GString,
 }
 
+impl_last_error!(KeyboardDevice);
+
 #[godot_api]
 impl KeyboardDevice {
     /// Create a new [KeyboardDevice] with the given DBus path
     fn from_path(path: GString) -> Gd<Self> {
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
+            let cache = PropertyCache::new(path.to_string().as_str(), &WAKEUP);
 
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
-                conn,
+                cache,
+                last_error: Default::default(),
                 path: path.clone().into(),
                 dbus_path: path,
                 name: Default::default(),
@@ -38,16 +43,16 @@ impl KeyboardDevice {
         })
     }
 
-    /// Return a proxy instance to the composite device
+    /// Return a proxy instance to the composite device whose property getters
+    /// read from the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<KeyboardProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            KeyboardProxyBlocking::builder(conn)
-                .path(self.path.clone())
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
+    }
+
+    /// Return a proxy instance to call methods on the composite device
+    fn get_call_proxy(&self) -> Option<KeyboardProxyBlocking> {
+        let result = self.cache.call_proxy();
+        self.last_error.check(self, result.map(Some))
     }
 
     /// Get or create a [KeyboardDevice] with the given DBus path. If an instance
@@ -86,12 +91,12 @@ impl KeyboardDevice {
         let Some(proxy) = self.get_proxy() else {
             return "".into();
         };
-        proxy.name().unwrap_or_default().into()
+        self.last_error.check(self, proxy.name()).into()
     }
 
     #[func]
     pub fn send_key(&self, key: GString, value: bool) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return;
         };
         let key_code: String = key.into();
@@ -1,15 +1,18 @@
 use godot::{classes::ResourceLoader, prelude::*};
 
-use crate::{dbus::inputplumber::mouse::MouseProxyBlocking, get_dbus_system_blocking};
+use crate::dbus::inputplumber::mouse::MouseProxyBlocking;
+use crate::dbus::property_cache::PropertyCache;
+use crate::dbus::{impl_last_error, LastError};
 
-use super::INPUT_PLUMBER_BUS;
+use super::{INPUT_PLUMBER_BUS, WAKEUP};
 
 #[derive(GodotClass)]
 #[class(no_init, base=Resource)]
 pub struct MouseDevice {
     base: Base<Resource>,
     path: String,
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<MouseProxyBlocking<'static>>,
+    last_error: LastError,
 
     #[allow(dead_code)]
     #[var(get = get_dbus_path)]
@@ -19,18 +22,20 @@ pub struct MouseDevice {
     name: GString,
 }
 
+impl_last_error!(MouseDevice);
+
 #[godot_api]
 impl MouseDevice {
     /// Create a new [MouseDevice] with the given DBus path
     fn from_path(path: GString) -> Gd<Self> {
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
+            let cache = PropertyCache::new(path.to_string().as_str(), &WAKEUP);
 
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
-                conn,
+                cache,
+                last_error: Default::default(),
                 path: path.clone().into(),
                 dbus_path: path,
                 name: Default::default(),
@@ -38,16 +43,16 @@ impl MouseDevice {
         })
     }
 
-    /// Return a proxy instance to the composite device
+    /// Return a proxy instance to the composite device whose property getters
+    /// read from the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<MouseProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            MouseProxyBlocking::builder(conn)
-                .path(self.path.clone())
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
+    }
+
+    /// Return a proxy instance to call methods on the composite device
+    fn get_call_proxy(&self) -> Option<MouseProxyBlocking> {
+        let result = self.cache.call_proxy();
+        self.last_error.check(self, result.map(Some))
     }
 
     /// Get or create a [KeyboardDevice] with the given DBus path. If an instance
@@ -86,12 +91,12 @@ impl MouseDevice {
         let Some(proxy) = self.get_proxy() else {
             return "".into();
         };
-        proxy.name().unwrap_or_default().into()
+        self.last_error.check(self, proxy.name()).into()
     }
 
     #[func]
     pub fn move_cursor(&self, x: i64, y: i64) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return;
         };
         proxy.move_cursor(x as i32, y as i32).ok();
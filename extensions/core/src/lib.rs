@@ -11,7 +11,11 @@ pub mod resource;
 pub mod system;
 pub mod vdf;
 
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex as StdMutex},
+    time::Duration,
+};
 
 use godot::prelude::*;
 use once_cell::sync::Lazy;
@@ -33,10 +37,17 @@ type Channel = (Sender<()>, Arc<Mutex<Receiver<()>>>);
 /// Global tokio runtime instance
 pub static RUNTIME: Lazy<Handle> = Lazy::new(tokio_init);
 /// Shared connection to the DBus system bus
-static DBUS_SYSTEM: Lazy<Arc<Mutex<Option<Connection>>>> = Lazy::new(dbus_system_init);
+static DBUS_SYSTEM: Lazy<StdMutex<Option<Connection>>> = Lazy::new(Default::default);
 /// Shared blocking connection to the DBus system bus
-static DBUS_SYSTEM_BLOCKING: Lazy<Option<blocking::Connection>> =
-    Lazy::new(dbus_system_blocking_init);
+static DBUS_SYSTEM_BLOCKING: Lazy<StdMutex<Option<blocking::Connection>>> =
+    Lazy::new(Default::default);
+/// Shared connection to the DBus session bus
+static DBUS_SESSION: Lazy<StdMutex<Option<Connection>>> = Lazy::new(Default::default);
+/// Shared blocking connection to the DBus session bus
+static DBUS_SESSION_BLOCKING: Lazy<StdMutex<Option<blocking::Connection>>> =
+    Lazy::new(Default::default);
+/// Addresses to use instead of the default address of a DBus bus
+static DBUS_ADDRESSES: Lazy<StdMutex<HashMap<DBusBus, String>>> = Lazy::new(Default::default);
 /// Channel used to signal shutting down tokio runtime
 static CHANNEL: Lazy<Channel> = Lazy::new(get_channel);
 
@@ -57,6 +68,7 @@ unsafe impl ExtensionLibrary for OpenGamepadUICore {
             return;
         }
         log::info!("De-initializing OpenGamepadUI Core");
+        resource::async_result::AsyncResult::clear_pending();
         tokio_deinit();
     }
 }
@@ -93,32 +105,128 @@ fn get_channel() -> (Sender<()>, Arc<Mutex<Receiver<()>>>) {
     (tx, Arc::new(Mutex::new(rx)))
 }
 
-fn dbus_system_init() -> Arc<Mutex<Option<Connection>>> {
-    Arc::new(Mutex::new(None))
+/// DBus message buses that a shared connection can be established with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DBusBus {
+    /// The system-wide bus used by system services (e.g. UPower, BlueZ)
+    #[default]
+    System,
+    /// The bus of the current user session (e.g. MPRIS players, notifications)
+    Session,
 }
 
-fn dbus_system_blocking_init() -> Option<blocking::Connection> {
-    blocking::Connection::system().ok()
+impl DBusBus {
+    /// Returns the shared async connection slot for the bus
+    fn connection(&self) -> &'static StdMutex<Option<Connection>> {
+        match self {
+            DBusBus::System => &DBUS_SYSTEM,
+            DBusBus::Session => &DBUS_SESSION,
+        }
+    }
+
+    /// Returns the shared blocking connection slot for the bus
+    fn connection_blocking(&self) -> &'static StdMutex<Option<blocking::Connection>> {
+        match self {
+            DBusBus::System => &DBUS_SYSTEM_BLOCKING,
+            DBusBus::Session => &DBUS_SESSION_BLOCKING,
+        }
+    }
+
+    /// Returns the address override for the bus, if one is set
+    fn address(&self) -> Option<String> {
+        DBUS_ADDRESSES.lock().unwrap().get(self).cloned()
+    }
+}
+
+/// Override the address used to connect to the given DBus bus (e.g.
+/// "unix:path=/tmp/test-bus"), or restore the default address if `None` is
+/// given. Existing shared connections to the bus are dropped, so connections
+/// returned afterwards will use the new address. This is mainly used to point
+/// the DBus wrappers at a private bus during testing.
+pub fn set_dbus_address(bus: DBusBus, address: Option<&str>) {
+    {
+        let mut addresses = DBUS_ADDRESSES.lock().unwrap();
+        match address {
+            Some(address) => addresses.insert(bus, address.to_string()),
+            None => addresses.remove(&bus),
+        };
+    }
+    bus.connection().lock().unwrap().take();
+    bus.connection_blocking().lock().unwrap().take();
+}
+
+/// Drop the shared connections to the given bus if they were made to the same
+/// bus instance as the given lost connection, so the next call to [get_dbus] or
+/// [get_dbus_blocking] connects again. Shared connections that were already
+/// replaced after the bus came back are kept.
+pub(crate) fn reset_dbus(bus: DBusBus, lost: &Connection) {
+    let guid = lost.server_guid();
+    {
+        let mut conn = bus.connection().lock().unwrap();
+        if conn.as_ref().is_some_and(|conn| conn.server_guid() == guid) {
+            log::debug!("Dropping lost connection to {bus:?} bus");
+            conn.take();
+        }
+    }
+    let mut conn = bus.connection_blocking().lock().unwrap();
+    if conn
+        .as_ref()
+        .is_some_and(|conn| conn.inner().server_guid() == guid)
+    {
+        conn.take();
+    }
+}
+
+/// Return or create a shared connection to the given DBus bus
+pub async fn get_dbus(bus: DBusBus) -> Result<Connection, zbus::Error> {
+    if let Some(conn) = bus.connection().lock().unwrap().as_ref() {
+        return Ok(conn.clone());
+    }
+    let new_conn = match (bus.address(), bus) {
+        (Some(address), _) => {
+            zbus::connection::Builder::address(address.as_str())?
+                .build()
+                .await?
+        }
+        (None, DBusBus::System) => Connection::system().await?,
+        (None, DBusBus::Session) => Connection::session().await?,
+    };
+
+    // Another task may have connected while this one was waiting
+    let mut conn = bus.connection().lock().unwrap();
+    Ok(conn.get_or_insert(new_conn).clone())
+}
+
+/// Return or create a shared blocking connection to the given DBus bus
+pub fn get_dbus_blocking(bus: DBusBus) -> Result<blocking::Connection, zbus::Error> {
+    let mut conn = bus.connection_blocking().lock().unwrap();
+    if let Some(conn) = conn.as_ref() {
+        return Ok(conn.clone());
+    }
+    let new_conn = match (bus.address(), bus) {
+        (Some(address), _) => blocking::connection::Builder::address(address.as_str())?.build()?,
+        (None, DBusBus::System) => blocking::Connection::system()?,
+        (None, DBusBus::Session) => blocking::Connection::session()?,
+    };
+    Ok(conn.get_or_insert(new_conn).clone())
 }
 
 /// Return or create a shared connection to the DBus system bus
 pub async fn get_dbus_system() -> Result<Connection, zbus::Error> {
-    let mut conn = DBUS_SYSTEM.lock().await;
-    if conn.is_some() {
-        let conn_clone = conn.as_ref().unwrap().clone();
-        Ok(conn_clone)
-    } else {
-        let new_conn = Connection::system().await?;
-        let conn_clone = new_conn.clone();
-        *conn = Some(new_conn);
-        Ok(conn_clone)
-    }
+    get_dbus(DBusBus::System).await
 }
 
 /// Return or create a shared blocking connection to the DBus system bus
 pub fn get_dbus_system_blocking() -> Result<blocking::Connection, zbus::Error> {
-    if let Some(conn) = DBUS_SYSTEM_BLOCKING.as_ref() {
-        return Ok(conn.clone());
-    }
-    blocking::Connection::system()
+    get_dbus_blocking(DBusBus::System)
+}
+
+/// Return or create a shared connection to the DBus session bus
+pub async fn get_dbus_session() -> Result<Connection, zbus::Error> {
+    get_dbus(DBusBus::Session).await
+}
+
+/// Return or create a shared blocking connection to the DBus session bus
+pub fn get_dbus_session_blocking() -> Result<blocking::Connection, zbus::Error> {
+    get_dbus_blocking(DBusBus::Session)
 }
@@ -6,10 +6,16 @@ pub mod ip4_config;
 
 use crate::{
     dbus::{
+        impl_last_error,
         networkmanager::network_manager::{NetworkManagerProxy, NetworkManagerProxyBlocking},
-        RunError,
+        object_watcher::{ObjectEvent, ObjectKind, ObjectWatcher},
+        property_cache::PropertyCache,
+        supervisor::{watch_service, ServiceEvent},
+        GodotVariant, LastError, RunError,
     },
-    get_dbus_system, get_dbus_system_blocking, RUNTIME,
+    get_dbus_system_blocking,
+    resource::dispatcher::{channel, Receiver, Sender, TryRecvError, Wakeup},
+    DBusBus, RUNTIME,
 };
 use access_point::NetworkAccessPoint;
 use active_connection::NetworkActiveConnection;
@@ -17,23 +23,20 @@ use device::NetworkDevice;
 use device_wireless::NetworkDeviceWireless;
 use futures_util::stream::StreamExt;
 use ip4_config::NetworkIpv4Config;
-use std::{
-    collections::HashMap,
-    sync::mpsc::{channel, Receiver, Sender, TryRecvError},
-    time::Duration,
-};
-use zbus::fdo::{ManagedObjects, ObjectManagerProxy};
+use std::collections::HashMap;
+use tokio::task::JoinHandle;
 
 use godot::{classes::Engine, obj::WithBaseField, prelude::*};
-use zbus::names::BusName;
+use once_cell::sync::Lazy;
+use zbus::{names::BusName, Connection};
 
 const NETWORK_MANAGER_BUS: &str = "org.freedesktop.NetworkManager";
 const OBJECT_MANAGER_PATH: &str = "/org/freedesktop";
+const NETWORK_MANAGER_PATH: &str = "/org/freedesktop/NetworkManager";
 
 /// Supported NetworkManager DBus objects
-#[derive(Debug)]
-enum ObjectType {
-    Unknown,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjectType {
     AccessPoint,
     AgentManager,
     ConnectionActive,
@@ -50,58 +53,50 @@ enum ObjectType {
     SettingsConnection,
 }
 
-impl ObjectType {
-    /// Returns the object type(s) from the list of implemented interfaces
-    fn from_ifaces(ifaces: Vec<String>) -> Vec<Self> {
-        let types = ifaces
-            .into_iter()
-            .map(|iface| match iface.as_str() {
-                "org.freedesktop.NetworkManager.AccessPoint" => Self::AccessPoint,
-                "org.freedesktop.NetworkManager.AgentManager" => Self::AgentManager,
-                "org.freedesktop.NetworkManager.Connection.Active" => Self::ConnectionActive,
-                "org.freedesktop.NetworkManager.Device" => Self::Device,
-                "org.freedesktop.NetworkManager.Device.Bluetooth" => Self::DeviceBluetooth,
-                "org.freedesktop.NetworkManager.Device.Generic" => Self::DeviceGeneric,
-                "org.freedesktop.NetworkManager.Device.Wired" => Self::DeviceWired,
-                "org.freedesktop.NetworkManager.Device.Wireless" => Self::DeviceWireless,
-                "org.freedesktop.NetworkManager.DHCP4Config" => Self::Dhcp4Config,
-                "org.freedesktop.NetworkManager.DHCP6Config" => Self::Dhcp6Config,
-                "org.freedesktop.NetworkManager.IP4Config" => Self::Ip4Config,
-                "org.freedesktop.NetworkManager.IP6Config" => Self::Ip6Config,
-                "org.freedesktop.NetworkManager.Settings" => Self::Settings,
-                "org.freedesktop.NetworkManager.Settings.Connection" => Self::SettingsConnection,
-                _ => Self::Unknown,
-            })
-            .collect();
-
-        types
+impl ObjectKind for ObjectType {
+    fn from_interface(iface: &str) -> Option<Self> {
+        let kind = match iface {
+            "org.freedesktop.NetworkManager.AccessPoint" => Self::AccessPoint,
+            "org.freedesktop.NetworkManager.AgentManager" => Self::AgentManager,
+            "org.freedesktop.NetworkManager.Connection.Active" => Self::ConnectionActive,
+            "org.freedesktop.NetworkManager.Device" => Self::Device,
+            "org.freedesktop.NetworkManager.Device.Bluetooth" => Self::DeviceBluetooth,
+            "org.freedesktop.NetworkManager.Device.Generic" => Self::DeviceGeneric,
+            "org.freedesktop.NetworkManager.Device.Wired" => Self::DeviceWired,
+            "org.freedesktop.NetworkManager.Device.Wireless" => Self::DeviceWireless,
+            "org.freedesktop.NetworkManager.DHCP4Config" => Self::Dhcp4Config,
+            "org.freedesktop.NetworkManager.DHCP6Config" => Self::Dhcp6Config,
+            "org.freedesktop.NetworkManager.IP4Config" => Self::Ip4Config,
+            "org.freedesktop.NetworkManager.IP6Config" => Self::Ip6Config,
+            "org.freedesktop.NetworkManager.Settings" => Self::Settings,
+            "org.freedesktop.NetworkManager.Settings.Connection" => Self::SettingsConnection,
+            _ => return None,
+        };
+        Some(kind)
     }
 }
 
 /// Signals that can be emitted
 #[derive(Debug)]
 enum Signal {
-    Started,
-    Stopped,
-    ObjectAdded {
-        path: String,
-        ifaces: Vec<ObjectType>,
-    },
-    ObjectRemoved {
-        path: String,
-        ifaces: Vec<ObjectType>,
-    },
     PropertyStateChanged(u32),
     PropertyConnectivityChanged(u32),
     PropertyPrimaryConnectionChanged(String),
 }
 
+/// Raised when the NetworkManager manager or any of its objects has events waiting to
+/// be dispatched by [NetworkManagerInstance::process]
+static WAKEUP: Lazy<Wakeup> = Lazy::new(Wakeup::default);
+
 #[derive(GodotClass)]
 #[class(base=Resource)]
 pub struct NetworkManagerInstance {
     base: Base<Resource>,
+    objects: ObjectWatcher<ObjectType>,
     rx: Receiver<Signal>,
     conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<NetworkManagerProxyBlocking<'static>>,
+    last_error: LastError,
     active_connections: HashMap<String, Gd<NetworkActiveConnection>>,
     access_points: HashMap<String, Gd<NetworkAccessPoint>>,
     devices: HashMap<String, Gd<NetworkDevice>>,
@@ -126,6 +121,8 @@ pub struct NetworkManagerInstance {
     wireless_enabled: bool,
 }
 
+impl_last_error!(NetworkManagerInstance);
+
 #[godot_api]
 impl NetworkManagerInstance {
     /// networking state is unknown
@@ -189,13 +186,20 @@ impl NetworkManagerInstance {
     #[signal]
     fn primary_connection_changed(connection: Option<Gd<NetworkActiveConnection>>);
 
-    /// Return a proxy instance to the NetworkManager
+    /// Emitted when a property of the NetworkManager service changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
+    /// Return a proxy instance to the NetworkManager whose property getters
+    /// read from the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<NetworkManagerProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            NetworkManagerProxyBlocking::builder(conn).build().ok()
-        } else {
-            None
-        }
+        self.cache.read_proxy()
+    }
+
+    /// Return a proxy instance to call methods on the NetworkManager
+    fn get_call_proxy(&self) -> Option<NetworkManagerProxyBlocking> {
+        let result = self.cache.call_proxy();
+        self.last_error.check(self, result.map(Some))
     }
 
     /// Returns true if the NetworkManager service is currently running
@@ -231,7 +235,7 @@ impl NetworkManagerInstance {
         let Some(proxy) = self.get_proxy() else {
             return NetworkManagerInstance::NM_STATE_UNKNOWN;
         };
-        proxy.state().unwrap_or_default()
+        self.last_error.check(self, proxy.state())
     }
 
     /// Indicates if wireless is currently enabled or not
@@ -240,16 +244,17 @@ impl NetworkManagerInstance {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.wireless_enabled().unwrap_or_default()
+        self.last_error.check(self, proxy.wireless_enabled())
     }
 
     /// Set whether wireless networking should be enabled
     #[func]
     pub fn set_wireless_enabled(&self, value: bool) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
-        proxy.set_wireless_enabled(value).unwrap_or_default()
+        self.last_error
+            .check(self, proxy.set_wireless_enabled(value))
     }
 
     /// The primary active connection being used to access the network
@@ -258,7 +263,7 @@ impl NetworkManagerInstance {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        let path = proxy.primary_connection().unwrap_or_default();
+        let path = self.last_error.check(self, proxy.primary_connection());
         if path.is_empty() || path.as_str() == "/" {
             return None;
         }
@@ -277,31 +282,32 @@ impl NetworkManagerInstance {
         devices
     }
 
-    /// Returns a HashMap of all the objects managed by this dbus interface
-    fn get_managed_objects(&self) -> Result<ManagedObjects, zbus::fdo::Error> {
-        let Some(conn) = self.conn.as_ref() else {
-            return Err(zbus::fdo::Error::Disconnected(
-                "No DBus connection found".into(),
-            ));
-        };
+    /// Process NetworkManager signals and emit them as Godot signals. This method is
+    /// called by the [ResourceProcessor] on frames where any of the objects of
+    /// this manager sent events, once the manager is registered with
+    /// [method ResourceRegistry.register_on_event].
+    #[func]
+    fn process(&mut self, _delta: f64) {
+        // Report this manager to the registry whenever its objects send events
+        WAKEUP.set_owner(self.base().instance_id().to_i64());
 
-        let bus = BusName::from_static_str(NETWORK_MANAGER_BUS).unwrap();
-        let object_manager = zbus::blocking::fdo::ObjectManagerProxy::builder(conn)
-            .destination(bus)
-            .ok()
-            .and_then(|builder| builder.path(OBJECT_MANAGER_PATH).ok())
-            .and_then(|builder| builder.build().ok());
-        let Some(object_manager) = object_manager else {
-            return Ok(ManagedObjects::new());
-        };
+        // Skip frames where none of the objects of this manager sent any events
+        if !WAKEUP.take() {
+            return;
+        }
 
-        object_manager.get_managed_objects()
-    }
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+
+        // Dispatch any objects that were added or removed
+        for event in self.objects.take_events() {
+            self.process_event(event);
+        }
 
-    /// Process NetworkManager signals and emit them as Godot signals. This method
-    /// should be called every frame in the "_process" loop of a node.
-    #[func]
-    fn process(&mut self) {
         // Drain all messages from the channel to process them
         loop {
             let signal = match self.rx.try_recv() {
@@ -330,24 +336,34 @@ impl NetworkManagerInstance {
         for (_, ap) in self.access_points.iter_mut() {
             ap.bind_mut().process();
         }
+        for (_, config) in self.ipv4_configs.iter_mut() {
+            config.bind_mut().process();
+        }
     }
 
-    /// Process and dispatch the given signal
-    fn process_signal(&mut self, signal: Signal) {
-        match signal {
-            Signal::Started => {
+    /// Process and dispatch the given object event
+    fn process_event(&mut self, event: ObjectEvent<ObjectType>) {
+        match event {
+            ObjectEvent::Started => {
+                // The service or the bus may have restarted, so reconnect
+                self.conn = get_dbus_system_blocking().ok();
                 self.base_mut().emit_signal("started", &[]);
             }
-            Signal::Stopped => {
-                self.devices.clear();
+            ObjectEvent::Stopped => {
                 self.base_mut().emit_signal("stopped", &[]);
             }
-            Signal::ObjectAdded { path, ifaces } => {
-                self.on_object_added(path.as_str(), ifaces);
+            ObjectEvent::Added { path, kind } => {
+                self.on_object_added(path.as_str(), kind);
             }
-            Signal::ObjectRemoved { path, ifaces } => {
-                self.on_object_removed(path.as_str(), ifaces);
+            ObjectEvent::Removed { path, kind } => {
+                self.on_object_removed(path.as_str(), kind);
             }
+        }
+    }
+
+    /// Process and dispatch the given signal
+    fn process_signal(&mut self, signal: Signal) {
+        match signal {
             Signal::PropertyStateChanged(value) => {
                 self.base_mut()
                     .emit_signal("state_changed", &[value.to_variant()]);
@@ -369,75 +385,69 @@ impl NetworkManagerInstance {
     }
 
     /// Track the given object and emit signals
-    fn on_object_added(&mut self, path: &str, types: Vec<ObjectType>) {
-        for iface in types {
-            match iface {
-                ObjectType::Unknown => (),
-                ObjectType::AccessPoint => {
-                    let ap = NetworkAccessPoint::new(path);
-                    self.access_points.insert(path.to_string(), ap.clone());
-                }
-                ObjectType::AgentManager => (),
-                ObjectType::ConnectionActive => {
-                    let connection = NetworkActiveConnection::new(path);
-                    self.active_connections
-                        .insert(path.to_string(), connection.clone());
-                }
-                ObjectType::Device => {
-                    let device = NetworkDevice::new(path);
-                    self.devices.insert(path.to_string(), device.clone());
-                }
-                ObjectType::DeviceBluetooth => (),
-                ObjectType::DeviceGeneric => (),
-                ObjectType::DeviceWired => (),
-                ObjectType::DeviceWireless => {
-                    let device = NetworkDeviceWireless::new(path);
-                    self.devices_wireless
-                        .insert(path.to_string(), device.clone());
-                }
-                ObjectType::Dhcp4Config => (),
-                ObjectType::Dhcp6Config => (),
-                ObjectType::Ip4Config => {
-                    let config = NetworkIpv4Config::new(path);
-                    self.ipv4_configs.insert(path.to_string(), config.clone());
-                }
-                ObjectType::Ip6Config => (),
-                ObjectType::Settings => (),
-                ObjectType::SettingsConnection => (),
+    fn on_object_added(&mut self, path: &str, kind: ObjectType) {
+        match kind {
+            ObjectType::AccessPoint => {
+                let ap = NetworkAccessPoint::new(path);
+                self.access_points.insert(path.to_string(), ap.clone());
             }
+            ObjectType::AgentManager => (),
+            ObjectType::ConnectionActive => {
+                let connection = NetworkActiveConnection::new(path);
+                self.active_connections
+                    .insert(path.to_string(), connection.clone());
+            }
+            ObjectType::Device => {
+                let device = NetworkDevice::new(path);
+                self.devices.insert(path.to_string(), device.clone());
+            }
+            ObjectType::DeviceBluetooth => (),
+            ObjectType::DeviceGeneric => (),
+            ObjectType::DeviceWired => (),
+            ObjectType::DeviceWireless => {
+                let device = NetworkDeviceWireless::new(path);
+                self.devices_wireless
+                    .insert(path.to_string(), device.clone());
+            }
+            ObjectType::Dhcp4Config => (),
+            ObjectType::Dhcp6Config => (),
+            ObjectType::Ip4Config => {
+                let config = NetworkIpv4Config::new(path);
+                self.ipv4_configs.insert(path.to_string(), config.clone());
+            }
+            ObjectType::Ip6Config => (),
+            ObjectType::Settings => (),
+            ObjectType::SettingsConnection => (),
         }
     }
 
     /// Remove the given object and emit signals
-    fn on_object_removed(&mut self, path: &str, types: Vec<ObjectType>) {
-        for iface in types {
-            match iface {
-                ObjectType::Unknown => (),
-                ObjectType::AccessPoint => {
-                    self.access_points.remove(path);
-                }
-                ObjectType::AgentManager => (),
-                ObjectType::ConnectionActive => {
-                    self.active_connections.remove(path);
-                }
-                ObjectType::Device => {
-                    self.devices.remove(path);
-                }
-                ObjectType::DeviceBluetooth => (),
-                ObjectType::DeviceGeneric => (),
-                ObjectType::DeviceWired => (),
-                ObjectType::DeviceWireless => {
-                    self.devices_wireless.remove(path);
-                }
-                ObjectType::Dhcp4Config => (),
-                ObjectType::Dhcp6Config => (),
-                ObjectType::Ip4Config => {
-                    self.ipv4_configs.remove(path);
-                }
-                ObjectType::Ip6Config => (),
-                ObjectType::Settings => (),
-                ObjectType::SettingsConnection => (),
+    fn on_object_removed(&mut self, path: &str, kind: ObjectType) {
+        match kind {
+            ObjectType::AccessPoint => {
+                self.access_points.remove(path);
+            }
+            ObjectType::AgentManager => (),
+            ObjectType::ConnectionActive => {
+                self.active_connections.remove(path);
+            }
+            ObjectType::Device => {
+                self.devices.remove(path);
+            }
+            ObjectType::DeviceBluetooth => (),
+            ObjectType::DeviceGeneric => (),
+            ObjectType::DeviceWired => (),
+            ObjectType::DeviceWireless => {
+                self.devices_wireless.remove(path);
             }
+            ObjectType::Dhcp4Config => (),
+            ObjectType::Dhcp6Config => (),
+            ObjectType::Ip4Config => {
+                self.ipv4_configs.remove(path);
+            }
+            ObjectType::Ip6Config => (),
+            ObjectType::Settings => (),
+            ObjectType::SettingsConnection => (),
         }
     }
 }
@@ -449,16 +459,20 @@ impl IResource for NetworkManagerInstance {
         log::debug!("Initializing NetworkManager instance");
 
         // Create a channel to communicate with the service
-        let (tx, rx) = channel();
+        let (tx, rx) = channel(&WAKEUP);
         let conn = get_dbus_system_blocking().ok();
+        let cache = PropertyCache::new(NETWORK_MANAGER_PATH, &WAKEUP);
 
         // Don't run in the editor
         let engine = Engine::singleton();
         if engine.is_editor_hint() {
             return Self {
                 base,
+                objects: Default::default(),
                 rx,
                 conn,
+                cache,
+                last_error: Default::default(),
                 connectivity: NetworkManagerInstance::NM_CONNECTIVITY_UNKNOWN,
                 active_connections: Default::default(),
                 access_points: Default::default(),
@@ -478,11 +492,22 @@ impl IResource for NetworkManagerInstance {
             }
         });
 
+        // Watch for devices, connections and access points
+        let objects = ObjectWatcher::new(
+            DBusBus::System,
+            NETWORK_MANAGER_BUS,
+            OBJECT_MANAGER_PATH,
+            &WAKEUP,
+        );
+
         // Create a new NetworkManager instance
         let mut instance = Self {
             base,
+            objects,
             rx,
             conn,
+            cache,
+            last_error: Default::default(),
             connectivity: NetworkManagerInstance::NM_CONNECTIVITY_UNKNOWN,
             active_connections: Default::default(),
             access_points: Default::default(),
@@ -493,17 +518,15 @@ impl IResource for NetworkManagerInstance {
             wireless_enabled: Default::default(),
             primary_connection: Default::default(),
         };
-        if !instance.is_running() {
-            return instance;
-        }
 
-        // Do initial object discovery
-        let objects = instance.get_managed_objects().unwrap_or_default();
-        for (path, ifaces) in objects.into_iter() {
-            let path = path.to_string();
-            let ifaces: Vec<String> = ifaces.into_keys().map(|v| v.to_string()).collect();
-            let obj_types = ObjectType::from_ifaces(ifaces);
-            instance.on_object_added(path.as_str(), obj_types);
+        // Track the objects that were discovered
+        let objects: Vec<(String, ObjectType)> = instance
+            .objects
+            .objects()
+            .map(|(path, kind)| (path.to_string(), kind))
+            .collect();
+        for (path, kind) in objects {
+            instance.on_object_added(path.as_str(), kind);
         }
 
         instance
@@ -514,115 +537,40 @@ impl IResource for NetworkManagerInstance {
 /// over the given channel so they can be processed during each engine frame.
 async fn run(tx: Sender<Signal>) -> Result<(), RunError> {
     log::debug!("Spawning networkmanager");
-    // Establish a connection to the system bus
-    let conn = get_dbus_system().await?;
 
-    // Spawn a task to listen for NetworkManager start/stop
-    let dbus_conn = conn.clone();
-    let signals_tx = tx.clone();
-    RUNTIME.spawn(async move {
-        let bus = BusName::from_static_str(NETWORK_MANAGER_BUS).unwrap();
-        let mut is_running = {
-            let dbus = zbus::fdo::DBusProxy::new(&dbus_conn).await.ok();
-            let Some(dbus) = dbus else {
-                return;
-            };
-            dbus.name_has_owner(bus.clone()).await.unwrap_or_default()
+    // Listen for property changes again whenever the bus comes back
+    let mut events = watch_service(DBusBus::System, NETWORK_MANAGER_BUS);
+    let mut task: Option<JoinHandle<()>> = None;
+    while let Some(event) = events.recv().await {
+        let ServiceEvent::Connected(conn) = event else {
+            continue;
         };
-
-        loop {
-            let dbus = zbus::fdo::DBusProxy::new(&dbus_conn).await.ok();
-            let Some(dbus) = dbus else {
-                break;
-            };
-            let running = dbus.name_has_owner(bus.clone()).await.unwrap_or_default();
-            if running != is_running {
-                let signal = if running {
-                    Signal::Started
-                } else {
-                    Signal::Stopped
-                };
-                if signals_tx.send(signal).is_err() {
-                    break;
-                }
-            }
-            is_running = running;
-            tokio::time::sleep(Duration::from_secs(5)).await;
+        if let Some(task) = task.take() {
+            task.abort();
         }
-    });
-
-    // Get a proxy instance to ObjectManager
-    let bus = BusName::from_static_str(NETWORK_MANAGER_BUS).unwrap();
-    let object_manager: ObjectManagerProxy = ObjectManagerProxy::builder(&conn)
-        .destination(bus)?
-        .path(OBJECT_MANAGER_PATH)?
-        .build()
-        .await?;
-
-    // Spawn a task to listen for objects added/removed
-    let mut ifaces_added = object_manager.receive_interfaces_added().await?;
-    let mut ifaces_removed = object_manager.receive_interfaces_removed().await?;
-    let signals_tx = tx.clone();
-    RUNTIME.spawn(async move {
-        loop {
-            tokio::select! {
-                signal = ifaces_added.next() => {
-                    let Some(signal) = signal else {
-                        break;
-                    };
-                    let args = match signal.args() {
-                        Ok(args) => args,
-                        Err(e) => {
-                            log::warn!("Failed to get signal args: ${e:?}");
-                            continue;
-                        }
-                    };
-
-                    let path = args.object_path.to_string();
-                    let ifaces = args
-                        .interfaces_and_properties
-                        .keys()
-                        .map(|v| v.to_string())
-                        .collect();
-                    let ifaces = ObjectType::from_ifaces(ifaces);
-                    let signal = Signal::ObjectAdded { path, ifaces };
-                    if signals_tx.send(signal).is_err() {
-                        break;
-                    }
-                }
-                signal = ifaces_removed.next() => {
-                    let Some(signal) = signal else {
-                        break;
-                    };
-                    let args = match signal.args() {
-                        Ok(args) => args,
-                        Err(e) => {
-                            log::warn!("Failed to get signal args: ${e:?}");
-                            continue;
-                        }
-                    };
-
-                    let path = args.object_path.to_string();
-                    let ifaces = args.interfaces.iter().map(|v| v.to_string()).collect();
-                    let ifaces = ObjectType::from_ifaces(ifaces);
-                    let signal = Signal::ObjectRemoved { path, ifaces };
-                    if signals_tx.send(signal).is_err() {
-                        break;
-                    }
-                }
-            }
+        match watch_properties(&conn, tx.clone()).await {
+            Ok(new_task) => task = Some(new_task),
+            Err(e) => log::warn!("Failed to watch NetworkManager properties: {e:?}"),
         }
-    });
+    }
+
+    Ok(())
+}
 
+/// Spawn a task to listen for NetworkManager property changes on the given connection
+async fn watch_properties(
+    conn: &Connection,
+    tx: Sender<Signal>,
+) -> Result<JoinHandle<()>, RunError> {
     // Get a proxy instance to Networkmanager
-    let network_manager = NetworkManagerProxy::builder(&conn).build().await?;
+    let network_manager = NetworkManagerProxy::builder(conn).build().await?;
 
     // Spawn a task for property changes
     let mut state_changed = network_manager.receive_state_changed().await;
     let mut connectivity_changed = network_manager.receive_connectivity_changed().await;
     let mut primary_changed = network_manager.receive_primary_connection_changed().await;
-    let signals_tx = tx.clone();
-    RUNTIME.spawn(async move {
+    let signals_tx = tx;
+    let properties_task = RUNTIME.spawn(async move {
         loop {
             tokio::select! {
                 signal = state_changed.next() => {
@@ -659,5 +607,5 @@ async fn run(tx: Sender<Signal>) -> Result<(), RunError> {
         }
     });
 
-    Ok(())
+    Ok(properties_task)
 }
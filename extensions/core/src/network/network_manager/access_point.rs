@@ -1,21 +1,24 @@
-use std::collections::HashMap;
-
 use godot::prelude::*;
 
 use godot::classes::{Resource, ResourceLoader};
-use zvariant::ObjectPath;
+use zvariant::{ObjectPath, OwnedObjectPath, StructureBuilder};
 
 use crate::dbus::networkmanager::access_point::{AccessPointProxy, AccessPointProxyBlocking};
-use crate::dbus::networkmanager::network_manager::NetworkManagerProxyBlocking;
-use crate::dbus::networkmanager::settings::SettingsProxyBlocking;
-use crate::dbus::RunError;
-use crate::{get_dbus_system, get_dbus_system_blocking, RUNTIME};
+use crate::dbus::networkmanager::network_manager::NetworkManagerProxy;
+use crate::dbus::networkmanager::settings::SettingsProxy;
+use crate::dbus::property_cache::PropertyCache;
+use crate::dbus::{impl_last_error, DBusVariant, GodotVariant, LastError, RunError};
+use crate::resource::async_result::AsyncResult;
+use crate::{
+    get_dbus_system,
+    resource::dispatcher::{channel, Receiver, Sender, TryRecvError},
+    RUNTIME,
+};
 use futures_util::stream::StreamExt;
-use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
 
 use super::active_connection::NetworkActiveConnection;
 use super::device::NetworkDevice;
-use super::NETWORK_MANAGER_BUS;
+use super::{NETWORK_MANAGER_BUS, WAKEUP};
 
 /// Signals that can be emitted by this resource
 #[derive(Debug)]
@@ -28,7 +31,8 @@ enum Signal {
 pub struct NetworkAccessPoint {
     base: Base<Resource>,
 
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<AccessPointProxyBlocking<'static>>,
+    last_error: LastError,
     rx: Receiver<Signal>,
     path: String,
 
@@ -74,6 +78,8 @@ pub struct NetworkAccessPoint {
     last_seen: i32,
 }
 
+impl_last_error!(NetworkAccessPoint);
+
 #[godot_api]
 impl NetworkAccessPoint {
     /// access point has no special capabilities
@@ -134,17 +140,19 @@ impl NetworkAccessPoint {
     #[signal]
     fn strength_changed(strength: u8);
 
+    /// Emitted when a property of the access point changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Create a new [NetworkAccessPoint] with the given DBus path
     pub fn from_path(path: GString) -> Gd<Self> {
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
-
             // Create a channel to communicate with the service
-            let (tx, rx) = channel();
+            let (tx, rx) = channel(&WAKEUP);
 
             // Spawn a task using the shared tokio runtime to listen for signals
             let dbus_path = path.to_string();
+            let cache = PropertyCache::new(dbus_path.as_str(), &WAKEUP);
             RUNTIME.spawn(async move {
                 if let Err(e) = run(dbus_path, tx).await {
                     log::error!("Failed to run AccessPoint task: ${e:?}");
@@ -154,7 +162,8 @@ impl NetworkAccessPoint {
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
-                conn,
+                cache,
+                last_error: Default::default(),
                 rx,
                 path: path.clone().into(),
                 dbus_path: path,
@@ -171,34 +180,10 @@ impl NetworkAccessPoint {
         })
     }
 
-    /// Return a proxy instance to the network device
+    /// Return a proxy instance to the network device whose property getters
+    /// read from the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<AccessPointProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            AccessPointProxyBlocking::builder(conn)
-                .path(self.path.clone())
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
-    }
-
-    /// Return a proxy instance to the settings interface
-    fn get_settings_proxy(&self) -> Option<SettingsProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            SettingsProxyBlocking::builder(conn).build().ok()
-        } else {
-            None
-        }
-    }
-
-    /// Return a proxy instance to the network manager interface
-    fn get_manager_proxy(&self) -> Option<NetworkManagerProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            NetworkManagerProxyBlocking::builder(conn).build().ok()
-        } else {
-            None
-        }
+        self.cache.read_proxy()
     }
 
     /// Get or create a [NetworkAccessPoint] with the given DBus path. If an instance
@@ -232,92 +217,50 @@ impl NetworkAccessPoint {
         self.dbus_path.clone()
     }
 
-    /// Start connecting to the access point with the given password.
+    /// Start connecting to the access point with the given password. The returned
+    /// [AsyncResult] completes with the [NetworkActiveConnection] once the
+    /// connection has been activated.
     #[func]
-    pub fn connect(
-        &self,
-        device: Gd<NetworkDevice>,
-        password: GString,
-    ) -> Option<Gd<NetworkActiveConnection>> {
-        let Some(settings) = self.get_settings_proxy() else {
-            log::warn!("Unable to connect; settings not found");
-            return None;
-        };
-        let Some(network_manager) = self.get_manager_proxy() else {
-            log::warn!("Unable to connect; network manager not found");
-            return None;
-        };
+    pub fn connect(&self, device: Gd<NetworkDevice>, password: GString) -> Gd<AsyncResult> {
         let ssid = self.get_ssid().to_string();
         if ssid.is_empty() {
-            log::warn!("SSID is empty; unable to connect");
-            return None;
+            return AsyncResult::from_error(RunError::InvalidArgument(
+                "SSID is empty; unable to connect".into(),
+            ));
         }
+        let device_path = device.bind().get_dbus_path().to_string();
+        let ap_path = self.path.clone();
 
         // Build the connection settings
-        let mut connection = HashMap::new();
-        let mut conn = HashMap::new();
-        let conn_type = zvariant::Value::from("802-11-wireless");
-        conn.insert("type", &conn_type);
-        let id = zvariant::Value::from(ssid.clone());
-        conn.insert("id", &id);
-        connection.insert("connection", conn);
-
-        let mut wireless = HashMap::new();
-        let wireless_ssid = zvariant::Value::from(ssid.as_bytes());
-        wireless.insert("ssid", &wireless_ssid);
-        connection.insert("802-11-wireless", wireless);
-
-        let key_mgmt = zvariant::Value::from("wpa-psk");
-        let psk = zvariant::Value::from(password.to_string());
+        let mut settings = dict! {
+            "connection": dict! {
+                "type": "802-11-wireless",
+                "id": ssid.clone(),
+            },
+            "802-11-wireless": dict! {
+                "ssid": PackedByteArray::from(ssid.as_bytes()),
+            },
+        };
         if !password.is_empty() {
-            let mut security = HashMap::new();
-            security.insert("key-mgmt", &key_mgmt);
-            security.insert("psk", &psk);
-
-            connection.insert("802-11-wireless-security", security);
+            let security = dict! {
+                "key-mgmt": "wpa-psk",
+                "psk": password,
+            };
+            settings.set("802-11-wireless-security", security);
         }
-
-        // Add the connection
-        let connection_path = match settings.add_connection(connection) {
-            Ok(conn_path) => conn_path,
-            Err(e) => {
-                log::warn!("Unable to add new connection: {e:?}");
-                return None;
-            }
-        };
-
-        let connection_path = ObjectPath::from(connection_path);
-        let device_path = match ObjectPath::try_from(device.bind().get_dbus_path().to_string()) {
-            Ok(path) => path,
+        let settings = match settings.to_variant().to_zvariant("a{sa{sv}}") {
+            Ok(settings) => settings,
             Err(e) => {
-                log::warn!("Invalid device path: {e:?}");
-                return None;
-            }
-        };
-        let ap_path = match ObjectPath::try_from(self.dbus_path.to_string()) {
-            Ok(path) => path,
-            Err(e) => {
-                log::warn!("Invalid access point path: {e:?}");
-                return None;
+                return AsyncResult::from_error(RunError::InvalidArgument(format!(
+                    "Invalid connection settings: {e}"
+                )));
             }
         };
 
-        // Activate the connection
-        let active_connection_path =
-            match network_manager.activate_connection(&connection_path, &device_path, &ap_path) {
-                Ok(active_connection_path) => active_connection_path,
-                Err(e) => {
-                    log::warn!("Failed to activate connection: {e:?}");
-                    return None;
-                }
-            };
-        if active_connection_path.is_empty() {
-            return None;
-        }
-
-        Some(NetworkActiveConnection::new(
-            active_connection_path.as_str(),
-        ))
+        AsyncResult::spawn_with(
+            activate_connection(settings, device_path, ap_path),
+            |path: OwnedObjectPath| NetworkActiveConnection::new(path.as_str()).to_variant(),
+        )
     }
 
     /// The Service Set Identifier identifying the access point.
@@ -326,7 +269,7 @@ impl NetworkAccessPoint {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        let value = proxy.ssid().unwrap_or_default();
+        let value = self.last_error.check(self, proxy.ssid());
         String::from_utf8_lossy(value.as_slice()).to_string().into()
     }
 
@@ -336,7 +279,7 @@ impl NetworkAccessPoint {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.strength().unwrap_or_default()
+        self.last_error.check(self, proxy.strength())
     }
 
     /// Flags describing the capabilities of the access point.
@@ -345,7 +288,7 @@ impl NetworkAccessPoint {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.flags().unwrap_or_default()
+        self.last_error.check(self, proxy.flags())
     }
 
     /// Flags describing the access point's capabilities according to WPA (Wifi Protected Access).
@@ -354,7 +297,7 @@ impl NetworkAccessPoint {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.wpa_flags().unwrap_or_default()
+        self.last_error.check(self, proxy.wpa_flags())
     }
 
     /// The radio channel frequency in use by the access point, in MHz.
@@ -363,7 +306,7 @@ impl NetworkAccessPoint {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.frequency().unwrap_or_default()
+        self.last_error.check(self, proxy.frequency())
     }
 
     /// The hardware address (BSSID) of the access point.
@@ -372,7 +315,7 @@ impl NetworkAccessPoint {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.hw_address().unwrap_or_default().into()
+        self.last_error.check(self, proxy.hw_address()).into()
     }
 
     /// Describes the operating mode of the access point.
@@ -381,7 +324,7 @@ impl NetworkAccessPoint {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.mode().unwrap_or_default()
+        self.last_error.check(self, proxy.mode())
     }
 
     /// The maximum bitrate this access point is capable of, in kilobits/second (Kb/s).
@@ -390,7 +333,7 @@ impl NetworkAccessPoint {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.max_bitrate().unwrap_or_default()
+        self.last_error.check(self, proxy.max_bitrate())
     }
 
     /// The timestamp (in CLOCK_BOOTTIME seconds) for the last time the access point was found in scan results. A value of -1 means the access point has never been found in scan results.
@@ -399,11 +342,18 @@ impl NetworkAccessPoint {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.last_seen().unwrap_or_default()
+        self.last_error.check(self, proxy.last_seen())
     }
 
     /// Process signals and emit them as Godot signals.
     pub fn process(&mut self) {
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+
         // Drain all messages from the channel to process them
         loop {
             let signal = match self.rx.try_recv() {
@@ -463,3 +413,36 @@ async fn run(path: String, tx: Sender<Signal>) -> Result<(), RunError> {
 
     Ok(())
 }
+
+/// Add a new connection with the given "a{sa{sv}}" settings, then activate it
+/// for the given access point on the given device. Returns the path to the
+/// active connection.
+async fn activate_connection(
+    settings: zvariant::Value<'static>,
+    device_path: String,
+    ap_path: String,
+) -> zbus::Result<OwnedObjectPath> {
+    let dbus = get_dbus_system().await?;
+    let settings_proxy = SettingsProxy::new(&dbus).await?;
+    let network_manager = NetworkManagerProxy::new(&dbus).await?;
+
+    // Add the connection
+    let body = StructureBuilder::new().append_field(settings).build();
+    let connection_path: OwnedObjectPath =
+        settings_proxy.inner().call("AddConnection", &body).await?;
+    let connection_path = ObjectPath::from(connection_path);
+    let device_path = ObjectPath::try_from(device_path)?;
+    let ap_path = ObjectPath::try_from(ap_path)?;
+
+    // Activate the connection
+    let active_connection_path = network_manager
+        .activate_connection(&connection_path, &device_path, &ap_path)
+        .await?;
+    if active_connection_path.is_empty() {
+        return Err(zbus::Error::Failure(
+            "NetworkManager did not activate the connection".into(),
+        ));
+    }
+
+    Ok(active_connection_path)
+}
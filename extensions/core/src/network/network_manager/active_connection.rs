@@ -3,13 +3,17 @@ use godot::prelude::*;
 use godot::classes::{Resource, ResourceLoader};
 
 use crate::dbus::networkmanager::active::{ActiveProxy, ActiveProxyBlocking};
-use crate::dbus::RunError;
-use crate::{get_dbus_system, get_dbus_system_blocking, RUNTIME};
+use crate::dbus::property_cache::PropertyCache;
+use crate::dbus::{impl_last_error, GodotVariant, LastError, RunError};
+use crate::{
+    get_dbus_system,
+    resource::dispatcher::{channel, Receiver, Sender, TryRecvError},
+    RUNTIME,
+};
 use futures_util::stream::StreamExt;
-use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
 
 use super::device::NetworkDevice;
-use super::NETWORK_MANAGER_BUS;
+use super::{NETWORK_MANAGER_BUS, WAKEUP};
 
 /// Signals that can be emitted by this resource
 #[derive(Debug)]
@@ -22,7 +26,8 @@ enum Signal {
 pub struct NetworkActiveConnection {
     base: Base<Resource>,
 
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<ActiveProxyBlocking<'static>>,
+    last_error: LastError,
     rx: Receiver<Signal>,
     path: String,
 
@@ -40,6 +45,8 @@ pub struct NetworkActiveConnection {
     state: u32,
 }
 
+impl_last_error!(NetworkActiveConnection);
+
 #[godot_api]
 impl NetworkActiveConnection {
     /// the state of the connection is unknown
@@ -62,17 +69,19 @@ impl NetworkActiveConnection {
     #[signal]
     fn state_changed(state: u32);
 
+    /// Emitted when a property of the connection changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Create a new [NetworkActiveConnection] with the given DBus path
     pub fn from_path(path: GString) -> Gd<Self> {
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
-
             // Create a channel to communicate with the service
-            let (tx, rx) = channel();
+            let (tx, rx) = channel(&WAKEUP);
 
             // Spawn a task using the shared tokio runtime to listen for signals
             let dbus_path = path.to_string();
+            let cache = PropertyCache::new(dbus_path.as_str(), &WAKEUP);
             RUNTIME.spawn(async move {
                 if let Err(e) = run(dbus_path, tx).await {
                     log::error!("Failed to run NetworkDevice task: ${e:?}");
@@ -82,7 +91,8 @@ impl NetworkActiveConnection {
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
-                conn,
+                cache,
+                last_error: Default::default(),
                 rx,
                 path: path.clone().into(),
                 dbus_path: path,
@@ -92,16 +102,10 @@ impl NetworkActiveConnection {
         })
     }
 
-    /// Return a proxy instance to the network device
+    /// Return a proxy instance to the network device whose property getters
+    /// read from the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<ActiveProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            ActiveProxyBlocking::builder(conn)
-                .path(self.path.clone())
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
     }
 
     /// Get or create a [NetworkActiveConnection] with the given DBus path. If an instance
@@ -141,7 +145,7 @@ impl NetworkActiveConnection {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        let paths = proxy.devices().unwrap_or_default();
+        let paths = self.last_error.check(self, proxy.devices());
         let mut devices = array![];
         for path in paths {
             let path = path.to_string();
@@ -158,11 +162,18 @@ impl NetworkActiveConnection {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.state().unwrap_or_default()
+        self.last_error.check(self, proxy.state())
     }
 
     /// Process signals and emit them as Godot signals.
     pub fn process(&mut self) {
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+
         // Drain all messages from the channel to process them
         loop {
             let signal = match self.rx.try_recv() {
@@ -4,14 +4,18 @@ use godot::prelude::*;
 use godot::classes::{Resource, ResourceLoader};
 
 use crate::dbus::networkmanager::device::{DeviceProxy, DeviceProxyBlocking};
-use crate::dbus::RunError;
-use crate::{get_dbus_system, get_dbus_system_blocking, RUNTIME};
+use crate::dbus::property_cache::PropertyCache;
+use crate::dbus::{impl_last_error, GodotVariant, LastError, RunError};
+use crate::{
+    get_dbus_system,
+    resource::dispatcher::{channel, Receiver, Sender, TryRecvError},
+    RUNTIME,
+};
 use futures_util::stream::StreamExt;
-use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
 
 use super::device_wireless::NetworkDeviceWireless;
 use super::ip4_config::NetworkIpv4Config;
-use super::NETWORK_MANAGER_BUS;
+use super::{NETWORK_MANAGER_BUS, WAKEUP};
 
 /// Signals that can be emitted by this resource
 #[derive(Debug)]
@@ -24,7 +28,8 @@ enum Signal {
 pub struct NetworkDevice {
     base: Base<Resource>,
 
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<DeviceProxyBlocking<'static>>,
+    last_error: LastError,
     rx: Receiver<Signal>,
     path: String,
 
@@ -54,6 +59,8 @@ pub struct NetworkDevice {
     wireless: Option<Gd<NetworkDeviceWireless>>,
 }
 
+impl_last_error!(NetworkDevice);
+
 #[godot_api]
 impl NetworkDevice {
     /// unknown device
@@ -164,17 +171,19 @@ impl NetworkDevice {
     #[signal]
     fn state_changed(state: u32);
 
+    /// Emitted when a property of the device changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Create a new [NetworkDevice] with the given DBus path
     pub fn from_path(path: GString) -> Gd<Self> {
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
-
             // Create a channel to communicate with the service
-            let (tx, rx) = channel();
+            let (tx, rx) = channel(&WAKEUP);
 
             // Spawn a task using the shared tokio runtime to listen for signals
             let dbus_path = path.to_string();
+            let cache = PropertyCache::new(dbus_path.as_str(), &WAKEUP);
             RUNTIME.spawn(async move {
                 if let Err(e) = run(dbus_path, tx).await {
                     log::error!("Failed to run NetworkDevice task: ${e:?}");
@@ -184,7 +193,8 @@ impl NetworkDevice {
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
-                conn,
+                cache,
+                last_error: Default::default(),
                 rx,
                 path: path.clone().into(), // Convert GString -> String.
                 dbus_path: path,
@@ -197,16 +207,10 @@ impl NetworkDevice {
         })
     }
 
-    /// Return a proxy instance to the network device
+    /// Return a proxy instance to the network device whose property getters
+    /// read from the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<DeviceProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            DeviceProxyBlocking::builder(conn)
-                .path(self.path.clone())
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
     }
 
     /// Get or create a [NetworkDevice] with the given DBus path. If an instance
@@ -268,7 +272,7 @@ impl NetworkDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.interface().unwrap_or_default().into()
+        self.last_error.check(self, proxy.interface()).into()
     }
 
     /// The [NetworkIpv4Config] describing the configuration of the device. Null if device has no IP.
@@ -277,7 +281,7 @@ impl NetworkDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        let path = proxy.ip4_config().unwrap_or_default();
+        let path = self.last_error.check(self, proxy.ip4_config());
         let res_path = format!("dbus://{NETWORK_MANAGER_BUS}{path}");
 
         // Check to see if a resource exists
@@ -307,6 +311,13 @@ impl NetworkDevice {
 
     /// Process NetworkDevice signals and emit them as Godot signals.
     pub fn process(&mut self) {
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+
         // Drain all messages from the channel to process them
         loop {
             let signal = match self.rx.try_recv() {
@@ -5,13 +5,18 @@ use godot::prelude::*;
 use godot::classes::{Resource, ResourceLoader};
 
 use crate::dbus::networkmanager::wireless::{WirelessProxy, WirelessProxyBlocking};
-use crate::dbus::RunError;
-use crate::{get_dbus_system, get_dbus_system_blocking, RUNTIME};
+use crate::dbus::property_cache::PropertyCache;
+use crate::dbus::{impl_last_error, GodotVariant, LastError, RunError};
+use crate::resource::async_result::AsyncResult;
+use crate::{
+    get_dbus_system,
+    resource::dispatcher::{channel, Receiver, Sender, TryRecvError},
+    RUNTIME,
+};
 use futures_util::stream::StreamExt;
-use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
 
 use super::access_point::NetworkAccessPoint;
-use super::NETWORK_MANAGER_BUS;
+use super::{NETWORK_MANAGER_BUS, WAKEUP};
 
 /// Signals that can be emitted by this resource
 #[derive(Debug)]
@@ -25,7 +30,8 @@ enum Signal {
 pub struct NetworkDeviceWireless {
     base: Base<Resource>,
 
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<WirelessProxyBlocking<'static>>,
+    last_error: LastError,
     rx: Receiver<Signal>,
     path: String,
 
@@ -51,6 +57,8 @@ pub struct NetworkDeviceWireless {
     hardware_address: GString,
 }
 
+impl_last_error!(NetworkDeviceWireless);
+
 #[godot_api]
 impl NetworkDeviceWireless {
     /// Emitted when a new access point is detected
@@ -60,17 +68,19 @@ impl NetworkDeviceWireless {
     #[signal]
     fn access_point_removed();
 
+    /// Emitted when a property of the wireless device changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Create a new [NetworkDeviceWireless] with the given DBus path
     pub fn from_path(path: GString) -> Gd<Self> {
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
-
             // Create a channel to communicate with the service
-            let (tx, rx) = channel();
+            let (tx, rx) = channel(&WAKEUP);
 
             // Spawn a task using the shared tokio runtime to listen for signals
             let dbus_path = path.to_string();
+            let cache = PropertyCache::new(dbus_path.as_str(), &WAKEUP);
             RUNTIME.spawn(async move {
                 if let Err(e) = run(dbus_path, tx).await {
                     log::error!("Failed to run NetworkDevice task: ${e:?}");
@@ -80,7 +90,8 @@ impl NetworkDeviceWireless {
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
-                conn,
+                cache,
+                last_error: Default::default(),
                 rx,
                 path: path.clone().into(),
                 dbus_path: path,
@@ -92,16 +103,16 @@ impl NetworkDeviceWireless {
         })
     }
 
-    /// Return a proxy instance to the network device
+    /// Return a proxy instance to the network device whose property getters
+    /// read from the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<WirelessProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            WirelessProxyBlocking::builder(conn)
-                .path(self.path.clone())
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
+    }
+
+    /// Return a proxy instance to call methods on the network device
+    fn get_call_proxy(&self) -> Option<WirelessProxyBlocking> {
+        let result = self.cache.call_proxy();
+        self.last_error.check(self, result.map(Some))
     }
 
     /// Get or create a [NetworkDeviceWireless] with the given DBus path. If an instance
@@ -141,18 +152,18 @@ impl NetworkDeviceWireless {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.bitrate().unwrap_or_default()
+        self.last_error.check(self, proxy.bitrate())
     }
 
     /// List of access point visible to this wireless device.
     #[func]
     pub fn get_access_points(&self) -> Array<Gd<NetworkAccessPoint>> {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
         let mut resource_loader = ResourceLoader::singleton();
         let mut value = array![];
-        let aps = proxy.get_access_points().unwrap_or_default();
+        let aps = self.last_error.check(self, proxy.get_access_points());
         for ap in aps {
             let res_path = format!("dbus://{NETWORK_MANAGER_BUS}/{ap}");
 
@@ -172,7 +183,7 @@ impl NetworkDeviceWireless {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        let ap = proxy.active_access_point().unwrap_or_default();
+        let ap = self.last_error.check(self, proxy.active_access_point());
         if ap.is_empty() {
             return None;
         }
@@ -189,13 +200,12 @@ impl NetworkDeviceWireless {
         None
     }
 
-    /// Request the device to scan
+    /// Request the device to scan. The returned [AsyncResult] completes once
+    /// NetworkManager has accepted the request.
     #[func]
-    pub fn request_scan(&self) {
-        let Some(proxy) = self.get_proxy() else {
-            return;
-        };
-        proxy.request_scan(HashMap::new()).unwrap_or_default()
+    pub fn request_scan(&self) -> Gd<AsyncResult> {
+        let proxy = self.cache.async_call_proxy::<WirelessProxy>();
+        AsyncResult::spawn(async move { proxy.await?.request_scan(HashMap::new()).await })
     }
 
     /// The active hardware address of the device.
@@ -204,11 +214,18 @@ impl NetworkDeviceWireless {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.hw_address().unwrap_or_default().into()
+        self.last_error.check(self, proxy.hw_address()).into()
     }
 
     /// Process signals and emit them as Godot signals.
     pub fn process(&mut self) {
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+
         // Drain all messages from the channel to process them
         loop {
             let signal = match self.rx.try_recv() {
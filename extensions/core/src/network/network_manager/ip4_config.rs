@@ -3,17 +3,18 @@ use godot::prelude::*;
 use godot::classes::{Resource, ResourceLoader};
 
 use crate::dbus::networkmanager::ip4config::IP4ConfigProxyBlocking;
-use crate::dbus::GodotVariant;
-use crate::get_dbus_system_blocking;
+use crate::dbus::property_cache::PropertyCache;
+use crate::dbus::{impl_last_error, GodotVariant, LastError};
 
-use super::NETWORK_MANAGER_BUS;
+use super::{NETWORK_MANAGER_BUS, WAKEUP};
 
 #[derive(GodotClass)]
 #[class(no_init, base=Resource)]
 pub struct NetworkIpv4Config {
     base: Base<Resource>,
 
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<IP4ConfigProxyBlocking<'static>>,
+    last_error: LastError,
     path: String,
 
     /// The DBus path of the [NetworkIpv4Config]
@@ -30,18 +31,24 @@ pub struct NetworkIpv4Config {
     gateway: GString,
 }
 
+impl_last_error!(NetworkIpv4Config);
+
 #[godot_api]
 impl NetworkIpv4Config {
+    /// Emitted when a property of the IPv4 configuration changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Create a new [NetworkIpv4Config] with the given DBus path
     pub fn from_path(path: GString) -> Gd<Self> {
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
+            let cache = PropertyCache::new(path.to_string().as_str(), &WAKEUP);
 
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
-                conn,
+                cache,
+                last_error: Default::default(),
                 path: path.clone().into(),
                 dbus_path: path,
                 addresses: Default::default(),
@@ -50,16 +57,10 @@ impl NetworkIpv4Config {
         })
     }
 
-    /// Return a proxy instance to the network device
+    /// Return a proxy instance to the network device whose property getters
+    /// read from the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<IP4ConfigProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            IP4ConfigProxyBlocking::builder(conn)
-                .path(self.path.clone())
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
     }
 
     /// Get or create a [NetworkIpv4Config] with the given DBus path. If an instance
@@ -99,7 +100,7 @@ impl NetworkIpv4Config {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.gateway().unwrap_or_default().to_godot()
+        self.last_error.check(self, proxy.gateway()).to_godot()
     }
 
     /// Array of IP address data objects. All addresses will include "address" (an IP address string), and "prefix" (a uint). Some addresses may include additional attributes.
@@ -109,7 +110,7 @@ impl NetworkIpv4Config {
             return Default::default();
         };
         let mut value = array![];
-        let data = proxy.address_data().unwrap_or_default();
+        let data = self.last_error.check(self, proxy.address_data());
         for entry in data {
             let mut dict = Dictionary::new();
             for (key, value) in entry.iter() {
@@ -125,5 +126,11 @@ impl NetworkIpv4Config {
     }
 
     /// Dispatches signals
-    pub fn process(&mut self) {}
+    pub fn process(&mut self) {
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+    }
 }
@@ -4,17 +4,21 @@ pub mod gpu;
 pub mod gpu_card;
 pub mod gpu_connector;
 
-use std::{
-    sync::mpsc::{channel, Receiver, Sender, TryRecvError},
-    time::Duration,
-};
-
 use cpu::Cpu;
 use godot::{classes::Engine, prelude::*};
 use gpu::Gpu;
+use once_cell::sync::Lazy;
 use zbus::names::BusName;
 
-use crate::{dbus::RunError, get_dbus_system, get_dbus_system_blocking, RUNTIME};
+use crate::{
+    dbus::{
+        supervisor::{watch_service, ServiceEvent},
+        RunError,
+    },
+    get_dbus_system_blocking,
+    resource::dispatcher::{channel, Receiver, Sender, TryRecvError, Wakeup},
+    DBusBus, RUNTIME,
+};
 
 pub const POWERSTATION_BUS: &str = "org.shadowblip.PowerStation";
 const POWERSTATION_CPU_PATH: &str = "/org/shadowblip/Performance/CPU";
@@ -27,6 +31,10 @@ enum Signal {
     Stopped,
 }
 
+/// Raised when the PowerStation manager or any of its objects has events waiting to
+/// be dispatched by [PowerStationInstance::process]
+static WAKEUP: Lazy<Wakeup> = Lazy::new(Wakeup::default);
+
 /// PowerStation dbus proxy
 #[derive(GodotClass)]
 #[class(base=Resource)]
@@ -81,10 +89,20 @@ impl PowerStationInstance {
         self.gpu_instance.clone()
     }
 
-    /// Process UPower signals and emit them as Godot signals. This method
-    /// should be called every frame in the "_process" loop of a node.
+    /// Process PowerStation signals and emit them as Godot signals. This method is
+    /// called by the [ResourceProcessor] on frames where any of the objects of
+    /// this manager sent events, once the manager is registered with
+    /// [method ResourceRegistry.register_on_event].
     #[func]
-    fn process(&mut self) {
+    fn process(&mut self, _delta: f64) {
+        // Report this manager to the registry whenever its objects send events
+        WAKEUP.set_owner(self.base().instance_id().to_i64());
+
+        // Skip frames where none of the objects of this manager sent any events
+        if !WAKEUP.take() {
+            return;
+        }
+
         // Drain all messages from the channel to process them
         loop {
             let signal = match self.rx.try_recv() {
@@ -114,12 +132,16 @@ impl PowerStationInstance {
     fn process_signal(&mut self, signal: Signal) {
         match signal {
             Signal::Started => {
-                // Create an instance for the CPU
+                // The service or the bus may have restarted, so reconnect and
+                // create new instances for the CPU and GPU
+                self.conn = get_dbus_system_blocking().ok();
                 self.cpu_instance = Some(Cpu::new(POWERSTATION_CPU_PATH));
+                self.gpu_instance = Some(Gpu::new(POWERSTATION_GPU_PATH));
                 self.base_mut().emit_signal("started", &[]);
             }
             Signal::Stopped => {
                 self.cpu_instance = None;
+                self.gpu_instance = None;
                 self.base_mut().emit_signal("stopped", &[]);
             }
         }
@@ -133,7 +155,7 @@ impl IResource for PowerStationInstance {
         log::debug!("Initializing PowerStation instance");
 
         // Create a channel to communicate with the service
-        let (tx, rx) = channel();
+        let (tx, rx) = channel(&WAKEUP);
         let conn = get_dbus_system_blocking().ok();
 
         // Don't run in the editor
@@ -179,50 +201,19 @@ impl IResource for PowerStationInstance {
 /// over the given channel so they can be processed during each engine frame.
 async fn run(tx: Sender<Signal>) -> Result<(), RunError> {
     log::debug!("Spawning PowerStation tasks");
-    // Establish a connection to the system bus
-    let conn = get_dbus_system().await?;
 
-    // Spawn a task to listen for PowerStation start/stop
-    let dbus_conn = conn.clone();
-    let signals_tx = tx.clone();
-    RUNTIME.spawn(async move {
-        let bus = BusName::from_static_str(POWERSTATION_BUS).unwrap();
-        let mut is_running = {
-            let dbus = zbus::fdo::DBusProxy::new(&dbus_conn).await.ok();
-            let Some(dbus) = dbus else {
-                return;
-            };
-            dbus.name_has_owner(bus.clone()).await.unwrap_or_default()
-        };
-        let signal = if is_running {
-            Signal::Started
-        } else {
-            Signal::Stopped
+    // Watch for PowerStation start/stop, reconnecting if the bus goes away
+    let mut events = watch_service(DBusBus::System, POWERSTATION_BUS);
+    while let Some(event) = events.recv().await {
+        let signal = match event {
+            ServiceEvent::Connected(_) => continue,
+            ServiceEvent::Started => Signal::Started,
+            ServiceEvent::Stopped => Signal::Stopped,
         };
-        if signals_tx.send(signal).is_err() {
-            return;
+        if tx.send(signal).is_err() {
+            break;
         }
-
-        loop {
-            let dbus = zbus::fdo::DBusProxy::new(&dbus_conn).await.ok();
-            let Some(dbus) = dbus else {
-                break;
-            };
-            let running = dbus.name_has_owner(bus.clone()).await.unwrap_or_default();
-            if running != is_running {
-                let signal = if running {
-                    Signal::Started
-                } else {
-                    Signal::Stopped
-                };
-                if signals_tx.send(signal).is_err() {
-                    break;
-                }
-            }
-            is_running = running;
-            tokio::time::sleep(Duration::from_secs(5)).await;
-        }
-    });
+    }
 
     Ok(())
 }
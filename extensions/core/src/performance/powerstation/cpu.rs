@@ -1,20 +1,21 @@
-use std::{
-    collections::HashMap,
-    sync::mpsc::{channel, Receiver, Sender, TryRecvError},
-};
+use std::collections::HashMap;
 
 use futures_util::StreamExt;
 use godot::{classes::ResourceLoader, prelude::*};
 
 use crate::{
     dbus::{
+        impl_last_error,
         powerstation::cpu::{CPUProxy, CPUProxyBlocking},
-        RunError,
+        property_cache::PropertyCache,
+        GodotVariant, LastError, RunError,
     },
-    get_dbus_system, get_dbus_system_blocking, RUNTIME,
+    get_dbus_system,
+    resource::dispatcher::{channel, Receiver, Sender, TryRecvError},
+    RUNTIME,
 };
 
-use super::{cpu_core::CpuCore, POWERSTATION_BUS};
+use super::{cpu_core::CpuCore, POWERSTATION_BUS, WAKEUP};
 
 /// Signals that can be emitted
 #[derive(Debug)]
@@ -27,7 +28,8 @@ enum Signal {
 pub struct Cpu {
     base: Base<Resource>,
     path: String,
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<CPUProxyBlocking<'static>>,
+    last_error: LastError,
     rx: Receiver<Signal>,
     cores: HashMap<String, Gd<CpuCore>>,
 
@@ -48,17 +50,22 @@ pub struct Cpu {
     smt_enabled: bool,
 }
 
+impl_last_error!(Cpu);
+
 #[godot_api]
 impl Cpu {
+    /// Emitted when a property of the CPU changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Create a new [Cpu] instance with the given DBus path
     fn from_path(path: GString) -> Gd<Self> {
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
-            let (tx, rx) = channel();
+            let (tx, rx) = channel(&WAKEUP);
 
             // Spawn a task to listen for CPU signals
-            let dbus_path = path.clone().into();
+            let dbus_path: String = path.clone().into();
+            let cache = PropertyCache::new(dbus_path.as_str(), &WAKEUP);
             RUNTIME.spawn(async move {
                 if let Err(e) = run(tx, dbus_path).await {
                     log::error!("Failed to run CPU task: ${e:?}");
@@ -68,7 +75,8 @@ impl Cpu {
             // Accept a base of type Base<Resource> and directly forward it.
             let mut instance = Self {
                 base,
-                conn,
+                cache,
+                last_error: Default::default(),
                 path: path.clone().into(),
                 cores: HashMap::new(),
                 rx,
@@ -79,9 +87,11 @@ impl Cpu {
                 smt_enabled: Default::default(),
             };
 
-            // Discover any CPU cores
+            // Discover any CPU cores. Errors cannot be reported before the object is
+            // constructed, so they are ignored.
             let mut cores = HashMap::new();
-            if let Some(cpu) = instance.get_proxy() {
+            let proxy = instance.cache.call_proxy().ok();
+            if let Some(cpu) = proxy {
                 if let Ok(core_paths) = cpu.enumerate_cores() {
                     for core_path in core_paths {
                         let core = CpuCore::new(core_path.as_str());
@@ -95,16 +105,16 @@ impl Cpu {
         })
     }
 
-    /// Return a proxy instance to the composite device
+    /// Return a proxy instance to the composite device whose property getters
+    /// read from the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<CPUProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            CPUProxyBlocking::builder(conn)
-                .path(self.path.clone())
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
+    }
+
+    /// Return a proxy instance to call methods on the composite device
+    fn get_call_proxy(&self) -> Option<CPUProxyBlocking> {
+        let result = self.cache.call_proxy();
+        self.last_error.check(self, result.map(Some))
     }
 
     /// Get or create a [DBusDevice] with the given DBus path. If an instance
@@ -166,13 +176,13 @@ impl Cpu {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.boost_enabled().unwrap_or_default()
+        self.last_error.check(self, proxy.boost_enabled())
     }
 
     /// Sets boost to the given value
     #[func]
     pub fn set_boost_enabled(&self, enabled: bool) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
         proxy.set_boost_enabled(enabled).ok();
@@ -184,7 +194,7 @@ impl Cpu {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.cores_count().unwrap_or_default()
+        self.last_error.check(self, proxy.cores_count())
     }
 
     /// Returns the number of enabled CPU cores
@@ -193,16 +203,17 @@ impl Cpu {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.cores_enabled().unwrap_or_default()
+        self.last_error.check(self, proxy.cores_enabled())
     }
 
     /// Set the number of enabled CPU cores. Cannot be less than 1.
     #[func]
     pub fn set_cores_enabled(&self, enabled_count: u32) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
-        proxy.set_cores_enabled(enabled_count).unwrap_or_default()
+        self.last_error
+            .check(self, proxy.set_cores_enabled(enabled_count))
     }
 
     /// Returns a list of supported CPU feature flags
@@ -211,7 +222,7 @@ impl Cpu {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        let features = proxy.features().unwrap_or_default();
+        let features = self.last_error.check(self, proxy.features());
         let features: Vec<GString> = features.into_iter().map(|f| f.to_godot()).collect();
         features.into()
     }
@@ -222,13 +233,13 @@ impl Cpu {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.smt_enabled().unwrap_or_default()
+        self.last_error.check(self, proxy.smt_enabled())
     }
 
     /// Set SMT to the given value
     #[func]
     pub fn set_smt_enabled(&self, enabled: bool) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
         proxy.set_smt_enabled(enabled).ok();
@@ -236,6 +247,13 @@ impl Cpu {
 
     /// Dispatches signals
     pub fn process(&mut self) {
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+
         // Drain all messages from the channel to process them
         loop {
             let signal = match self.rx.try_recv() {
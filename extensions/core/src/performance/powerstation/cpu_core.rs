@@ -1,17 +1,19 @@
-use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
-
 use futures_util::StreamExt;
 use godot::{classes::ResourceLoader, prelude::*};
 
 use crate::{
     dbus::{
+        impl_last_error,
         powerstation::core::{CoreProxy, CoreProxyBlocking},
-        RunError,
+        property_cache::PropertyCache,
+        GodotVariant, LastError, RunError,
     },
-    get_dbus_system, get_dbus_system_blocking, RUNTIME,
+    get_dbus_system,
+    resource::dispatcher::{channel, Receiver, Sender, TryRecvError},
+    RUNTIME,
 };
 
-use super::POWERSTATION_BUS;
+use super::{POWERSTATION_BUS, WAKEUP};
 
 /// Signals that can be emitted
 #[derive(Debug)]
@@ -24,7 +26,8 @@ enum Signal {
 pub struct CpuCore {
     base: Base<Resource>,
     path: String,
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<CoreProxyBlocking<'static>>,
+    last_error: LastError,
     rx: Receiver<Signal>,
 
     #[allow(dead_code)]
@@ -38,17 +41,22 @@ pub struct CpuCore {
     online: bool,
 }
 
+impl_last_error!(CpuCore);
+
 #[godot_api]
 impl CpuCore {
+    /// Emitted when a property of the CPU core changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Create a new [EventDevice] with the given DBus path
     fn from_path(path: GString) -> Gd<Self> {
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
-            let (tx, rx) = channel();
+            let (tx, rx) = channel(&WAKEUP);
 
             // Spawn a task to listen for CPU signals
-            let dbus_path = path.clone().into();
+            let dbus_path: String = path.clone().into();
+            let cache = PropertyCache::new(dbus_path.as_str(), &WAKEUP);
             RUNTIME.spawn(async move {
                 if let Err(e) = run(tx, dbus_path).await {
                     log::error!("Failed to run CPU Core task: ${e:?}");
@@ -58,7 +66,8 @@ impl CpuCore {
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
-                conn,
+                cache,
+                last_error: Default::default(),
                 path: path.clone().into(),
                 rx,
                 core_id: Default::default(),
@@ -68,16 +77,16 @@ impl CpuCore {
         })
     }
 
-    /// Return a proxy instance to the composite device
+    /// Return a proxy instance to the composite device whose property getters
+    /// read from the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<CoreProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            CoreProxyBlocking::builder(conn)
-                .path(self.path.clone())
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
+    }
+
+    /// Return a proxy instance to call methods on the composite device
+    fn get_call_proxy(&self) -> Option<CoreProxyBlocking> {
+        let result = self.cache.call_proxy();
+        self.last_error.check(self, result.map(Some))
     }
 
     /// Get or create a [DBusDevice] with the given DBus path. If an instance
@@ -117,7 +126,7 @@ impl CpuCore {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.core_id().unwrap_or_default()
+        self.last_error.check(self, proxy.core_id())
     }
 
     /// Return the core number of the CPU core
@@ -126,7 +135,7 @@ impl CpuCore {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.number().unwrap_or_default()
+        self.last_error.check(self, proxy.number())
     }
 
     /// Return whether or not the CPU core is online
@@ -135,20 +144,27 @@ impl CpuCore {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.online().unwrap_or_default()
+        self.last_error.check(self, proxy.online())
     }
 
     /// Set the online status of the core to the given value
     #[func]
     pub fn set_online(&self, online: bool) {
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return Default::default();
         };
-        proxy.set_online(online).unwrap_or_default()
+        self.last_error.check(self, proxy.set_online(online))
     }
 
     /// Dispatches signals
     pub fn process(&mut self) {
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+
         // Drain all messages from the channel to process them
         loop {
             let signal = match self.rx.try_recv() {
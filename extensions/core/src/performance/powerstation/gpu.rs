@@ -1,7 +1,9 @@
 use std::collections::HashMap;
 
 use godot::{classes::ResourceLoader, prelude::*};
+use zbus::proxy::CacheProperties;
 
+use crate::dbus::{impl_last_error, LastError, RunError};
 use crate::{dbus::powerstation::gpu::GPUProxyBlocking, get_dbus_system_blocking};
 
 use super::{gpu_card::GpuCard, POWERSTATION_BUS};
@@ -11,29 +13,32 @@ use super::{gpu_card::GpuCard, POWERSTATION_BUS};
 pub struct Gpu {
     base: Base<Resource>,
     path: String,
-    conn: Option<zbus::blocking::Connection>,
+    last_error: LastError,
     cards: HashMap<String, Gd<GpuCard>>,
 }
 
+impl_last_error!(Gpu);
+
 #[godot_api]
 impl Gpu {
     /// Create a new [Cpu] instance with the given DBus path
     fn from_path(path: GString) -> Gd<Self> {
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
-
             // Accept a base of type Base<Resource> and directly forward it.
             let mut instance = Self {
                 base,
-                conn,
+                last_error: Default::default(),
                 path: path.clone().into(),
                 cards: HashMap::new(),
             };
 
-            // Discover any GPU cards
+            // Discover any GPU cards. Errors cannot be reported before the object is
+            // constructed, so only look for them if there is a connection.
             let mut cards = HashMap::new();
-            if let Some(gpu) = instance.get_proxy() {
+            let proxy = get_dbus_system_blocking()
+                .ok()
+                .and_then(|_| instance.get_proxy());
+            if let Some(gpu) = proxy {
                 if let Ok(card_paths) = gpu.enumerate_cards() {
                     for card_path in card_paths {
                         let core = GpuCard::new(card_path.as_str());
@@ -49,12 +54,15 @@ impl Gpu {
 
     /// Return a proxy instance to the composite device
     fn get_proxy(&self) -> Option<GPUProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            GPUProxyBlocking::builder(conn)
+        if let Ok(conn) = get_dbus_system_blocking() {
+            GPUProxyBlocking::builder(&conn)
                 .path(self.path.clone())
                 .ok()
+                .map(|builder| builder.cache_properties(CacheProperties::No))
                 .and_then(|builder| builder.build().ok())
         } else {
+            self.last_error
+                .report(self.to_gd().upcast(), RunError::NotConnected);
             None
         }
     }
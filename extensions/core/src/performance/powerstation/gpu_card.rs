@@ -1,37 +1,30 @@
-use std::{
-    collections::HashMap,
-    sync::mpsc::{channel, Receiver, Sender, TryRecvError},
-};
+use std::{collections::HashMap, future::Future};
 
-use futures_util::StreamExt;
 use godot::{classes::ResourceLoader, prelude::*};
 
 use crate::{
     dbus::{
+        impl_last_error,
         powerstation::{
             card::{CardProxy, CardProxyBlocking},
             tdp::{TDPProxy, TDPProxyBlocking},
         },
-        RunError,
+        property_cache::PropertyCache,
+        GodotVariant, LastError,
     },
-    get_dbus_system, get_dbus_system_blocking, RUNTIME,
+    resource::async_result::AsyncResult,
 };
 
-use super::{gpu_connector::GpuConnector, POWERSTATION_BUS};
-
-/// Signals that can be emitted
-#[derive(Debug)]
-enum Signal {
-    Updated,
-}
+use super::{gpu_connector::GpuConnector, POWERSTATION_BUS, WAKEUP};
 
 #[derive(GodotClass)]
 #[class(no_init, base=Resource)]
 pub struct GpuCard {
     base: Base<Resource>,
     dbus_path: String,
-    conn: Option<zbus::blocking::Connection>,
-    rx: Receiver<Signal>,
+    cache: PropertyCache<CardProxyBlocking<'static>>,
+    last_error: LastError,
+    tdp_cache: PropertyCache<TDPProxyBlocking<'static>>,
     connectors: HashMap<String, Gd<GpuConnector>>,
 
     #[allow(dead_code)]
@@ -123,29 +116,33 @@ pub struct GpuCard {
     thermal_throttle_limit_c: f64,
 }
 
+impl_last_error!(GpuCard);
+
 #[godot_api]
 impl GpuCard {
+    /// Emitted when a property of the GPU card changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
+    /// Emitted once per frame when any property of the GPU card changed
+    #[signal]
+    fn updated();
+
     /// Create a new [EventDevice] with the given DBus path
     fn from_path(path: GString) -> Gd<Self> {
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
-            let (tx, rx) = channel();
-
-            // Spawn a task to listen for GPU Card signals
-            let dbus_path = path.clone().into();
-            RUNTIME.spawn(async move {
-                if let Err(e) = run(tx, dbus_path).await {
-                    log::error!("Failed to run GPU Card task: ${e:?}");
-                }
-            });
+            // Cache the properties of the card and listen for changes
+            let dbus_path: String = path.clone().into();
+            let cache = PropertyCache::new(dbus_path.as_str(), &WAKEUP);
+            let tdp_cache = PropertyCache::new(dbus_path.as_str(), &WAKEUP);
 
             // Accept a base of type Base<Resource> and directly forward it.
             let mut instance = Self {
                 base,
-                conn,
+                cache,
+                last_error: Default::default(),
+                tdp_cache,
                 dbus_path: path.clone().into(),
-                rx,
                 boost: Default::default(),
                 class: Default::default(),
                 class_id: Default::default(),
@@ -171,9 +168,11 @@ impl GpuCard {
                 vendor_id: Default::default(),
             };
 
-            // Discover any connectors
+            // Discover any connectors. Errors cannot be reported before the object is
+            // constructed, so they are ignored.
             let mut connectors = HashMap::new();
-            if let Some(card) = instance.get_proxy() {
+            let proxy = instance.cache.call_proxy().ok();
+            if let Some(card) = proxy {
                 if let Ok(connector_paths) = card.enumerate_connectors() {
                     for conn_path in connector_paths {
                         let connector = GpuConnector::new(conn_path.as_str());
@@ -187,28 +186,56 @@ impl GpuCard {
         })
     }
 
-    /// Return a proxy instance to the GPU card interface
+    /// Return a proxy instance to the GPU card interface whose property getters
+    /// read from the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<CardProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            CardProxyBlocking::builder(conn)
-                .path(self.dbus_path.clone())
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
+    }
+
+    /// Return a proxy instance to call methods on the GPU card interface
+    fn get_call_proxy(&self) -> Option<CardProxyBlocking> {
+        let result = self.cache.call_proxy();
+        self.last_error.check(self, result.map(Some))
     }
 
-    /// Return a proxy instance to the TDP interface
+    /// Return a proxy instance to the TDP interface whose property getters read
+    /// from the cache, or from the bus until the cache has been filled
     fn get_tdp_proxy(&self) -> Option<TDPProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            TDPProxyBlocking::builder(conn)
-                .path(self.dbus_path.clone())
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.tdp_cache.read_proxy()
+    }
+
+    /// Report a failure of the given operation on this card. This is used by
+    /// setters that do not return their [AsyncResult].
+    fn report_errors(&self, mut result: Gd<AsyncResult>) {
+        let card = self.to_gd();
+        result.bind_mut().on_error(move |kind, message| {
+            let object = card.clone().upcast();
+            card.bind()
+                .last_error
+                .report_kind(object, kind, message.to_string());
+        });
+    }
+
+    /// Call the given method on the GPU card interface in the async runtime
+    fn call_card<F, Fut, T>(&self, method: F) -> Gd<AsyncResult>
+    where
+        F: FnOnce(CardProxy<'static>) -> Fut + Send + 'static,
+        Fut: Future<Output = zbus::Result<T>> + Send,
+        T: ToGodot + Send + 'static,
+    {
+        let proxy = self.cache.async_call_proxy();
+        AsyncResult::spawn(async move { method(proxy.await?).await })
+    }
+
+    /// Call the given method on the TDP interface in the async runtime
+    fn call_tdp<F, Fut, T>(&self, method: F) -> Gd<AsyncResult>
+    where
+        F: FnOnce(TDPProxy<'static>) -> Fut + Send + 'static,
+        Fut: Future<Output = zbus::Result<T>> + Send,
+        T: ToGodot + Send + 'static,
+    {
+        let proxy = self.tdp_cache.async_call_proxy();
+        AsyncResult::spawn(async move { method(proxy.await?).await })
     }
 
     /// Get or create a [DBusDevice] with the given DBus path. If an instance
@@ -253,10 +280,10 @@ impl GpuCard {
     #[func]
     pub fn get_connectors(&self) -> Array<Gd<GpuConnector>> {
         let mut connectors = array![];
-        let Some(proxy) = self.get_proxy() else {
+        let Some(proxy) = self.get_call_proxy() else {
             return connectors;
         };
-        let paths = proxy.enumerate_connectors().unwrap_or_default();
+        let paths = self.last_error.check(self, proxy.enumerate_connectors());
         for path in paths {
             let connector = GpuConnector::new(path.as_str());
             connectors.push(&connector);
@@ -270,7 +297,7 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.name().unwrap_or_default().into()
+        self.last_error.check(self, proxy.name()).into()
     }
 
     #[func]
@@ -278,7 +305,7 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.path().unwrap_or_default().into()
+        self.last_error.check(self, proxy.path()).into()
     }
 
     #[func]
@@ -286,17 +313,21 @@ impl GpuCard {
         let Some(proxy) = self.get_tdp_proxy() else {
             return Default::default();
         };
-        proxy.thermal_throttle_limit_c().unwrap_or_default()
+        self.last_error
+            .check(self, proxy.thermal_throttle_limit_c())
     }
 
     #[func]
     pub fn set_thermal_throttle_limit_c(&self, value: f64) {
-        let Some(proxy) = self.get_tdp_proxy() else {
-            return Default::default();
-        };
-        proxy
-            .set_thermal_throttle_limit_c(value)
-            .unwrap_or_default()
+        let result = self.set_thermal_throttle_limit_c_async(value);
+        self.report_errors(result);
+    }
+
+    /// Set the thermal throttle limit without blocking. The returned [AsyncResult] completes
+    /// once the value has been set.
+    #[func]
+    pub fn set_thermal_throttle_limit_c_async(&self, value: f64) -> Gd<AsyncResult> {
+        self.call_tdp(move |proxy| async move { proxy.set_thermal_throttle_limit_c(value).await })
     }
 
     #[func]
@@ -304,7 +335,9 @@ impl GpuCard {
         let Some(proxy) = self.get_tdp_proxy() else {
             return Default::default();
         };
-        let available = proxy.power_profiles_available().unwrap_or_default();
+        let available = self
+            .last_error
+            .check(self, proxy.power_profiles_available());
         let mut result = PackedStringArray::new();
         for profile in available.iter() {
             let godot_profile = profile.to_godot();
@@ -318,16 +351,21 @@ impl GpuCard {
         let Some(proxy) = self.get_tdp_proxy() else {
             return Default::default();
         };
-        proxy.power_profile().unwrap_or_default().into()
+        self.last_error.check(self, proxy.power_profile()).into()
     }
 
     #[func]
     pub fn set_power_profile(&self, value: GString) {
-        let Some(proxy) = self.get_tdp_proxy() else {
-            return Default::default();
-        };
+        let result = self.set_power_profile_async(value);
+        self.report_errors(result);
+    }
+
+    /// Set the power profile without blocking. The returned [AsyncResult] completes
+    /// once the value has been set.
+    #[func]
+    pub fn set_power_profile_async(&self, value: GString) -> Gd<AsyncResult> {
         let value = value.to_string();
-        proxy.set_power_profile(value.as_str()).unwrap_or_default()
+        self.call_tdp(move |proxy| async move { proxy.set_power_profile(value.as_str()).await })
     }
 
     #[func]
@@ -335,15 +373,20 @@ impl GpuCard {
         let Some(proxy) = self.get_tdp_proxy() else {
             return Default::default();
         };
-        proxy.boost().unwrap_or_default()
+        self.last_error.check(self, proxy.boost())
     }
 
     #[func]
     pub fn set_boost(&self, value: f64) {
-        let Some(proxy) = self.get_tdp_proxy() else {
-            return Default::default();
-        };
-        proxy.set_boost(value).unwrap_or_default()
+        let result = self.set_boost_async(value);
+        self.report_errors(result);
+    }
+
+    /// Set the TDP boost without blocking. The returned [AsyncResult] completes
+    /// once the value has been set.
+    #[func]
+    pub fn set_boost_async(&self, value: f64) -> Gd<AsyncResult> {
+        self.call_tdp(move |proxy| async move { proxy.set_boost(value).await })
     }
 
     #[func]
@@ -351,15 +394,20 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.manual_clock().unwrap_or_default()
+        self.last_error.check(self, proxy.manual_clock())
     }
 
     #[func]
     pub fn set_manual_clock(&self, value: bool) {
-        let Some(proxy) = self.get_proxy() else {
-            return Default::default();
-        };
-        proxy.set_manual_clock(value).unwrap_or_default()
+        let result = self.set_manual_clock_async(value);
+        self.report_errors(result);
+    }
+
+    /// Set the manual clock control without blocking. The returned [AsyncResult] completes
+    /// once the value has been set.
+    #[func]
+    pub fn set_manual_clock_async(&self, value: bool) -> Gd<AsyncResult> {
+        self.call_card(move |proxy| async move { proxy.set_manual_clock(value).await })
     }
 
     #[func]
@@ -367,15 +415,20 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.clock_value_mhz_min().unwrap_or_default()
+        self.last_error.check(self, proxy.clock_value_mhz_min())
     }
 
     #[func]
     pub fn set_clock_value_mhz_min(&self, value: f64) {
-        let Some(proxy) = self.get_proxy() else {
-            return Default::default();
-        };
-        proxy.set_clock_value_mhz_min(value).unwrap_or_default()
+        let result = self.set_clock_value_mhz_min_async(value);
+        self.report_errors(result);
+    }
+
+    /// Set the minimum clock value without blocking. The returned [AsyncResult] completes
+    /// once the value has been set.
+    #[func]
+    pub fn set_clock_value_mhz_min_async(&self, value: f64) -> Gd<AsyncResult> {
+        self.call_card(move |proxy| async move { proxy.set_clock_value_mhz_min(value).await })
     }
 
     #[func]
@@ -383,15 +436,20 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.clock_value_mhz_max().unwrap_or_default()
+        self.last_error.check(self, proxy.clock_value_mhz_max())
     }
 
     #[func]
     pub fn set_clock_value_mhz_max(&self, value: f64) {
-        let Some(proxy) = self.get_proxy() else {
-            return Default::default();
-        };
-        proxy.set_clock_value_mhz_max(value).unwrap_or_default()
+        let result = self.set_clock_value_mhz_max_async(value);
+        self.report_errors(result);
+    }
+
+    /// Set the maximum clock value without blocking. The returned [AsyncResult] completes
+    /// once the value has been set.
+    #[func]
+    pub fn set_clock_value_mhz_max_async(&self, value: f64) -> Gd<AsyncResult> {
+        self.call_card(move |proxy| async move { proxy.set_clock_value_mhz_max(value).await })
     }
 
     #[func]
@@ -399,7 +457,7 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.device_id().unwrap_or_default().into()
+        self.last_error.check(self, proxy.device_id()).into()
     }
 
     #[func]
@@ -407,7 +465,7 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.subdevice_id().unwrap_or_default().into()
+        self.last_error.check(self, proxy.subdevice_id()).into()
     }
 
     #[func]
@@ -415,7 +473,7 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.subvendor_id().unwrap_or_default().into()
+        self.last_error.check(self, proxy.subvendor_id()).into()
     }
 
     #[func]
@@ -423,7 +481,7 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.vendor().unwrap_or_default().into()
+        self.last_error.check(self, proxy.vendor()).into()
     }
 
     #[func]
@@ -431,7 +489,7 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.vendor_id().unwrap_or_default().into()
+        self.last_error.check(self, proxy.vendor_id()).into()
     }
 
     #[func]
@@ -439,15 +497,20 @@ impl GpuCard {
         let Some(proxy) = self.get_tdp_proxy() else {
             return Default::default();
         };
-        proxy.tdp().unwrap_or_default()
+        self.last_error.check(self, proxy.tdp())
     }
 
     #[func]
     pub fn set_tdp(&self, value: f64) {
-        let Some(proxy) = self.get_tdp_proxy() else {
-            return Default::default();
-        };
-        proxy.set_tdp(value).unwrap_or_default()
+        let result = self.set_tdp_async(value);
+        self.report_errors(result);
+    }
+
+    /// Set the TDP without blocking. The returned [AsyncResult] completes
+    /// once the value has been set.
+    #[func]
+    pub fn set_tdp_async(&self, value: f64) -> Gd<AsyncResult> {
+        self.call_tdp(move |proxy| async move { proxy.set_tdp(value).await })
     }
 
     #[func]
@@ -455,7 +518,7 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.subdevice().unwrap_or_default().into()
+        self.last_error.check(self, proxy.subdevice()).into()
     }
 
     #[func]
@@ -463,7 +526,7 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.revision_id().unwrap_or_default().into()
+        self.last_error.check(self, proxy.revision_id()).into()
     }
 
     #[func]
@@ -471,7 +534,7 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.device().unwrap_or_default().into()
+        self.last_error.check(self, proxy.device()).into()
     }
 
     #[func]
@@ -479,7 +542,7 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.clock_limit_mhz_min().unwrap_or_default()
+        self.last_error.check(self, proxy.clock_limit_mhz_min())
     }
 
     #[func]
@@ -487,7 +550,7 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.clock_limit_mhz_max().unwrap_or_default()
+        self.last_error.check(self, proxy.clock_limit_mhz_max())
     }
 
     #[func]
@@ -495,7 +558,7 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.class_id().unwrap_or_default().into()
+        self.last_error.check(self, proxy.class_id()).into()
     }
 
     #[func]
@@ -503,24 +566,22 @@ impl GpuCard {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.class().unwrap_or_default().into()
+        self.last_error.check(self, proxy.class()).into()
     }
 
     /// Dispatches signals
     pub fn process(&mut self) {
-        // Drain all messages from the channel to process them
-        loop {
-            let signal = match self.rx.try_recv() {
-                Ok(value) => value,
-                Err(e) => match e {
-                    TryRecvError::Empty => break,
-                    TryRecvError::Disconnected => {
-                        log::error!("Backend thread is not running!");
-                        return;
-                    }
-                },
-            };
-            self.process_signal(signal);
+        // Emit any properties that changed since the last frame
+        let mut changes = self.cache.take_changes();
+        changes.extend(self.tdp_cache.take_changes());
+        let updated = !changes.is_empty();
+        for (name, value) in changes {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+        if updated {
+            self.base_mut().emit_signal("updated", &[]);
         }
 
         // Process child connector signals
@@ -528,106 +589,4 @@ impl GpuCard {
             connector.bind_mut().process();
         }
     }
-
-    /// Process and dispatch the given signal
-    fn process_signal(&mut self, signal: Signal) {
-        log::trace!("Got signal: {signal:?}");
-        match signal {
-            Signal::Updated => {
-                self.base_mut().emit_signal("updated", &[]);
-            }
-        }
-    }
-}
-
-/// Runs GPU connector tasks in Tokio to listen for DBus signals and send them
-/// over the given channel so they can be processed during each engine frame.
-async fn run(tx: Sender<Signal>, path: String) -> Result<(), RunError> {
-    // Establish a connection to the system bus
-    let conn = get_dbus_system().await?;
-    let proxy = CardProxy::builder(&conn)
-        .path(path.clone())?
-        .build()
-        .await?;
-
-    let signals_tx = tx.clone();
-    let mut property_changed = proxy.receive_clock_value_mhz_min_changed().await;
-    RUNTIME.spawn(async move {
-        while (property_changed.next().await).is_some() {
-            let signal = Signal::Updated;
-            if signals_tx.send(signal).is_err() {
-                break;
-            }
-        }
-    });
-
-    let signals_tx = tx.clone();
-    let mut property_changed = proxy.receive_clock_value_mhz_max_changed().await;
-    RUNTIME.spawn(async move {
-        while (property_changed.next().await).is_some() {
-            let signal = Signal::Updated;
-            if signals_tx.send(signal).is_err() {
-                break;
-            }
-        }
-    });
-
-    let signals_tx = tx.clone();
-    let mut property_changed = proxy.receive_manual_clock_changed().await;
-    RUNTIME.spawn(async move {
-        while (property_changed.next().await).is_some() {
-            let signal = Signal::Updated;
-            if signals_tx.send(signal).is_err() {
-                break;
-            }
-        }
-    });
-
-    let tdp_proxy = TDPProxy::builder(&conn).path(path)?.build().await?;
-
-    let signals_tx = tx.clone();
-    let mut property_changed = tdp_proxy.receive_tdp_changed().await;
-    RUNTIME.spawn(async move {
-        while (property_changed.next().await).is_some() {
-            let signal = Signal::Updated;
-            if signals_tx.send(signal).is_err() {
-                break;
-            }
-        }
-    });
-
-    let signals_tx = tx.clone();
-    let mut property_changed = tdp_proxy.receive_boost_changed().await;
-    RUNTIME.spawn(async move {
-        while (property_changed.next().await).is_some() {
-            let signal = Signal::Updated;
-            if signals_tx.send(signal).is_err() {
-                break;
-            }
-        }
-    });
-
-    let signals_tx = tx.clone();
-    let mut property_changed = tdp_proxy.receive_power_profile_changed().await;
-    RUNTIME.spawn(async move {
-        while (property_changed.next().await).is_some() {
-            let signal = Signal::Updated;
-            if signals_tx.send(signal).is_err() {
-                break;
-            }
-        }
-    });
-
-    let signals_tx = tx.clone();
-    let mut property_changed = tdp_proxy.receive_thermal_throttle_limit_c_changed().await;
-    RUNTIME.spawn(async move {
-        while (property_changed.next().await).is_some() {
-            let signal = Signal::Updated;
-            if signals_tx.send(signal).is_err() {
-                break;
-            }
-        }
-    });
-
-    Ok(())
 }
@@ -1,17 +1,19 @@
-use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
-
 use futures_util::StreamExt;
 use godot::{classes::ResourceLoader, prelude::*};
 
 use crate::{
     dbus::{
+        impl_last_error,
         powerstation::connector::{ConnectorProxy, ConnectorProxyBlocking},
-        RunError,
+        property_cache::PropertyCache,
+        GodotVariant, LastError, RunError,
     },
-    get_dbus_system, get_dbus_system_blocking, RUNTIME,
+    get_dbus_system,
+    resource::dispatcher::{channel, Receiver, Sender, TryRecvError},
+    RUNTIME,
 };
 
-use super::POWERSTATION_BUS;
+use super::{POWERSTATION_BUS, WAKEUP};
 
 /// Signals that can be emitted
 #[derive(Debug)]
@@ -24,7 +26,8 @@ enum Signal {
 pub struct GpuConnector {
     base: Base<Resource>,
     dbus_path: String,
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<ConnectorProxyBlocking<'static>>,
+    last_error: LastError,
     rx: Receiver<Signal>,
 
     #[allow(dead_code)]
@@ -50,17 +53,22 @@ pub struct GpuConnector {
     status: GString,
 }
 
+impl_last_error!(GpuConnector);
+
 #[godot_api]
 impl GpuConnector {
+    /// Emitted when a property of the GPU connector changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Create a new [EventDevice] with the given DBus path
     fn from_path(path: GString) -> Gd<Self> {
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
-            let (tx, rx) = channel();
+            let (tx, rx) = channel(&WAKEUP);
 
             // Spawn a task to listen for CPU signals
-            let dbus_path = path.clone().into();
+            let dbus_path: String = path.clone().into();
+            let cache = PropertyCache::new(dbus_path.as_str(), &WAKEUP);
             RUNTIME.spawn(async move {
                 if let Err(e) = run(tx, dbus_path).await {
                     log::error!("Failed to run CPU Core task: ${e:?}");
@@ -70,7 +78,8 @@ impl GpuConnector {
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
-                conn,
+                cache,
+                last_error: Default::default(),
                 dbus_path: path.clone().into(),
                 rx,
                 dpms: Default::default(),
@@ -84,16 +93,10 @@ impl GpuConnector {
         })
     }
 
-    /// Return a proxy instance to the composite device
+    /// Return a proxy instance to the composite device whose property getters
+    /// read from the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<ConnectorProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            ConnectorProxyBlocking::builder(conn)
-                .path(self.dbus_path.clone())
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
     }
 
     /// Get or create a [DBusDevice] with the given DBus path. If an instance
@@ -132,7 +135,7 @@ impl GpuConnector {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.dpms().unwrap_or_default()
+        self.last_error.check(self, proxy.dpms())
     }
 
     #[func]
@@ -140,7 +143,7 @@ impl GpuConnector {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.enabled().unwrap_or_default()
+        self.last_error.check(self, proxy.enabled())
     }
 
     #[func]
@@ -148,7 +151,7 @@ impl GpuConnector {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.id().unwrap_or_default()
+        self.last_error.check(self, proxy.id())
     }
 
     #[func]
@@ -156,7 +159,7 @@ impl GpuConnector {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        let modes = proxy.modes().unwrap_or_default();
+        let modes = self.last_error.check(self, proxy.modes());
         let modes: Vec<GString> = modes.into_iter().map(|m| m.to_godot()).collect();
         modes.into()
     }
@@ -166,7 +169,7 @@ impl GpuConnector {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.name().unwrap_or_default().into()
+        self.last_error.check(self, proxy.name()).into()
     }
 
     #[func]
@@ -174,7 +177,7 @@ impl GpuConnector {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.path().unwrap_or_default().into()
+        self.last_error.check(self, proxy.path()).into()
     }
 
     #[func]
@@ -182,11 +185,18 @@ impl GpuConnector {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.status().unwrap_or_default().into()
+        self.last_error.check(self, proxy.status()).into()
     }
 
     /// Dispatches signals
     pub fn process(&mut self) {
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+
         // Drain all messages from the channel to process them
         loop {
             let signal = match self.rx.try_recv() {
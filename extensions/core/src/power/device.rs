@@ -1,17 +1,19 @@
-use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
-
 use futures_util::StreamExt;
 use godot::{classes::ResourceLoader, prelude::*};
 
 use crate::{
     dbus::{
+        impl_last_error,
+        property_cache::PropertyCache,
         upower::device::{DeviceProxy, DeviceProxyBlocking},
-        RunError,
+        GodotVariant, LastError, RunError,
     },
-    get_dbus_system, get_dbus_system_blocking, RUNTIME,
+    get_dbus_system,
+    resource::dispatcher::{channel, Receiver, Sender, TryRecvError},
+    RUNTIME,
 };
 
-use super::upower::UPOWER_BUS;
+use super::upower::{UPOWER_BUS, WAKEUP};
 
 /// Signals that can be emitted
 #[derive(Debug)]
@@ -24,7 +26,8 @@ enum Signal {
 pub struct UPowerDevice {
     base: Base<Resource>,
     rx: Receiver<Signal>,
-    conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<DeviceProxyBlocking<'static>>,
+    last_error: LastError,
     #[var]
     dbus_path: GString,
     #[allow(dead_code)]
@@ -116,6 +119,8 @@ pub struct UPowerDevice {
     warning_level: u32,
 }
 
+impl_last_error!(UPowerDevice);
+
 #[godot_api]
 impl UPowerDevice {
     #[constant]
@@ -238,11 +243,16 @@ impl UPowerDevice {
     #[signal]
     fn updated();
 
+    /// Emitted when a property of the device changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Create a new [UPowerDevice] with the given DBus path
     pub fn from_path(path: GString) -> Gd<Self> {
         // Create a channel to communicate with the signals task
-        let (tx, rx) = channel();
-        let dbus_path = path.clone().into();
+        let (tx, rx) = channel(&WAKEUP);
+        let dbus_path: String = path.clone().into();
+        let cache = PropertyCache::new(dbus_path.as_str(), &WAKEUP);
 
         // Spawn a task using the shared tokio runtime to listen for signals
         RUNTIME.spawn(async move {
@@ -252,14 +262,12 @@ impl UPowerDevice {
         });
 
         Gd::from_init_fn(|base| {
-            // Create a connection to DBus
-            let conn = get_dbus_system_blocking().ok();
-
             // Accept a base of type Base<Resource> and directly forward it.
             Self {
                 base,
                 rx,
-                conn,
+                cache,
+                last_error: Default::default(),
                 dbus_path: path,
                 battery_level: Default::default(),
                 charge_cycles: Default::default(),
@@ -294,17 +302,10 @@ impl UPowerDevice {
         })
     }
 
-    /// Return a proxy instance to the device
+    /// Return a proxy instance to the device whose property getters read from
+    /// the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<DeviceProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            let path: String = self.dbus_path.clone().into();
-            DeviceProxyBlocking::builder(conn)
-                .path(path)
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
     }
 
     /// Get or create a [UPowerDevice] with the given DBus path. If an instance
@@ -337,7 +338,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.battery_level().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.battery_level())
     }
 
     #[func]
@@ -345,7 +346,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.charge_cycles().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.charge_cycles())
     }
 
     #[func]
@@ -353,7 +354,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.energy().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.energy())
     }
 
     #[func]
@@ -361,7 +362,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.energy_empty().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.energy_empty())
     }
 
     #[func]
@@ -369,7 +370,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.energy_full().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.energy_full())
     }
 
     #[func]
@@ -377,7 +378,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.energy_full_design().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.energy_full_design())
     }
 
     #[func]
@@ -385,7 +386,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.energy_rate().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.energy_rate())
     }
 
     #[func]
@@ -393,7 +394,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.has_history().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.has_history())
     }
 
     #[func]
@@ -401,7 +402,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.has_statistics().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.has_statistics())
     }
 
     #[func]
@@ -409,7 +410,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.icon_name().ok().unwrap_or_default().into()
+        self.last_error.check(self, proxy.icon_name()).into()
     }
 
     #[func]
@@ -417,7 +418,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.is_present().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.is_present())
     }
 
     #[func]
@@ -425,7 +426,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.is_rechargeable().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.is_rechargeable())
     }
 
     #[func]
@@ -433,7 +434,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.luminosity().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.luminosity())
     }
 
     #[func]
@@ -441,7 +442,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.model().ok().unwrap_or_default().into()
+        self.last_error.check(self, proxy.model()).into()
     }
 
     #[func]
@@ -449,7 +450,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.native_path().ok().unwrap_or_default().into()
+        self.last_error.check(self, proxy.native_path()).into()
     }
 
     #[func]
@@ -457,7 +458,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.online().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.online())
     }
 
     #[func]
@@ -465,7 +466,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.percentage().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.percentage())
     }
 
     #[func]
@@ -473,7 +474,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.power_supply().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.power_supply())
     }
 
     #[func]
@@ -481,7 +482,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.serial().ok().unwrap_or_default().into()
+        self.last_error.check(self, proxy.serial()).into()
     }
 
     #[func]
@@ -489,7 +490,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.state().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.state())
     }
 
     #[func]
@@ -497,7 +498,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.technology().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.technology())
     }
 
     #[func]
@@ -505,7 +506,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.temperature().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.temperature())
     }
 
     #[func]
@@ -513,7 +514,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.time_to_empty().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.time_to_empty())
     }
 
     #[func]
@@ -521,7 +522,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.time_to_full().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.time_to_full())
     }
 
     #[func]
@@ -529,7 +530,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.type_().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.type_())
     }
 
     #[func]
@@ -537,7 +538,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.update_time().ok().unwrap_or_default() as i64
+        self.last_error.check(self, proxy.update_time()) as i64
     }
 
     #[func]
@@ -545,7 +546,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.vendor().ok().unwrap_or_default().into()
+        self.last_error.check(self, proxy.vendor()).into()
     }
 
     #[func]
@@ -553,7 +554,7 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.voltage().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.voltage())
     }
 
     #[func]
@@ -561,11 +562,18 @@ impl UPowerDevice {
         let Some(proxy) = self.get_proxy() else {
             return Default::default();
         };
-        proxy.warning_level().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.warning_level())
     }
 
     /// Dispatches signals
     pub fn process(&mut self) {
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+
         // Drain all messages from the channel to process them
         loop {
             let signal = match self.rx.try_recv() {
@@ -1,15 +1,20 @@
-use std::{
-    collections::HashMap,
-    sync::mpsc::{channel, Receiver, Sender, TryRecvError},
-    time::Duration,
-};
+use std::collections::HashMap;
 
 use godot::{classes::Engine, prelude::*};
+use once_cell::sync::Lazy;
 use zbus::names::BusName;
 
 use crate::{
-    dbus::{upower::UPowerProxyBlocking, RunError},
-    get_dbus_system, get_dbus_system_blocking, RUNTIME,
+    dbus::{
+        impl_last_error,
+        property_cache::PropertyCache,
+        supervisor::{watch_service, ServiceEvent},
+        upower::UPowerProxyBlocking,
+        GodotVariant, LastError, RunError,
+    },
+    get_dbus_system_blocking,
+    resource::dispatcher::{channel, Receiver, Sender, TryRecvError, Wakeup},
+    DBusBus, RUNTIME,
 };
 
 use super::device::UPowerDevice;
@@ -25,6 +30,10 @@ enum Signal {
     Stopped,
 }
 
+/// Raised when the UPower manager or any of its objects has events waiting to
+/// be dispatched by [UPowerInstance::process]
+pub(super) static WAKEUP: Lazy<Wakeup> = Lazy::new(Wakeup::default);
+
 /// UPower dbus proxy for power management
 #[derive(GodotClass)]
 #[class(base=Resource)]
@@ -32,12 +41,16 @@ pub struct UPowerInstance {
     base: Base<Resource>,
     rx: Receiver<Signal>,
     conn: Option<zbus::blocking::Connection>,
+    cache: PropertyCache<UPowerProxyBlocking<'static>>,
+    last_error: LastError,
     devices: HashMap<String, Gd<UPowerDevice>>,
     #[allow(dead_code)]
     #[var(get = get_on_battery)]
     on_battery: bool,
 }
 
+impl_last_error!(UPowerInstance);
+
 #[godot_api]
 impl UPowerInstance {
     /// Emitted when UPower is detected as running
@@ -48,6 +61,10 @@ impl UPowerInstance {
     #[signal]
     fn stopped();
 
+    /// Emitted when a property of the UPower service changes. Changes are coalesced, so this fires at most once per frame for each property with its latest value.
+    #[signal]
+    fn property_changed(name: GString, value: Variant);
+
     /// Returns true if the UPower service is currently running
     #[func]
     fn is_running(&self) -> bool {
@@ -68,7 +85,7 @@ impl UPowerInstance {
         let Some(proxy) = self.get_proxy() else {
             return false;
         };
-        proxy.on_battery().ok().unwrap_or_default()
+        self.last_error.check(self, proxy.on_battery())
     }
 
     /// Get the object to the "display device", a composite device that represents the status icon to show in desktop environments.
@@ -83,9 +100,27 @@ impl UPowerInstance {
         device
     }
 
-    /// Process UPower signals and emit them as Godot signals. This method should be called every frame in the `_process` loop of a node.
+    /// Process UPower signals and emit them as Godot signals. This method is
+    /// called by the [ResourceProcessor] on frames where any of the objects of
+    /// this manager sent events, once the manager is registered with
+    /// [method ResourceRegistry.register_on_event].
     #[func]
-    fn process(&mut self) {
+    fn process(&mut self, _delta: f64) {
+        // Report this manager to the registry whenever its objects send events
+        WAKEUP.set_owner(self.base().instance_id().to_i64());
+
+        // Skip frames where none of the objects of this manager sent any events
+        if !WAKEUP.take() {
+            return;
+        }
+
+        // Emit any properties that changed since the last frame
+        for (name, value) in self.cache.take_changes() {
+            let value = value.as_godot_variant().unwrap_or_default();
+            self.base_mut()
+                .emit_signal("property_changed", &[name.to_variant(), value]);
+        }
+
         // Drain all messages from the channel to process them
         loop {
             let signal = match self.rx.try_recv() {
@@ -111,24 +146,22 @@ impl UPowerInstance {
     fn process_signal(&mut self, signal: Signal) {
         match signal {
             Signal::Started => {
+                // The service or the bus may have restarted, so reconnect
+                self.conn = get_dbus_system_blocking().ok();
                 self.base_mut().emit_signal("started", &[]);
             }
             Signal::Stopped => {
+                // Devices are created again when they are requested
+                self.devices.clear();
                 self.base_mut().emit_signal("stopped", &[]);
             }
         }
     }
 
-    /// Return a proxy instance to the UPower object
+    /// Return a proxy instance to the UPower object whose property getters read
+    /// from the cache, or from the bus until the cache has been filled
     fn get_proxy(&self) -> Option<UPowerProxyBlocking> {
-        if let Some(conn) = self.conn.as_ref() {
-            UPowerProxyBlocking::builder(conn)
-                .path(UPOWER_PATH)
-                .ok()
-                .and_then(|builder| builder.build().ok())
-        } else {
-            None
-        }
+        self.cache.read_proxy()
     }
 }
 
@@ -139,8 +172,9 @@ impl IResource for UPowerInstance {
         log::debug!("Initializing UPower instance");
 
         // Create a channel to communicate with the service
-        let (tx, rx) = channel();
+        let (tx, rx) = channel(&WAKEUP);
         let conn = get_dbus_system_blocking().ok();
+        let cache = PropertyCache::new(UPOWER_PATH, &WAKEUP);
 
         // Don't run in the editor
         let engine = Engine::singleton();
@@ -149,6 +183,8 @@ impl IResource for UPowerInstance {
                 base,
                 rx,
                 conn,
+                cache,
+                last_error: Default::default(),
                 devices: Default::default(),
                 on_battery: Default::default(),
             };
@@ -166,6 +202,8 @@ impl IResource for UPowerInstance {
             base,
             rx,
             conn,
+            cache,
+            last_error: Default::default(),
             devices: HashMap::new(),
             on_battery: false,
         }
@@ -176,42 +214,19 @@ impl IResource for UPowerInstance {
 /// over the given channel so they can be processed during each engine frame.
 async fn run(tx: Sender<Signal>) -> Result<(), RunError> {
     log::debug!("Spawning UPower tasks");
-    // Establish a connection to the system bus
-    let conn = get_dbus_system().await?;
 
-    // Spawn a task to listen for UPower start/stop
-    let dbus_conn = conn.clone();
-    let signals_tx = tx.clone();
-    RUNTIME.spawn(async move {
-        let bus = BusName::from_static_str(UPOWER_BUS).unwrap();
-        let mut is_running = {
-            let dbus = zbus::fdo::DBusProxy::new(&dbus_conn).await.ok();
-            let Some(dbus) = dbus else {
-                return;
-            };
-            dbus.name_has_owner(bus.clone()).await.unwrap_or_default()
+    // Watch for UPower start/stop, reconnecting if the bus goes away
+    let mut events = watch_service(DBusBus::System, UPOWER_BUS);
+    while let Some(event) = events.recv().await {
+        let signal = match event {
+            ServiceEvent::Connected(_) => continue,
+            ServiceEvent::Started => Signal::Started,
+            ServiceEvent::Stopped => Signal::Stopped,
         };
-
-        loop {
-            let dbus = zbus::fdo::DBusProxy::new(&dbus_conn).await.ok();
-            let Some(dbus) = dbus else {
-                break;
-            };
-            let running = dbus.name_has_owner(bus.clone()).await.unwrap_or_default();
-            if running != is_running {
-                let signal = if running {
-                    Signal::Started
-                } else {
-                    Signal::Stopped
-                };
-                if signals_tx.send(signal).is_err() {
-                    break;
-                }
-            }
-            is_running = running;
-            tokio::time::sleep(Duration::from_secs(5)).await;
+        if tx.send(signal).is_err() {
+            break;
         }
-    });
+    }
 
     Ok(())
 }
@@ -1,2 +1,4 @@
+pub mod async_result;
+pub mod dispatcher;
 pub mod resource_processor;
 pub mod resource_registry;
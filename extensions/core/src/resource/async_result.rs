@@ -0,0 +1,215 @@
+use std::{cell::RefCell, future::Future};
+
+use godot::{obj::WithBaseField, prelude::*};
+use once_cell::sync::Lazy;
+
+use crate::{
+    dbus::{ErrorKind, RunError},
+    resource::dispatcher::{channel, Receiver, TryRecvError, Wakeup},
+    RUNTIME,
+};
+
+/// Value produced by a finished operation. The value is converted into a
+/// [Variant] on the main thread, since Godot types cannot be sent between threads.
+type Outcome = Result<Box<dyn FnOnce() -> Variant + Send>, (ErrorKind, String)>;
+
+/// Function that is called on the main thread with the error of a failed operation
+type ErrorHandler = Box<dyn FnOnce(ErrorKind, &str)>;
+
+/// Raised when any pending operation finished
+static WAKEUP: Lazy<Wakeup> = Lazy::new(Wakeup::default);
+
+thread_local! {
+    /// Results that are waiting for their operation to finish
+    static PENDING: RefCell<Vec<Gd<AsyncResult>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Result of an operation that runs in the background.
+///
+/// Slow or privileged operations (e.g. setting the TDP or pairing a bluetooth device) return an [AsyncResult] instead of blocking the main thread. Once the operation finishes, the [signal completed] signal is emitted from the process loop of the [ResourceRegistry], so a [ResourceProcessor] must be in the scene tree for results to be delivered.
+///
+/// Example
+///
+/// [codeblock]
+/// var result := device.pair()
+/// await result.completed
+/// if not result.is_ok():
+///     print("Failed to pair: ", result.get_error())
+/// if result.get_error_code() == DBusError.PERMISSION_DENIED:
+///     print("Not allowed to pair")
+/// [/codeblock]
+#[derive(GodotClass)]
+#[class(no_init, base=RefCounted)]
+pub struct AsyncResult {
+    base: Base<RefCounted>,
+    rx: Option<Receiver<Outcome>>,
+    on_error: Option<ErrorHandler>,
+    done: bool,
+    result: Variant,
+    error: GString,
+    error_code: i32,
+}
+
+#[godot_api]
+impl AsyncResult {
+    /// Emitted when the operation finishes. If the operation failed, the result will be null and the error will describe what went wrong.
+    #[signal]
+    fn completed(result: Variant, error: GString);
+
+    /// Run the given future in the tokio runtime and return an [AsyncResult] that completes with its output
+    pub fn spawn<F, T, E>(future: F) -> Gd<Self>
+    where
+        F: Future<Output = Result<T, E>> + Send + 'static,
+        T: ToGodot + Send + 'static,
+        E: Into<RunError> + 'static,
+    {
+        Self::spawn_with(future, |value| value.to_variant())
+    }
+
+    /// Run the given future in the tokio runtime and return an [AsyncResult] that
+    /// completes with its output. The output is converted into a [Variant] on the
+    /// main thread using the given function, so it can be used to create Godot
+    /// objects from the output.
+    pub fn spawn_with<F, T, E, C>(future: F, convert: C) -> Gd<Self>
+    where
+        F: Future<Output = Result<T, E>> + Send + 'static,
+        T: Send + 'static,
+        E: Into<RunError> + 'static,
+        C: FnOnce(T) -> Variant + Send + 'static,
+    {
+        let (tx, rx) = channel(&WAKEUP);
+        RUNTIME.spawn(async move {
+            let outcome: Outcome = match future.await {
+                Ok(value) => Ok(Box::new(move || convert(value))),
+                Err(e) => {
+                    let error: RunError = e.into();
+                    Err((error.kind(), error.to_string()))
+                }
+            };
+            let _ = tx.send(outcome);
+        });
+
+        Self::pending(rx)
+    }
+
+    /// Return an [AsyncResult] that fails with the given error. This is used when
+    /// an operation cannot be started at all.
+    pub fn from_error(error: RunError) -> Gd<Self> {
+        let (tx, rx) = channel(&WAKEUP);
+        let _ = tx.send(Err((error.kind(), error.to_string())));
+        Self::pending(rx)
+    }
+
+    /// Create a new [AsyncResult] that will be delivered by the process loop
+    fn pending(rx: Receiver<Outcome>) -> Gd<Self> {
+        let result = Gd::from_init_fn(|base| Self {
+            base,
+            rx: Some(rx),
+            on_error: None,
+            done: false,
+            result: Variant::nil(),
+            error: Default::default(),
+            error_code: 0,
+        });
+        PENDING.with_borrow_mut(|pending| pending.push(result.clone()));
+        result
+    }
+
+    /// Call the given function on the main thread with the error if the
+    /// operation fails. This is used when the [AsyncResult] is not returned to
+    /// the caller (e.g. by property setters), so failures are still reported.
+    pub fn on_error<F>(&mut self, handler: F)
+    where
+        F: FnOnce(ErrorKind, &str) + 'static,
+    {
+        self.on_error = Some(Box::new(handler));
+    }
+
+    /// Emit [signal completed] for every operation that finished since the last
+    /// frame. This is called from the process loop of the [ResourceRegistry].
+    pub fn process_pending() {
+        // Skip frames where no operation finished
+        if !WAKEUP.take() {
+            return;
+        }
+
+        // Take the list so results can be created while signals are emitted
+        let results = PENDING.take();
+        let mut still_pending = vec![];
+        for mut result in results {
+            if !result.bind_mut().poll() {
+                still_pending.push(result);
+            }
+        }
+        PENDING.with_borrow_mut(|pending| pending.extend(still_pending));
+    }
+
+    /// Drop all pending results. This should be called before the extension is
+    /// unloaded.
+    pub fn clear_pending() {
+        PENDING.take();
+    }
+
+    /// Check whether the operation has finished and emit [signal completed] if it
+    /// has. Returns true once the result has been delivered.
+    fn poll(&mut self) -> bool {
+        let Some(rx) = self.rx.as_ref() else {
+            return true;
+        };
+        let outcome = match rx.try_recv() {
+            Ok(outcome) => outcome,
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Disconnected) => {
+                Err((ErrorKind::Failed, "Operation was cancelled".into()))
+            }
+        };
+        self.rx = None;
+        self.done = true;
+        match outcome {
+            Ok(convert) => self.result = convert(),
+            Err((kind, message)) => {
+                if let Some(on_error) = self.on_error.take() {
+                    on_error(kind, message.as_str());
+                }
+                self.error_code = kind.code();
+                self.error = message.into();
+            }
+        }
+
+        let result = self.result.clone();
+        let error = self.error.to_variant();
+        self.base_mut().emit_signal("completed", &[result, error]);
+        true
+    }
+
+    /// Returns true if the operation has finished
+    #[func]
+    pub fn is_completed(&self) -> bool {
+        self.done
+    }
+
+    /// Returns true if the operation finished without an error
+    #[func]
+    pub fn is_ok(&self) -> bool {
+        self.done && self.error.is_empty()
+    }
+
+    /// Returns the result of the operation, or null if it has not finished or failed
+    #[func]
+    pub fn get_result(&self) -> Variant {
+        self.result.clone()
+    }
+
+    /// Returns the error message if the operation failed
+    #[func]
+    pub fn get_error(&self) -> GString {
+        self.error.clone()
+    }
+
+    /// Returns one of the [DBusError] constants describing why the operation
+    /// failed, or [constant DBusError.OK] if it did not fail
+    #[func]
+    pub fn get_error_code(&self) -> i32 {
+        self.error_code
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+pub use std::sync::mpsc::{SendError, TryRecvError};
+
+/// Owners of the [Wakeup] flags that were raised since [take_woken] was last
+/// called
+static WOKEN: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+/// Number of events that were sent but not received yet
+static QUEUE_DEPTH: AtomicUsize = AtomicUsize::new(0);
+/// Highest number of events that were waiting to be received at the same time
+static PEAK_QUEUE_DEPTH: AtomicUsize = AtomicUsize::new(0);
+/// Number of events that were received
+static DISPATCHED: AtomicU64 = AtomicU64::new(0);
+/// Total time in nanoseconds between sending and receiving all events
+static TOTAL_LATENCY: AtomicU64 = AtomicU64::new(0);
+/// Longest time in nanoseconds between sending and receiving an event
+static MAX_LATENCY: AtomicU64 = AtomicU64::new(0);
+
+/// Snapshot of the statistics of all events sent through dispatcher channels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Number of events waiting to be received
+    pub queue_depth: usize,
+    /// Highest number of events that were waiting at the same time
+    pub peak_queue_depth: usize,
+    /// Number of events that were received
+    pub dispatched: u64,
+    /// Average time between sending and receiving an event
+    pub average_latency: Duration,
+    /// Longest time between sending and receiving an event
+    pub max_latency: Duration,
+}
+
+/// Returns the current dispatch statistics
+pub fn metrics() -> Metrics {
+    let dispatched = DISPATCHED.load(Ordering::Relaxed);
+    let total_latency = TOTAL_LATENCY.load(Ordering::Relaxed);
+    let average_latency = total_latency.checked_div(dispatched).unwrap_or_default();
+    Metrics {
+        queue_depth: QUEUE_DEPTH.load(Ordering::Relaxed),
+        peak_queue_depth: PEAK_QUEUE_DEPTH.load(Ordering::Relaxed),
+        dispatched,
+        average_latency: Duration::from_nanos(average_latency),
+        max_latency: Duration::from_nanos(MAX_LATENCY.load(Ordering::Relaxed)),
+    }
+}
+
+/// Reset the peak queue depth, dispatch count and latencies. The current queue
+/// depth is kept, since those events are still waiting.
+pub fn reset_metrics() {
+    PEAK_QUEUE_DEPTH.store(QUEUE_DEPTH.load(Ordering::Relaxed), Ordering::Relaxed);
+    DISPATCHED.store(0, Ordering::Relaxed);
+    TOTAL_LATENCY.store(0, Ordering::Relaxed);
+    MAX_LATENCY.store(0, Ordering::Relaxed);
+}
+
+/// Returns the owners of all [Wakeup] flags that were raised since the last
+/// call, in the order they were raised. Only flags with an owner set with
+/// [Wakeup::set_owner] are reported.
+pub fn take_woken() -> Vec<i64> {
+    std::mem::take(&mut *WOKEN.lock().unwrap())
+}
+
+/// Flag that is raised whenever an event is sent over one of the channels
+/// created with it. Each consumer on the main thread (e.g. a manager's
+/// `process()` method) keeps its own [Wakeup] and shares it with the channels
+/// it drains, so it can skip all work on frames where none of its events were
+/// sent. Clones share the same flag.
+#[derive(Debug, Clone)]
+pub struct Wakeup {
+    state: Arc<WakeupState>,
+}
+
+#[derive(Debug)]
+struct WakeupState {
+    pending: AtomicBool,
+    /// Identifier reported by [take_woken] when the flag is raised, or 0
+    owner: AtomicI64,
+}
+
+impl Default for Wakeup {
+    /// Returns a [Wakeup] that is pending, so the first check always processes
+    fn default() -> Self {
+        Self {
+            state: Arc::new(WakeupState {
+                pending: AtomicBool::new(true),
+                owner: AtomicI64::new(0),
+            }),
+        }
+    }
+}
+
+impl Wakeup {
+    /// Returns true if events were sent since the last call. This should be
+    /// checked before draining any channels, so events sent while draining
+    /// cause another wakeup.
+    pub fn take(&self) -> bool {
+        self.state.pending.swap(false, Ordering::AcqRel)
+    }
+
+    /// Raise the flag without sending an event. This can be used by producers
+    /// that hand data to the main thread without a channel.
+    pub fn notify(&self) {
+        if self.state.pending.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let owner = self.state.owner.load(Ordering::Acquire);
+        if owner != 0 {
+            WOKEN.lock().unwrap().push(owner);
+        }
+    }
+
+    /// Report the given non-zero owner (e.g. the instance id of the object that
+    /// drains the channels) from [take_woken] every time the flag is raised
+    pub fn set_owner(&self, owner: i64) {
+        self.state.owner.store(owner, Ordering::Release);
+    }
+}
+
+/// Create a new channel for delivering events from background tasks to the
+/// main thread. It works like [std::sync::mpsc::channel], but every sent
+/// event raises the given [Wakeup] and is counted in the dispatch [Metrics].
+pub fn channel<T>(wakeup: &Wakeup) -> (Sender<T>, Receiver<T>) {
+    let (tx, rx) = mpsc::channel();
+    let wakeup = wakeup.clone();
+    (Sender { tx, wakeup }, Receiver { rx })
+}
+
+/// Sending half of a dispatcher [channel]
+#[derive(Debug)]
+pub struct Sender<T> {
+    tx: mpsc::Sender<(Instant, T)>,
+    wakeup: Wakeup,
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            wakeup: self.wakeup.clone(),
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    /// Send the given event and raise the [Wakeup] of the channel. Fails if the
+    /// receiver was dropped.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        // Count the event before sending so the receiver never sees it first
+        let depth = QUEUE_DEPTH.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Err(SendError((_, value))) = self.tx.send((Instant::now(), value)) {
+            QUEUE_DEPTH.fetch_sub(1, Ordering::Relaxed);
+            return Err(SendError(value));
+        }
+        PEAK_QUEUE_DEPTH.fetch_max(depth, Ordering::Relaxed);
+        self.wakeup.notify();
+        Ok(())
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        // Wake up the consumer so it can notice when the channel disconnects
+        self.wakeup.notify();
+    }
+}
+
+/// Receiving half of a dispatcher [channel]
+#[derive(Debug)]
+pub struct Receiver<T> {
+    rx: mpsc::Receiver<(Instant, T)>,
+}
+
+impl<T> Receiver<T> {
+    /// Return the next event if one is waiting, without blocking
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let (sent, value) = self.rx.try_recv()?;
+        QUEUE_DEPTH.fetch_sub(1, Ordering::Relaxed);
+        let latency = sent.elapsed().as_nanos() as u64;
+        DISPATCHED.fetch_add(1, Ordering::Relaxed);
+        TOTAL_LATENCY.fetch_add(latency, Ordering::Relaxed);
+        MAX_LATENCY.fetch_max(latency, Ordering::Relaxed);
+        Ok(value)
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        // Events that were never received should not count as waiting
+        while self.rx.try_recv().is_ok() {
+            QUEUE_DEPTH.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
@@ -1,5 +1,9 @@
+use std::collections::HashMap;
+
 use godot::{classes::ResourceLoader, prelude::*};
 
+use super::{async_result::AsyncResult, dispatcher};
+
 /// Path to the main [ResourceRegistry] instance
 const RESOURCE_REGISTRY: &str = "res://core/systems/resource/resource_registry.tres";
 
@@ -15,11 +19,18 @@ const RESOURCE_REGISTRY: &str = "res://core/systems/resource/resource_registry.t
 /// var registry := load("res://path/to/registry.tres") as ResourceRegistry
 /// registry.register(self)
 /// [/codeblock]
+///
+/// Resources that only need to dispatch events from background tasks can use [method register_on_event] instead, so their [method process] method is only called on frames where events are waiting for them.
 #[derive(GodotClass)]
 #[class(init, base=Resource)]
 pub struct ResourceRegistry {
     base: Base<Resource>,
+    /// Resources processed every frame
     resources: Array<Gd<RefCounted>>,
+    /// Resources processed when woken up by events, by their instance id
+    event_resources: HashMap<i64, Gd<RefCounted>>,
+    /// Instance ids of the event resources to process on the next frame
+    woken: Vec<i64>,
     child_nodes: Array<Gd<Node>>,
 }
 
@@ -57,10 +68,36 @@ impl ResourceRegistry {
         log::trace!("Registered resources: {}", self.resources);
     }
 
+    /// Register the given resource with the registry. The given resource will have its [method process] method called by a [ResourceProcessor] only on frames where events from background tasks are waiting to be dispatched to it, and once after it was registered.
+    #[func]
+    pub fn register_on_event(&mut self, resource: Gd<RefCounted>) {
+        log::trace!("Registering resource for events: {resource}");
+        if !resource.has_method("process") {
+            log::error!(
+                "Tried to register resource for processing, but resource has no process method: {resource}"
+            );
+            return;
+        }
+        let id = resource.instance_id().to_i64();
+        if self.event_resources.insert(id, resource).is_some() {
+            log::trace!("Resource already registered");
+            return;
+        }
+
+        // Events may have been sent before the resource was registered
+        self.woken.push(id);
+        log::trace!("Registered event resources: {}", self.event_resources.len());
+    }
+
     /// Unregister the given resource from the registry.
     #[func]
     pub fn unregister(&mut self, resource: Gd<RefCounted>) {
         log::trace!("Unregistering resource: {resource}");
+        let id = resource.instance_id().to_i64();
+        if self.event_resources.remove(&id).is_some() {
+            log::trace!("Registered event resources: {}", self.event_resources.len());
+            return;
+        }
         if !self.resources.contains(&resource) {
             log::warn!("Resource is not registered: {resource}");
             return;
@@ -69,13 +106,51 @@ impl ResourceRegistry {
         log::trace!("Registered resources: {}", self.resources);
     }
 
-    /// Calls the `process()` method on all registered [Resource] objects. This should be called from a [Node] in the scene tree like the [ResourceProcessor].
+    /// Calls the `process()` method on all registered [Resource] objects, and on the resources registered with [method register_on_event] that have events waiting. This should be called from a [Node] in the scene tree like the [ResourceProcessor].
     #[func]
     pub fn process(&mut self, delta: f64) {
+        // Deliver the results of any finished async operations
+        AsyncResult::process_pending();
+
         // Call process on each registered resource
         for mut resource in self.resources.iter_shared() {
             resource.call("process", &[delta.to_variant()]);
         }
+
+        // Call process on each event resource that was woken up
+        let mut woken = std::mem::take(&mut self.woken);
+        woken.extend(dispatcher::take_woken());
+        woken.sort_unstable();
+        woken.dedup();
+        for id in woken {
+            let Some(resource) = self.event_resources.get(&id) else {
+                continue;
+            };
+            let mut resource = resource.clone();
+            resource.call("process", &[delta.to_variant()]);
+        }
+    }
+
+    /// Returns statistics about events dispatched from background tasks to the main thread. The returned dictionary contains the number of events currently waiting ("queue_depth"), the highest number of events that were waiting at the same time ("peak_queue_depth"), the number of dispatched events ("dispatched"), and the average and longest time in microseconds between an event being sent and dispatched ("average_latency_usec" and "max_latency_usec").
+    #[func]
+    pub fn get_dispatch_metrics(&self) -> Dictionary {
+        let metrics = dispatcher::metrics();
+        let mut dict = Dictionary::new();
+        dict.set("queue_depth", metrics.queue_depth as i64);
+        dict.set("peak_queue_depth", metrics.peak_queue_depth as i64);
+        dict.set("dispatched", metrics.dispatched as i64);
+        dict.set(
+            "average_latency_usec",
+            metrics.average_latency.as_micros() as i64,
+        );
+        dict.set("max_latency_usec", metrics.max_latency.as_micros() as i64);
+        dict
+    }
+
+    /// Resets the peak queue depth, dispatch count and latencies returned by [method get_dispatch_metrics]
+    #[func]
+    pub fn reset_dispatch_metrics(&self) {
+        dispatcher::reset_metrics();
     }
 
     /// Adds the given node to the [ResourceProcessor] node associated with this registry. This provides a way for resources to add nodes into the scene tree.
@@ -1,3 +1,4 @@
 pub mod command;
+pub mod process_group;
 pub mod pty;
 pub mod subreaper;
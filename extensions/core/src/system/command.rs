@@ -1,14 +1,25 @@
-use std::{
-    sync::mpsc::{channel, Receiver, Sender},
-    time::{Duration, Instant},
-};
+use std::{io::Write, os::unix::process::CommandExt, process::Stdio, time::Duration};
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
 
 use godot::{obj::WithBaseField, prelude::*};
 
-use crate::{resource::resource_registry::ResourceRegistry, RUNTIME};
+use crate::{
+    resource::dispatcher::{channel, Receiver, Sender, Wakeup},
+    resource::resource_registry::ResourceRegistry,
+    system::process_group,
+    RUNTIME,
+};
 
 /// Signals that can be emitted by this class
+#[derive(Debug, PartialEq, Eq)]
 pub enum Signal {
+    StdoutLine {
+        line: String,
+    },
+    StderrLine {
+        line: String,
+    },
     Finished {
         stdout: String,
         stderr: String,
@@ -16,10 +27,78 @@ pub enum Signal {
     },
 }
 
+/// Commands that can be sent to a running command
+#[derive(Debug)]
+pub enum ProcessCommand {
+    Cancel,
+    Signal { signal: i32 },
+}
+
+/// Commands that can be sent to the standard input of a running command
+#[derive(Debug)]
+pub enum StdinCommand {
+    Write { data: Vec<u8> },
+    Close,
+}
+
+/// Build a command that runs the given program with the given arguments in
+/// its own process group. The given environment variables are added to the
+/// environment of this process, or replace it if `clear_env` is set. An empty
+/// working directory runs the command in the current directory.
+pub fn build_command(
+    program: &str,
+    args: &[String],
+    env: &[(String, String)],
+    clear_env: bool,
+    working_dir: &str,
+) -> std::process::Command {
+    let mut command = std::process::Command::new(program);
+    command.args(args);
+    if clear_env {
+        command.env_clear();
+    }
+    for (key, value) in env {
+        command.env(key, value);
+    }
+    if !working_dir.is_empty() {
+        command.current_dir(working_dir);
+    }
+    // Run in a new process group, so every process started by the command
+    // can be signaled at once
+    command.process_group(0);
+    command
+}
+
+/// Read the given output of a command line by line until it is closed,
+/// calling the given function with each line without its line ending. Returns
+/// everything that was read.
+pub async fn read_lines<R: AsyncRead + Unpin>(
+    reader: R,
+    mut on_line: impl FnMut(String),
+) -> std::io::Result<Vec<u8>> {
+    let mut reader = BufReader::new(reader);
+    let mut output = Vec::new();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).await? == 0 {
+            return Ok(output);
+        }
+        output.extend_from_slice(line.as_slice());
+        let text = line.strip_suffix(b"\n").unwrap_or(line.as_slice());
+        let text = text.strip_suffix(b"\r").unwrap_or(text);
+        on_line(String::from_utf8_lossy(text).to_string());
+    }
+}
+
 /// Class for executing OS commands asyncronously or syncronously.
 ///
 /// The [method execute] method will start executing the given command asyncronously and will fire the [signal finished] signal when the command has completed. The [member stdout], [member stderr], and [member code] will be populated with the commands output and exit code. The [member timeout] property can also be set to abort the running command after a certain amount of time.
 ///
+/// Commands run in their own process group. Cancelling a command or reaching its timeout sends SIGTERM to every process in the group, including processes started by the command, and sends SIGKILL to any process still running after [member kill_grace_period]. Other signals can be sent to the group with [method send_signal].
+///
+/// While the command runs asyncronously, the [signal stdout_line] and [signal stderr_line] signals fire for every line the command writes. The [member env], [member clear_env] and [member working_dir] properties set the environment the command runs in, and [member stdin_data] is written to the standard input of the command when it starts. Set [member keep_stdin_open] to write more input later with [method write_stdin].
+///
 /// The [method execute_blocking] will execute the given command syncronously, blocking the main thread until the command has completed.
 ///
 /// When using the asyncronous method, the [ResourceProcessor] node [b]must[/b] be added to the scene tree or the [signal finished] signal will never fire.
@@ -27,12 +106,14 @@ pub enum Signal {
 #[class(init, base=RefCounted)]
 pub struct Command {
     base: Base<RefCounted>,
+    /// Raised when the running command sends a signal to this object
+    wakeup: Wakeup,
     /// Receiver to listen for signals emitted from the async runtime
     rx: Option<Receiver<Signal>>,
-    /// Transmitter for sending cancellation messages to the running command
-    cancel_tx: Option<tokio::sync::mpsc::Sender<()>>,
-    /// Time when the command started executing
-    start_time: Option<Instant>,
+    /// Transmitter for sending cancellation and signal messages to the running command
+    cmd_tx: Option<tokio::sync::mpsc::Sender<ProcessCommand>>,
+    /// Transmitter for writing to the standard input of the running command
+    stdin_tx: Option<tokio::sync::mpsc::Sender<StdinCommand>>,
 
     /// Command to execute
     #[var]
@@ -58,6 +139,30 @@ pub struct Command {
     #[var]
     #[init(val = Default::default())]
     timeout: f64,
+    /// Environment variables to set for the command, in addition to the environment of this process unless [member clear_env] is set.
+    #[var]
+    #[init(val = Default::default())]
+    env: Dictionary,
+    /// Whether the command should only get the variables in [member env] instead of inheriting the environment of this process.
+    #[var]
+    #[init(val = Default::default())]
+    clear_env: bool,
+    /// Directory to run the command in. An empty string runs the command in the current directory.
+    #[var]
+    #[init(val = Default::default())]
+    working_dir: GString,
+    /// Data written to the standard input of the command when it starts.
+    #[var]
+    #[init(val = Default::default())]
+    stdin_data: PackedByteArray,
+    /// Whether the standard input of the command stays open after [member stdin_data] was written, so more input can be written with [method write_stdin] until [method close_stdin] is called. Otherwise the command reads the end of its input after [member stdin_data].
+    #[var]
+    #[init(val = Default::default())]
+    keep_stdin_open: bool,
+    /// Time in seconds that the processes of a cancelled or timed out command get to exit after SIGTERM before they are killed with SIGKILL.
+    #[var]
+    #[init(val = process_group::DEFAULT_GRACE_PERIOD.as_secs_f64())]
+    kill_grace_period: f64,
 }
 
 #[godot_api]
@@ -65,6 +170,32 @@ impl Command {
     /// Exit code for cancelled commands
     #[constant]
     const EXIT_CODE_CANCEL: i32 = 130;
+    /// Signal to hang up the command, used with [method send_signal]
+    #[constant]
+    const SIGNAL_HUP: i32 = nix::sys::signal::Signal::SIGHUP as i32;
+    /// Signal to interrupt the command, used with [method send_signal]
+    #[constant]
+    const SIGNAL_INT: i32 = nix::sys::signal::Signal::SIGINT as i32;
+    /// Signal to kill the command, used with [method send_signal]
+    #[constant]
+    const SIGNAL_KILL: i32 = nix::sys::signal::Signal::SIGKILL as i32;
+    /// Signal to terminate the command, used with [method send_signal]
+    #[constant]
+    const SIGNAL_TERM: i32 = nix::sys::signal::Signal::SIGTERM as i32;
+    /// Signal to continue a stopped command, used with [method send_signal]
+    #[constant]
+    const SIGNAL_CONT: i32 = nix::sys::signal::Signal::SIGCONT as i32;
+    /// Signal to stop the command until [constant SIGNAL_CONT] is sent, used with [method send_signal]
+    #[constant]
+    const SIGNAL_STOP: i32 = nix::sys::signal::Signal::SIGSTOP as i32;
+
+    /// Emitted for each line the command writes to its standard output while executing asyncronously
+    #[signal]
+    fn stdout_line(line: GString);
+
+    /// Emitted for each line the command writes to its standard error output while executing asyncronously
+    #[signal]
+    fn stderr_line(line: GString);
 
     /// Emitted when the command has finished executing
     #[signal]
@@ -75,47 +206,85 @@ impl Command {
     fn create(command: GString, args: Array<GString>) -> Gd<Self> {
         Gd::from_init_fn(|base| Self {
             base,
+            wakeup: Default::default(),
             rx: None,
-            cancel_tx: None,
-            start_time: None,
+            cmd_tx: None,
+            stdin_tx: None,
             command,
             args,
             stdout: Default::default(),
             stderr: Default::default(),
             code: Default::default(),
             timeout: Default::default(),
+            env: Default::default(),
+            clear_env: Default::default(),
+            working_dir: Default::default(),
+            stdin_data: Default::default(),
+            keep_stdin_open: Default::default(),
+            kill_grace_period: process_group::DEFAULT_GRACE_PERIOD.as_secs_f64(),
         })
     }
 
-    /// Cancels the executing command, sending a kill signal to the running process.
+    /// Cancels the executing command, terminating every process in its process group.
     #[func]
     pub fn cancel(&mut self) {
-        let Some(cancel) = self.cancel_tx.take() else {
+        let Some(cancel) = self.cmd_tx.take() else {
             return;
         };
-        if let Err(e) = cancel.blocking_send(()) {
+        if let Err(e) = cancel.blocking_send(ProcessCommand::Cancel) {
             log::warn!("Failed to send cancellation signal: {e:?}");
         }
     }
 
+    /// Sends the given signal (e.g. [constant SIGNAL_INT]) to every process in the process group of the executing command. Returns an error code if the command is not running.
+    #[func]
+    pub fn send_signal(&self, signal: i32) -> i32 {
+        let Some(cmd_tx) = self.cmd_tx.as_ref() else {
+            log::error!("Command is not running to send signal {signal}");
+            return -1;
+        };
+        if let Err(e) = cmd_tx.blocking_send(ProcessCommand::Signal { signal }) {
+            log::error!("Failed to send signal {signal} to command: {e:?}");
+            return -1;
+        }
+        0
+    }
+
+    /// Writes the given data to the standard input of the running command. Requires [member keep_stdin_open] to be set when the command is executed. Returns an error code if the command is not running or its standard input was closed.
+    #[func]
+    pub fn write_stdin(&mut self, data: PackedByteArray) -> i32 {
+        let Some(stdin_tx) = self.stdin_tx.as_ref() else {
+            log::error!("Command is not running with an open stdin");
+            return -1;
+        };
+        let command = StdinCommand::Write {
+            data: data.to_vec(),
+        };
+        if let Err(e) = stdin_tx.blocking_send(command) {
+            log::error!("Failed to write to command stdin: {e:?}");
+            self.stdin_tx = None;
+            return -1;
+        }
+        0
+    }
+
+    /// Closes the standard input of the running command, so it reads the end of its input.
+    #[func]
+    pub fn close_stdin(&mut self) {
+        let Some(stdin_tx) = self.stdin_tx.take() else {
+            return;
+        };
+        if let Err(e) = stdin_tx.blocking_send(StdinCommand::Close) {
+            log::debug!("Failed to close command stdin: {e:?}");
+        }
+    }
+
     /// Process signals and emit them as Godot signals.
     #[func]
     pub fn process(&mut self, _delta: f64) {
-        // If a timeout was specified, check to see if the command should be
-        // cancelled.
-        if self.timeout > 0.0 {
-            if let Some(start_time) = self.start_time {
-                let elapsed = start_time.elapsed();
-                let timeout = Duration::from_secs_f64(self.timeout);
-                if elapsed > timeout {
-                    log::debug!(
-                        "Timed out waiting for command: {} {:?}",
-                        self.command,
-                        self.args
-                    );
-                    self.cancel();
-                }
-            }
+        // Skip frames where the running command sent no signals
+        if !self.wakeup.take() {
+            return;
         }
 
         // Get the signal receiver
@@ -140,6 +309,14 @@ impl Command {
     /// Process and dispatch the given signal
     fn process_signal(&mut self, signal: Signal) {
         match signal {
+            Signal::StdoutLine { line } => {
+                self.base_mut()
+                    .emit_signal("stdout_line", &[line.to_godot().to_variant()]);
+            }
+            Signal::StderrLine { line } => {
+                self.base_mut()
+                    .emit_signal("stderr_line", &[line.to_godot().to_variant()]);
+            }
             Signal::Finished {
                 stdout,
                 stderr,
@@ -149,7 +326,8 @@ impl Command {
                 self.stderr = stderr.to_godot();
                 self.code = code;
                 self.rx = None;
-                self.cancel_tx = None;
+                self.cmd_tx = None;
+                self.stdin_tx = None;
 
                 // Schedule unregistering this object from the [ResourceProcessor]
                 if let Some(registry) = ResourceRegistry::get_registry().as_mut() {
@@ -171,30 +349,50 @@ impl Command {
             return -1;
         }
 
-        // Convert the args
-        let cmd = self.command.to_string();
-        let args: Vec<String> = self.args.iter_shared().map(|v| v.to_string()).collect();
+        // Build the command
+        let command = self.build_command();
+        let timeout = Duration::try_from_secs_f64(self.timeout)
+            .ok()
+            .filter(|timeout| !timeout.is_zero());
+        let stdin_data = self.stdin_data.to_vec();
 
         // Create a communication channel
-        let (tx, rx) = channel();
+        let (tx, rx) = channel(&self.wakeup);
         self.rx = Some(rx);
 
-        // Create a second channel for sending cancel signals to the executing program
-        let (cancel_tx, cancel_rx) = tokio::sync::mpsc::channel(1);
-        self.cancel_tx = Some(cancel_tx);
+        // Create a second channel for sending cancel and other signals to the executing program
+        let (cmd_tx, cmd_rx) = tokio::sync::mpsc::channel(64);
+        self.cmd_tx = Some(cmd_tx);
+        let grace_period = Duration::try_from_secs_f64(self.kill_grace_period).unwrap_or_default();
+
+        // Create a third channel for writing to the stdin of the executing program
+        let stdin_rx = if self.keep_stdin_open {
+            let (stdin_tx, stdin_rx) = tokio::sync::mpsc::channel(64);
+            self.stdin_tx = Some(stdin_tx);
+            Some(stdin_rx)
+        } else {
+            None
+        };
 
         // Spawn a task to run the command
         RUNTIME.spawn(async move {
-            Command::run(cmd, args, tx, cancel_rx).await;
+            Command::run(
+                command,
+                stdin_data,
+                stdin_rx,
+                timeout,
+                grace_period,
+                tx,
+                cmd_rx,
+            )
+            .await;
         });
 
-        // Set the start time of the command
-        self.start_time = Some(Instant::now());
-
         // Add to [ResourceProcessor]
         if let Some(registry) = ResourceRegistry::get_registry().as_mut() {
             let this: Gd<RefCounted> = self.to_gd().upcast();
-            registry.call_deferred("register", &[this.to_variant()]);
+            self.wakeup.set_owner(this.instance_id().to_i64());
+            registry.call_deferred("register_on_event", &[this.to_variant()]);
         } else {
             log::warn!("Unable to load ResourceRegistry. Signals will not fire unless this class's 'process' method is called every frame.");
         }
@@ -205,17 +403,39 @@ impl Command {
     /// Execute the command syncronously, blocking the current thread execution until the command has completed.
     #[func]
     pub fn execute_blocking(&mut self) -> i32 {
-        // Convert the args
-        let cmd = self.command.to_string();
-        let args: Vec<String> = self.args.iter_shared().map(|v| v.to_string()).collect();
+        let mut command = self.build_command();
+        let stdin_data = self.stdin_data.to_vec();
+        let stdin = if stdin_data.is_empty() {
+            Stdio::null()
+        } else {
+            Stdio::piped()
+        };
+        let child = command
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                log::error!("Failed to execute command {command:?}: {e:?}");
+                return -1;
+            }
+        };
 
-        let output = std::process::Command::new(cmd.as_str())
-            .args(args.as_slice())
-            .output();
-        let output = match output {
+        // Write the input from another thread, so commands that produce a lot
+        // of output before reading all of their input can't block forever
+        if let Some(mut stdin) = child.stdin.take() {
+            std::thread::spawn(move || {
+                if let Err(e) = stdin.write_all(stdin_data.as_slice()) {
+                    log::debug!("Failed to write command stdin: {e:?}");
+                }
+            });
+        }
+        let output = match child.wait_with_output() {
             Ok(out) => out,
             Err(e) => {
-                log::error!("Failed to execute command '{cmd}' {args:?}: {e:?}");
+                log::error!("Failed to execute command {command:?}: {e:?}");
                 return -1;
             }
         };
@@ -231,60 +451,190 @@ impl Command {
         code
     }
 
-    /// Runs the given command asyncronously in the tokio runtime
-    async fn run(
-        cmd: String,
-        args: Vec<String>,
+    /// Build the command to execute from the properties of this class
+    fn build_command(&self) -> std::process::Command {
+        let args: Vec<String> = self.args.iter_shared().map(|v| v.to_string()).collect();
+        let env: Vec<(String, String)> = self
+            .env
+            .iter_shared()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        build_command(
+            &self.command.to_string(),
+            &args,
+            &env,
+            self.clear_env,
+            &self.working_dir.to_string(),
+        )
+    }
+
+    /// Runs the given command asyncronously in the tokio runtime, sending each
+    /// line of output and the result over the given channel. The given data is
+    /// written to the standard input of the command, followed by anything sent
+    /// over `stdin_rx` until it is closed.
+    pub async fn run(
+        command: std::process::Command,
+        stdin_data: Vec<u8>,
+        stdin_rx: Option<tokio::sync::mpsc::Receiver<StdinCommand>>,
+        timeout: Option<Duration>,
+        grace_period: Duration,
         tx: Sender<Signal>,
-        mut cancel_rx: tokio::sync::mpsc::Receiver<()>,
+        mut cmd_rx: tokio::sync::mpsc::Receiver<ProcessCommand>,
     ) {
+        let stdin = if stdin_data.is_empty() && stdin_rx.is_none() {
+            Stdio::null()
+        } else {
+            Stdio::piped()
+        };
+
         // Build the command to execute
-        let task = tokio::process::Command::new(cmd.as_str())
-            .args(args.as_slice())
+        let mut command = tokio::process::Command::from(command);
+        let child = command
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
             .kill_on_drop(true)
-            .output();
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                log::error!("Failed to execute command {command:?}: {e:?}");
+                let signal = Signal::Finished {
+                    stdout: Default::default(),
+                    stderr: Default::default(),
+                    code: -1,
+                };
+                if let Err(e) = tx.send(signal) {
+                    log::error!("Failed to send signal: {e:?}");
+                }
+                return;
+            }
+        };
+
+        // Spawn a task to write the standard input of the command, so the
+        // command can't block on its input while its output is being read
+        if let Some(stdin) = child.stdin.take() {
+            RUNTIME.spawn(Command::write_stdin_task(stdin, stdin_data, stdin_rx));
+        }
+
+        // Emit each line of output as the command writes it
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stdout_tx = tx.clone();
+        let stdout_task = read_lines(stdout, move |line| {
+            if let Err(e) = stdout_tx.send(Signal::StdoutLine { line }) {
+                log::error!("Failed to send signal: {e:?}");
+            }
+        });
+        let stderr = child.stderr.take().expect("stderr is piped");
+        let stderr_tx = tx.clone();
+        let stderr_task = read_lines(stderr, move |line| {
+            if let Err(e) = stderr_tx.send(Signal::StderrLine { line }) {
+                log::error!("Failed to send signal: {e:?}");
+            }
+        });
+        let pid = child.id();
 
         // Select between either the command completing or cancelling
-        tokio::select! {
-            // Branch if command finishes executing
-            output = task => {
-                // Construct the signal to send based on the output
-                let signal = match output {
-                    Ok(output) => {
-                        let code = output.status.code().unwrap_or_default();
-                        let stdout = String::from_utf8_lossy(output.stdout.as_slice()).to_string();
-                        let stderr = String::from_utf8_lossy(output.stderr.as_slice()).to_string();
-                        Signal::Finished {
-                            stdout,
-                            stderr,
-                            code,
-                        }
+        let output = {
+            let task = async { tokio::try_join!(child.wait(), stdout_task, stderr_task) };
+            tokio::pin!(task);
+            let timeout = Command::wait_timeout(timeout);
+            tokio::pin!(timeout);
+            loop {
+                tokio::select! {
+                    // Branch if command finishes executing
+                    output = &mut task => break Some(output),
+                    // Branch if the timeout expired
+                    _ = &mut timeout => {
+                        log::debug!("Timed out waiting for command: {command:?}");
+                        break None;
                     }
-                    Err(e) => {
-                        log::error!("Failed to execute command '{cmd}' {args:?}: {e:?}");
-                        Signal::Finished {
-                            stdout: Default::default(),
-                            stderr: Default::default(),
-                            code: -1,
+                    // Branch if a cancellation or other signal was sent
+                    cmd = cmd_rx.recv() => match cmd {
+                        Some(ProcessCommand::Signal { signal }) => {
+                            let Some(pid) = pid else {
+                                continue;
+                            };
+                            if let Err(e) = process_group::send_signal(pid, signal) {
+                                log::error!("Failed to send signal {signal} to command {command:?}: {e}");
+                            }
                         }
-                    }
-                };
+                        Some(ProcessCommand::Cancel) | None => break None,
+                    },
+                }
+            }
+        };
 
-                if let Err(e) = tx.send(signal) {
-                    log::error!("Failed to send signal: {e:?}");
+        // Construct the signal to send based on the output
+        let signal = match output {
+            Some(Ok((status, stdout, stderr))) => {
+                let code = status.code().unwrap_or_default();
+                let stdout = String::from_utf8_lossy(stdout.as_slice()).to_string();
+                let stderr = String::from_utf8_lossy(stderr.as_slice()).to_string();
+                Signal::Finished {
+                    stdout,
+                    stderr,
+                    code,
                 }
-            },
-            // Branch if cancellation signal was sent
-            _ = cancel_rx.recv() => {
-                let signal = Signal::Finished {
+            }
+            Some(Err(e)) => {
+                log::error!("Failed to execute command {command:?}: {e:?}");
+                Signal::Finished {
+                    stdout: Default::default(),
+                    stderr: Default::default(),
+                    code: -1,
+                }
+            }
+            // Terminate the command and any processes it started when it was
+            // cancelled
+            None => {
+                if let Err(e) = process_group::terminate(&mut child, grace_period).await {
+                    log::error!("Failed to terminate command {command:?}: {e:?}");
+                }
+                Signal::Finished {
                     stdout: Default::default(),
                     stderr: Default::default(),
                     code: Command::EXIT_CODE_CANCEL,
-                };
-                if let Err(e) = tx.send(signal) {
-                    log::error!("Failed to send signal: {e:?}");
                 }
             }
+        };
+        if let Err(e) = tx.send(signal) {
+            log::error!("Failed to send signal: {e:?}");
+        }
+    }
+
+    /// Write the given data to the standard input of a command, then write
+    /// any data sent over the given channel until it is closed
+    async fn write_stdin_task(
+        mut stdin: tokio::process::ChildStdin,
+        data: Vec<u8>,
+        rx: Option<tokio::sync::mpsc::Receiver<StdinCommand>>,
+    ) {
+        if let Err(e) = stdin.write_all(data.as_slice()).await {
+            log::debug!("Failed to write command stdin: {e:?}");
+            return;
+        }
+        let Some(mut rx) = rx else {
+            return;
+        };
+        while let Some(command) = rx.recv().await {
+            match command {
+                StdinCommand::Write { data } => {
+                    if let Err(e) = stdin.write_all(data.as_slice()).await {
+                        log::debug!("Failed to write command stdin: {e:?}");
+                        return;
+                    }
+                }
+                StdinCommand::Close => return,
+            }
+        }
+    }
+
+    /// Wait until the given timeout expires, or forever if there is no timeout
+    async fn wait_timeout(timeout: Option<Duration>) {
+        match timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
         }
     }
 }
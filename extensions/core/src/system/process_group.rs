@@ -0,0 +1,141 @@
+use std::{
+    io,
+    process::ExitStatus,
+    time::{Duration, Instant},
+};
+
+use nix::{
+    errno::Errno,
+    sys::signal::{killpg, Signal},
+    unistd::Pid,
+};
+use tokio::process::Child;
+
+/// Time that a process group gets to exit after SIGTERM before it is killed
+pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(5);
+/// How often a terminating process group is checked for whether it exited
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Send the given signal number (e.g. 2 for SIGINT) to every process in the
+/// process group with the given id. Child processes that are spawned with
+/// `process_group(0)` lead a group with their own PID as the id.
+pub fn send_signal(pgid: u32, signal: i32) -> Result<(), Errno> {
+    let signal = Signal::try_from(signal)?;
+    killpg(Pid::from_raw(pgid as i32), signal)
+}
+
+/// State of a process as read from `/proc/<pid>/stat`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessStat {
+    pub pid: u32,
+    /// Single character state of the process (e.g. 'R' for running or 'Z'
+    /// for zombie)
+    pub state: char,
+    pub ppid: u32,
+    pub pgid: u32,
+}
+
+impl ProcessStat {
+    /// Parse the contents of a `/proc/<pid>/stat` file
+    pub fn parse(stat: &str) -> Option<Self> {
+        // The command name can contain spaces and parentheses, so the other
+        // fields are found after its last closing parenthesis
+        let (pid, rest) = stat.split_once(" (")?;
+        let (_, rest) = rest.rsplit_once(')')?;
+        let mut fields = rest.split_whitespace();
+        let state = fields.next()?.chars().next()?;
+        let ppid = fields.next()?.parse().ok()?;
+        let pgid = fields.next()?.parse().ok()?;
+        Some(Self {
+            pid: pid.trim().parse().ok()?,
+            state,
+            ppid,
+            pgid,
+        })
+    }
+
+    /// Returns true if the process exited but was not reaped by its parent yet
+    pub fn is_zombie(&self) -> bool {
+        self.state == 'Z'
+    }
+}
+
+/// Returns the state of every process that is currently running
+pub fn processes() -> Vec<ProcessStat> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().parse::<u32>().is_ok())
+        .filter_map(|entry| std::fs::read_to_string(entry.path().join("stat")).ok())
+        .filter_map(|stat| ProcessStat::parse(stat.as_str()))
+        .collect()
+}
+
+/// Returns the PIDs of all descendants of the process with the given PID
+/// among the given processes that are still running
+pub fn descendants(processes: &[ProcessStat], pid: u32) -> Vec<u32> {
+    let mut descendants = Vec::new();
+    let mut parents = vec![pid];
+    while let Some(parent) = parents.pop() {
+        for process in processes.iter() {
+            if process.ppid != parent || process.is_zombie() {
+                continue;
+            }
+            descendants.push(process.pid);
+            parents.push(process.pid);
+        }
+    }
+    descendants.sort_unstable();
+    descendants
+}
+
+/// Returns true if any process of the process group with the given id is
+/// still running. Processes that exited but were not reaped yet are ignored.
+pub fn is_group_running(pgid: u32) -> bool {
+    processes()
+        .iter()
+        .any(|process| process.pgid == pgid && !process.is_zombie())
+}
+
+/// Terminate the process group led by the given child. The group is sent
+/// SIGTERM and gets the given grace period for all of its processes to exit,
+/// including descendants that outlive the child. Processes that are still
+/// running after that are killed with SIGKILL. Returns the exit status of the
+/// child.
+pub async fn terminate(child: &mut Child, grace_period: Duration) -> io::Result<ExitStatus> {
+    // The child was already waited for, so its group can't be found anymore
+    let Some(pgid) = child.id() else {
+        return child.wait().await;
+    };
+    if let Err(e) = send_signal(pgid, Signal::SIGTERM as i32) {
+        log::debug!("Failed to terminate process group {pgid}: {e}");
+    }
+
+    let deadline = Instant::now() + grace_period;
+    let mut status = None;
+    loop {
+        if status.is_none() {
+            status = child.try_wait()?;
+        }
+        if status.is_some() && !is_group_running(pgid) {
+            break;
+        }
+        if Instant::now() >= deadline {
+            log::debug!(
+                "Process group {pgid} is still running after {grace_period:?}. Killing it."
+            );
+            if let Err(e) = send_signal(pgid, Signal::SIGKILL as i32) {
+                log::debug!("Failed to kill process group {pgid}: {e}");
+            }
+            break;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    match status {
+        Some(status) => Ok(status),
+        None => child.wait().await,
+    }
+}
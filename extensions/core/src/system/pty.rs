@@ -1,8 +1,5 @@
 use nix::pty::{openpty, Winsize};
-use std::{
-    ffi::OsString,
-    sync::mpsc::{channel, Receiver, Sender, TryRecvError},
-};
+use std::{ffi::OsString, time::Duration};
 use tokio::{
     fs::File,
     io::{AsyncReadExt, AsyncWriteExt, BufReader, BufWriter},
@@ -12,6 +9,8 @@ use tokio::{
 
 use godot::{obj::WithBaseField, prelude::*};
 
+use crate::resource::dispatcher::{channel, Receiver, Sender, TryRecvError, Wakeup};
+use crate::system::process_group;
 use crate::RUNTIME;
 
 /// Signals that can be emitted
@@ -33,12 +32,18 @@ enum PtyCommand {
 #[derive(Debug)]
 enum ProcessCommand {
     Kill,
+    Signal { signal: i32 },
 }
 
+/// Node for running a process inside of a pseudo terminal.
+///
+/// The process runs in its own process group. [method kill] sends SIGTERM to every process in the group, including processes started by the process, and sends SIGKILL to any process still running after [member kill_grace_period]. Other signals can be sent to the group with [method send_signal].
 #[derive(GodotClass)]
 #[class(base=Node)]
 pub struct Pty {
     base: Base<Node>,
+    /// Tracks whether any events need to be dispatched this frame
+    wakeup: Wakeup,
     /// Receiver to listen for signals emitted from the async runtime
     rx: Receiver<Signal>,
     /// Transmitter to send signals from the async runtime
@@ -63,10 +68,34 @@ pub struct Pty {
     /// Height of the pseudo terminal in pixels
     #[export]
     height_px: i32,
+    /// Time in seconds that killed processes get to exit after SIGTERM
+    /// before they are killed with SIGKILL
+    #[export]
+    kill_grace_period: f64,
 }
 
 #[godot_api]
 impl Pty {
+    /// Signal to hang up the process, used with [method send_signal]
+    #[constant]
+    const SIGNAL_HUP: i32 = nix::sys::signal::Signal::SIGHUP as i32;
+    /// Signal to interrupt the process, used with [method send_signal]
+    #[constant]
+    const SIGNAL_INT: i32 = nix::sys::signal::Signal::SIGINT as i32;
+    /// Signal to kill the process, used with [method send_signal]
+    #[constant]
+    const SIGNAL_KILL: i32 = nix::sys::signal::Signal::SIGKILL as i32;
+    /// Signal to terminate the process, used with [method send_signal]
+    #[constant]
+    const SIGNAL_TERM: i32 = nix::sys::signal::Signal::SIGTERM as i32;
+    /// Signal to continue a stopped process, used with [method send_signal]
+    #[constant]
+    const SIGNAL_CONT: i32 = nix::sys::signal::Signal::SIGCONT as i32;
+    /// Signal to stop the process until [constant SIGNAL_CONT] is sent, used
+    /// with [method send_signal]
+    #[constant]
+    const SIGNAL_STOP: i32 = nix::sys::signal::Signal::SIGSTOP as i32;
+
     /// Emitted when a process is started in the PTY. Returns the PID of the
     /// started process.
     #[signal]
@@ -123,8 +152,9 @@ impl Pty {
         0
     }
 
-    /// Kill the currently running child process running in the PTY. Returns an
-    /// error code if the PTY is not currently executing a process.
+    /// Kill the currently running child process running in the PTY and any
+    /// processes it started. Returns an error code if the PTY is not currently
+    /// executing a process.
     #[func]
     fn kill(&self) -> i32 {
         let Some(cmd_tx) = self.cmd_tx.as_ref() else {
@@ -139,6 +169,23 @@ impl Pty {
         0
     }
 
+    /// Send the given signal (e.g. [constant SIGNAL_INT]) to every process in
+    /// the process group of the child process running in the PTY. Returns an
+    /// error code if the PTY is not currently executing a process.
+    #[func]
+    fn send_signal(&self, signal: i32) -> i32 {
+        let Some(cmd_tx) = self.cmd_tx.as_ref() else {
+            log::error!("PTY is not open to send signal {signal}");
+            return -1;
+        };
+        let command = ProcessCommand::Signal { signal };
+        if let Err(e) = cmd_tx.blocking_send(command) {
+            log::error!("Error sending signal command to PTY: {e:?}");
+            return -1;
+        }
+        0
+    }
+
     /// Execute the given command inside the PTY. This command is executed
     /// asyncronously and will emit signals whenever new output is available.
     #[func]
@@ -181,13 +228,15 @@ impl Pty {
 
         // Spawn a task to run the command
         let signals_tx = self.tx.clone();
+        let grace_period = Duration::try_from_secs_f64(self.kill_grace_period).unwrap_or_default();
         RUNTIME.spawn(async move {
             let mut binding = Command::new(command.clone());
             let cmd = binding
                 .args(args)
                 .stdin(stdin)
                 .stdout(stdout)
-                .stderr(stderr);
+                .stderr(stderr)
+                .process_group(0);
             let child = match cmd.spawn() {
                 Ok(child) => child,
                 Err(e) => {
@@ -210,7 +259,7 @@ impl Pty {
             }
 
             // Wait for the process to finish
-            let exit_code = Pty::process_child(child, cmd_rx).await;
+            let exit_code = Pty::process_child(child, cmd_rx, grace_period).await;
 
             // Send the exit code with the finished signal
             let signal = Signal::Finished { exit_code };
@@ -258,6 +307,7 @@ impl Pty {
             log::debug!("Finished");
         });
         self.running = true;
+        self.base_mut().set_process(true);
 
         0
     }
@@ -267,7 +317,9 @@ impl Pty {
     async fn process_child(
         mut child: Child,
         mut cmd_rx: tokio::sync::mpsc::Receiver<ProcessCommand>,
+        grace_period: Duration,
     ) -> i32 {
+        let pid = child.id();
         loop {
             select! {
                 // Handle waiting for child exit
@@ -286,7 +338,22 @@ impl Pty {
                 Some(cmd) = cmd_rx.recv() => {
                     match cmd {
                         ProcessCommand::Kill => {
-                            child.start_kill().unwrap_or_default();
+                            let status = match process_group::terminate(&mut child, grace_period).await {
+                                Ok(status) => status,
+                                Err(e) => {
+                                    log::error!("Error killing child: {e:?}");
+                                    break -1;
+                                }
+                            };
+                            break status.code().unwrap_or(0);
+                        }
+                        ProcessCommand::Signal { signal } => {
+                            let Some(pid) = pid else {
+                                continue;
+                            };
+                            if let Err(e) = process_group::send_signal(pid, signal) {
+                                log::error!("Error sending signal {signal} to child: {e}");
+                            }
                         }
                     }
                 }
@@ -349,10 +416,12 @@ impl INode for Pty {
     /// Called upon object initialization in the engine
     fn init(base: Base<Self::Base>) -> Self {
         // Create a channel to communicate with the async runtime
-        let (tx, rx) = channel();
+        let wakeup = Wakeup::default();
+        let (tx, rx) = channel(&wakeup);
 
         Self {
             base,
+            wakeup,
             rx,
             tx,
             pty_tx: None,
@@ -362,11 +431,24 @@ impl INode for Pty {
             columns: 8000,
             width_px: 8000,
             height_px: 8000,
+            kill_grace_period: process_group::DEFAULT_GRACE_PERIOD.as_secs_f64(),
         }
     }
 
-    /// Executed every engine frame
+    /// Called when the node enters the scene tree for the first time
+    fn ready(&mut self) {
+        // Only process frames while a process is running
+        let running = self.running;
+        self.base_mut().set_process(running);
+    }
+
+    /// Executed every engine frame while a process is running
     fn process(&mut self, _delta: f64) {
+        // Skip frames where the process sent no events
+        if !self.wakeup.take() {
+            return;
+        }
+
         // Drain all messages from the channel to process them
         loop {
             let signal = match self.rx.try_recv() {
@@ -381,5 +463,10 @@ impl INode for Pty {
             };
             self.process_signal(signal);
         }
+
+        // Stop processing frames once the process finished
+        if !self.running {
+            self.base_mut().set_process(false);
+        }
     }
 }
@@ -4,39 +4,250 @@
 
 use nix::{
     errno::Errno,
-    sys::{prctl, wait::wait},
-    unistd::{execvp, fork, ForkResult},
+    libc,
+    sys::{
+        prctl,
+        signal::{signal, SigHandler, Signal as UnixSignal},
+        wait::{wait, waitpid, WaitStatus},
+    },
+    unistd::{_exit, fork, ForkResult, Pid},
 };
 
-use std::ffi::{CStr, CString};
+use std::{
+    collections::HashSet,
+    ffi::{c_char, CString},
+    io::{self, PipeReader, PipeWriter, Read, Write},
+    time::Duration,
+};
+
+use godot::{obj::WithBaseField, prelude::*};
+
+use crate::{
+    resource::dispatcher::{channel, Receiver, Sender, Wakeup},
+    resource::resource_registry::ResourceRegistry,
+    system::process_group,
+    RUNTIME,
+};
+
+/// How often the descendants of a subreaper are checked for new processes
+const MONITOR_INTERVAL: Duration = Duration::from_millis(500);
+/// Exit code of a process that could not execute its command
+const EXIT_CODE_EXEC_FAILED: i32 = 127;
+
+/// Messages that a subreaper writes to its pipe about the processes it runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReaperMessage {
+    /// The command was started in the process with the given PID
+    Started { pid: u32 },
+    /// A descendant of the subreaper exited with the given status
+    Exited { pid: u32, status: i32 },
+    /// All descendants exited, and the command exited with the given status
+    AllExited { status: i32 },
+}
+
+impl ReaperMessage {
+    /// Size of an encoded message. Messages are smaller than PIPE_BUF, so
+    /// each message is written to the pipe at once.
+    pub const SIZE: usize = 12;
+
+    /// Encode the message to be written to a pipe
+    pub fn to_bytes(self) -> [u8; Self::SIZE] {
+        let (kind, pid, status) = match self {
+            Self::Started { pid } => (0u32, pid, 0i32),
+            Self::Exited { pid, status } => (1, pid, status),
+            Self::AllExited { status } => (2, 0, status),
+        };
+        let mut bytes = [0; Self::SIZE];
+        bytes[0..4].copy_from_slice(&kind.to_ne_bytes());
+        bytes[4..8].copy_from_slice(&pid.to_ne_bytes());
+        bytes[8..12].copy_from_slice(&status.to_ne_bytes());
+        bytes
+    }
+
+    /// Decode a message that was read from a pipe
+    pub fn from_bytes(bytes: [u8; Self::SIZE]) -> Option<Self> {
+        let field = |index: usize| {
+            let mut field = [0; 4];
+            field.copy_from_slice(&bytes[index * 4..index * 4 + 4]);
+            field
+        };
+        let pid = u32::from_ne_bytes(field(1));
+        let status = i32::from_ne_bytes(field(2));
+        match u32::from_ne_bytes(field(0)) {
+            0 => Some(Self::Started { pid }),
+            1 => Some(Self::Exited { pid, status }),
+            2 => Some(Self::AllExited { status }),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the PID and status of a process that exited. The status is the exit
+/// code of the process, or the negated number of the signal that terminated
+/// it. Returns `None` if the process did not exit.
+pub fn exit_status(status: WaitStatus) -> Option<(u32, i32)> {
+    match status {
+        WaitStatus::Exited(pid, code) => Some((pid.as_raw() as u32, code)),
+        WaitStatus::Signaled(pid, signal, _) => Some((pid.as_raw() as u32, -(signal as i32))),
+        _ => None,
+    }
+}
+
+/// Spawn a new subreaper process that executes the given command and waits
+/// for all of its descendants to exit. The subreaper reports its children as
+/// [ReaperMessage]s to the returned pipe, which can be dropped if nobody is
+/// interested. Returns the PID of the subreaper.
+pub fn spawn(command: &str, args: &[String]) -> io::Result<(u32, PipeReader)> {
+    // Everything is allocated before forking, as the forked copy of this
+    // multithreaded process may only call async-signal-safe functions
+    let command = CString::new(command)?;
+    let mut c_args = vec![command.clone()];
+    for arg in args {
+        c_args.push(CString::new(arg.as_str())?);
+    }
+    let mut argv: Vec<*const c_char> = c_args.iter().map(|arg| arg.as_ptr()).collect();
+    argv.push(std::ptr::null());
+    let (reader, writer) = io::pipe()?;
 
-use godot::prelude::*;
+    match unsafe { fork() }? {
+        // Parent process of the fork. Should return the subreaper process id
+        ForkResult::Parent { child } => Ok((child.as_raw() as u32, reader)),
+
+        // Child subreaper process of the fork. The subreaper process will
+        // execute the provided command and wait for all its child processes
+        // to exit.
+        ForkResult::Child => {
+            drop(reader);
+            run_subreaper(command.as_ptr(), argv.as_ptr(), writer)
+        }
+    }
+}
+
+/// Sets the current process to be a child subreaper and executes the given
+/// command. The subreaper process will wait until all children have exited
+/// before exiting itself with the exit code of the command. The arguments are
+/// a null-terminated array like `execvp` expects, which must have been built
+/// before forking.
+fn run_subreaper(command: *const c_char, argv: *const *const c_char, mut writer: PipeWriter) -> ! {
+    // Set the current process to be a subreaper. A subreaper MUST wait
+    // for all child processes to exit to clean up.
+    if prctl::set_child_subreaper(true).is_err() {
+        _exit(EXIT_CODE_EXEC_FAILED);
+    }
+
+    // Spawn the desired process
+    let main_pid = match unsafe { fork() } {
+        Ok(ForkResult::Parent { child }) => child.as_raw() as u32,
+        // Child process of the fork. Should execute the command.
+        Ok(ForkResult::Child) => {
+            // Unlike nix's execvp, this doesn't allocate the argument array
+            unsafe { libc::execvp(command, argv) };
+            _exit(EXIT_CODE_EXEC_FAILED);
+        }
+        Err(_) => _exit(EXIT_CODE_EXEC_FAILED),
+    };
+
+    // Nobody may be reading the pipe anymore. Writing to it should then fail
+    // instead of killing the subreaper. This is only set after forking, so
+    // the command doesn't inherit it.
+    let _ = unsafe { signal(UnixSignal::SIGPIPE, SigHandler::SigIgn) };
+    let mut send = |message: ReaperMessage| {
+        let _ = writer.write_all(&message.to_bytes());
+    };
+    send(ReaperMessage::Started { pid: main_pid });
+
+    // Wait for all child processes to exit
+    let mut main_status = EXIT_CODE_EXEC_FAILED;
+    loop {
+        let status = match wait() {
+            Ok(status) => status,
+            Err(Errno::EINTR) => continue,
+            Err(_) => break,
+        };
+        let Some((pid, status)) = exit_status(status) else {
+            continue;
+        };
+        if pid == main_pid {
+            main_status = status;
+        }
+        send(ReaperMessage::Exited { pid, status });
+    }
+    send(ReaperMessage::AllExited {
+        status: main_status,
+    });
+
+    // Exit without running the exit handlers of the forked process
+    let code = if main_status < 0 {
+        128 - main_status
+    } else {
+        main_status
+    };
+    _exit(code)
+}
+
+/// Signals that can be emitted by this class
+#[derive(Debug)]
+enum Signal {
+    Started { pid: u32 },
+    ChildSpawned { pid: u32 },
+    ChildExited { pid: u32, status: i32 },
+    AllExited { status: i32 },
+}
 
 /// [SubReaper] provides methods for spawning a process in a subreaper.
+///
+/// The static [method create_process] method only returns the PID of the subreaper. A [SubReaper] instance started with [method start] also reports the processes that run under the subreaper: [signal child_spawned] fires for new descendants, [signal child_exited] for every descendant that exits, and [signal all_exited] once the subreaper exited. Exit statuses are the exit code of the process, or the negated number of the signal that terminated it (e.g. -9 for SIGKILL). Processes that exit shortly after they were started may only be reported by [signal child_exited].
+///
+/// The [ResourceProcessor] node [b]must[/b] be added to the scene tree or the signals of a started [SubReaper] will never fire.
 #[derive(GodotClass)]
 #[class(base=RefCounted)]
 struct SubReaper {
     base: Base<RefCounted>,
+    /// Raised when the subreaper sends a signal to this object
+    wakeup: Wakeup,
+    /// Receiver to listen for signals emitted from the async runtime
+    rx: Option<Receiver<Signal>>,
+    /// PIDs of the descendants that are still running
+    descendants: HashSet<u32>,
+    /// PIDs of the descendants that exited while the subreaper runs, so late
+    /// reports of them being spawned are ignored
+    exited: HashSet<u32>,
+
+    /// PID of the subreaper process after [method start] was called
+    #[var(get)]
+    pid: i32,
+    /// PID of the process that executes the command after it was started
+    #[var(get)]
+    main_pid: i32,
+    /// Whether the subreaper is still running
+    #[var(get)]
+    running: bool,
 }
 
 #[godot_api]
 impl SubReaper {
+    /// Emitted when a new process is started under the subreaper, including
+    /// the process that executes the command
+    #[signal]
+    fn child_spawned(pid: i32);
+
+    /// Emitted when a process under the subreaper exited with the given status
+    #[signal]
+    fn child_exited(pid: i32, status: i32);
+
+    /// Emitted when all processes under the subreaper exited. Returns the exit
+    /// status of the process that executed the command.
+    #[signal]
+    fn all_exited(main_status: i32);
+
     /// Spawn a new subreaper process and execute the given command and arguments
     /// under that subreaper. This will force all descendent processes to reparent
     /// themselves to the subreaper instead of PID 1.
     #[func]
     pub fn create_process(command: GString, args: PackedStringArray) -> i32 {
-        match unsafe { fork() } {
-            // Parent process of the fork. Should return the subreaper process id
-            Ok(ForkResult::Parent { child }) => child.into(),
-
-            // Child subreaper process of the fork. The subreaper process will execute
-            // the provided command and wait for all its child processes to exit.
-            Ok(ForkResult::Child) => {
-                Self::subreaper_create_process(command, args);
-                0
-            }
-
+        let args: Vec<String> = args.as_slice().iter().map(String::from).collect();
+        match spawn(command.to_string().as_str(), args.as_slice()) {
+            Ok((pid, _)) => pid as i32,
             // If forking fails, return an invalid PID
             Err(e) => {
                 log::error!("Error forking command: {command} | {e}");
@@ -45,75 +256,181 @@ impl SubReaper {
         }
     }
 
-    /// Sets the current process to be a child subreaper and executes the given
-    /// command. The subreaper process will wait until all children have exited
-    /// before stopping itself.
-    fn subreaper_create_process(command: GString, args: PackedStringArray) {
-        // Set the current process to be a subreaper. A subreaper MUST wait
-        // for all child processes to exit to clean up.
-        match prctl::set_child_subreaper(true) {
-            Ok(_) => (),
+    /// Spawn a new subreaper process like [method create_process] and report
+    /// the processes that run under it with signals. Returns the PID of the
+    /// subreaper, or -1 if it could not be started.
+    #[func]
+    pub fn start(&mut self, command: GString, args: PackedStringArray) -> i32 {
+        if self.running {
+            log::error!("Subreaper is already running");
+            return -1;
+        }
+        let args: Vec<String> = args.as_slice().iter().map(String::from).collect();
+        let (pid, reader) = match spawn(command.to_string().as_str(), args.as_slice()) {
+            Ok(spawned) => spawned,
             Err(e) => {
-                panic!("Error setting child as subreaper for command: {command} | {e}");
+                log::error!("Error forking command: {command} | {e}");
+                return -1;
             }
         };
+        self.pid = pid as i32;
+        self.main_pid = 0;
+        self.running = true;
+        self.descendants.clear();
+        self.exited.clear();
 
-        // Spawn the desired process
-        match unsafe { fork() } {
-            // Parent process of the fork. Should wait for all child processes
-            // to exit.
-            Ok(ForkResult::Parent { child: _ }) => {
-                // Wait for all child processes to exit
-                loop {
-                    match wait() {
-                        Ok(_) => (),
-                        Err(e) => {
-                            if e == Errno::ECHILD {
-                                break;
-                            }
-                            println!("Got unexpected error: {e}")
-                        }
-                    };
-                }
+        // Create a communication channel
+        let (tx, rx) = channel(&self.wakeup);
+        self.rx = Some(rx);
+
+        // Spawn tasks to read the reports of the subreaper and to look for
+        // new descendants
+        let monitor_tx = tx.clone();
+        RUNTIME.spawn_blocking(move || SubReaper::read_messages(reader, pid, tx));
+        RUNTIME.spawn_blocking(move || SubReaper::monitor_descendants(pid, monitor_tx));
 
-                // No "quick_exit" exists, so instead use execvp to run
-                // a simple program to exit cleanly.
-                println!("No more children for subreaper. Exiting.");
-                let cmd = CString::new("true").unwrap();
-                match execvp::<&CStr>(cmd.as_c_str(), &[cmd.as_c_str()]) {
-                    Ok(_) => (),
-                    Err(e) => {
-                        panic!("Error exiting subreaper: {e:?}");
-                    }
+        // Add to [ResourceProcessor]
+        if let Some(registry) = ResourceRegistry::get_registry().as_mut() {
+            let this: Gd<RefCounted> = self.to_gd().upcast();
+            self.wakeup.set_owner(this.instance_id().to_i64());
+            registry.call_deferred("register_on_event", &[this.to_variant()]);
+        } else {
+            log::warn!("Unable to load ResourceRegistry. Signals will not fire unless this class's 'process' method is called every frame.");
+        }
+
+        self.pid
+    }
+
+    /// Returns the PIDs of the processes under the subreaper that are still
+    /// running
+    #[func]
+    pub fn get_descendants(&self) -> PackedInt32Array {
+        let mut descendants: Vec<i32> = self.descendants.iter().map(|pid| *pid as i32).collect();
+        descendants.sort_unstable();
+        PackedInt32Array::from(descendants.as_slice())
+    }
+
+    /// Process signals and emit them as Godot signals.
+    #[func]
+    pub fn process(&mut self, _delta: f64) {
+        // Skip frames where the subreaper sent no signals
+        if !self.wakeup.take() {
+            return;
+        }
+
+        // Get the signal receiver
+        let Some(rx) = self.rx.as_ref() else {
+            return;
+        };
+
+        // Drain all messages from the channel to process them
+        let mut signals = Vec::new();
+        while let Ok(signal) = rx.try_recv() {
+            signals.push(signal);
+        }
+
+        for signal in signals {
+            self.process_signal(signal);
+        }
+    }
+
+    /// Process and dispatch the given signal
+    fn process_signal(&mut self, signal: Signal) {
+        match signal {
+            Signal::Started { pid } => {
+                // The subreaper reports the command as started, so an earlier
+                // process with the same PID has exited for good
+                self.exited.remove(&pid);
+                self.main_pid = pid as i32;
+                self.process_signal(Signal::ChildSpawned { pid });
+            }
+            Signal::ChildSpawned { pid } => {
+                if self.exited.contains(&pid) || !self.descendants.insert(pid) {
+                    return;
                 }
+                self.base_mut()
+                    .emit_signal("child_spawned", &[pid.to_variant()]);
             }
+            Signal::ChildExited { pid, status } => {
+                self.descendants.remove(&pid);
+                self.exited.insert(pid);
+                self.base_mut()
+                    .emit_signal("child_exited", &[pid.to_variant(), status.to_variant()]);
+            }
+            Signal::AllExited { status } => {
+                self.running = false;
+                self.rx = None;
+                self.descendants.clear();
+                self.exited.clear();
 
-            // Child process of the fork. Should execute the command.
-            Ok(ForkResult::Child) => {
-                // Convert the command to a CString
-                let command = CString::new(command.to_string().as_str()).unwrap();
-
-                // Build the arguments list. The first argument in this list
-                // MUST be the command that is being executed.
-                let mut c_args: Vec<CString> = vec![command.clone()];
-                for arg in args.to_vec() {
-                    let c_arg = CString::new(arg.to_string().as_str()).unwrap();
-                    c_args.push(c_arg);
+                // Schedule unregistering this object from the [ResourceProcessor]
+                if let Some(registry) = ResourceRegistry::get_registry().as_mut() {
+                    let this: Gd<RefCounted> = self.to_gd().upcast();
+                    registry.call_deferred("unregister", &[this.to_variant()]);
                 }
 
-                // Execute the command
-                match execvp(command.as_c_str(), c_args.as_slice()) {
-                    Ok(_) => (),
-                    Err(e) => {
-                        panic!("Error executing command: {command:?} | {e}");
-                    }
+                self.base_mut()
+                    .emit_signal("all_exited", &[status.to_variant()]);
+            }
+        }
+    }
+
+    /// Read the reports of the subreaper with the given PID until it exits
+    fn read_messages(mut reader: PipeReader, reaper: u32, tx: Sender<Signal>) {
+        let mut main_status = None;
+        let mut bytes = [0; ReaperMessage::SIZE];
+        while reader.read_exact(&mut bytes).is_ok() {
+            let Some(message) = ReaperMessage::from_bytes(bytes) else {
+                continue;
+            };
+            let signal = match message {
+                ReaperMessage::Started { pid } => Signal::Started { pid },
+                ReaperMessage::Exited { pid, status } => Signal::ChildExited { pid, status },
+                ReaperMessage::AllExited { status } => {
+                    main_status = Some(status);
+                    break;
                 }
+            };
+            if let Err(e) = tx.send(signal) {
+                log::debug!("Stopped reading subreaper {reaper}: {e:?}");
+                break;
             }
+        }
+        drop(reader);
 
-            // Panic if forking fails.
-            Err(e) => {
-                panic!("Error forking subprocess for command: {command} | {e}");
+        // Clean up the subreaper, which exits after all descendants exited
+        if let Err(e) = waitpid(Pid::from_raw(reaper as i32), None) {
+            log::debug!("Failed to wait for subreaper {reaper}: {e}");
+        }
+        let status = main_status.unwrap_or(-1);
+        if let Err(e) = tx.send(Signal::AllExited { status }) {
+            log::debug!("Failed to send exit status of subreaper {reaper}: {e:?}");
+        }
+    }
+
+    /// Look for new descendants of the subreaper with the given PID every
+    /// [MONITOR_INTERVAL] until it exits
+    fn monitor_descendants(reaper: u32, tx: Sender<Signal>) {
+        let mut known = HashSet::new();
+        loop {
+            let processes = process_group::processes();
+            let running = processes
+                .iter()
+                .any(|process| process.pid == reaper && !process.is_zombie());
+            if !running {
+                return;
             }
+            let descendants = process_group::descendants(processes.as_slice(), reaper);
+            for pid in descendants.iter() {
+                if !known.insert(*pid) {
+                    continue;
+                }
+                if tx.send(Signal::ChildSpawned { pid: *pid }).is_err() {
+                    return;
+                }
+            }
+            known.retain(|pid| descendants.contains(pid));
+            std::thread::sleep(MONITOR_INTERVAL);
         }
     }
 }
@@ -121,6 +438,15 @@ impl SubReaper {
 #[godot_api]
 impl IRefCounted for SubReaper {
     fn init(base: Base<Self::Base>) -> Self {
-        Self { base }
+        Self {
+            base,
+            wakeup: Default::default(),
+            rx: None,
+            descendants: Default::default(),
+            exited: Default::default(),
+            pid: 0,
+            main_pid: 0,
+            running: false,
+        }
     }
 }
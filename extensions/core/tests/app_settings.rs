@@ -0,0 +1,108 @@
+use opengamepadui_core::gamescope::app_settings::{AppSettings, AppSettingsStore, SETTING_KEYS};
+
+#[test]
+fn test_app_settings_keys() {
+    let mut settings = AppSettings::default();
+    for (i, key) in SETTING_KEYS.iter().enumerate() {
+        assert!(settings.set(key, Some(i as u32)));
+    }
+    for (i, key) in SETTING_KEYS.iter().enumerate() {
+        let expected = if *key == "allow_tearing" {
+            (i != 0) as u32
+        } else {
+            i as u32
+        };
+        assert_eq!(settings.get(key), Some(expected));
+    }
+
+    // Unknown settings are rejected
+    assert!(!settings.set("unknown", Some(1)));
+    assert_eq!(settings.get("unknown"), None);
+
+    for key in SETTING_KEYS {
+        assert!(settings.set(key, None));
+    }
+    assert!(settings.is_empty());
+}
+
+#[test]
+fn test_app_settings_resolve() {
+    let mut store = AppSettingsStore::default();
+    store.defaults.sharpness = Some(10);
+    assert!(store.set(7, "fps_limit", Some(30)));
+    assert!(store.set(7, "allow_tearing", Some(1)));
+
+    // Apps with settings use their own values, then defaults
+    let settings = store.resolve(7);
+    assert_eq!(settings.fps_limit, Some(30));
+    assert_eq!(settings.allow_tearing, Some(true));
+    assert_eq!(settings.sharpness, Some(10));
+    assert_eq!(settings.blur_radius, None);
+
+    // Unknown apps fall back to the defaults
+    let settings = store.resolve(8);
+    assert_eq!(settings.fps_limit, None);
+    assert_eq!(settings.allow_tearing, None);
+    assert_eq!(settings.sharpness, Some(10));
+
+    // Apps without any settings left are removed
+    assert!(store.set(7, "fps_limit", None));
+    assert!(store.set(7, "allow_tearing", None));
+    assert!(store.apps.is_empty());
+    assert!(!store.set(7, "unknown", Some(1)));
+    assert!(store.apps.is_empty());
+}
+
+#[test]
+fn test_app_settings_switch() {
+    let current = |key: &str| match key {
+        "fps_limit" => 60,
+        "sharpness" => 2,
+        _ => 0,
+    };
+    let mut baseline = AppSettings::default();
+
+    // Only settings that are set are written, and their values are kept
+    let target = AppSettings {
+        fps_limit: Some(30),
+        ..Default::default()
+    };
+    let changes = baseline.switch_to(target, current);
+    assert_eq!(changes, target);
+    assert_eq!(baseline.fps_limit, Some(60));
+    assert_eq!(baseline.sharpness, None);
+
+    // Settings the previous app overrode are restored, others are left alone
+    let target = AppSettings {
+        sharpness: Some(5),
+        ..Default::default()
+    };
+    let changes = baseline.switch_to(target, current);
+    assert_eq!(
+        changes,
+        AppSettings {
+            fps_limit: Some(60),
+            sharpness: Some(5),
+            ..Default::default()
+        }
+    );
+    assert_eq!(baseline.fps_limit, None);
+    assert_eq!(baseline.sharpness, Some(2));
+
+    // The kept value is not read again while the setting stays overridden
+    let target = AppSettings {
+        sharpness: Some(8),
+        ..Default::default()
+    };
+    let changes = baseline.switch_to(target, |_| 100);
+    assert_eq!(changes, target);
+    assert_eq!(baseline.sharpness, Some(2));
+
+    // Nothing is written when no setting is set or overridden
+    let changes = baseline.switch_to(AppSettings::default(), current);
+    assert_eq!(changes.sharpness, Some(2));
+    assert!(baseline.is_empty());
+    assert!(baseline
+        .switch_to(AppSettings::default(), current)
+        .is_empty());
+}
@@ -0,0 +1,183 @@
+use opengamepadui_core::gamescope::app_tracker::{AppEvent, AppModel};
+
+/// Returns the parent of the given process in a fake process tree where
+/// 100 -> 101 -> 102 and 200 -> 201
+fn parent_of(pid: u32) -> Option<u32> {
+    match pid {
+        101 => Some(100),
+        102 => Some(101),
+        201 => Some(200),
+        _ => Some(1),
+    }
+}
+
+#[test]
+fn test_app_lifecycle() {
+    let mut model = AppModel::default();
+
+    // Windows with an app id start the app
+    model.window_created(10, 7, &[300], parent_of);
+    model.window_created(11, 7, &[300], parent_of);
+    assert_eq!(
+        model.take_events(),
+        vec![
+            AppEvent::Started {
+                app_id: 7,
+                pids: vec![300]
+            },
+            AppEvent::WindowChanged {
+                app_id: 7,
+                window_id: 10
+            },
+        ]
+    );
+    assert_eq!(model.running_apps(), vec![7]);
+
+    // Destroying the main window makes the next window the main window
+    model.window_destroyed(10);
+    assert_eq!(
+        model.take_events(),
+        vec![AppEvent::WindowChanged {
+            app_id: 7,
+            window_id: 11
+        }]
+    );
+
+    // Apps don't exit while they have windows or running processes
+    model.check_exited(|_| false);
+    assert!(model.take_events().is_empty());
+    model.window_destroyed(11);
+    assert!(model.has_windowless_apps());
+    model.check_exited(|pid| pid == 300);
+    assert!(model.take_events().is_empty());
+    model.check_exited(|_| false);
+    assert_eq!(model.take_events(), vec![AppEvent::Exited { app_id: 7 }]);
+    assert!(model.running_apps().is_empty());
+    assert!(!model.has_windowless_apps());
+}
+
+#[test]
+fn test_app_process_tree() {
+    let mut model = AppModel::default();
+    model.launched(5, 100);
+    model.launched(6, 200);
+    assert!(model.running_apps().is_empty());
+
+    // Windows without an app id belong to the app that launched their process
+    model.window_created(20, 0, &[102], parent_of);
+    model.window_created(21, 0, &[201], parent_of);
+    model.window_created(22, 0, &[400], parent_of);
+    assert_eq!(
+        model.take_events(),
+        vec![
+            AppEvent::Started {
+                app_id: 5,
+                pids: vec![100, 102]
+            },
+            AppEvent::WindowChanged {
+                app_id: 5,
+                window_id: 20
+            },
+            AppEvent::Started {
+                app_id: 6,
+                pids: vec![200, 201]
+            },
+            AppEvent::WindowChanged {
+                app_id: 6,
+                window_id: 21
+            },
+        ]
+    );
+    assert_eq!(model.app(5).unwrap().windows, vec![20]);
+    assert_eq!(model.app(6).unwrap().windows, vec![21]);
+
+    // Launched apps that never created a window exit silently
+    model.launched(8, 800);
+    model.check_exited(|pid| pid != 800);
+    assert!(model.take_events().is_empty());
+    assert!(model.app(8).is_none());
+}
+
+#[test]
+fn test_app_focus() {
+    let mut model = AppModel::default();
+    model.window_created(30, 1, &[], parent_of);
+    model.window_created(31, 2, &[], parent_of);
+    model.take_events();
+
+    model.focus_changed(1);
+    model.focus_changed(1);
+    model.focus_changed(2);
+    model.focus_changed(769);
+    model.focus_changed(1);
+    assert_eq!(
+        model.take_events(),
+        vec![
+            AppEvent::Focused { app_id: 1 },
+            AppEvent::Focused { app_id: 2 },
+            AppEvent::Focused { app_id: 769 },
+            AppEvent::Focused { app_id: 1 },
+        ]
+    );
+    assert_eq!(model.focused(), 1);
+    assert_eq!(model.focus_history(), &[2, 1]);
+
+    // Exited apps are removed from the focus history
+    model.window_destroyed(30);
+    model.check_exited(|_| false);
+    assert_eq!(model.focus_history(), &[2]);
+}
+
+#[test]
+fn test_app_id_changed() {
+    let mut model = AppModel::default();
+    model.launched(5, 100);
+
+    // Windows without an app id are assigned once they get one
+    model.window_created(40, 0, &[300], parent_of);
+    assert!(model.take_events().is_empty());
+    model.app_id_changed(40, 9, &[300], parent_of);
+    assert_eq!(
+        model.take_events(),
+        vec![
+            AppEvent::Started {
+                app_id: 9,
+                pids: vec![300]
+            },
+            AppEvent::WindowChanged {
+                app_id: 9,
+                window_id: 40
+            },
+        ]
+    );
+
+    // Setting the same app id again changes nothing
+    model.app_id_changed(40, 9, &[300], parent_of);
+    assert!(model.take_events().is_empty());
+
+    // Windows move to the app with their new app id
+    model.window_created(41, 769, &[102], parent_of);
+    model.take_events();
+    model.app_id_changed(41, 5, &[102], parent_of);
+    assert_eq!(
+        model.take_events(),
+        vec![
+            AppEvent::Started {
+                app_id: 5,
+                pids: vec![100, 102]
+            },
+            AppEvent::WindowChanged {
+                app_id: 5,
+                window_id: 41
+            },
+        ]
+    );
+    assert!(model.app(769).unwrap().windows.is_empty());
+    assert_eq!(model.app(5).unwrap().windows, vec![41]);
+
+    // Windows whose app id is removed fall back to the app of their processes
+    model.app_id_changed(40, 0, &[101], parent_of);
+    assert!(model.app(9).unwrap().windows.is_empty());
+    assert_eq!(model.app(5).unwrap().windows, vec![41, 40]);
+    assert!(model.take_events().is_empty());
+}
@@ -0,0 +1,217 @@
+mod common;
+
+use common::{
+    blocking, mock::bluez::*, next_signal, wait_for_change, wait_for_property, wait_until, TestBus,
+};
+use opengamepadui_core::{
+    bluetooth::bluez::ObjectType,
+    dbus::{
+        bluez::{
+            adapter1::{Adapter1Proxy, Adapter1ProxyBlocking},
+            device1::{Device1Proxy, Device1ProxyBlocking},
+        },
+        object_watcher::{ObjectEvent, ObjectWatcher},
+        property_cache::PropertyCache,
+    },
+    resource::dispatcher::Wakeup,
+    set_dbus_address, DBusBus,
+};
+use zbus::fdo::ObjectManagerProxy;
+
+/// Build a proxy to the ObjectManager of the mock service
+async fn object_manager(conn: &zbus::Connection) -> ObjectManagerProxy<'static> {
+    ObjectManagerProxy::builder(conn)
+        .destination(BLUEZ_BUS)
+        .unwrap()
+        .path(BLUEZ_MANAGER_PATH)
+        .unwrap()
+        .build()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_adapter_discovery() {
+    let bus = TestBus::start();
+    let _service = BluezMock::start(bus.address()).await.unwrap();
+
+    let conn = bus.connect().await;
+    let object_manager = object_manager(&conn).await;
+    let objects = object_manager.get_managed_objects().await.unwrap();
+    let adapters: Vec<_> = objects
+        .iter()
+        .filter(|(_, ifaces)| ifaces.contains_key("org.bluez.Adapter1"))
+        .map(|(path, _)| path.to_string())
+        .collect();
+    assert_eq!(adapters, vec![ADAPTER_PATH.to_string()]);
+}
+
+#[tokio::test]
+async fn test_adapter_power_and_scan() {
+    let bus = TestBus::start();
+    let _service = BluezMock::start(bus.address()).await.unwrap();
+
+    let conn = bus.connect().await;
+    let adapter = Adapter1Proxy::builder(&conn)
+        .path(ADAPTER_PATH)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(adapter.address().await.unwrap(), "00:11:22:33:44:55");
+    assert!(!adapter.powered().await.unwrap());
+
+    // Discovery fails while the adapter is powered off
+    assert!(adapter.start_discovery().await.is_err());
+
+    let mut changed = adapter.receive_powered_changed().await;
+    adapter.set_powered(true).await.unwrap();
+    assert!(wait_for_property(&mut changed, true).await);
+
+    let mut discovering = adapter.receive_discovering_changed().await;
+    adapter.start_discovery().await.unwrap();
+    assert!(wait_for_property(&mut discovering, true).await);
+    adapter.stop_discovery().await.unwrap();
+    assert!(wait_for_property(&mut discovering, false).await);
+}
+
+#[tokio::test]
+async fn test_device_hotplug() {
+    let bus = TestBus::start();
+    let service = BluezMock::start(bus.address()).await.unwrap();
+
+    let conn = bus.connect().await;
+    let object_manager = object_manager(&conn).await;
+    let mut added = object_manager.receive_interfaces_added().await.unwrap();
+    let mut removed = object_manager.receive_interfaces_removed().await.unwrap();
+
+    let path = service
+        .add_device("AA:BB:CC:DD:EE:FF", "Gamepad")
+        .await
+        .unwrap();
+    let signal = next_signal(&mut added).await.expect("InterfacesAdded");
+    let args = signal.args().unwrap();
+    assert_eq!(args.object_path.as_str(), path);
+    assert!(args
+        .interfaces_and_properties
+        .contains_key("org.bluez.Device1"));
+
+    assert!(service.remove_device(path.as_str()).await.unwrap());
+    let signal = next_signal(&mut removed).await.expect("InterfacesRemoved");
+    let args = signal.args().unwrap();
+    assert_eq!(args.object_path.as_str(), path);
+}
+
+#[tokio::test]
+async fn test_device_pair_and_connect() {
+    let bus = TestBus::start();
+    let service = BluezMock::start(bus.address()).await.unwrap();
+    let path = service
+        .add_device("AA:BB:CC:DD:EE:FF", "Gamepad")
+        .await
+        .unwrap();
+
+    let conn = bus.connect().await;
+    let device = Device1Proxy::builder(&conn)
+        .path(path)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(device.name().await.unwrap(), "Gamepad");
+    assert_eq!(device.adapter().await.unwrap().as_str(), ADAPTER_PATH);
+    assert_eq!(device.rssi().await.unwrap(), -60);
+    assert!(!device.paired().await.unwrap());
+
+    let mut paired = device.receive_paired_changed().await;
+    let mut connected = device.receive_connected_changed().await;
+    device.pair().await.unwrap();
+    assert!(wait_for_property(&mut paired, true).await);
+    device.connect().await.unwrap();
+    assert!(wait_for_property(&mut connected, true).await);
+    device.disconnect().await.unwrap();
+    assert!(wait_for_property(&mut connected, false).await);
+}
+
+// The wrappers use the shared system bus connection, so everything that
+// changes the address override lives in a single test.
+#[tokio::test]
+async fn test_wrapper_state() {
+    let bus = TestBus::start();
+    set_dbus_address(DBusBus::System, Some(bus.address()));
+    let service = BluezMock::start(bus.address()).await.unwrap();
+
+    // BluezInstance tracks adapters and devices by their interfaces
+    let wakeup = Wakeup::default();
+    let watcher_wakeup = wakeup.clone();
+    let mut objects = blocking(move || {
+        ObjectWatcher::<ObjectType>::new(
+            DBusBus::System,
+            BLUEZ_BUS,
+            BLUEZ_MANAGER_PATH,
+            &watcher_wakeup,
+        )
+    })
+    .await;
+    assert_eq!(
+        objects.paths(ObjectType::Adapter),
+        vec![ADAPTER_PATH.to_string()]
+    );
+    assert!(objects.paths(ObjectType::Device).is_empty());
+
+    // BluetoothAdapter reads its properties from the cache
+    let adapter: PropertyCache<Adapter1ProxyBlocking> = PropertyCache::new(ADAPTER_PATH, &wakeup);
+    assert!(wait_until(|| adapter.is_ready()).await);
+    let proxy = adapter.proxy().unwrap();
+    assert_eq!(
+        proxy.cached_address().unwrap().as_deref(),
+        Some("00:11:22:33:44:55")
+    );
+    assert_eq!(proxy.cached_powered().unwrap(), Some(false));
+
+    // Setting a property is reported back as a change and updates the cache
+    let adapter = blocking(move || {
+        adapter.call_proxy().unwrap().set_powered(true).unwrap();
+        adapter
+    })
+    .await;
+    assert!(wait_for_change(&adapter, "Powered").await);
+    assert!(wait_until(|| proxy.cached_powered().unwrap() == Some(true)).await);
+
+    // New devices are reported once and get their own cache
+    let path = service
+        .add_device("AA:BB:CC:DD:EE:FF", "Gamepad")
+        .await
+        .unwrap();
+    let added = ObjectEvent::Added {
+        path: path.clone(),
+        kind: ObjectType::Device,
+    };
+    assert!(wait_until(|| objects.take_events().contains(&added)).await);
+    assert!(objects.contains(path.as_str(), ObjectType::Device));
+
+    let device: PropertyCache<Device1ProxyBlocking> = PropertyCache::new(path.as_str(), &wakeup);
+    assert!(wait_until(|| device.is_ready()).await);
+    let proxy = device.proxy().unwrap();
+    assert_eq!(proxy.cached_name().unwrap().as_deref(), Some("Gamepad"));
+    assert_eq!(proxy.cached_paired().unwrap(), Some(false));
+
+    let device = blocking(move || {
+        device.call_proxy().unwrap().pair().unwrap();
+        device
+    })
+    .await;
+    assert!(wait_for_change(&device, "Paired").await);
+    assert!(wait_until(|| proxy.cached_paired().unwrap() == Some(true)).await);
+
+    // Removed devices are dropped from the registry
+    service.remove_device(path.as_str()).await.unwrap();
+    let removed = ObjectEvent::Removed {
+        path: path.clone(),
+        kind: ObjectType::Device,
+    };
+    assert!(wait_until(|| objects.take_events().contains(&removed)).await);
+    assert!(!objects.contains(path.as_str(), ObjectType::Device));
+
+    set_dbus_address(DBusBus::System, None);
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{TestDisplay, SIGNAL_TIMEOUT};
+use opengamepadui_core::{
+    gamescope::clipboard::{
+        latin1_to_string, string_to_latin1, ClipboardDisplay, ClipboardEvent, Selection,
+    },
+    resource::dispatcher::{channel, Receiver, Wakeup},
+};
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        xproto::{
+            Atom, AtomEnum, ConnectionExt, CreateWindowAux, EventMask, PropMode,
+            SelectionNotifyEvent, Window, WindowClass, SELECTION_NOTIFY_EVENT,
+        },
+        Event,
+    },
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+    COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT, CURRENT_TIME, NONE,
+};
+
+/// Wait for the next clipboard event, returning `None` if nothing arrives
+/// within [SIGNAL_TIMEOUT]
+fn next_event(rx: &Receiver<ClipboardEvent>) -> Option<ClipboardEvent> {
+    let start = Instant::now();
+    while start.elapsed() < SIGNAL_TIMEOUT {
+        if let Ok(event) = rx.try_recv() {
+            return Some(event);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    None
+}
+
+/// Wait for the next event of the given X11 connection
+fn next_x11_event(conn: &RustConnection) -> Option<Event> {
+    let start = Instant::now();
+    while start.elapsed() < SIGNAL_TIMEOUT {
+        if let Some(event) = conn.poll_for_event().unwrap() {
+            return Some(event);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    None
+}
+
+/// Create an unmapped window for the test client
+fn create_window(conn: &RustConnection, root: Window) -> Window {
+    let window = conn.generate_id().unwrap();
+    conn.create_window(
+        COPY_DEPTH_FROM_PARENT,
+        window,
+        root,
+        0,
+        0,
+        1,
+        1,
+        0,
+        WindowClass::INPUT_OUTPUT,
+        COPY_FROM_PARENT,
+        &CreateWindowAux::new(),
+    )
+    .unwrap();
+    window
+}
+
+/// Returns the atom with the given name
+fn atom(conn: &RustConnection, name: &str) -> Atom {
+    conn.intern_atom(false, name.as_bytes())
+        .unwrap()
+        .reply()
+        .unwrap()
+        .atom
+}
+
+#[test]
+fn test_latin1() {
+    assert_eq!(latin1_to_string(b"caf\xe9"), "café");
+    assert_eq!(string_to_latin1("café"), b"caf\xe9");
+    assert_eq!(string_to_latin1("5 €"), b"5 ?");
+}
+
+#[test]
+#[ignore = "requires Xvfb"]
+fn test_clipboard_display() {
+    let display = TestDisplay::start();
+    let (conn, screen) = display.connect();
+    let root = conn.setup().roots[screen].root;
+    let window = create_window(&conn, root);
+    let clipboard_atom = atom(&conn, "CLIPBOARD");
+    let utf8_string = atom(&conn, "UTF8_STRING");
+    let property = atom(&conn, "TEST_PASTE");
+
+    let (tx, rx) = channel(&Wakeup::default());
+    let clipboard = ClipboardDisplay::connect(display.name(), tx).unwrap();
+
+    // Text set on the display can be pasted by other clients
+    clipboard
+        .set_text(Selection::Clipboard, "2FA: 123456")
+        .unwrap();
+    assert_eq!(
+        clipboard.text(Selection::Clipboard).as_deref(),
+        Some("2FA: 123456")
+    );
+    conn.convert_selection(window, clipboard_atom, utf8_string, property, CURRENT_TIME)
+        .unwrap();
+    conn.flush().unwrap();
+    let Some(Event::SelectionNotify(event)) = next_x11_event(&conn) else {
+        panic!("Expected a SelectionNotify event");
+    };
+    assert_eq!(event.property, property);
+    let reply = conn
+        .get_property(true, window, property, AtomEnum::ANY, 0, 1024)
+        .unwrap()
+        .reply()
+        .unwrap();
+    assert_eq!(reply.value, b"2FA: 123456");
+
+    // Text copied by other clients is sent as an event
+    conn.set_selection_owner(window, clipboard_atom, CURRENT_TIME)
+        .unwrap();
+    conn.flush().unwrap();
+    let Some(Event::SelectionRequest(request)) = next_x11_event(&conn) else {
+        panic!("Expected a SelectionRequest event");
+    };
+    assert_eq!(request.target, utf8_string);
+    conn.change_property8(
+        PropMode::REPLACE,
+        request.requestor,
+        request.property,
+        utf8_string,
+        "héllo".as_bytes(),
+    )
+    .unwrap();
+    let notify = SelectionNotifyEvent {
+        response_type: SELECTION_NOTIFY_EVENT,
+        sequence: 0,
+        time: request.time,
+        requestor: request.requestor,
+        selection: request.selection,
+        target: request.target,
+        property: request.property,
+    };
+    conn.send_event(false, request.requestor, EventMask::NO_EVENT, notify)
+        .unwrap();
+    conn.flush().unwrap();
+    let expected = ClipboardEvent::Changed {
+        display: display.name().to_string(),
+        selection: Selection::Clipboard,
+        text: "héllo".to_string(),
+    };
+    assert_eq!(next_event(&rx), Some(expected));
+    assert_eq!(clipboard.text(Selection::Clipboard), None);
+
+    // Selections are given up when the clipboard is dropped
+    clipboard.set_text(Selection::Primary, "primary").unwrap();
+    drop(clipboard);
+    let start = Instant::now();
+    let primary_owner = || {
+        let reply = conn.get_selection_owner(AtomEnum::PRIMARY.into()).unwrap();
+        reply.reply().unwrap().owner
+    };
+    while primary_owner() != NONE && start.elapsed() < SIGNAL_TIMEOUT {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(primary_owner(), NONE);
+}
@@ -0,0 +1,163 @@
+use std::{process::Stdio, time::Duration};
+
+use opengamepadui_core::{
+    resource::dispatcher::{channel, Receiver, Wakeup},
+    system::command::{build_command, read_lines, Command, ProcessCommand, Signal, StdinCommand},
+};
+
+/// Arguments to run the given script with `/bin/sh -c`
+fn script_args(script: &str) -> Vec<String> {
+    vec!["-c".to_string(), script.to_string()]
+}
+
+/// Returns all signals that were sent over the given channel
+fn take_signals(rx: &Receiver<Signal>) -> Vec<Signal> {
+    let mut signals = Vec::new();
+    while let Ok(signal) = rx.try_recv() {
+        signals.push(signal);
+    }
+    signals
+}
+
+#[tokio::test]
+async fn test_read_lines() {
+    let input: &[u8] = b"first\r\nsecond\n\ncaf\xc3\xa9\nno newline";
+    let mut lines = Vec::new();
+    let output = read_lines(input, |line| lines.push(line)).await.unwrap();
+    assert_eq!(output, input);
+    assert_eq!(lines, vec!["first", "second", "", "café", "no newline"]);
+}
+
+#[tokio::test]
+async fn test_read_command_lines() {
+    // Lines arrive while the command is still running
+    let mut child = tokio::process::Command::new("sh")
+        .args(["-c", "echo started; read line; echo \"read $line\""])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let stdout = child.stdout.take().unwrap();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let reader = tokio::spawn(read_lines(stdout, move |line| tx.send(line).unwrap()));
+
+    assert_eq!(rx.recv().await.as_deref(), Some("started"));
+    tokio::io::AsyncWriteExt::write_all(&mut stdin, b"input\n")
+        .await
+        .unwrap();
+    assert_eq!(rx.recv().await.as_deref(), Some("read input"));
+
+    let output = reader.await.unwrap().unwrap();
+    assert_eq!(output, b"started\nread input\n");
+    assert!(child.wait().await.unwrap().success());
+}
+
+#[test]
+fn test_build_command_environment() {
+    let script = "echo \"$GREETING\"; echo \"${HOME:-unset}\"; pwd";
+    let env = vec![("GREETING".to_string(), "hello".to_string())];
+
+    // Variables are added to the environment of this process
+    let output = build_command("/bin/sh", &script_args(script), &env, false, "/")
+        .output()
+        .unwrap();
+    let home = std::env::var("HOME").unwrap_or_else(|_| "unset".to_string());
+    let expected = format!("hello\n{home}\n/\n");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+
+    // Only the given variables are set with a cleared environment
+    let output = build_command("/bin/sh", &script_args(script), &env, true, "/tmp")
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "hello\nunset\n/tmp\n"
+    );
+
+    // An empty working directory keeps the current directory
+    let output = build_command("/bin/sh", &script_args("pwd"), &[], false, "")
+        .output()
+        .unwrap();
+    let current_dir = std::env::current_dir().unwrap();
+    let expected = format!("{}\n", current_dir.display());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+}
+
+#[tokio::test]
+async fn test_run_command_stdin() {
+    let command = build_command("/bin/sh", &script_args("cat"), &[], false, "");
+    let (tx, rx) = channel(&Wakeup::default());
+    let (_cmd_tx, cmd_rx) = tokio::sync::mpsc::channel::<ProcessCommand>(1);
+    let (stdin_tx, stdin_rx) = tokio::sync::mpsc::channel(8);
+    let run = tokio::spawn(Command::run(
+        command,
+        b"first\n".to_vec(),
+        Some(stdin_rx),
+        None,
+        Duration::ZERO,
+        tx,
+        cmd_rx,
+    ));
+
+    // More input can be written after the initial data until stdin is closed
+    let data = b"second\n".to_vec();
+    stdin_tx.send(StdinCommand::Write { data }).await.unwrap();
+    stdin_tx.send(StdinCommand::Close).await.unwrap();
+    tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .expect("command should exit once stdin is closed")
+        .unwrap();
+
+    let signals = take_signals(&rx);
+    assert_eq!(
+        signals,
+        vec![
+            Signal::StdoutLine {
+                line: "first".to_string()
+            },
+            Signal::StdoutLine {
+                line: "second".to_string()
+            },
+            Signal::Finished {
+                stdout: "first\nsecond\n".to_string(),
+                stderr: String::new(),
+                code: 0,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_run_command_output_order() {
+    // Wait between lines, so lines of both outputs arrive in a known order
+    let script = "echo out1; sleep 0.2; echo err1 >&2; sleep 0.2; echo out2; echo out3; exit 3";
+    let command = build_command("/bin/sh", &script_args(script), &[], false, "");
+    let (tx, rx) = channel(&Wakeup::default());
+    let (_cmd_tx, cmd_rx) = tokio::sync::mpsc::channel::<ProcessCommand>(1);
+    Command::run(command, Vec::new(), None, None, Duration::ZERO, tx, cmd_rx).await;
+
+    let signals = take_signals(&rx);
+    assert_eq!(
+        signals,
+        vec![
+            Signal::StdoutLine {
+                line: "out1".to_string()
+            },
+            Signal::StderrLine {
+                line: "err1".to_string()
+            },
+            Signal::StdoutLine {
+                line: "out2".to_string()
+            },
+            Signal::StdoutLine {
+                line: "out3".to_string()
+            },
+            Signal::Finished {
+                stdout: "out1\nout2\nout3\n".to_string(),
+                stderr: "err1\n".to_string(),
+                code: 3,
+            },
+        ]
+    );
+}
@@ -0,0 +1,126 @@
+use std::{
+    io::{BufRead, BufReader},
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use zbus::{connection, Connection};
+
+/// Counter used to give every private bus in a test run a unique directory
+static BUS_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// A private `dbus-daemon` instance listening on a unix socket in a temporary
+/// directory. The daemon is killed and the directory removed when dropped.
+pub struct TestBus {
+    daemon: Option<Child>,
+    dir: PathBuf,
+    address: String,
+}
+
+impl TestBus {
+    /// Start a new private bus. Panics if `dbus-daemon` could not be started,
+    /// so a missing daemon fails the tests instead of skipping them.
+    pub fn start() -> Self {
+        let id = BUS_COUNT.fetch_add(1, Ordering::SeqCst);
+        let dir = std::env::temp_dir().join(format!("ogui-test-bus-{}-{id}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("temporary directory for the test bus");
+        let socket = dir.join("bus");
+        let address = format!("unix:path={}", socket.display());
+
+        let mut bus = Self {
+            daemon: None,
+            dir,
+            address,
+        };
+        if let Err(e) = bus.spawn_daemon() {
+            panic!("Failed to start dbus-daemon, which the D-Bus tests require: {e:?}");
+        }
+
+        bus
+    }
+
+    /// Returns the address of the private bus
+    pub fn address(&self) -> &str {
+        self.address.as_str()
+    }
+
+    /// Create a new client connection to the private bus
+    pub async fn connect(&self) -> Connection {
+        connection::Builder::address(self.address())
+            .expect("valid bus address")
+            .build()
+            .await
+            .expect("connection to private bus")
+    }
+
+    /// Kill the running daemon. Any connections to the bus will be dropped.
+    pub fn stop(&mut self) {
+        let Some(mut daemon) = self.daemon.take() else {
+            return;
+        };
+        daemon.kill().ok();
+        daemon.wait().ok();
+        std::fs::remove_file(self.dir.join("bus")).ok();
+    }
+
+    /// Start the daemon again on the same address after calling [TestBus::stop].
+    pub fn restart(&mut self) -> std::io::Result<()> {
+        self.stop();
+        self.spawn_daemon()
+    }
+
+    /// Write the bus configuration and spawn the daemon, waiting until it is
+    /// ready to accept connections.
+    fn spawn_daemon(&mut self) -> std::io::Result<()> {
+        let config = self.dir.join("bus.conf");
+        std::fs::write(&config, bus_config(self.address()))?;
+
+        let mut daemon = Command::new("dbus-daemon")
+            .arg(format!("--config-file={}", config.display()))
+            .arg("--nofork")
+            .arg("--print-address")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        // The daemon prints its address once it is listening
+        let stdout = daemon.stdout.take().expect("piped stdout");
+        let mut line = String::new();
+        BufReader::new(stdout).read_line(&mut line)?;
+        if line.trim().is_empty() {
+            daemon.kill().ok();
+            return Err(std::io::Error::other("dbus-daemon exited before starting"));
+        }
+        self.daemon = Some(daemon);
+
+        Ok(())
+    }
+}
+
+impl Drop for TestBus {
+    fn drop(&mut self) {
+        self.stop();
+        std::fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+/// Returns a permissive bus configuration that listens on the given address
+/// and allows any connection to own any name.
+fn bus_config(address: &str) -> String {
+    format!(
+        r#"<!DOCTYPE busconfig PUBLIC "-//freedesktop//DTD D-Bus Bus Configuration 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd">
+<busconfig>
+  <type>session</type>
+  <listen>{address}</listen>
+  <auth>EXTERNAL</auth>
+  <policy context="default">
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow eavesdrop="true"/>
+    <allow own="*"/>
+  </policy>
+</busconfig>
+"#
+    )
+}
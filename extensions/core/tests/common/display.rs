@@ -0,0 +1,59 @@
+use std::{
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+};
+
+use x11rb::rust_connection::RustConnection;
+
+/// A private `Xvfb` X11 server on a free display number. The server is killed
+/// when dropped.
+pub struct TestDisplay {
+    server: Child,
+    name: String,
+}
+
+impl TestDisplay {
+    /// Start a new X11 server. Panics if `Xvfb` could not be started. Tests
+    /// that use it are marked as ignored, so they only run when requested with
+    /// `cargo test -- --include-ignored`.
+    pub fn start() -> Self {
+        let mut server = Command::new("Xvfb")
+            .args(["-displayfd", "1", "-nolisten", "tcp"])
+            .args(["-screen", "0", "1280x800x24"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Xvfb, which the X11 tests require");
+
+        // The server prints its display number once it is ready
+        let stdout = server.stdout.take().expect("piped stdout");
+        let mut line = String::new();
+        BufReader::new(stdout).read_line(&mut line).ok();
+        if line.trim().is_empty() {
+            server.kill().ok();
+            server.wait().ok();
+            panic!("Xvfb exited before starting");
+        }
+        let name = format!(":{}", line.trim());
+
+        Self { server, name }
+    }
+
+    /// Returns the name of the display (e.g. ":99")
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Create a new client connection to the display, returning the
+    /// connection and its screen number
+    pub fn connect(&self) -> (RustConnection, usize) {
+        RustConnection::connect(Some(self.name())).expect("connection to test display")
+    }
+}
+
+impl Drop for TestDisplay {
+    fn drop(&mut self) {
+        self.server.kill().ok();
+        self.server.wait().ok();
+    }
+}
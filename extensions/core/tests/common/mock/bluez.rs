@@ -0,0 +1,189 @@
+use zbus::{connection, fdo::ObjectManager, interface, zvariant::OwnedObjectPath, Connection};
+
+pub const BLUEZ_BUS: &str = "org.bluez";
+pub const BLUEZ_MANAGER_PATH: &str = "/";
+pub const ADAPTER_PATH: &str = "/org/bluez/hci0";
+
+/// Stand-in for `org.bluez.Adapter1`
+#[derive(Debug, Default)]
+pub struct MockAdapter {
+    pub address: String,
+    pub powered: bool,
+    pub discovering: bool,
+}
+
+#[interface(name = "org.bluez.Adapter1")]
+impl MockAdapter {
+    async fn start_discovery(
+        &mut self,
+        #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        if !self.powered {
+            return Err(zbus::fdo::Error::Failed("Resource Not Ready".into()));
+        }
+        self.discovering = true;
+        self.discovering_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    async fn stop_discovery(
+        &mut self,
+        #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.discovering = false;
+        self.discovering_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn address(&self) -> String {
+        self.address.clone()
+    }
+
+    #[zbus(property)]
+    fn name(&self) -> String {
+        "mock".into()
+    }
+
+    #[zbus(property)]
+    fn alias(&self) -> String {
+        "mock".into()
+    }
+
+    #[zbus(property)]
+    fn discovering(&self) -> bool {
+        self.discovering
+    }
+
+    #[zbus(property)]
+    fn powered(&self) -> bool {
+        self.powered
+    }
+
+    #[zbus(property)]
+    fn set_powered(&mut self, value: bool) {
+        self.powered = value;
+    }
+
+    #[zbus(property, name = "UUIDs")]
+    fn uuids(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+/// Stand-in for `org.bluez.Device1`
+#[derive(Debug, Default)]
+pub struct MockDevice {
+    pub adapter: String,
+    pub address: String,
+    pub name: String,
+    pub paired: bool,
+    pub connected: bool,
+    pub rssi: i16,
+}
+
+#[interface(name = "org.bluez.Device1")]
+impl MockDevice {
+    async fn pair(
+        &mut self,
+        #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.paired = true;
+        self.paired_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    async fn connect(
+        &mut self,
+        #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.connected = true;
+        self.connected_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    async fn disconnect(
+        &mut self,
+        #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.connected = false;
+        self.connected_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn adapter(&self) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(self.adapter.as_str()).expect("valid path")
+    }
+
+    #[zbus(property)]
+    fn address(&self) -> String {
+        self.address.clone()
+    }
+
+    #[zbus(property)]
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    #[zbus(property)]
+    fn paired(&self) -> bool {
+        self.paired
+    }
+
+    #[zbus(property)]
+    fn connected(&self) -> bool {
+        self.connected
+    }
+
+    #[zbus(property, name = "RSSI")]
+    fn rssi(&self) -> i16 {
+        self.rssi
+    }
+}
+
+/// Mock BlueZ service
+pub struct BluezMock {
+    pub conn: Connection,
+}
+
+impl BluezMock {
+    /// Claim the BlueZ bus name on the given bus and serve a single adapter
+    pub async fn start(address: &str) -> zbus::Result<Self> {
+        let adapter = MockAdapter {
+            address: "00:11:22:33:44:55".into(),
+            ..Default::default()
+        };
+        let conn = connection::Builder::address(address)?
+            .name(BLUEZ_BUS)?
+            .serve_at(BLUEZ_MANAGER_PATH, ObjectManager)?
+            .serve_at(ADAPTER_PATH, adapter)?
+            .build()
+            .await?;
+
+        Ok(Self { conn })
+    }
+
+    /// Add a discovered device with the given address. Returns its path.
+    pub async fn add_device(&self, address: &str, name: &str) -> zbus::Result<String> {
+        let path = format!("{ADAPTER_PATH}/dev_{}", address.replace(':', "_"));
+        let device = MockDevice {
+            adapter: ADAPTER_PATH.to_string(),
+            address: address.to_string(),
+            name: name.to_string(),
+            rssi: -60,
+            ..Default::default()
+        };
+        self.conn.object_server().at(path.as_str(), device).await?;
+
+        Ok(path)
+    }
+
+    /// Remove the device at the given path
+    pub async fn remove_device(&self, path: &str) -> zbus::Result<bool> {
+        self.conn
+            .object_server()
+            .remove::<MockDevice, _>(path)
+            .await
+    }
+}
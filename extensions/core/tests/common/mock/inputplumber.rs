@@ -0,0 +1,147 @@
+use zbus::{connection, fdo::ObjectManager, interface, Connection};
+
+pub const INPUT_PLUMBER_BUS: &str = "org.shadowblip.InputPlumber";
+pub const INPUT_PLUMBER_PATH: &str = "/org/shadowblip/InputPlumber";
+pub const MANAGER_PATH: &str = "/org/shadowblip/InputPlumber/Manager";
+
+/// Stand-in for `org.shadowblip.InputManager`
+#[derive(Debug, Default)]
+pub struct MockInputManager {
+    pub manage_all_devices: bool,
+}
+
+#[interface(name = "org.shadowblip.InputManager")]
+impl MockInputManager {
+    #[zbus(property)]
+    fn manage_all_devices(&self) -> bool {
+        self.manage_all_devices
+    }
+
+    #[zbus(property)]
+    fn set_manage_all_devices(&mut self, value: bool) {
+        self.manage_all_devices = value;
+    }
+
+    #[zbus(property)]
+    fn intercept_mode(&self) -> String {
+        "none".into()
+    }
+}
+
+/// Stand-in for `org.shadowblip.Input.CompositeDevice`
+#[derive(Debug, Default)]
+pub struct MockCompositeDevice {
+    pub name: String,
+    pub intercept_mode: u32,
+    pub profile_name: String,
+    pub dbus_devices: Vec<String>,
+}
+
+#[interface(name = "org.shadowblip.Input.CompositeDevice")]
+impl MockCompositeDevice {
+    fn load_profile_path(&mut self, path: &str) {
+        self.profile_name = path.to_string();
+    }
+
+    fn set_intercept_activation(&self, _activation_events: Vec<String>, _target_event: &str) {}
+
+    #[zbus(property)]
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    #[zbus(property)]
+    fn profile_name(&self) -> String {
+        self.profile_name.clone()
+    }
+
+    #[zbus(property)]
+    fn intercept_mode(&self) -> u32 {
+        self.intercept_mode
+    }
+
+    #[zbus(property)]
+    fn set_intercept_mode(&mut self, value: u32) {
+        self.intercept_mode = value;
+    }
+
+    #[zbus(property)]
+    fn dbus_devices(&self) -> Vec<String> {
+        self.dbus_devices.clone()
+    }
+
+    #[zbus(property)]
+    fn capabilities(&self) -> Vec<String> {
+        vec!["Gamepad:Button:South".into(), "Gamepad:Button:Guide".into()]
+    }
+}
+
+/// Stand-in for `org.shadowblip.Input.DBusDevice`
+#[derive(Debug, Default)]
+pub struct MockDBusDevice {
+    pub name: String,
+}
+
+#[interface(name = "org.shadowblip.Input.DBusDevice")]
+impl MockDBusDevice {
+    #[zbus(property)]
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    #[zbus(signal)]
+    pub async fn input_event(
+        ctxt: &zbus::SignalContext<'_>,
+        event: &str,
+        value: f64,
+    ) -> zbus::Result<()>;
+}
+
+/// Mock InputPlumber service
+pub struct InputPlumberMock {
+    pub conn: Connection,
+}
+
+impl InputPlumberMock {
+    /// Claim the InputPlumber bus name on the given bus and serve the input
+    /// manager and object manager.
+    pub async fn start(address: &str) -> zbus::Result<Self> {
+        let conn = connection::Builder::address(address)?
+            .name(INPUT_PLUMBER_BUS)?
+            .serve_at(INPUT_PLUMBER_PATH, ObjectManager)?
+            .serve_at(MANAGER_PATH, MockInputManager::default())?
+            .build()
+            .await?;
+
+        Ok(Self { conn })
+    }
+
+    /// Add a composite device with the given index and name. Returns its path.
+    pub async fn add_composite_device(&self, index: u32, name: &str) -> zbus::Result<String> {
+        let path = format!("{INPUT_PLUMBER_PATH}/CompositeDevice{index}");
+        let dbus_path = format!("{INPUT_PLUMBER_PATH}/devices/target/dbus{index}");
+        let device = MockCompositeDevice {
+            name: name.to_string(),
+            dbus_devices: vec![dbus_path.clone()],
+            ..Default::default()
+        };
+        self.conn.object_server().at(path.as_str(), device).await?;
+        let dbus_device = MockDBusDevice {
+            name: format!("{name} DBus Device"),
+        };
+        self.conn
+            .object_server()
+            .at(dbus_path.as_str(), dbus_device)
+            .await?;
+
+        Ok(path)
+    }
+
+    /// Remove the composite device at the given path
+    pub async fn remove_composite_device(&self, path: &str) -> zbus::Result<bool> {
+        self.conn
+            .object_server()
+            .remove::<MockCompositeDevice, _>(path)
+            .await
+    }
+}
@@ -0,0 +1,9 @@
+//! Stand-in D-Bus services built with zbus. Each mock claims the well-known
+//! name of the real service on a [super::TestBus] and serves a subset of the
+//! interfaces described under `opengamepadui_core::dbus`.
+pub mod bluez;
+pub mod inputplumber;
+pub mod network_manager;
+pub mod powerstation;
+pub mod udisks2;
+pub mod upower;
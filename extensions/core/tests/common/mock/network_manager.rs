@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use zbus::{
+    connection,
+    fdo::ObjectManager,
+    interface,
+    zvariant::{OwnedObjectPath, Value},
+    Connection,
+};
+
+pub const NETWORK_MANAGER_BUS: &str = "org.freedesktop.NetworkManager";
+pub const OBJECT_MANAGER_PATH: &str = "/org/freedesktop";
+pub const NETWORK_MANAGER_PATH: &str = "/org/freedesktop/NetworkManager";
+pub const WIFI_DEVICE_PATH: &str = "/org/freedesktop/NetworkManager/Devices/1";
+
+/// NM_DEVICE_TYPE_WIFI
+const DEVICE_TYPE_WIFI: u32 = 2;
+/// NM_STATE_CONNECTED_GLOBAL
+const STATE_CONNECTED_GLOBAL: u32 = 70;
+
+/// Stand-in for `org.freedesktop.NetworkManager`
+#[derive(Debug)]
+pub struct MockNetworkManager {
+    pub wireless_enabled: bool,
+}
+
+impl Default for MockNetworkManager {
+    fn default() -> Self {
+        Self {
+            wireless_enabled: true,
+        }
+    }
+}
+
+#[interface(name = "org.freedesktop.NetworkManager")]
+impl MockNetworkManager {
+    fn get_devices(&self) -> Vec<OwnedObjectPath> {
+        vec![OwnedObjectPath::try_from(WIFI_DEVICE_PATH).expect("valid path")]
+    }
+
+    fn get_all_devices(&self) -> Vec<OwnedObjectPath> {
+        self.get_devices()
+    }
+
+    #[zbus(property)]
+    fn state(&self) -> u32 {
+        STATE_CONNECTED_GLOBAL
+    }
+
+    #[zbus(property)]
+    fn connectivity(&self) -> u32 {
+        4
+    }
+
+    #[zbus(property)]
+    fn wireless_enabled(&self) -> bool {
+        self.wireless_enabled
+    }
+
+    #[zbus(property)]
+    fn set_wireless_enabled(&mut self, value: bool) {
+        self.wireless_enabled = value;
+    }
+
+    #[zbus(property)]
+    fn primary_connection(&self) -> OwnedObjectPath {
+        OwnedObjectPath::try_from("/").expect("valid path")
+    }
+
+    #[zbus(property)]
+    fn active_connections(&self) -> Vec<OwnedObjectPath> {
+        vec![]
+    }
+}
+
+/// Stand-in for `org.freedesktop.NetworkManager.Device`
+#[derive(Debug, Default)]
+pub struct MockDevice {
+    pub interface: String,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.Device")]
+impl MockDevice {
+    #[zbus(property)]
+    fn interface(&self) -> String {
+        self.interface.clone()
+    }
+
+    #[zbus(property)]
+    fn device_type(&self) -> u32 {
+        DEVICE_TYPE_WIFI
+    }
+
+    #[zbus(property)]
+    fn state(&self) -> u32 {
+        100
+    }
+
+    #[zbus(property)]
+    fn managed(&self) -> bool {
+        true
+    }
+}
+
+/// Stand-in for `org.freedesktop.NetworkManager.Device.Wireless`
+#[derive(Debug, Default)]
+pub struct MockWireless {
+    pub access_points: Vec<String>,
+    pub scan_requests: u32,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.Device.Wireless")]
+impl MockWireless {
+    fn get_access_points(&self) -> Vec<OwnedObjectPath> {
+        self.access_points
+            .iter()
+            .map(|path| OwnedObjectPath::try_from(path.as_str()).expect("valid path"))
+            .collect()
+    }
+
+    fn get_all_access_points(&self) -> Vec<OwnedObjectPath> {
+        self.get_access_points()
+    }
+
+    fn request_scan(&mut self, _options: HashMap<String, Value<'_>>) {
+        self.scan_requests += 1;
+    }
+
+    #[zbus(signal)]
+    pub async fn access_point_added(
+        ctxt: &zbus::SignalContext<'_>,
+        access_point: OwnedObjectPath,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    pub async fn access_point_removed(
+        ctxt: &zbus::SignalContext<'_>,
+        access_point: OwnedObjectPath,
+    ) -> zbus::Result<()>;
+}
+
+/// Stand-in for `org.freedesktop.NetworkManager.AccessPoint`
+#[derive(Debug, Default)]
+pub struct MockAccessPoint {
+    pub ssid: String,
+    pub strength: u8,
+}
+
+#[interface(name = "org.freedesktop.NetworkManager.AccessPoint")]
+impl MockAccessPoint {
+    #[zbus(property)]
+    fn ssid(&self) -> Vec<u8> {
+        self.ssid.clone().into_bytes()
+    }
+
+    #[zbus(property)]
+    fn strength(&self) -> u8 {
+        self.strength
+    }
+
+    #[zbus(property)]
+    fn frequency(&self) -> u32 {
+        5180
+    }
+
+    #[zbus(property)]
+    fn hw_address(&self) -> String {
+        "AA:BB:CC:DD:EE:FF".into()
+    }
+
+    #[zbus(property)]
+    fn flags(&self) -> u32 {
+        1
+    }
+
+    #[zbus(property)]
+    fn wpa_flags(&self) -> u32 {
+        0
+    }
+
+    #[zbus(property)]
+    fn rsn_flags(&self) -> u32 {
+        0x188
+    }
+}
+
+/// Mock NetworkManager service
+pub struct NetworkManagerMock {
+    pub conn: Connection,
+}
+
+impl NetworkManagerMock {
+    /// Claim the NetworkManager bus name on the given bus and serve a single
+    /// wireless device.
+    pub async fn start(address: &str) -> zbus::Result<Self> {
+        let device = MockDevice {
+            interface: "wlan0".into(),
+        };
+        let conn = connection::Builder::address(address)?
+            .name(NETWORK_MANAGER_BUS)?
+            .serve_at(OBJECT_MANAGER_PATH, ObjectManager)?
+            .serve_at(NETWORK_MANAGER_PATH, MockNetworkManager::default())?
+            .serve_at(WIFI_DEVICE_PATH, device)?
+            .serve_at(WIFI_DEVICE_PATH, MockWireless::default())?
+            .build()
+            .await?;
+
+        Ok(Self { conn })
+    }
+
+    /// Add an access point visible to the wireless device. Returns its path.
+    pub async fn add_access_point(
+        &self,
+        index: u32,
+        ssid: &str,
+        strength: u8,
+    ) -> zbus::Result<String> {
+        let path = format!("{NETWORK_MANAGER_PATH}/AccessPoint/{index}");
+        let access_point = MockAccessPoint {
+            ssid: ssid.to_string(),
+            strength,
+        };
+        self.conn
+            .object_server()
+            .at(path.as_str(), access_point)
+            .await?;
+
+        let iface = self
+            .conn
+            .object_server()
+            .interface::<_, MockWireless>(WIFI_DEVICE_PATH)
+            .await?;
+        iface.get_mut().await.access_points.push(path.clone());
+        let object_path = OwnedObjectPath::try_from(path.as_str())?;
+        MockWireless::access_point_added(iface.signal_context(), object_path).await?;
+
+        Ok(path)
+    }
+
+    /// Update the signal strength of the access point at the given path,
+    /// emitting PropertiesChanged.
+    pub async fn set_strength(&self, path: &str, strength: u8) -> zbus::Result<()> {
+        let iface = self
+            .conn
+            .object_server()
+            .interface::<_, MockAccessPoint>(path)
+            .await?;
+        let mut access_point = iface.get_mut().await;
+        access_point.strength = strength;
+        access_point.strength_changed(iface.signal_context()).await
+    }
+
+    /// Returns the number of scans requested on the wireless device
+    pub async fn scan_requests(&self) -> zbus::Result<u32> {
+        let iface = self
+            .conn
+            .object_server()
+            .interface::<_, MockWireless>(WIFI_DEVICE_PATH)
+            .await?;
+        let scans = iface.get().await.scan_requests;
+        Ok(scans)
+    }
+}
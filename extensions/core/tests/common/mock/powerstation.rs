@@ -0,0 +1,238 @@
+use zbus::{connection, fdo::ObjectManager, interface, zvariant::OwnedObjectPath, Connection};
+
+pub const POWERSTATION_BUS: &str = "org.shadowblip.PowerStation";
+pub const POWERSTATION_PATH: &str = "/org/shadowblip/Performance";
+pub const CPU_PATH: &str = "/org/shadowblip/Performance/CPU";
+pub const GPU_PATH: &str = "/org/shadowblip/Performance/GPU";
+pub const CARD_PATH: &str = "/org/shadowblip/Performance/GPU/card0";
+
+/// Stand-in for `org.shadowblip.CPU`
+#[derive(Debug)]
+pub struct MockCpu {
+    pub boost_enabled: bool,
+    pub smt_enabled: bool,
+    pub cores_count: u32,
+    pub cores_enabled: u32,
+}
+
+impl Default for MockCpu {
+    fn default() -> Self {
+        Self {
+            boost_enabled: true,
+            smt_enabled: true,
+            cores_count: 8,
+            cores_enabled: 8,
+        }
+    }
+}
+
+#[interface(name = "org.shadowblip.CPU")]
+impl MockCpu {
+    fn enumerate_cores(&self) -> Vec<OwnedObjectPath> {
+        (0..self.cores_count)
+            .map(|i| OwnedObjectPath::try_from(format!("{CPU_PATH}/Core{i}")).expect("valid path"))
+            .collect()
+    }
+
+    fn has_feature(&self, flag: &str) -> bool {
+        flag == "smt"
+    }
+
+    #[zbus(property)]
+    fn boost_enabled(&self) -> bool {
+        self.boost_enabled
+    }
+
+    #[zbus(property)]
+    fn set_boost_enabled(&mut self, value: bool) {
+        self.boost_enabled = value;
+    }
+
+    #[zbus(property)]
+    fn cores_count(&self) -> u32 {
+        self.cores_count
+    }
+
+    #[zbus(property)]
+    fn cores_enabled(&self) -> u32 {
+        self.cores_enabled
+    }
+
+    #[zbus(property)]
+    fn set_cores_enabled(&mut self, value: u32) -> zbus::fdo::Result<()> {
+        if value == 0 || value > self.cores_count {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "Invalid core count: {value}"
+            )));
+        }
+        self.cores_enabled = value;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn features(&self) -> Vec<String> {
+        vec!["smt".into()]
+    }
+
+    #[zbus(property)]
+    fn smt_enabled(&self) -> bool {
+        self.smt_enabled
+    }
+
+    #[zbus(property)]
+    fn set_smt_enabled(&mut self, value: bool) {
+        self.smt_enabled = value;
+    }
+}
+
+/// Stand-in for `org.shadowblip.GPU`
+#[derive(Debug, Default)]
+pub struct MockGpu {
+    pub cards: Vec<String>,
+}
+
+#[interface(name = "org.shadowblip.GPU")]
+impl MockGpu {
+    fn enumerate_cards(&self) -> Vec<OwnedObjectPath> {
+        self.cards
+            .iter()
+            .map(|path| OwnedObjectPath::try_from(path.as_str()).expect("valid path"))
+            .collect()
+    }
+}
+
+/// Stand-in for `org.shadowblip.GPU.Card`
+#[derive(Debug, Default)]
+pub struct MockCard {
+    pub name: String,
+    pub manual_clock: bool,
+}
+
+#[interface(name = "org.shadowblip.GPU.Card")]
+impl MockCard {
+    fn enumerate_connectors(&self) -> Vec<OwnedObjectPath> {
+        vec![]
+    }
+
+    #[zbus(property)]
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    #[zbus(property)]
+    fn class(&self) -> String {
+        "integrated".into()
+    }
+
+    #[zbus(property)]
+    fn vendor(&self) -> String {
+        "AMD".into()
+    }
+
+    #[zbus(property)]
+    fn manual_clock(&self) -> bool {
+        self.manual_clock
+    }
+
+    #[zbus(property)]
+    fn set_manual_clock(&mut self, value: bool) {
+        self.manual_clock = value;
+    }
+}
+
+/// Stand-in for `org.shadowblip.GPU.Card.TDP`
+#[derive(Debug)]
+pub struct MockTdp {
+    pub tdp: f64,
+    pub boost: f64,
+    pub power_profile: String,
+}
+
+impl Default for MockTdp {
+    fn default() -> Self {
+        Self {
+            tdp: 15.0,
+            boost: 5.0,
+            power_profile: "power-saving".into(),
+        }
+    }
+}
+
+#[interface(name = "org.shadowblip.GPU.Card.TDP")]
+impl MockTdp {
+    #[zbus(property)]
+    fn boost(&self) -> f64 {
+        self.boost
+    }
+
+    #[zbus(property)]
+    fn set_boost(&mut self, value: f64) {
+        self.boost = value;
+    }
+
+    #[zbus(property)]
+    fn power_profiles_available(&self) -> Vec<String> {
+        vec!["power-saving".into(), "max-performance".into()]
+    }
+
+    #[zbus(property)]
+    fn power_profile(&self) -> String {
+        self.power_profile.clone()
+    }
+
+    #[zbus(property)]
+    fn set_power_profile(&mut self, value: String) {
+        self.power_profile = value;
+    }
+
+    #[zbus(property, name = "TDP")]
+    fn tdp(&self) -> f64 {
+        self.tdp
+    }
+
+    #[zbus(property, name = "TDP")]
+    fn set_tdp(&mut self, value: f64) -> zbus::fdo::Result<()> {
+        if value <= 0.0 {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "Invalid TDP: {value}"
+            )));
+        }
+        self.tdp = value;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn thermal_throttle_limit_c(&self) -> f64 {
+        95.0
+    }
+}
+
+/// Mock PowerStation service
+pub struct PowerStationMock {
+    pub conn: Connection,
+}
+
+impl PowerStationMock {
+    /// Claim the PowerStation bus name on the given bus and serve a CPU and a
+    /// single GPU card with TDP control.
+    pub async fn start(address: &str) -> zbus::Result<Self> {
+        let gpu = MockGpu {
+            cards: vec![CARD_PATH.to_string()],
+        };
+        let card = MockCard {
+            name: "card0".into(),
+            ..Default::default()
+        };
+        let conn = connection::Builder::address(address)?
+            .name(POWERSTATION_BUS)?
+            .serve_at(POWERSTATION_PATH, ObjectManager)?
+            .serve_at(CPU_PATH, MockCpu::default())?
+            .serve_at(GPU_PATH, gpu)?
+            .serve_at(CARD_PATH, card)?
+            .serve_at(CARD_PATH, MockTdp::default())?
+            .build()
+            .await?;
+
+        Ok(Self { conn })
+    }
+}
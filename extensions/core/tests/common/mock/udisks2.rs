@@ -0,0 +1,133 @@
+use zbus::{connection, fdo::ObjectManager, interface, zvariant::OwnedObjectPath, Connection};
+
+pub const UDISKS2_BUS: &str = "org.freedesktop.UDisks2";
+pub const UDISKS2_PATH: &str = "/org/freedesktop/UDisks2";
+
+/// Stand-in for `org.freedesktop.UDisks2.Drive`
+#[derive(Debug, Default)]
+pub struct MockDrive {
+    pub id: String,
+    pub model: String,
+    pub size: u64,
+}
+
+#[interface(name = "org.freedesktop.UDisks2.Drive")]
+impl MockDrive {
+    #[zbus(property)]
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    #[zbus(property)]
+    fn model(&self) -> String {
+        self.model.clone()
+    }
+
+    #[zbus(property)]
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    #[zbus(property)]
+    fn removable(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn rotation_rate(&self) -> i32 {
+        0
+    }
+}
+
+/// Stand-in for `org.freedesktop.UDisks2.Block`
+#[derive(Debug, Default)]
+pub struct MockBlock {
+    pub device: String,
+    pub drive: String,
+    pub size: u64,
+    pub id_type: String,
+}
+
+#[interface(name = "org.freedesktop.UDisks2.Block")]
+impl MockBlock {
+    #[zbus(property)]
+    fn device(&self) -> Vec<u8> {
+        // Device paths are NUL-terminated byte strings
+        let mut device = self.device.clone().into_bytes();
+        device.push(0);
+        device
+    }
+
+    #[zbus(property)]
+    fn drive(&self) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(self.drive.as_str()).expect("valid path")
+    }
+
+    #[zbus(property)]
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    #[zbus(property)]
+    fn id_type(&self) -> String {
+        self.id_type.clone()
+    }
+
+    #[zbus(property)]
+    fn read_only(&self) -> bool {
+        false
+    }
+}
+
+/// Mock UDisks2 service
+pub struct UDisks2Mock {
+    pub conn: Connection,
+}
+
+impl UDisks2Mock {
+    /// Claim the UDisks2 bus name on the given bus and serve the object manager
+    pub async fn start(address: &str) -> zbus::Result<Self> {
+        let conn = connection::Builder::address(address)?
+            .name(UDISKS2_BUS)?
+            .serve_at(UDISKS2_PATH, ObjectManager)?
+            .build()
+            .await?;
+
+        Ok(Self { conn })
+    }
+
+    /// Add a drive with the given id and a block device for it. Returns the
+    /// paths of the drive and the block device.
+    pub async fn add_drive(&self, id: &str, device: &str) -> zbus::Result<(String, String)> {
+        let drive_path = format!("{UDISKS2_PATH}/drives/{id}");
+        let drive = MockDrive {
+            id: id.to_string(),
+            model: "Mock Drive".into(),
+            size: 512 * 1024 * 1024 * 1024,
+        };
+        self.conn
+            .object_server()
+            .at(drive_path.as_str(), drive)
+            .await?;
+
+        let name = device.trim_start_matches("/dev/");
+        let block_path = format!("{UDISKS2_PATH}/block_devices/{name}");
+        let block = MockBlock {
+            device: device.to_string(),
+            drive: drive_path.clone(),
+            size: 512 * 1024 * 1024 * 1024,
+            ..Default::default()
+        };
+        self.conn
+            .object_server()
+            .at(block_path.as_str(), block)
+            .await?;
+
+        Ok((drive_path, block_path))
+    }
+
+    /// Remove the drive at the given path
+    pub async fn remove_drive(&self, path: &str) -> zbus::Result<bool> {
+        self.conn.object_server().remove::<MockDrive, _>(path).await
+    }
+}
@@ -0,0 +1,134 @@
+use zbus::{connection, interface, zvariant::OwnedObjectPath, Connection};
+
+pub const UPOWER_BUS: &str = "org.freedesktop.UPower";
+pub const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+pub const DISPLAY_DEVICE_PATH: &str = "/org/freedesktop/UPower/devices/DisplayDevice";
+
+/// Stand-in for `org.freedesktop.UPower`
+#[derive(Debug, Default)]
+pub struct MockUPower {
+    pub on_battery: bool,
+}
+
+#[interface(name = "org.freedesktop.UPower")]
+impl MockUPower {
+    fn enumerate_devices(&self) -> Vec<OwnedObjectPath> {
+        vec![OwnedObjectPath::try_from(DISPLAY_DEVICE_PATH).expect("valid path")]
+    }
+
+    fn get_critical_action(&self) -> String {
+        "PowerOff".into()
+    }
+
+    fn get_display_device(&self) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(DISPLAY_DEVICE_PATH).expect("valid path")
+    }
+
+    #[zbus(property)]
+    fn daemon_version(&self) -> String {
+        "1.90.0".into()
+    }
+
+    #[zbus(property)]
+    fn lid_is_closed(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn lid_is_present(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn on_battery(&self) -> bool {
+        self.on_battery
+    }
+}
+
+/// Stand-in for `org.freedesktop.UPower.Device`
+#[derive(Debug)]
+pub struct MockDevice {
+    pub percentage: f64,
+    pub state: u32,
+}
+
+impl Default for MockDevice {
+    fn default() -> Self {
+        Self {
+            percentage: 80.0,
+            state: 2,
+        }
+    }
+}
+
+#[interface(name = "org.freedesktop.UPower.Device")]
+impl MockDevice {
+    fn refresh(&self) {}
+
+    #[zbus(property)]
+    fn percentage(&self) -> f64 {
+        self.percentage
+    }
+
+    #[zbus(property)]
+    fn state(&self) -> u32 {
+        self.state
+    }
+
+    #[zbus(property)]
+    fn is_present(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn icon_name(&self) -> String {
+        "battery-good-symbolic".into()
+    }
+
+    #[zbus(property, name = "Type")]
+    fn type_(&self) -> u32 {
+        2
+    }
+
+    #[zbus(property)]
+    fn time_to_empty(&self) -> i64 {
+        3600
+    }
+
+    #[zbus(property)]
+    fn time_to_full(&self) -> i64 {
+        0
+    }
+}
+
+/// Mock UPower service
+pub struct UPowerMock {
+    pub conn: Connection,
+}
+
+impl UPowerMock {
+    /// Claim the UPower bus name on the given bus and serve the display device
+    pub async fn start(address: &str) -> zbus::Result<Self> {
+        let conn = connection::Builder::address(address)?
+            .name(UPOWER_BUS)?
+            .serve_at(UPOWER_PATH, MockUPower::default())?
+            .serve_at(DISPLAY_DEVICE_PATH, MockDevice::default())?
+            .build()
+            .await?;
+
+        Ok(Self { conn })
+    }
+
+    /// Update the battery percentage of the display device, emitting
+    /// PropertiesChanged.
+    pub async fn set_percentage(&self, percentage: f64) -> zbus::Result<()> {
+        let iface = self
+            .conn
+            .object_server()
+            .interface::<_, MockDevice>(DISPLAY_DEVICE_PATH)
+            .await?;
+        let mut device = iface.get_mut().await;
+        device.percentage = percentage;
+        device.percentage_changed(iface.signal_context()).await
+    }
+}
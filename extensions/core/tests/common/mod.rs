@@ -0,0 +1,101 @@
+//! Shared helpers for the D-Bus and X11 integration tests.
+//!
+//! Each integration test starts its own private `dbus-daemon` using [TestBus]
+//! and serves stand-in objects for the service under test from the [mock]
+//! module. The tests exercise the D-Bus proxies and the engine-independent
+//! plumbing (shared connections, `PropertyCache`, `ObjectWatcher`, service
+//! supervision) that the Godot wrapper classes are built on. The wrapper
+//! classes themselves need a running engine and are not constructed here.
+//! Tests fail if `dbus-daemon` is not installed. X11 tests use
+//! a private `Xvfb` server from [TestDisplay]. They are ignored by default, as
+//! `Xvfb` is rarely installed, and run with `make test-x11`.
+#![allow(dead_code)]
+
+pub mod bus;
+pub mod display;
+pub mod mock;
+
+use std::time::Duration;
+
+use futures_util::{Stream, StreamExt};
+use opengamepadui_core::dbus::property_cache::PropertyCache;
+use zbus::{
+    proxy::{PropertyStream, ProxyDefault},
+    zvariant::OwnedValue,
+};
+
+pub use bus::TestBus;
+pub use display::TestDisplay;
+
+/// How long to wait for an expected signal before failing a test
+pub const SIGNAL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Wait for the next item on the given stream, returning `None` if nothing
+/// arrives within [SIGNAL_TIMEOUT].
+pub async fn next_signal<S>(stream: &mut S) -> Option<S::Item>
+where
+    S: Stream + Unpin,
+{
+    tokio::time::timeout(SIGNAL_TIMEOUT, stream.next())
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Wait for the given property stream to report the expected value. Returns
+/// `false` if the value is not seen within [SIGNAL_TIMEOUT].
+pub async fn wait_for_property<T>(stream: &mut PropertyStream<'_, T>, expected: T) -> bool
+where
+    T: TryFrom<OwnedValue> + PartialEq + Unpin,
+    T::Error: Into<zbus::Error>,
+{
+    let wait = async {
+        while let Some(change) = stream.next().await {
+            if change.get().await.ok().as_ref() == Some(&expected) {
+                return true;
+            }
+        }
+        false
+    };
+    tokio::time::timeout(SIGNAL_TIMEOUT, wait)
+        .await
+        .unwrap_or_default()
+}
+
+/// Wait until the given condition is true, returning `false` if it does not
+/// happen within [SIGNAL_TIMEOUT].
+pub async fn wait_until(mut condition: impl FnMut() -> bool) -> bool {
+    let wait = async {
+        while !condition() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    };
+    tokio::time::timeout(SIGNAL_TIMEOUT, wait).await.is_ok()
+}
+
+/// Run the given function on a blocking thread and return its result. The
+/// wrapper classes call blocking proxies from the engine thread, which must
+/// not happen on the async runtime of a test.
+pub async fn blocking<T, F>(f: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f).await.unwrap()
+}
+
+/// Wait until the given cache reports a change of the given property, the
+/// way the wrapper classes emit `property_changed` signals. Returns `false`
+/// if the change is not seen within [SIGNAL_TIMEOUT].
+pub async fn wait_for_change<P>(cache: &PropertyCache<P>, name: &str) -> bool
+where
+    P: ProxyDefault + From<zbus::Proxy<'static>>,
+{
+    wait_until(|| {
+        cache
+            .take_changes()
+            .iter()
+            .any(|(changed, _)| changed == name)
+    })
+    .await
+}
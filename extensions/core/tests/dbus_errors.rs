@@ -0,0 +1,48 @@
+mod common;
+
+use common::{mock::powerstation::*, TestBus};
+use opengamepadui_core::dbus::{powerstation::tdp::TDPProxy, ErrorKind, RunError};
+use zbus::proxy::CacheProperties;
+
+#[tokio::test]
+async fn test_error_kinds() {
+    let bus = TestBus::start();
+    let conn = bus.connect().await;
+    let tdp = TDPProxy::builder(&conn)
+        .path(CARD_PATH)
+        .unwrap()
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+        .unwrap();
+
+    // Calls should fail as not running until the service is started
+    let error = RunError::from(tdp.tdp().await.unwrap_err());
+    assert_eq!(error.kind(), ErrorKind::ServiceNotRunning);
+
+    let _service = PowerStationMock::start(bus.address()).await.unwrap();
+    assert_eq!(tdp.tdp().await.unwrap(), 15.0);
+
+    // Values rejected by the service should be invalid arguments
+    let error = RunError::from(tdp.set_tdp(-1.0).await.unwrap_err());
+    assert_eq!(error.kind(), ErrorKind::InvalidArgument);
+    assert!(error.to_string().contains("Invalid TDP"));
+
+    // Service specific error names should be classified by their last part
+    let kinds = [
+        (
+            "org.freedesktop.DBus.Error.AccessDenied",
+            ErrorKind::PermissionDenied,
+        ),
+        ("org.bluez.Error.NotAuthorized", ErrorKind::PermissionDenied),
+        (
+            "org.bluez.Error.InvalidArguments",
+            ErrorKind::InvalidArgument,
+        ),
+        ("org.freedesktop.DBus.Error.NoReply", ErrorKind::Timeout),
+        ("org.bluez.Error.Failed", ErrorKind::Failed),
+    ];
+    for (name, kind) in kinds {
+        assert_eq!(ErrorKind::from_error_name(name), kind, "{name}");
+    }
+}
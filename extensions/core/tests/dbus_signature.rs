@@ -0,0 +1,89 @@
+use opengamepadui_core::dbus::{split_signatures, VariantConversionError};
+
+/// Returns the error message of splitting the given signature
+fn split_error(signature: &str) -> String {
+    split_signatures(signature).unwrap_err().to_string()
+}
+
+#[test]
+fn test_split_basic_types() {
+    assert_eq!(
+        split_signatures("ybnqiuxtdsogv").unwrap(),
+        vec!["y", "b", "n", "q", "i", "u", "x", "t", "d", "s", "o", "g", "v"]
+    );
+    assert!(split_signatures("").unwrap().is_empty());
+}
+
+#[test]
+fn test_split_container_types() {
+    assert_eq!(
+        split_signatures("sa{sv}(ii)").unwrap(),
+        vec!["s", "a{sv}", "(ii)"]
+    );
+    assert_eq!(
+        split_signatures("aaya{sa{sv}}a(sai)").unwrap(),
+        vec!["aay", "a{sa{sv}}", "a(sai)"]
+    );
+    assert_eq!(split_signatures("((i(s))v)").unwrap(), vec!["((i(s))v)"]);
+}
+
+/// Assert that every given signature is rejected
+fn assert_invalid(signatures: &[&str]) {
+    for signature in signatures {
+        assert!(
+            split_signatures(signature).is_err(),
+            "'{signature}' should be invalid"
+        );
+    }
+}
+
+#[test]
+fn test_split_invalid_signatures() {
+    // Unknown and incomplete types
+    assert_invalid(&["z", "a", "(", "(ii", "a{sv", "i)"]);
+    // Structs need at least one field
+    assert_invalid(&["()", "a()"]);
+    // Dictionary entries must be array elements with a basic key type
+    assert_invalid(&["{sv}", "a{vs}", "a{ass}", "a{(i)s}", "a{s}", "a{sss}"]);
+    // File descriptors cannot be created from Godot values
+    assert_invalid(&["h", "ah", "a{sh}", "a{hs}", "(ih)"]);
+    assert_eq!(split_error("sh"), "invalid DBus signature 'h'");
+}
+
+#[test]
+fn test_conversion_error_messages() {
+    let error = VariantConversionError::InvalidValue {
+        signature: "o".into(),
+        reason: "invalid object path".into(),
+    };
+    assert_eq!(
+        error.to_string(),
+        "invalid value for DBus type 'o': invalid object path"
+    );
+
+    let error = VariantConversionError::OutOfRange {
+        signature: "y".into(),
+        value: 256,
+    };
+    assert_eq!(
+        error.to_string(),
+        "value 256 is out of range for DBus type 'y'"
+    );
+
+    // Nested errors report the full location of the failing value
+    let error = VariantConversionError::Nested {
+        location: "argument 1".into(),
+        error: Box::new(VariantConversionError::Nested {
+            location: "[\"ipv4\"]".into(),
+            error: Box::new(VariantConversionError::LengthMismatch {
+                signature: "(ss)".into(),
+                expected: 2,
+                found: 3,
+            }),
+        }),
+    };
+    assert_eq!(
+        error.to_string(),
+        "signature '(ss)' expects 2 value(s), but 3 were given at argument 1[\"ipv4\"]"
+    );
+}
@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use opengamepadui_core::resource::dispatcher::{
+    channel, metrics, reset_metrics, take_woken, TryRecvError, Wakeup,
+};
+
+#[test]
+fn test_dispatcher() {
+    let wakeup = Wakeup::default();
+    let other = Wakeup::default();
+    other.take();
+
+    // A new wakeup is pending so the first frame always processes
+    assert!(wakeup.take());
+    assert!(!wakeup.take());
+
+    // Sending events wakes up the consumer of the channel and is counted as
+    // waiting. Other consumers are not woken up.
+    let (tx, rx) = channel(&wakeup);
+    tx.send(1).unwrap();
+    tx.send(2).unwrap();
+    tx.send(3).unwrap();
+    assert!(wakeup.take());
+    assert!(!wakeup.take());
+    assert!(!other.take());
+    let stats = metrics();
+    assert_eq!(stats.queue_depth, 3);
+    assert_eq!(stats.peak_queue_depth, 3);
+    assert_eq!(stats.dispatched, 0);
+
+    // Receiving events records them as dispatched
+    std::thread::sleep(Duration::from_millis(10));
+    assert_eq!(rx.try_recv(), Ok(1));
+    assert_eq!(rx.try_recv(), Ok(2));
+    assert_eq!(rx.try_recv(), Ok(3));
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    let stats = metrics();
+    assert_eq!(stats.queue_depth, 0);
+    assert_eq!(stats.peak_queue_depth, 3);
+    assert_eq!(stats.dispatched, 3);
+    assert!(stats.max_latency >= Duration::from_millis(10));
+    assert!(stats.average_latency >= Duration::from_millis(10));
+    assert!(stats.average_latency <= stats.max_latency);
+
+    // Events can be sent from other threads
+    let sender = tx.clone();
+    std::thread::spawn(move || sender.send(4).unwrap())
+        .join()
+        .unwrap();
+    assert!(wakeup.take());
+    assert_eq!(rx.try_recv(), Ok(4));
+
+    // Notifying wakes up the consumer without sending an event
+    wakeup.notify();
+    assert!(wakeup.take());
+    assert!(!wakeup.take());
+
+    // Owners are reported once each time their flag is raised
+    assert!(take_woken().is_empty());
+    wakeup.set_owner(42);
+    wakeup.notify();
+    tx.send(5).unwrap();
+    assert_eq!(take_woken(), vec![42]);
+    assert!(take_woken().is_empty());
+    assert!(wakeup.take());
+    assert_eq!(rx.try_recv(), Ok(5));
+    wakeup.notify();
+    assert_eq!(take_woken(), vec![42]);
+    wakeup.take();
+    wakeup.set_owner(0);
+
+    // Resetting keeps the events that are still waiting
+    tx.send(6).unwrap();
+    reset_metrics();
+    let stats = metrics();
+    assert_eq!(stats.queue_depth, 1);
+    assert_eq!(stats.peak_queue_depth, 1);
+    assert_eq!(stats.dispatched, 0);
+    assert_eq!(stats.average_latency, Duration::ZERO);
+    assert_eq!(stats.max_latency, Duration::ZERO);
+
+    // Dropping the receiver discards waiting events and disconnects senders
+    drop(rx);
+    assert_eq!(metrics().queue_depth, 0);
+    assert_eq!(tx.send(7).map_err(|e| e.0), Err(7));
+    assert_eq!(metrics().queue_depth, 0);
+
+    // Dropping all senders wakes up consumers to notice the disconnect
+    let (tx, rx) = channel::<i32>(&wakeup);
+    wakeup.take();
+    drop(tx);
+    assert!(wakeup.take());
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+}
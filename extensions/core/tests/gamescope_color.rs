@@ -0,0 +1,80 @@
+use opengamepadui_core::gamescope::color::{
+    shaper_lut, temperature_gains, NightLightSchedule, NEUTRAL_TEMPERATURE,
+};
+
+#[test]
+fn test_night_light_schedule() {
+    // 20:00 until 07:00 with a one hour transition
+    let schedule = NightLightSchedule {
+        start: 20 * 60,
+        end: 7 * 60,
+        transition: 60,
+    };
+    assert_eq!(schedule.amount(12.0 * 60.0), 0.0);
+    assert_eq!(schedule.amount(20.0 * 60.0), 0.0);
+    assert_eq!(schedule.amount(20.5 * 60.0), 0.5);
+    assert_eq!(schedule.amount(21.0 * 60.0), 1.0);
+    assert_eq!(schedule.amount(0.0), 1.0);
+    assert_eq!(schedule.amount(6.5 * 60.0), 0.5);
+    assert_eq!(schedule.amount(7.0 * 60.0), 0.0);
+
+    // Without a transition the night-light switches on and off instantly
+    let schedule = NightLightSchedule {
+        start: 60,
+        end: 120,
+        transition: 0,
+    };
+    assert_eq!(schedule.amount(59.0), 0.0);
+    assert_eq!(schedule.amount(60.0), 1.0);
+    assert_eq!(schedule.amount(119.0), 1.0);
+    assert_eq!(schedule.amount(120.0), 0.0);
+
+    // Transitions longer than half the window never reach full strength
+    let schedule = NightLightSchedule {
+        start: 0,
+        end: 60,
+        transition: 120,
+    };
+    assert_eq!(schedule.amount(30.0), 1.0);
+    assert_eq!(schedule.amount(15.0), 0.5);
+
+    // An empty window disables the night-light
+    let schedule = NightLightSchedule {
+        start: 60,
+        end: 60,
+        transition: 10,
+    };
+    assert_eq!(schedule.amount(60.0), 0.0);
+}
+
+#[test]
+fn test_temperature_gains() {
+    assert_eq!(temperature_gains(NEUTRAL_TEMPERATURE), [1.0, 1.0, 1.0]);
+
+    // Warm temperatures reduce blue more than green, and keep red
+    let [red, green, blue] = temperature_gains(3000);
+    assert_eq!(red, 1.0);
+    assert!(green < 1.0);
+    assert!(blue < green);
+
+    // Cold temperatures reduce red
+    let [red, _, blue] = temperature_gains(9000);
+    assert!(red < 1.0);
+    assert_eq!(blue, 1.0);
+}
+
+#[test]
+fn test_shaper_lut() {
+    let lut = shaper_lut(1.0, [1.0, 1.0, 1.0], 3);
+    assert_eq!(
+        lut,
+        "LUT_1D_SIZE 3\n0.000000 0.000000 0.000000\n0.500000 0.500000 0.500000\n1.000000 1.000000 1.000000\n"
+    );
+
+    // Gains scale each channel and gamma is applied afterwards
+    let lut = shaper_lut(2.0, [1.0, 0.25, 0.0], 2);
+    assert_eq!(
+        lut,
+        "LUT_1D_SIZE 2\n0.000000 0.000000 0.000000\n1.000000 0.500000 0.000000\n"
+    );
+}
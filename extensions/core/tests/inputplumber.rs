@@ -0,0 +1,169 @@
+mod common;
+
+use common::{mock::inputplumber::*, next_signal, wait_for_property, TestBus};
+use opengamepadui_core::dbus::inputplumber::{
+    composite_device::CompositeDeviceProxy, dbus_device::DBusDeviceProxy,
+    input_manager::InputManagerProxy,
+};
+use zbus::fdo::ObjectManagerProxy;
+
+/// Build a proxy to the ObjectManager of the mock service
+async fn object_manager(conn: &zbus::Connection) -> ObjectManagerProxy<'static> {
+    ObjectManagerProxy::builder(conn)
+        .destination(INPUT_PLUMBER_BUS)
+        .unwrap()
+        .path(INPUT_PLUMBER_PATH)
+        .unwrap()
+        .build()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_composite_device_discovery() {
+    let bus = TestBus::start();
+    let service = InputPlumberMock::start(bus.address()).await.unwrap();
+    let existing = service
+        .add_composite_device(0, "Existing Gamepad")
+        .await
+        .unwrap();
+
+    let conn = bus.connect().await;
+    let object_manager = object_manager(&conn).await;
+
+    // Initial discovery
+    let objects = object_manager.get_managed_objects().await.unwrap();
+    let (_, ifaces) = objects
+        .iter()
+        .find(|(path, _)| path.as_str() == existing)
+        .expect("composite device should be managed");
+    assert!(ifaces.contains_key("org.shadowblip.Input.CompositeDevice"));
+
+    // Hotplug
+    let mut added = object_manager.receive_interfaces_added().await.unwrap();
+    let path = service
+        .add_composite_device(1, "New Gamepad")
+        .await
+        .unwrap();
+    let signal = next_signal(&mut added).await.expect("InterfacesAdded");
+    let args = signal.args().unwrap();
+    assert_eq!(args.object_path.as_str(), path);
+    assert!(args
+        .interfaces_and_properties
+        .contains_key("org.shadowblip.Input.CompositeDevice"));
+
+    // Unplug
+    let mut removed = object_manager.receive_interfaces_removed().await.unwrap();
+    assert!(service
+        .remove_composite_device(path.as_str())
+        .await
+        .unwrap());
+    let signal = next_signal(&mut removed).await.expect("InterfacesRemoved");
+    let args = signal.args().unwrap();
+    assert_eq!(args.object_path.as_str(), path);
+}
+
+#[tokio::test]
+async fn test_composite_device_properties() {
+    let bus = TestBus::start();
+    let service = InputPlumberMock::start(bus.address()).await.unwrap();
+    let path = service
+        .add_composite_device(0, "Test Gamepad")
+        .await
+        .unwrap();
+
+    let conn = bus.connect().await;
+    let device = CompositeDeviceProxy::builder(&conn)
+        .path(path)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+
+    assert_eq!(device.name().await.unwrap(), "Test Gamepad");
+    assert_eq!(device.intercept_mode().await.unwrap(), 0);
+    assert_eq!(device.capabilities().await.unwrap().len(), 2);
+
+    // Writing the intercept mode should emit a change
+    let mut changed = device.receive_intercept_mode_changed().await;
+    device.set_intercept_mode(2).await.unwrap();
+    assert!(wait_for_property(&mut changed, 2).await);
+    assert_eq!(device.intercept_mode().await.unwrap(), 2);
+
+    // The target dbus device should be reachable
+    let dbus_paths = device.dbus_devices().await.unwrap();
+    let dbus_device = DBusDeviceProxy::builder(&conn)
+        .path(dbus_paths[0].clone())
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(
+        dbus_device.name().await.unwrap(),
+        "Test Gamepad DBus Device"
+    );
+}
+
+#[tokio::test]
+async fn test_input_manager_properties() {
+    let bus = TestBus::start();
+    let _service = InputPlumberMock::start(bus.address()).await.unwrap();
+
+    let conn = bus.connect().await;
+    let manager = InputManagerProxy::new(&conn).await.unwrap();
+    assert!(!manager.manage_all_devices().await.unwrap());
+    let mut changed = manager.receive_manage_all_devices_changed().await;
+    manager.set_manage_all_devices(true).await.unwrap();
+    assert!(wait_for_property(&mut changed, true).await);
+}
+
+#[tokio::test]
+async fn test_dbus_device_input_event() {
+    let bus = TestBus::start();
+    let service = InputPlumberMock::start(bus.address()).await.unwrap();
+    service
+        .add_composite_device(0, "Test Gamepad")
+        .await
+        .unwrap();
+    let dbus_path = format!("{INPUT_PLUMBER_PATH}/devices/target/dbus0");
+
+    let conn = bus.connect().await;
+    let device = DBusDeviceProxy::builder(&conn)
+        .path(dbus_path.clone())
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    let mut events = device.receive_input_event().await.unwrap();
+
+    let iface = service
+        .conn
+        .object_server()
+        .interface::<_, MockDBusDevice>(dbus_path.as_str())
+        .await
+        .unwrap();
+    MockDBusDevice::input_event(iface.signal_context(), "ui_accept", 1.0)
+        .await
+        .unwrap();
+
+    let event = next_signal(&mut events).await.expect("InputEvent");
+    let args = event.args().unwrap();
+    assert_eq!(args.event, "ui_accept");
+    assert_eq!(args.value, 1.0);
+}
+
+#[tokio::test]
+async fn test_service_running() {
+    let bus = TestBus::start();
+    let conn = bus.connect().await;
+    let dbus = zbus::fdo::DBusProxy::new(&conn).await.unwrap();
+    let name = zbus::names::BusName::try_from(INPUT_PLUMBER_BUS).unwrap();
+
+    // The name is only owned while the service runs
+    assert!(!dbus.name_has_owner(name.clone()).await.unwrap());
+    let service = InputPlumberMock::start(bus.address()).await.unwrap();
+    assert!(dbus.name_has_owner(name.clone()).await.unwrap());
+    drop(service);
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    assert!(!dbus.name_has_owner(name).await.unwrap());
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{
+    blocking, mock::network_manager::*, next_signal, wait_for_change, wait_for_property,
+    wait_until, TestBus,
+};
+use opengamepadui_core::{
+    dbus::{
+        networkmanager::{
+            access_point::{AccessPointProxy, AccessPointProxyBlocking},
+            device::DeviceProxy,
+            network_manager::{NetworkManagerProxy, NetworkManagerProxyBlocking},
+            wireless::WirelessProxy,
+        },
+        object_watcher::{ObjectEvent, ObjectWatcher},
+        property_cache::PropertyCache,
+    },
+    network::network_manager::ObjectType,
+    resource::dispatcher::Wakeup,
+    set_dbus_address, DBusBus,
+};
+use zbus::fdo::ObjectManagerProxy;
+
+#[tokio::test]
+async fn test_network_manager_properties() {
+    let bus = TestBus::start();
+    let _service = NetworkManagerMock::start(bus.address()).await.unwrap();
+
+    let conn = bus.connect().await;
+    let network_manager = NetworkManagerProxy::new(&conn).await.unwrap();
+    assert_eq!(network_manager.state().await.unwrap(), 70);
+    assert_eq!(network_manager.connectivity().await.unwrap(), 4);
+    assert_eq!(
+        network_manager.primary_connection().await.unwrap().as_str(),
+        "/"
+    );
+
+    let mut changed = network_manager.receive_wireless_enabled_changed().await;
+    network_manager.set_wireless_enabled(false).await.unwrap();
+    assert!(wait_for_property(&mut changed, false).await);
+}
+
+#[tokio::test]
+async fn test_device_discovery() {
+    let bus = TestBus::start();
+    let _service = NetworkManagerMock::start(bus.address()).await.unwrap();
+
+    let conn = bus.connect().await;
+    let object_manager = ObjectManagerProxy::builder(&conn)
+        .destination(NETWORK_MANAGER_BUS)
+        .unwrap()
+        .path(OBJECT_MANAGER_PATH)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    let objects = object_manager.get_managed_objects().await.unwrap();
+    let (_, ifaces) = objects
+        .iter()
+        .find(|(path, _)| path.as_str() == WIFI_DEVICE_PATH)
+        .expect("wireless device should be managed");
+    assert!(ifaces.contains_key("org.freedesktop.NetworkManager.Device"));
+    assert!(ifaces.contains_key("org.freedesktop.NetworkManager.Device.Wireless"));
+
+    let network_manager = NetworkManagerProxy::new(&conn).await.unwrap();
+    let devices = network_manager.get_devices().await.unwrap();
+    assert_eq!(devices.len(), 1);
+    let device = DeviceProxy::builder(&conn)
+        .path(devices[0].clone())
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(device.interface().await.unwrap(), "wlan0");
+    assert_eq!(device.device_type().await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_access_points() {
+    let bus = TestBus::start();
+    let service = NetworkManagerMock::start(bus.address()).await.unwrap();
+
+    let conn = bus.connect().await;
+    let wireless = WirelessProxy::builder(&conn)
+        .path(WIFI_DEVICE_PATH)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    assert!(wireless.get_access_points().await.unwrap().is_empty());
+
+    // Scanning and discovering a new access point
+    wireless.request_scan(HashMap::new()).await.unwrap();
+    assert_eq!(service.scan_requests().await.unwrap(), 1);
+    let mut added = wireless.receive_access_point_added().await.unwrap();
+    let path = service.add_access_point(0, "MockNet", 70).await.unwrap();
+    let signal = next_signal(&mut added).await.expect("AccessPointAdded");
+    assert_eq!(signal.args().unwrap().access_point.as_str(), path);
+    assert_eq!(wireless.get_access_points().await.unwrap().len(), 1);
+
+    let access_point = AccessPointProxy::builder(&conn)
+        .path(path.clone())
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(access_point.ssid().await.unwrap(), b"MockNet");
+    assert_eq!(access_point.strength().await.unwrap(), 70);
+
+    // Signal strength changes are pushed through PropertiesChanged
+    let mut strength = access_point.receive_strength_changed().await;
+    service.set_strength(path.as_str(), 35).await.unwrap();
+    assert!(wait_for_property(&mut strength, 35).await);
+}
+
+// The wrappers use the shared system bus connection, so everything that
+// changes the address override lives in a single test.
+#[tokio::test]
+async fn test_wrapper_state() {
+    let bus = TestBus::start();
+    set_dbus_address(DBusBus::System, Some(bus.address()));
+    let service = NetworkManagerMock::start(bus.address()).await.unwrap();
+
+    // NetworkManagerInstance classifies devices by the interfaces they implement
+    let wakeup = Wakeup::default();
+    let watcher_wakeup = wakeup.clone();
+    let mut objects = blocking(move || {
+        ObjectWatcher::<ObjectType>::new(
+            DBusBus::System,
+            NETWORK_MANAGER_BUS,
+            OBJECT_MANAGER_PATH,
+            &watcher_wakeup,
+        )
+    })
+    .await;
+    assert!(objects.contains(WIFI_DEVICE_PATH, ObjectType::Device));
+    assert_eq!(
+        objects.paths(ObjectType::DeviceWireless),
+        vec![WIFI_DEVICE_PATH.to_string()]
+    );
+    assert!(objects.paths(ObjectType::AccessPoint).is_empty());
+
+    // Manager properties are read from the cache and changes are reported
+    let manager: PropertyCache<NetworkManagerProxyBlocking> =
+        PropertyCache::new(NETWORK_MANAGER_PATH, &wakeup);
+    assert!(wait_until(|| manager.is_ready()).await);
+    let proxy = manager.proxy().unwrap();
+    assert_eq!(proxy.cached_state().unwrap(), Some(70));
+    let manager = blocking(move || {
+        let proxy = manager.call_proxy().unwrap();
+        proxy.set_wireless_enabled(false).unwrap();
+        manager
+    })
+    .await;
+    assert!(wait_for_change(&manager, "WirelessEnabled").await);
+    assert!(wait_until(|| proxy.cached_wireless_enabled().unwrap() == Some(false)).await);
+
+    // Access points found by a scan are reported once and get their own cache
+    let path = service.add_access_point(0, "MockNet", 70).await.unwrap();
+    let added = ObjectEvent::Added {
+        path: path.clone(),
+        kind: ObjectType::AccessPoint,
+    };
+    assert!(wait_until(|| objects.take_events().contains(&added)).await);
+
+    let access_point: PropertyCache<AccessPointProxyBlocking> =
+        PropertyCache::new(path.as_str(), &wakeup);
+    assert!(wait_until(|| access_point.is_ready()).await);
+    let proxy = access_point.proxy().unwrap();
+    assert_eq!(
+        proxy.cached_ssid().unwrap().as_deref(),
+        Some(&b"MockNet"[..])
+    );
+    assert_eq!(proxy.cached_strength().unwrap(), Some(70));
+
+    service.set_strength(path.as_str(), 35).await.unwrap();
+    assert!(wait_for_change(&access_point, "Strength").await);
+    assert!(wait_until(|| proxy.cached_strength().unwrap() == Some(35)).await);
+
+    set_dbus_address(DBusBus::System, None);
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{mock::bluez::*, wait_until, TestBus, SIGNAL_TIMEOUT};
+use opengamepadui_core::{
+    dbus::object_watcher::{ObjectEvent, ObjectKind, ObjectWatcher},
+    resource::dispatcher::Wakeup,
+    set_dbus_address, DBusBus,
+};
+
+/// Kinds of BlueZ objects used to test the watcher
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    Adapter,
+    Device,
+}
+
+impl ObjectKind for Kind {
+    fn from_interface(iface: &str) -> Option<Self> {
+        match iface {
+            "org.bluez.Adapter1" => Some(Self::Adapter),
+            "org.bluez.Device1" => Some(Self::Device),
+            _ => None,
+        }
+    }
+}
+
+/// Take events from the watcher until at least the given number of events
+/// was received or [SIGNAL_TIMEOUT] passed
+async fn take_events(watcher: &mut ObjectWatcher<Kind>, count: usize) -> Vec<ObjectEvent<Kind>> {
+    let mut events = vec![];
+    let wait = async {
+        while events.len() < count {
+            events.extend(watcher.take_events());
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    };
+    let _ = tokio::time::timeout(SIGNAL_TIMEOUT, wait).await;
+    events
+}
+
+#[test]
+fn test_object_kind_from_interfaces() {
+    let ifaces = [
+        "org.freedesktop.DBus.Properties",
+        "org.bluez.Device1",
+        "org.bluez.Device1",
+    ];
+    assert_eq!(Kind::from_interfaces(ifaces), vec![Kind::Device]);
+    assert!(Kind::from_interfaces(["org.bluez.Battery1"]).is_empty());
+}
+
+// Watching objects changes the address of the shared system bus connection,
+// so everything lives in a single test.
+#[tokio::test]
+async fn test_object_watcher() {
+    let bus = TestBus::start();
+    set_dbus_address(DBusBus::System, Some(bus.address()));
+    let service = BluezMock::start(bus.address()).await.unwrap();
+
+    // Existing objects should be registered without sending events for them
+    let wakeup = Wakeup::default();
+    let watcher_wakeup = wakeup.clone();
+    let mut watcher = tokio::task::spawn_blocking(move || {
+        ObjectWatcher::<Kind>::new(
+            DBusBus::System,
+            BLUEZ_BUS,
+            BLUEZ_MANAGER_PATH,
+            &watcher_wakeup,
+        )
+    })
+    .await
+    .unwrap();
+    assert_eq!(watcher.paths(Kind::Adapter), vec![ADAPTER_PATH.to_string()]);
+    assert!(watcher.paths(Kind::Device).is_empty());
+    wakeup.take();
+
+    // Objects should be classified by interface when they are added
+    let path = service
+        .add_device("AA:BB:CC:DD:EE:FF", "Controller")
+        .await
+        .unwrap();
+    assert!(wait_until(|| wakeup.take()).await);
+    let events = take_events(&mut watcher, 1).await;
+    let added = ObjectEvent::Added {
+        path: path.clone(),
+        kind: Kind::Device,
+    };
+    assert_eq!(events, vec![added]);
+    assert!(watcher.contains(path.as_str(), Kind::Device));
+    assert!(!watcher.contains(path.as_str(), Kind::Adapter));
+
+    service.remove_device(path.as_str()).await.unwrap();
+    let events = take_events(&mut watcher, 1).await;
+    let removed = ObjectEvent::Removed {
+        path: path.clone(),
+        kind: Kind::Device,
+    };
+    assert_eq!(events, vec![removed]);
+    assert!(!watcher.contains(path.as_str(), Kind::Device));
+
+    // Stopping the service should remove every object
+    drop(service);
+    let events = take_events(&mut watcher, 2).await;
+    let removed = ObjectEvent::Removed {
+        path: ADAPTER_PATH.to_string(),
+        kind: Kind::Adapter,
+    };
+    assert_eq!(events, vec![removed, ObjectEvent::Stopped]);
+    assert_eq!(watcher.objects().count(), 0);
+
+    // Objects should be discovered again once the service is back
+    let _service = BluezMock::start(bus.address()).await.unwrap();
+    let events = take_events(&mut watcher, 2).await;
+    let added = ObjectEvent::Added {
+        path: ADAPTER_PATH.to_string(),
+        kind: Kind::Adapter,
+    };
+    assert_eq!(events, vec![ObjectEvent::Started, added]);
+
+    // Nothing should be reported twice
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let events = watcher.take_events();
+    assert!(events.is_empty(), "{events:?}");
+    assert_eq!(watcher.paths(Kind::Adapter), vec![ADAPTER_PATH.to_string()]);
+
+    set_dbus_address(DBusBus::System, None);
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{TestDisplay, SIGNAL_TIMEOUT};
+use opengamepadui_core::{
+    gamescope::ping::{PingEvent, PingTracker, WindowPinger},
+    resource::dispatcher::{channel, Receiver, Wakeup},
+};
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        xproto::{
+            Atom, AtomEnum, ConnectionExt, CreateWindowAux, EventMask, InputFocus, PropMode,
+            WindowClass,
+        },
+        Event,
+    },
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+    COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT, CURRENT_TIME,
+};
+
+/// Wait for the next ping event, returning `None` if nothing arrives within
+/// [SIGNAL_TIMEOUT]
+fn next_event(rx: &Receiver<PingEvent>) -> Option<PingEvent> {
+    let start = Instant::now();
+    while start.elapsed() < SIGNAL_TIMEOUT {
+        if let Ok(event) = rx.try_recv() {
+            return Some(event);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    None
+}
+
+/// Returns the atom with the given name
+fn atom(conn: &RustConnection, name: &str) -> Atom {
+    conn.intern_atom(false, name.as_bytes())
+        .unwrap()
+        .reply()
+        .unwrap()
+        .atom
+}
+
+#[test]
+fn test_ping_tracker() {
+    let timeout = Duration::from_secs(5);
+    let start = Instant::now();
+    let mut tracker = PingTracker::new(timeout);
+    tracker.set_focused(Some(1));
+
+    // Windows are pinged again once they answered
+    assert_eq!(tracker.tick(start), (vec![1], vec![]));
+    assert_eq!(
+        tracker.tick(start + Duration::from_secs(1)),
+        (vec![], vec![])
+    );
+    assert_eq!(tracker.pong(1), None);
+    assert_eq!(
+        tracker.tick(start + Duration::from_secs(2)),
+        (vec![1], vec![])
+    );
+
+    // Windows that don't answer within the timeout are unresponsive once
+    let late = start + Duration::from_secs(7);
+    let expected = (vec![], vec![PingEvent::Unresponsive(1)]);
+    assert_eq!(tracker.tick(late), expected);
+    assert_eq!(tracker.tick(late), (vec![], vec![]));
+    assert!(tracker.is_unresponsive(1));
+
+    // Unresponsive windows are tracked until they answer, even without focus
+    tracker.set_focused(Some(2));
+    assert_eq!(tracker.tick(late), (vec![2], vec![]));
+    assert_eq!(tracker.pong(1), Some(PingEvent::Responsive(1)));
+    assert!(!tracker.is_unresponsive(1));
+    assert_eq!(tracker.pong(1), None);
+
+    // Destroyed windows are forgotten
+    tracker.window_destroyed(2);
+    assert_eq!(tracker.focused(), None);
+    assert_eq!(tracker.tick(late), (vec![], vec![]));
+}
+
+#[test]
+#[ignore = "requires Xvfb"]
+fn test_window_pinger() {
+    let display = TestDisplay::start();
+    let (conn, screen) = display.connect();
+    let root = conn.setup().roots[screen].root;
+
+    // Create a focused window that supports _NET_WM_PING
+    let window = conn.generate_id().unwrap();
+    conn.create_window(
+        COPY_DEPTH_FROM_PARENT,
+        window,
+        root,
+        0,
+        0,
+        640,
+        480,
+        0,
+        WindowClass::INPUT_OUTPUT,
+        COPY_FROM_PARENT,
+        &CreateWindowAux::new(),
+    )
+    .unwrap();
+    let wm_protocols = atom(&conn, "WM_PROTOCOLS");
+    let net_wm_ping = atom(&conn, "_NET_WM_PING");
+    conn.change_property32(
+        PropMode::REPLACE,
+        window,
+        wm_protocols,
+        AtomEnum::ATOM,
+        &[net_wm_ping],
+    )
+    .unwrap();
+    conn.map_window(window).unwrap();
+    conn.sync().unwrap();
+    conn.set_input_focus(InputFocus::PARENT, window, CURRENT_TIME)
+        .unwrap();
+    conn.sync().unwrap();
+
+    // The window doesn't answer the first ping
+    let (tx, rx) = channel(&Wakeup::default());
+    let timeout = Duration::from_millis(500);
+    let pinger = WindowPinger::start(display.name(), timeout, tx).unwrap();
+    assert_eq!(next_event(&rx), Some(PingEvent::Unresponsive(window)));
+    assert!(pinger.is_unresponsive(window));
+
+    // Answering the ping makes the window responsive again
+    let ping = loop {
+        match conn.wait_for_event().unwrap() {
+            Event::ClientMessage(event) if event.type_ == wm_protocols => break event,
+            _ => continue,
+        }
+    };
+    assert_eq!(ping.data.as_data32()[0], net_wm_ping);
+    let mut pong = ping;
+    pong.window = root;
+    let mask = EventMask::SUBSTRUCTURE_NOTIFY | EventMask::SUBSTRUCTURE_REDIRECT;
+    conn.send_event(false, root, mask, pong).unwrap();
+    conn.flush().unwrap();
+    assert_eq!(next_event(&rx), Some(PingEvent::Responsive(window)));
+    assert!(!pinger.is_unresponsive(window));
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use common::{
+    blocking, mock::powerstation::*, next_signal, wait_for_change, wait_for_property, wait_until,
+    TestBus,
+};
+use opengamepadui_core::{
+    dbus::{
+        powerstation::{
+            card::{CardProxy, CardProxyBlocking},
+            cpu::{CPUProxy, CPUProxyBlocking},
+            gpu::GPUProxy,
+            tdp::{TDPProxy, TDPProxyBlocking},
+        },
+        property_cache::PropertyCache,
+    },
+    resource::dispatcher::Wakeup,
+    set_dbus_address, DBusBus,
+};
+
+#[tokio::test]
+async fn test_cpu_properties() {
+    let bus = TestBus::start();
+    let _service = PowerStationMock::start(bus.address()).await.unwrap();
+
+    let conn = bus.connect().await;
+    let cpu = CPUProxy::new(&conn).await.unwrap();
+    assert_eq!(cpu.cores_count().await.unwrap(), 8);
+    assert_eq!(cpu.enumerate_cores().await.unwrap().len(), 8);
+    assert!(cpu.has_feature("smt").await.unwrap());
+    assert!(cpu.smt_enabled().await.unwrap());
+
+    let mut changed = cpu.receive_boost_enabled_changed().await;
+    cpu.set_boost_enabled(false).await.unwrap();
+    assert!(wait_for_property(&mut changed, false).await);
+    let mut changed = cpu.receive_cores_enabled_changed().await;
+    cpu.set_cores_enabled(4).await.unwrap();
+    assert!(wait_for_property(&mut changed, 4).await);
+
+    // Invalid values should be rejected by the service
+    assert!(cpu.set_cores_enabled(0).await.is_err());
+    assert_eq!(cpu.cores_enabled().await.unwrap(), 4);
+}
+
+#[tokio::test]
+async fn test_gpu_cards() {
+    let bus = TestBus::start();
+    let _service = PowerStationMock::start(bus.address()).await.unwrap();
+
+    let conn = bus.connect().await;
+    let gpu = GPUProxy::new(&conn).await.unwrap();
+    let cards = gpu.enumerate_cards().await.unwrap();
+    assert_eq!(cards.len(), 1);
+    assert_eq!(cards[0].as_str(), CARD_PATH);
+
+    let card = CardProxy::builder(&conn)
+        .path(cards[0].clone())
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(card.name().await.unwrap(), "card0");
+    assert_eq!(card.class().await.unwrap(), "integrated");
+    let mut changed = card.receive_manual_clock_changed().await;
+    card.set_manual_clock(true).await.unwrap();
+    assert!(wait_for_property(&mut changed, true).await);
+}
+
+#[tokio::test]
+async fn test_tdp() {
+    let bus = TestBus::start();
+    let _service = PowerStationMock::start(bus.address()).await.unwrap();
+
+    let conn = bus.connect().await;
+    let tdp = TDPProxy::builder(&conn)
+        .path(CARD_PATH)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(tdp.tdp().await.unwrap(), 15.0);
+    assert_eq!(tdp.boost().await.unwrap(), 5.0);
+    assert_eq!(tdp.power_profiles_available().await.unwrap().len(), 2);
+
+    // Setting the TDP should emit a property change
+    let mut changed = tdp.receive_tdp_changed().await;
+    tdp.set_tdp(25.0).await.unwrap();
+    assert!(wait_for_property(&mut changed, 25.0).await);
+
+    let mut changed = tdp.receive_power_profile_changed().await;
+    tdp.set_power_profile("max-performance").await.unwrap();
+    assert!(wait_for_property(&mut changed, "max-performance".to_string()).await);
+
+    // Invalid values should be rejected by the service
+    assert!(tdp.set_tdp(-1.0).await.is_err());
+    assert_eq!(tdp.tdp().await.unwrap(), 25.0);
+}
+
+#[tokio::test]
+async fn test_service_running() {
+    let bus = TestBus::start();
+    let conn = bus.connect().await;
+    let dbus = zbus::fdo::DBusProxy::new(&conn).await.unwrap();
+    let name = zbus::names::BusName::try_from(POWERSTATION_BUS).unwrap();
+
+    // Ownership changes are announced when the service starts and stops
+    let mut owner_changed = dbus
+        .receive_name_owner_changed_with_args(&[(0, POWERSTATION_BUS)])
+        .await
+        .unwrap();
+    let service = PowerStationMock::start(bus.address()).await.unwrap();
+    let signal = next_signal(&mut owner_changed)
+        .await
+        .expect("NameOwnerChanged");
+    assert!(signal.args().unwrap().new_owner().is_some());
+    assert!(dbus.name_has_owner(name.clone()).await.unwrap());
+
+    drop(service);
+    let signal = next_signal(&mut owner_changed)
+        .await
+        .expect("NameOwnerChanged");
+    assert!(signal.args().unwrap().new_owner().is_none());
+    assert!(!dbus.name_has_owner(name).await.unwrap());
+}
+
+// The wrappers use the shared system bus connection, so everything that
+// changes the address override lives in a single test.
+#[tokio::test]
+async fn test_wrapper_state() {
+    let bus = TestBus::start();
+    set_dbus_address(DBusBus::System, Some(bus.address()));
+    let _service = PowerStationMock::start(bus.address()).await.unwrap();
+
+    // GpuCard caches both the card and the TDP interface of the same object
+    let wakeup = Wakeup::default();
+    let card: PropertyCache<CardProxyBlocking> = PropertyCache::new(CARD_PATH, &wakeup);
+    let tdp: PropertyCache<TDPProxyBlocking> = PropertyCache::new(CARD_PATH, &wakeup);
+    assert!(wait_until(|| card.is_ready() && tdp.is_ready()).await);
+    let card_proxy = card.proxy().unwrap();
+    let tdp_proxy = tdp.proxy().unwrap();
+    assert_eq!(
+        card_proxy.cached_class().unwrap().as_deref(),
+        Some("integrated")
+    );
+    assert_eq!(tdp_proxy.cached_tdp().unwrap(), Some(15.0));
+
+    // The TDP is set without blocking and reported back as a change
+    let proxy: TDPProxy = tdp.async_call_proxy().await.unwrap();
+    proxy.set_tdp(25.0).await.unwrap();
+    assert!(wait_for_change(&tdp, "TDP").await);
+    assert!(wait_until(|| tdp_proxy.cached_tdp().unwrap() == Some(25.0)).await);
+    assert!(card.take_changes().is_empty());
+
+    // Errors from the service are returned to the caller and leave the cache
+    // untouched
+    assert!(proxy.set_tdp(-1.0).await.is_err());
+    assert_eq!(tdp_proxy.cached_tdp().unwrap(), Some(25.0));
+
+    // CPU settings are changed with a blocking call
+    let cpu: PropertyCache<CPUProxyBlocking> = PropertyCache::new(CPU_PATH, &wakeup);
+    assert!(wait_until(|| cpu.is_ready()).await);
+    let cpu = blocking(move || {
+        cpu.call_proxy().unwrap().set_cores_enabled(4).unwrap();
+        cpu
+    })
+    .await;
+    assert!(wait_for_change(&cpu, "CoresEnabled").await);
+    let proxy = cpu.proxy().unwrap();
+    assert!(wait_until(|| proxy.cached_cores_enabled().unwrap() == Some(4)).await);
+
+    set_dbus_address(DBusBus::System, None);
+}
@@ -0,0 +1,100 @@
+use std::{
+    process::Stdio,
+    time::{Duration, Instant},
+};
+
+use nix::sys::signal::Signal;
+use opengamepadui_core::system::process_group::{
+    descendants, is_group_running, send_signal, terminate, ProcessStat,
+};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::{Child, Command},
+};
+
+/// Start the given shell script in a new process group and wait until it
+/// wrote its first line, so it had time to set up its signal handlers
+async fn start_group(script: &str) -> Child {
+    let mut child = Command::new("sh")
+        .args(["-c", script])
+        .stdout(Stdio::piped())
+        .process_group(0)
+        .spawn()
+        .unwrap();
+    let stdout = child.stdout.take().unwrap();
+    let mut line = String::new();
+    BufReader::new(stdout).read_line(&mut line).await.unwrap();
+    child
+}
+
+#[test]
+fn test_process_stat() {
+    let stat = "1234 (Steam (game) x) S 1000 1234 1234 0 -1 4194560 2416 0";
+    let expected = ProcessStat {
+        pid: 1234,
+        state: 'S',
+        ppid: 1000,
+        pgid: 1234,
+    };
+    assert_eq!(ProcessStat::parse(stat), Some(expected));
+    assert!(ProcessStat::parse("1234 (zombie) Z 1 1 1")
+        .unwrap()
+        .is_zombie());
+    assert_eq!(ProcessStat::parse("garbage"), None);
+}
+
+#[test]
+fn test_descendants() {
+    let process = |pid, state, ppid| ProcessStat {
+        pid,
+        state,
+        ppid,
+        pgid: pid,
+    };
+    let processes = [
+        process(10, 'S', 1),
+        process(11, 'S', 10),
+        process(12, 'R', 11),
+        process(13, 'Z', 10),
+        process(14, 'S', 1),
+    ];
+    assert_eq!(descendants(&processes, 10), vec![11, 12]);
+    assert_eq!(descendants(&processes, 14), Vec::<u32>::new());
+}
+
+#[tokio::test]
+async fn test_terminate_group() {
+    // Processes started by the shell are terminated along with it
+    let mut child = start_group("sleep 30 & echo started; wait").await;
+    let pgid = child.id().unwrap();
+    assert!(is_group_running(pgid));
+    let start = Instant::now();
+    let status = terminate(&mut child, Duration::from_secs(10))
+        .await
+        .unwrap();
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert!(!status.success());
+    assert!(!is_group_running(pgid));
+}
+
+#[tokio::test]
+async fn test_kill_group() {
+    // Processes that ignore SIGTERM are killed after the grace period
+    let mut child = start_group("trap '' TERM; sleep 30 & echo started; wait").await;
+    let pgid = child.id().unwrap();
+    let grace_period = Duration::from_millis(500);
+    let start = Instant::now();
+    terminate(&mut child, grace_period).await.unwrap();
+    assert!(start.elapsed() >= grace_period);
+    assert!(!is_group_running(pgid));
+}
+
+#[tokio::test]
+async fn test_send_signal() {
+    let mut child = start_group("echo started; sleep 30").await;
+    let pgid = child.id().unwrap();
+    assert!(send_signal(pgid, -1).is_err());
+    send_signal(pgid, Signal::SIGINT as i32).unwrap();
+    let status = child.wait().await.unwrap();
+    assert!(!status.success());
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{mock::powerstation::*, mock::upower::*, wait_until, TestBus, SIGNAL_TIMEOUT};
+use opengamepadui_core::{
+    dbus::{
+        powerstation::tdp::{TDPProxy, TDPProxyBlocking},
+        property_cache::PropertyCache,
+        upower::device::{DeviceProxy, DeviceProxyBlocking},
+        RunError,
+    },
+    resource::dispatcher::Wakeup,
+    set_dbus_address, DBusBus,
+};
+use zbus::zvariant::OwnedValue;
+
+/// Wait for the cache to report changes, collecting them until they settle
+async fn wait_for_changes<P>(cache: &PropertyCache<P>) -> Vec<(String, OwnedValue)>
+where
+    P: zbus::proxy::ProxyDefault + From<zbus::Proxy<'static>>,
+{
+    let mut changes = vec![];
+    let _ = tokio::time::timeout(SIGNAL_TIMEOUT, async {
+        loop {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let new_changes = cache.take_changes();
+            if new_changes.is_empty() && !changes.is_empty() {
+                break;
+            }
+            changes.extend(new_changes);
+        }
+    })
+    .await;
+    changes
+}
+
+// The cache uses the shared system bus connection, so everything that changes
+// the address override lives in a single test.
+#[tokio::test]
+async fn test_property_cache() {
+    let bus = TestBus::start();
+    set_dbus_address(DBusBus::System, Some(bus.address()));
+    let _service = PowerStationMock::start(bus.address()).await.unwrap();
+
+    // The cache should be filled with the current values once created
+    let wakeup = Wakeup::default();
+    let cache: PropertyCache<TDPProxyBlocking> = PropertyCache::new(CARD_PATH, &wakeup);
+    assert!(wait_until(|| cache.is_ready()).await);
+    let proxy = cache.proxy().unwrap();
+    assert_eq!(proxy.cached_tdp().unwrap(), Some(15.0));
+    assert_eq!(proxy.tdp().unwrap(), 15.0);
+    assert!(cache.take_changes().is_empty());
+    wakeup.take();
+
+    // Changes should be reported once with their latest value
+    let conn = bus.connect().await;
+    let tdp = TDPProxy::builder(&conn)
+        .path(CARD_PATH)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    tdp.set_tdp(20.0).await.unwrap();
+    tdp.set_tdp(25.0).await.unwrap();
+    tdp.set_boost(10.0).await.unwrap();
+    assert!(wait_until(|| wakeup.take()).await);
+    let changes = wait_for_changes(&cache).await;
+    let names: Vec<&str> = changes.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["TDP", "Boost"]);
+    assert_eq!(f64::try_from(&changes[0].1).unwrap(), 25.0);
+    assert_eq!(f64::try_from(&changes[1].1).unwrap(), 10.0);
+    assert!(wait_until(|| proxy.cached_tdp().unwrap() == Some(25.0)).await);
+
+    // Properties read before the cache is filled are read from the bus
+    let cache: PropertyCache<TDPProxyBlocking> = PropertyCache::new(CARD_PATH, &wakeup);
+    let value = tokio::task::spawn_blocking(move || cache.read_proxy().unwrap().tdp().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(value, 25.0);
+
+    // The cache should be filled once the service starts
+    let cache: PropertyCache<DeviceProxyBlocking> =
+        PropertyCache::new(DISPLAY_DEVICE_PATH, &wakeup);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!cache.is_ready());
+    assert!(cache.proxy().is_none());
+
+    // Methods can be called before the cache is filled, using a proxy that
+    // does not read any properties
+    let cache = tokio::task::spawn_blocking(move || {
+        let proxy = cache.call_proxy().unwrap();
+        assert_eq!(proxy.cached_percentage().unwrap(), None);
+        cache
+    })
+    .await
+    .unwrap();
+    let proxy: DeviceProxy = cache.async_call_proxy().await.unwrap();
+    assert_eq!(proxy.cached_percentage().unwrap(), None);
+
+    let upower = UPowerMock::start(bus.address()).await.unwrap();
+    assert!(wait_until(|| cache.is_ready()).await);
+    assert_eq!(
+        cache.proxy().unwrap().cached_percentage().unwrap(),
+        Some(80.0)
+    );
+
+    upower.set_percentage(42.0).await.unwrap();
+    let changes = wait_for_changes(&cache).await;
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].0, "Percentage");
+
+    // Methods cannot be called while the bus is unreachable
+    set_dbus_address(DBusBus::System, Some("unix:path=/nonexistent"));
+    let cache: PropertyCache<DeviceProxyBlocking> =
+        PropertyCache::new(DISPLAY_DEVICE_PATH, &wakeup);
+    let result = tokio::task::spawn_blocking(move || cache.call_proxy().map(|_| ()))
+        .await
+        .unwrap();
+    assert!(matches!(result, Err(RunError::NotConnected)));
+
+    set_dbus_address(DBusBus::System, None);
+}
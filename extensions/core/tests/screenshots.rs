@@ -0,0 +1,124 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use opengamepadui_core::gamescope::screenshots::{
+    is_screenshot_name, png_dimensions, thumbnail_path, ScreenshotLibrary, ScreenshotRequests,
+};
+
+/// Start of a PNG file with the given dimensions
+fn png_header(width: u32, height: u32) -> Vec<u8> {
+    let mut header = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+    header
+}
+
+/// Returns a new empty directory for the given test
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ogui-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_screenshot_names() {
+    assert!(is_screenshot_name("gamescope_2024-05-01_12-30-00.png"));
+    assert!(is_screenshot_name("gamescope_2024-05-01_12-30-00.avif"));
+    assert!(!is_screenshot_name("gamescope_2024-05-01_12-30-00.png.tmp"));
+    assert!(!is_screenshot_name("other.png"));
+
+    assert_eq!(
+        thumbnail_path("/screenshots/7/gamescope_1.avif".as_ref()),
+        PathBuf::from("/screenshots/7/thumbnails/gamescope_1.png")
+    );
+}
+
+#[test]
+fn test_png_dimensions() {
+    assert_eq!(png_dimensions(&png_header(1280, 800)), Some((1280, 800)));
+    assert_eq!(png_dimensions(&png_header(1280, 800)[..20]), None);
+    assert_eq!(png_dimensions(b"not a png image at all!!"), None);
+}
+
+#[test]
+fn test_screenshot_library() {
+    let dir = test_dir("screenshot-library");
+    let source_dir = dir.join("source");
+    fs::create_dir_all(&source_dir).unwrap();
+    let library = ScreenshotLibrary::new(dir.join("library"));
+
+    // An empty library has no screenshots
+    assert!(library.scan().unwrap().is_empty());
+
+    let source = source_dir.join("gamescope_1.png");
+    fs::write(&source, png_header(640, 480)).unwrap();
+    let info = library.import(&source, 7).unwrap();
+    assert_eq!(info.path, dir.join("library/7/gamescope_1.png"));
+    assert_eq!(info.app_id, 7);
+    assert_eq!((info.width, info.height), (640, 480));
+    assert_eq!(info.size, png_header(640, 480).len() as u64);
+    assert!(!source.exists());
+
+    // Screenshots with the same name as an earlier one are not overwritten
+    fs::write(&source, png_header(320, 240)).unwrap();
+    let duplicate = library.import(&source, 7).unwrap();
+    assert_eq!(duplicate.path, dir.join("library/7/gamescope_1-1.png"));
+    assert_eq!((duplicate.width, duplicate.height), (320, 240));
+    library.delete(&duplicate.path).unwrap();
+
+    // Copied screenshots are left where they were written
+    fs::write(&source, png_header(320, 240)).unwrap();
+    let copy = library.copy(&source, 7).unwrap();
+    assert_eq!(copy.path, dir.join("library/7/gamescope_1-1.png"));
+    assert!(source.exists());
+    library.delete(&copy.path).unwrap();
+    fs::remove_file(&source).unwrap();
+
+    let source = source_dir.join("gamescope_2.avif");
+    fs::write(&source, b"avif").unwrap();
+    library.import(&source, 8).unwrap();
+
+    // Screenshots are grouped by app
+    let screenshots = library.scan().unwrap();
+    assert_eq!(screenshots.keys().copied().collect::<Vec<_>>(), vec![7, 8]);
+    assert_eq!(screenshots[&7], vec![info.clone()]);
+    assert_eq!(
+        (screenshots[&8][0].width, screenshots[&8][0].height),
+        (0, 0)
+    );
+
+    // Deleting a screenshot also deletes its thumbnail
+    fs::create_dir_all(info.thumbnail.parent().unwrap()).unwrap();
+    fs::write(&info.thumbnail, b"thumbnail").unwrap();
+    library.delete(&info.path).unwrap();
+    assert!(!info.thumbnail.exists());
+    assert!(!library.scan().unwrap().contains_key(&7));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_screenshot_requests() {
+    let timeout = Duration::from_secs(10);
+    let start = Instant::now();
+    let mut requests = ScreenshotRequests::new();
+
+    // Screenshots that were not requested are not taken
+    assert!(!requests.take(start, timeout));
+
+    // Each request is taken by a single screenshot
+    requests.request(start);
+    requests.request(start);
+    assert!(requests.take(start + Duration::from_secs(1), timeout));
+    assert!(requests.take(start + Duration::from_secs(1), timeout));
+    assert!(!requests.take(start + Duration::from_secs(1), timeout));
+
+    // Requests that were never written expire
+    requests.request(start);
+    assert!(!requests.take(start + Duration::from_secs(11), timeout));
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::{mock::upower::*, TestBus};
+use opengamepadui_core::{
+    dbus::upower::UPowerProxy, get_dbus, get_dbus_blocking, get_dbus_session,
+    get_dbus_session_blocking, set_dbus_address, DBusBus,
+};
+
+// The shared connections are global to the process, so everything that
+// changes the address override lives in a single test.
+#[tokio::test]
+async fn test_address_override() {
+    let bus = TestBus::start();
+    let _service = UPowerMock::start(bus.address()).await.unwrap();
+
+    // Point both buses at the private bus so the shared connections find the
+    // mock
+    set_dbus_address(DBusBus::Session, Some(bus.address()));
+    set_dbus_address(DBusBus::System, Some(bus.address()));
+
+    // Shared connections should be reused until the address changes
+    let conn = get_dbus_session().await.unwrap();
+    let shared = get_dbus(DBusBus::Session).await.unwrap();
+    assert_eq!(conn.unique_name(), shared.unique_name());
+    let upower = UPowerProxy::new(&conn).await.unwrap();
+    assert!(upower.lid_is_present().await.unwrap());
+
+    let system = get_dbus(DBusBus::System).await.unwrap();
+    assert_ne!(conn.unique_name(), system.unique_name());
+    let upower = UPowerProxy::new(&system).await.unwrap();
+    assert!(!upower.on_battery().await.unwrap());
+
+    let blocking = tokio::task::spawn_blocking(|| {
+        let conn = get_dbus_session_blocking().unwrap();
+        let shared = get_dbus_blocking(DBusBus::Session).unwrap();
+        assert_eq!(conn.unique_name(), shared.unique_name());
+        conn.unique_name().map(|name| name.to_string())
+    })
+    .await
+    .unwrap();
+    assert!(blocking.is_some());
+
+    // Changing the address should drop the existing shared connections
+    let other_bus = TestBus::start();
+    set_dbus_address(DBusBus::Session, Some(other_bus.address()));
+    let other = get_dbus_session().await.unwrap();
+    assert_ne!(conn.unique_name(), other.unique_name());
+    assert!(UPowerProxy::new(&other)
+        .await
+        .unwrap()
+        .on_battery()
+        .await
+        .is_err());
+
+    set_dbus_address(DBusBus::Session, None);
+    set_dbus_address(DBusBus::System, None);
+}
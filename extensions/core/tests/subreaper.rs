@@ -0,0 +1,89 @@
+use std::io::{PipeReader, Read};
+
+use nix::{
+    sys::{
+        signal::Signal,
+        wait::{waitpid, WaitStatus},
+    },
+    unistd::Pid,
+};
+use opengamepadui_core::system::subreaper::{exit_status, spawn, ReaperMessage};
+
+/// Read all messages from a subreaper until it exits
+fn read_messages(mut reader: PipeReader) -> Vec<ReaperMessage> {
+    let mut messages = Vec::new();
+    let mut bytes = [0; ReaperMessage::SIZE];
+    while reader.read_exact(&mut bytes).is_ok() {
+        messages.push(ReaperMessage::from_bytes(bytes).unwrap());
+    }
+    messages
+}
+
+#[test]
+fn test_reaper_message() {
+    let messages = [
+        ReaperMessage::Started { pid: 1234 },
+        ReaperMessage::Exited {
+            pid: 1235,
+            status: -9,
+        },
+        ReaperMessage::AllExited { status: 3 },
+    ];
+    for message in messages {
+        assert_eq!(ReaperMessage::from_bytes(message.to_bytes()), Some(message));
+    }
+    assert_eq!(ReaperMessage::from_bytes([0xff; ReaperMessage::SIZE]), None);
+}
+
+#[test]
+fn test_exit_status() {
+    let pid = Pid::from_raw(1234);
+    assert_eq!(exit_status(WaitStatus::Exited(pid, 3)), Some((1234, 3)));
+    let signaled = WaitStatus::Signaled(pid, Signal::SIGKILL, false);
+    assert_eq!(exit_status(signaled), Some((1234, -9)));
+    assert_eq!(exit_status(WaitStatus::StillAlive), None);
+}
+
+#[test]
+fn test_spawn() {
+    // Descendants that outlive the command are reported too
+    let args = ["-c".to_string(), "(sleep 0.2; exit 4) & exit 3".to_string()];
+    let (reaper, reader) = spawn("sh", &args).unwrap();
+    let messages = read_messages(reader);
+    let Some(ReaperMessage::Started { pid: main_pid }) = messages.first().copied() else {
+        panic!("Expected the command to be started first");
+    };
+    assert_eq!(messages.len(), 4);
+    assert_eq!(
+        messages[1],
+        ReaperMessage::Exited {
+            pid: main_pid,
+            status: 3
+        }
+    );
+    assert!(matches!(
+        messages[2],
+        ReaperMessage::Exited { pid, status: 4 } if pid != main_pid
+    ));
+    assert_eq!(messages[3], ReaperMessage::AllExited { status: 3 });
+
+    // The subreaper exits with the exit code of the command
+    let reaper = Pid::from_raw(reaper as i32);
+    assert_eq!(
+        waitpid(reaper, None).unwrap(),
+        WaitStatus::Exited(reaper, 3)
+    );
+
+    // Commands that can't be executed exit like in a shell
+    let (reaper, reader) = spawn("/nonexistent", &[]).unwrap();
+    let messages = read_messages(reader);
+    assert_eq!(
+        messages.last(),
+        Some(&ReaperMessage::AllExited { status: 127 })
+    );
+    let reaper = Pid::from_raw(reaper as i32);
+    assert_eq!(
+        waitpid(reaper, None).unwrap(),
+        WaitStatus::Exited(reaper, 127)
+    );
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::{mock::upower::*, wait_until, TestBus, SIGNAL_TIMEOUT};
+use opengamepadui_core::{
+    dbus::{
+        property_cache::PropertyCache,
+        supervisor::{watch_service, ServiceEvent},
+        upower::UPowerProxyBlocking,
+    },
+    get_dbus, get_dbus_system_blocking,
+    resource::dispatcher::Wakeup,
+    set_dbus_address, DBusBus,
+};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// Wait for the next service event, returning `None` if nothing arrives within
+/// [SIGNAL_TIMEOUT].
+async fn next_event(events: &mut UnboundedReceiver<ServiceEvent>) -> Option<ServiceEvent> {
+    tokio::time::timeout(SIGNAL_TIMEOUT, events.recv())
+        .await
+        .ok()
+        .flatten()
+}
+
+// Reconnecting replaces the shared system bus connection, so everything that
+// changes the address override lives in a single test.
+#[tokio::test]
+async fn test_bus_restart() {
+    let mut bus = TestBus::start();
+    set_dbus_address(DBusBus::System, Some(bus.address()));
+    let service = UPowerMock::start(bus.address()).await.unwrap();
+
+    // The state of the service when the watcher starts should not be reported
+    let mut events = watch_service(DBusBus::System, UPOWER_BUS);
+    let Some(ServiceEvent::Connected(first)) = next_event(&mut events).await else {
+        panic!("Expected a connection to the bus");
+    };
+    let wakeup = Wakeup::default();
+    let cache: PropertyCache<UPowerProxyBlocking> = PropertyCache::new(UPOWER_PATH, &wakeup);
+    assert!(wait_until(|| cache.is_ready()).await);
+
+    // Killing the bus should stop the service and empty the cache
+    bus.stop();
+    drop(service);
+    let event = next_event(&mut events).await;
+    assert!(matches!(event, Some(ServiceEvent::Stopped)), "{event:?}");
+    assert!(wait_until(|| !cache.is_ready()).await);
+
+    // A new connection should be made once the bus is back
+    bus.restart().unwrap();
+    let Some(ServiceEvent::Connected(second)) = next_event(&mut events).await else {
+        panic!("Expected a new connection to the bus");
+    };
+    assert_ne!(first.server_guid(), second.server_guid());
+    let service = UPowerMock::start(bus.address()).await.unwrap();
+    let event = next_event(&mut events).await;
+    assert!(matches!(event, Some(ServiceEvent::Started)), "{event:?}");
+
+    // Shared connections should be made to the restarted bus
+    let conn = get_dbus(DBusBus::System).await.unwrap();
+    assert_eq!(conn.server_guid(), second.server_guid());
+    let guid = tokio::task::spawn_blocking(|| {
+        let conn = get_dbus_system_blocking().unwrap();
+        conn.inner().server_guid().to_string()
+    })
+    .await
+    .unwrap();
+    assert_eq!(guid, second.server_guid().as_str());
+
+    // The cache should be filled again from the restarted service
+    assert!(wait_until(|| cache.is_ready()).await);
+    let proxy = cache.proxy().unwrap();
+    assert_eq!(proxy.cached_on_battery().unwrap(), Some(false));
+
+    // Stopping only the service should not drop the connection
+    drop(service);
+    let event = next_event(&mut events).await;
+    assert!(matches!(event, Some(ServiceEvent::Stopped)), "{event:?}");
+    let _service = UPowerMock::start(bus.address()).await.unwrap();
+    let event = next_event(&mut events).await;
+    assert!(matches!(event, Some(ServiceEvent::Started)), "{event:?}");
+
+    set_dbus_address(DBusBus::System, None);
+}
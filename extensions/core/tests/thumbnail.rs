@@ -0,0 +1,126 @@
+mod common;
+
+use common::TestDisplay;
+use opengamepadui_core::gamescope::{
+    thumbnail::{thumbnail_size, PixelFormat, WindowImage},
+    x11_connection::X11Connection,
+};
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{ConnectionExt, CreateWindowAux, WindowClass},
+    wrapper::ConnectionExt as _,
+    COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT,
+};
+
+/// Returns an image of the given size in the BGRX format with every pixel
+/// set to the given value
+fn solid_image(width: u32, height: u32, pixel: u32) -> WindowImage {
+    let data = pixel.to_le_bytes().repeat((width * height) as usize);
+    WindowImage {
+        width,
+        height,
+        format: PixelFormat::BGRX,
+        data,
+    }
+}
+
+#[test]
+fn test_thumbnail_size() {
+    assert_eq!(thumbnail_size(1280, 800, 320, 320), (320, 200));
+    assert_eq!(thumbnail_size(800, 1280, 320, 320), (200, 320));
+    assert_eq!(thumbnail_size(1280, 800, 320, 0), (320, 200));
+    assert_eq!(thumbnail_size(1280, 800, 0, 100), (160, 100));
+    // Images are never upscaled
+    assert_eq!(thumbnail_size(100, 50, 320, 320), (100, 50));
+    assert_eq!(thumbnail_size(0, 50, 320, 320), (0, 0));
+    // Very wide images keep at least one row
+    assert_eq!(thumbnail_size(10000, 1, 100, 100), (100, 1));
+}
+
+#[test]
+fn test_pixel_formats() {
+    let image = solid_image(2, 1, 0x00123456);
+    assert_eq!(image.stride(), 8);
+    assert_eq!(image.pixel(1, 0), Some([0x12, 0x34, 0x56]));
+    assert_eq!(image.pixel(2, 0), None);
+
+    // 16-bit RGB565 pixels are expanded to 8 bits per channel
+    let image = WindowImage {
+        width: 3,
+        height: 1,
+        format: PixelFormat {
+            bits_per_pixel: 16,
+            scanline_pad: 32,
+            red_mask: 0xf800,
+            green_mask: 0x07e0,
+            blue_mask: 0x001f,
+            big_endian: true,
+        },
+        data: vec![0xf8, 0x00, 0x07, 0xe0, 0x00, 0x1f, 0, 0],
+    };
+    assert_eq!(image.stride(), 8);
+    assert_eq!(image.pixel(0, 0), Some([255, 0, 0]));
+    assert_eq!(image.pixel(1, 0), Some([0, 255, 0]));
+    assert_eq!(image.pixel(2, 0), Some([0, 0, 255]));
+}
+
+#[test]
+fn test_thumbnail() {
+    // Left half black and right half white
+    let mut image = solid_image(4, 2, 0);
+    for y in 0..2 {
+        let row = y * image.stride();
+        image.data[row + 8..row + 16].fill(0xff);
+    }
+    let thumbnail = image.thumbnail(2, 0).unwrap();
+    assert_eq!((thumbnail.width, thumbnail.height), (2, 1));
+    assert_eq!(thumbnail.rgba, vec![0, 0, 0, 255, 255, 255, 255, 255]);
+
+    // Pixels are averaged
+    let thumbnail = image.thumbnail(1, 0).unwrap();
+    assert_eq!(thumbnail.rgba, vec![127, 127, 127, 255]);
+
+    // Unsupported formats can't be converted
+    image.format.bits_per_pixel = 8;
+    assert_eq!(image.thumbnail(1, 0), None);
+}
+
+#[test]
+#[ignore = "requires Xvfb"]
+fn test_capture_window() {
+    let display = TestDisplay::start();
+    let (conn, screen) = display.connect();
+    let root = conn.setup().roots[screen].root;
+
+    let window = conn.generate_id().unwrap();
+    conn.create_window(
+        COPY_DEPTH_FROM_PARENT,
+        window,
+        root,
+        0,
+        0,
+        640,
+        400,
+        0,
+        WindowClass::INPUT_OUTPUT,
+        COPY_FROM_PARENT,
+        &CreateWindowAux::new().background_pixel(0xff0000),
+    )
+    .unwrap();
+    conn.sync().unwrap();
+
+    // Unmapped windows can't be captured
+    let x11 = X11Connection::connect(display.name()).unwrap();
+    assert_eq!(x11.capture_window(window).unwrap(), None);
+
+    conn.map_window(window).unwrap();
+    conn.clear_area(false, window, 0, 0, 0, 0).unwrap();
+    conn.sync().unwrap();
+    let image = x11.capture_window(window).unwrap().unwrap();
+    assert_eq!((image.width, image.height), (640, 400));
+    assert_eq!(image.pixel(320, 200), Some([255, 0, 0]));
+
+    let thumbnail = image.thumbnail(64, 64).unwrap();
+    assert_eq!((thumbnail.width, thumbnail.height), (64, 40));
+    assert_eq!(&thumbnail.rgba[..4], &[255, 0, 0, 255]);
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::{mock::udisks2::*, next_signal, TestBus};
+use opengamepadui_core::dbus::udisks2::{block::BlockProxy, drive::DriveProxy};
+use zbus::fdo::ObjectManagerProxy;
+
+/// Build a proxy to the ObjectManager of the mock service
+async fn object_manager(conn: &zbus::Connection) -> ObjectManagerProxy<'static> {
+    ObjectManagerProxy::builder(conn)
+        .destination(UDISKS2_BUS)
+        .unwrap()
+        .path(UDISKS2_PATH)
+        .unwrap()
+        .build()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_drive_discovery() {
+    let bus = TestBus::start();
+    let service = UDisks2Mock::start(bus.address()).await.unwrap();
+    let (drive_path, block_path) = service.add_drive("Mock_SSD", "/dev/nvme0n1").await.unwrap();
+
+    let conn = bus.connect().await;
+    let object_manager = object_manager(&conn).await;
+    let objects = object_manager.get_managed_objects().await.unwrap();
+
+    let has_iface = |path: &str, iface: &str| {
+        objects
+            .iter()
+            .find(|(obj_path, _)| obj_path.as_str() == path)
+            .map(|(_, ifaces)| ifaces.keys().any(|name| name.as_str() == iface))
+            .unwrap_or_default()
+    };
+    assert!(has_iface(&drive_path, "org.freedesktop.UDisks2.Drive"));
+    assert!(has_iface(&block_path, "org.freedesktop.UDisks2.Block"));
+}
+
+#[tokio::test]
+async fn test_drive_hotplug() {
+    let bus = TestBus::start();
+    let service = UDisks2Mock::start(bus.address()).await.unwrap();
+
+    let conn = bus.connect().await;
+    let object_manager = object_manager(&conn).await;
+    let mut added = object_manager.receive_interfaces_added().await.unwrap();
+    let mut removed = object_manager.receive_interfaces_removed().await.unwrap();
+
+    // Adding a drive adds both its drive and its block device object
+    let (drive_path, block_path) = service.add_drive("Mock_SD", "/dev/mmcblk0").await.unwrap();
+    let mut added_paths = vec![];
+    for _ in 0..2 {
+        let signal = next_signal(&mut added).await.expect("InterfacesAdded");
+        added_paths.push(signal.args().unwrap().object_path.to_string());
+    }
+    assert!(added_paths.contains(&drive_path));
+    assert!(added_paths.contains(&block_path));
+
+    assert!(service.remove_drive(drive_path.as_str()).await.unwrap());
+    let signal = next_signal(&mut removed).await.expect("InterfacesRemoved");
+    let args = signal.args().unwrap();
+    assert_eq!(args.object_path.as_str(), drive_path);
+    assert!(args.interfaces.contains(&"org.freedesktop.UDisks2.Drive"));
+}
+
+#[tokio::test]
+async fn test_drive_properties() {
+    let bus = TestBus::start();
+    let service = UDisks2Mock::start(bus.address()).await.unwrap();
+    let (drive_path, block_path) = service.add_drive("Mock_SSD", "/dev/nvme0n1").await.unwrap();
+
+    let conn = bus.connect().await;
+    let drive = DriveProxy::builder(&conn)
+        .path(drive_path.clone())
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(drive.id().await.unwrap(), "Mock_SSD");
+    assert_eq!(drive.model().await.unwrap(), "Mock Drive");
+    assert_eq!(drive.size().await.unwrap(), 512 * 1024 * 1024 * 1024);
+
+    let block = BlockProxy::builder(&conn)
+        .path(block_path)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(block.drive().await.unwrap().as_str(), drive_path);
+    assert_eq!(block.device().await.unwrap(), b"/dev/nvme0n1\0");
+}
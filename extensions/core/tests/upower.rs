@@ -0,0 +1,106 @@
+mod common;
+
+use common::{blocking, mock::upower::*, wait_for_change, wait_for_property, wait_until, TestBus};
+use opengamepadui_core::{
+    dbus::{
+        property_cache::PropertyCache,
+        upower::{
+            device::{DeviceProxy, DeviceProxyBlocking},
+            UPowerProxy, UPowerProxyBlocking,
+        },
+    },
+    resource::dispatcher::Wakeup,
+    set_dbus_address, DBusBus,
+};
+
+#[tokio::test]
+async fn test_upower_properties() {
+    let bus = TestBus::start();
+    let _service = UPowerMock::start(bus.address()).await.unwrap();
+
+    let conn = bus.connect().await;
+    let upower = UPowerProxy::new(&conn).await.unwrap();
+    assert!(!upower.on_battery().await.unwrap());
+    assert!(upower.lid_is_present().await.unwrap());
+    assert_eq!(upower.get_critical_action().await.unwrap(), "PowerOff");
+    let display = upower.get_display_device().await.unwrap();
+    assert_eq!(display.as_str(), DISPLAY_DEVICE_PATH);
+    assert_eq!(upower.enumerate_devices().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_display_device() {
+    let bus = TestBus::start();
+    let service = UPowerMock::start(bus.address()).await.unwrap();
+
+    let conn = bus.connect().await;
+    let device = DeviceProxy::builder(&conn)
+        .path(DISPLAY_DEVICE_PATH)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(device.percentage().await.unwrap(), 80.0);
+    assert_eq!(device.state().await.unwrap(), 2);
+    assert_eq!(device.time_to_empty().await.unwrap(), 3600);
+    assert!(device.is_present().await.unwrap());
+    device.refresh().await.unwrap();
+
+    // Battery changes are pushed through PropertiesChanged
+    let mut changed = device.receive_percentage_changed().await;
+    service.set_percentage(42.0).await.unwrap();
+    assert!(wait_for_property(&mut changed, 42.0).await);
+}
+
+#[tokio::test]
+async fn test_service_running() {
+    let bus = TestBus::start();
+    let conn = bus.connect().await;
+    let dbus = zbus::fdo::DBusProxy::new(&conn).await.unwrap();
+    let name = zbus::names::BusName::try_from(UPOWER_BUS).unwrap();
+
+    assert!(!dbus.name_has_owner(name.clone()).await.unwrap());
+    let _service = UPowerMock::start(bus.address()).await.unwrap();
+    assert!(dbus.name_has_owner(name).await.unwrap());
+}
+
+// The wrappers use the shared system bus connection, so everything that
+// changes the address override lives in a single test.
+#[tokio::test]
+async fn test_wrapper_state() {
+    let bus = TestBus::start();
+    set_dbus_address(DBusBus::System, Some(bus.address()));
+    let service = UPowerMock::start(bus.address()).await.unwrap();
+
+    // UPowerInstance reads its properties from the cache
+    let wakeup = Wakeup::default();
+    let upower: PropertyCache<UPowerProxyBlocking> = PropertyCache::new(UPOWER_PATH, &wakeup);
+    assert!(wait_until(|| upower.is_ready()).await);
+    let proxy = upower.proxy().unwrap();
+    assert_eq!(proxy.cached_on_battery().unwrap(), Some(false));
+    assert_eq!(proxy.cached_lid_is_present().unwrap(), Some(true));
+
+    // The display device is looked up with a method call and gets its own cache
+    let (upower, path) = blocking(move || {
+        let path = upower.call_proxy().unwrap().get_display_device().unwrap();
+        (upower, path)
+    })
+    .await;
+    assert_eq!(path.as_str(), DISPLAY_DEVICE_PATH);
+    assert!(upower.take_changes().is_empty());
+
+    let device: PropertyCache<DeviceProxyBlocking> = PropertyCache::new(path.as_str(), &wakeup);
+    assert!(wait_until(|| device.is_ready()).await);
+    let proxy = device.proxy().unwrap();
+    assert_eq!(proxy.cached_percentage().unwrap(), Some(80.0));
+    assert_eq!(proxy.cached_state().unwrap(), Some(2));
+
+    // Battery changes are reported as property changes and update the cache
+    wakeup.take();
+    service.set_percentage(42.0).await.unwrap();
+    assert!(wait_until(|| wakeup.take()).await);
+    assert!(wait_for_change(&device, "Percentage").await);
+    assert!(wait_until(|| proxy.cached_percentage().unwrap() == Some(42.0)).await);
+
+    set_dbus_address(DBusBus::System, None);
+}
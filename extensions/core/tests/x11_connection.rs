@@ -0,0 +1,205 @@
+mod common;
+
+use common::TestDisplay;
+use opengamepadui_core::gamescope::x11_connection::{
+    parse_display_modes, parse_wm_class, DisplayMode, X11Connection,
+};
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{AtomEnum, ConnectionExt, CreateWindowAux, PropMode, Window, WindowClass},
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+    COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT,
+};
+
+/// Create a new window on the given connection
+fn create_window(conn: &RustConnection, parent: Window, x: i16, y: i16) -> Window {
+    let window = conn.generate_id().unwrap();
+    conn.create_window(
+        COPY_DEPTH_FROM_PARENT,
+        window,
+        parent,
+        x,
+        y,
+        640,
+        480,
+        0,
+        WindowClass::INPUT_OUTPUT,
+        COPY_FROM_PARENT,
+        &CreateWindowAux::new(),
+    )
+    .unwrap();
+    window
+}
+
+/// Intern the atom with the given name
+fn atom(conn: &RustConnection, name: &str) -> u32 {
+    conn.intern_atom(false, name.as_bytes())
+        .unwrap()
+        .reply()
+        .unwrap()
+        .atom
+}
+
+#[test]
+fn test_parse_wm_class() {
+    let (instance, class) = parse_wm_class(b"steam_app_123\0SteamApp\0");
+    assert_eq!(instance, "steam_app_123");
+    assert_eq!(class, "SteamApp");
+    let (instance, class) = parse_wm_class(b"");
+    assert_eq!(instance, "");
+    assert_eq!(class, "");
+}
+
+#[test]
+fn test_parse_display_modes() {
+    let modes = parse_display_modes("1280x800@60 1280x800@40.2 invalid 800x@60 1920x1080@59.94");
+    assert_eq!(
+        modes,
+        vec![
+            DisplayMode {
+                width: 1280,
+                height: 800,
+                refresh_rate: 60
+            },
+            DisplayMode {
+                width: 1280,
+                height: 800,
+                refresh_rate: 40
+            },
+            DisplayMode {
+                width: 1920,
+                height: 1080,
+                refresh_rate: 60
+            },
+        ]
+    );
+    assert!(parse_display_modes("").is_empty());
+}
+
+#[test]
+#[ignore = "requires Xvfb"]
+fn test_window_info() {
+    let display = TestDisplay::start();
+    let (conn, screen) = display.connect();
+    let root = conn.setup().roots[screen].root;
+
+    // Create a fullscreen splash window for a game
+    let window = create_window(&conn, root, 10, 20);
+    conn.change_property8(
+        PropMode::REPLACE,
+        window,
+        AtomEnum::WM_CLASS,
+        AtomEnum::STRING,
+        b"game\0Game\0",
+    )
+    .unwrap();
+    conn.change_property8(
+        PropMode::REPLACE,
+        window,
+        AtomEnum::WM_NAME,
+        AtomEnum::STRING,
+        b"My Game",
+    )
+    .unwrap();
+    conn.change_property32(
+        PropMode::REPLACE,
+        window,
+        atom(&conn, "_NET_WM_PID"),
+        AtomEnum::CARDINAL,
+        &[1234],
+    )
+    .unwrap();
+    conn.change_property32(
+        PropMode::REPLACE,
+        window,
+        atom(&conn, "_NET_WM_WINDOW_TYPE"),
+        AtomEnum::ATOM,
+        &[atom(&conn, "_NET_WM_WINDOW_TYPE_SPLASH")],
+    )
+    .unwrap();
+    conn.change_property32(
+        PropMode::REPLACE,
+        window,
+        atom(&conn, "_NET_WM_STATE"),
+        AtomEnum::ATOM,
+        &[atom(&conn, "_NET_WM_STATE_FULLSCREEN")],
+    )
+    .unwrap();
+    conn.map_window(window).unwrap();
+
+    // Create an unmapped dialog inside of the window
+    let dialog = create_window(&conn, window, 5, 5);
+    conn.change_property32(
+        PropMode::REPLACE,
+        dialog,
+        AtomEnum::WM_TRANSIENT_FOR,
+        AtomEnum::WINDOW,
+        &[window],
+    )
+    .unwrap();
+    conn.sync().unwrap();
+
+    let x11 = X11Connection::connect(display.name()).unwrap();
+    assert_eq!(x11.root(), root);
+
+    let info = x11.get_window_info(window).unwrap();
+    assert_eq!(info.name, "My Game");
+    assert_eq!(info.wm_class_instance, "game");
+    assert_eq!(info.wm_class, "Game");
+    assert_eq!(info.pid, 1234);
+    assert_eq!(info.window_types, vec!["splash".to_string()]);
+    assert_eq!((info.x, info.y), (10, 20));
+    assert_eq!((info.width, info.height), (640, 480));
+    assert!(info.mapped);
+    assert!(info.visible);
+    assert_eq!(info.transient_for, 0);
+    assert!(info.fullscreen);
+    assert!(!info.hidden);
+
+    let info = x11.get_window_info(dialog).unwrap();
+    assert_eq!((info.x, info.y), (15, 25));
+    assert!(!info.mapped);
+    assert!(!info.visible);
+    assert_eq!(info.transient_for, window);
+    assert!(info.window_types.is_empty());
+
+    // Unknown windows are reported as errors
+    assert!(x11.get_window_info(window + 100).is_err());
+}
+
+#[test]
+#[ignore = "requires Xvfb"]
+fn test_root_properties() {
+    let display = TestDisplay::start();
+    let x11 = X11Connection::connect(display.name()).unwrap();
+
+    // Unset properties are reported as missing
+    assert_eq!(x11.get_root_cardinal("GAMESCOPE_SHARPNESS").unwrap(), None);
+
+    x11.set_root_cardinals("GAMESCOPE_SHARPNESS", &[5]).unwrap();
+    assert_eq!(
+        x11.get_root_cardinal("GAMESCOPE_SHARPNESS").unwrap(),
+        Some(5)
+    );
+    x11.set_root_cardinals("GAMESCOPE_COLOR_NIGHTMODE", &[1, 2, 3])
+        .unwrap();
+    assert_eq!(
+        x11.get_root_cardinals("GAMESCOPE_COLOR_NIGHTMODE").unwrap(),
+        Some(vec![1, 2, 3])
+    );
+
+    x11.remove_root_property("GAMESCOPE_SHARPNESS").unwrap();
+    assert_eq!(x11.get_root_cardinal("GAMESCOPE_SHARPNESS").unwrap(), None);
+
+    x11.set_root_string("GAMESCOPE_DISPLAY_MODE_LIST_EXTERNAL", "1280x800@60")
+        .unwrap();
+    assert_eq!(
+        x11.get_root_string("GAMESCOPE_DISPLAY_MODE_LIST_EXTERNAL")
+            .unwrap(),
+        Some("1280x800@60".to_string())
+    );
+
+    // The root window has the size of the screen
+    assert_eq!(x11.get_root_size().unwrap(), (1280, 800));
+}
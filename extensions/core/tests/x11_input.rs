@@ -0,0 +1,134 @@
+mod common;
+
+use common::TestDisplay;
+use opengamepadui_core::gamescope::{
+    x11_connection::X11Connection,
+    x11_input::{char_to_keysym, key_name_to_keysym, Keymap, BUTTON_LEFT},
+};
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        xproto::{ConnectionExt, CreateWindowAux, EventMask, InputFocus, WindowClass},
+        Event,
+    },
+    wrapper::ConnectionExt as _,
+    COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT, CURRENT_TIME,
+};
+
+#[test]
+fn test_key_names() {
+    assert_eq!(key_name_to_keysym("KEY_A"), Some('a' as u32));
+    assert_eq!(key_name_to_keysym("KEY_7"), Some('7' as u32));
+    assert_eq!(key_name_to_keysym("KEY_F1"), Some(0xffbe));
+    assert_eq!(key_name_to_keysym("KEY_F12"), Some(0xffc9));
+    assert_eq!(key_name_to_keysym("KEY_KP5"), Some(0xffb5));
+    assert_eq!(key_name_to_keysym("KEY_ENTER"), Some(0xff0d));
+    assert_eq!(key_name_to_keysym("KEY_LEFTSHIFT"), Some(0xffe1));
+    assert_eq!(key_name_to_keysym("KEY_UNKNOWN"), None);
+    assert_eq!(key_name_to_keysym("A"), None);
+}
+
+#[test]
+fn test_char_keysyms() {
+    assert_eq!(char_to_keysym('a'), 0x61);
+    assert_eq!(char_to_keysym('A'), 0x41);
+    assert_eq!(char_to_keysym('é'), 0xe9);
+    assert_eq!(char_to_keysym('\n'), 0xff0d);
+    assert_eq!(char_to_keysym('€'), 0x0100_20ac);
+}
+
+#[test]
+fn test_keymap() {
+    // Keycodes 8-11 with two keysyms each: "a A", "1 !", nothing, "b B"
+    let mut keymap = Keymap {
+        min_keycode: 8,
+        keysyms_per_keycode: 2,
+        keysyms: vec![0x61, 0x41, 0x31, 0x21, 0, 0, 0x62, 0x42],
+    };
+    assert_eq!(keymap.find(0x61), Some((8, false)));
+    assert_eq!(keymap.find(0x41), Some((8, true)));
+    assert_eq!(keymap.find(0x21), Some((9, true)));
+    assert_eq!(keymap.find(0x20ac), None);
+
+    // Unused keycodes can be mapped to new keysyms
+    assert_eq!(keymap.spare_keycode(), Some(10));
+    keymap.set(10, 0x20ac);
+    assert_eq!(keymap.find(0x20ac), Some((10, false)));
+    assert_eq!(keymap.spare_keycode(), None);
+}
+
+#[test]
+#[ignore = "requires Xvfb"]
+fn test_input_injection() {
+    let display = TestDisplay::start();
+    let (conn, screen) = display.connect();
+    let root = conn.setup().roots[screen].root;
+
+    // Create a focused window that receives key and button events
+    let window = conn.generate_id().unwrap();
+    let events = EventMask::KEY_PRESS | EventMask::BUTTON_PRESS;
+    conn.create_window(
+        COPY_DEPTH_FROM_PARENT,
+        window,
+        root,
+        0,
+        0,
+        640,
+        480,
+        0,
+        WindowClass::INPUT_OUTPUT,
+        COPY_FROM_PARENT,
+        &CreateWindowAux::new().event_mask(events),
+    )
+    .unwrap();
+    conn.map_window(window).unwrap();
+    conn.sync().unwrap();
+    conn.set_input_focus(InputFocus::POINTER_ROOT, window, CURRENT_TIME)
+        .unwrap();
+    conn.sync().unwrap();
+
+    let x11 = X11Connection::connect(display.name()).unwrap();
+    x11.move_cursor(100, 50).unwrap();
+    let pointer = conn.query_pointer(root).unwrap().reply().unwrap();
+    assert_eq!((pointer.root_x, pointer.root_y), (100, 50));
+    x11.move_cursor_relative(10, -10).unwrap();
+    let pointer = conn.query_pointer(root).unwrap().reply().unwrap();
+    assert_eq!((pointer.root_x, pointer.root_y), (110, 40));
+
+    // Keys are sent to the focused window, including characters that are
+    // not on the keyboard
+    x11.type_text("a€").unwrap();
+    x11.send_button(BUTTON_LEFT, true).unwrap();
+    x11.send_button(BUTTON_LEFT, false).unwrap();
+    conn.sync().unwrap();
+
+    let mut key_presses = 0;
+    let mut button_presses = 0;
+    while let Some(event) = conn.poll_for_event().unwrap() {
+        match event {
+            Event::KeyPress(_) => key_presses += 1,
+            Event::ButtonPress(event) => {
+                assert_eq!(event.detail, BUTTON_LEFT);
+                button_presses += 1;
+            }
+            _ => (),
+        }
+    }
+    assert_eq!(key_presses, 2);
+    assert_eq!(button_presses, 1);
+
+    // Spare keycodes are unmapped again once the key is released
+    let setup = conn.setup();
+    let count = setup.max_keycode - setup.min_keycode + 1;
+    let reply = conn
+        .get_keyboard_mapping(setup.min_keycode, count)
+        .unwrap()
+        .reply()
+        .unwrap();
+    let keymap = Keymap {
+        min_keycode: setup.min_keycode,
+        keysyms_per_keycode: reply.keysyms_per_keycode,
+        keysyms: reply.keysyms,
+    };
+    assert_eq!(keymap.find(char_to_keysym('€')), None);
+}